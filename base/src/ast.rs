@@ -268,6 +268,8 @@ pub enum Pattern<Id> {
     },
     /// A literal pattern
     Literal(Literal),
+    /// Or-pattern, eg: `Red | Green`. Every alternative binds the same variables
+    Or(Vec<SpannedPattern<Id>>),
     /// An invalid pattern
     Error,
 }
@@ -278,9 +280,37 @@ impl<Id> Default for Pattern<Id> {
     }
 }
 
+impl<Id> Pattern<Id> {
+    /// Returns how many patterns this pattern expands to if every or-pattern in it is replaced by
+    /// one of its alternatives (saturating at `usize::MAX`)
+    pub fn or_pattern_expansion(&self) -> usize {
+        fn product<'a, Id: 'a>(patterns: impl IntoIterator<Item = &'a Pattern<Id>>) -> usize {
+            patterns.into_iter().fold(1, |count, pattern| {
+                count.saturating_mul(pattern.or_pattern_expansion())
+            })
+        }
+        match *self {
+            Pattern::Or(ref alternatives) => alternatives.iter().fold(0, |count, alternative| {
+                count.saturating_add(alternative.value.or_pattern_expansion())
+            }),
+            Pattern::As(_, ref pat) => pat.value.or_pattern_expansion(),
+            Pattern::Record { ref fields, .. } => product(
+                fields
+                    .iter()
+                    .filter_map(|field| field.value.as_ref().map(|pat| &pat.value)),
+            ),
+            Pattern::Tuple { ref elems, .. } => product(elems.iter().map(|pat| &pat.value)),
+            Pattern::Constructor(_, ref args) => product(args.iter().map(|pat| &pat.value)),
+            Pattern::Ident(_) | Pattern::Literal(_) | Pattern::Error => 1,
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Alternative<Id> {
     pub pattern: SpannedPattern<Id>,
+    /// An optional `if` guard which must evaluate to `True` for the alternative to be taken
+    pub guard: Option<SpannedExpr<Id>>,
     pub expr: SpannedExpr<Id>,
 }
 
@@ -691,6 +721,9 @@ pub fn walk_expr<'a, V: ?Sized + $trait_name<'a>>(v: &mut V, e: &'a $($mut)* Spa
             v.visit_expr(expr);
             for alt in alts {
                 v.visit_pattern(&$($mut)* alt.pattern);
+                if let Some(ref $($mut)* guard) = alt.guard {
+                    v.visit_expr(guard);
+                }
                 v.visit_expr(&$($mut)* alt.expr);
             }
         }
@@ -813,6 +846,11 @@ pub fn walk_pattern<'a, V: ?Sized + $trait_name<'a>>(v: &mut V, p: &'a $($mut)*
             }
         }
         Pattern::Ident(ref $($mut)* id) => v.visit_ident(id),
+        Pattern::Or(ref $($mut)* alternatives) => {
+            for alternative in alternatives {
+                v.visit_pattern(alternative);
+            }
+        }
         Pattern::Literal(_) | Pattern::Error => (),
     }
 }
@@ -972,6 +1010,10 @@ impl Typed for Pattern<Symbol> {
             Pattern::Record { ref typ, .. } => Ok(typ.clone()),
             Pattern::Tuple { ref typ, .. } => Ok(typ.clone()),
             Pattern::Constructor(ref id, ref args) => get_return_type(env, &id.typ, args.len()),
+            Pattern::Or(ref alternatives) => match alternatives.first() {
+                Some(alternative) => alternative.try_type_of(env),
+                None => Ok(Type::hole()),
+            },
             Pattern::Error => Ok(Type::hole()),
             Pattern::Literal(ref l) => l.try_type_of(env),
         }
@@ -11,6 +11,7 @@ use std::sync::Arc;
 use crate::serde::de::{DeserializeSeed, DeserializeState, Deserializer, Error};
 use crate::serde::ser::{SerializeState, Serializer};
 
+use crate::fnv::FnvMap;
 use crate::kind::ArcKind;
 use crate::symbol::Symbol;
 use crate::types::{AliasData, ArcType, Type, TypeExt};
@@ -34,6 +35,11 @@ impl AsRef<NodeToId> for SeSeed {
 
 pub struct Seed<Id, T> {
     nodes: crate::serialization::NodeMap,
+    // Symbols are compared by identity so every occurence of a name must deserialize to the same
+    // symbol
+    symbols: FnvMap<String, Symbol>,
+    // Alias groups which are deserialized to an existing group, keyed by the name of the aliases
+    alias_groups: FnvMap<String, Arc<[AliasData<Id, T>]>>,
     _marker: PhantomData<(Id, T)>,
 }
 
@@ -59,15 +65,69 @@ impl<Id, T> Seed<Id, T> {
     pub fn new(nodes: crate::serialization::NodeMap) -> Self {
         Seed {
             nodes,
+            symbols: FnvMap::default(),
+            alias_groups: FnvMap::default(),
             _marker: PhantomData,
         }
     }
+
+    /// Makes any symbol with the same name as `symbol` deserialize to `symbol`. Symbols are
+    /// compared by identity so this lets deserialized types refer to the same aliases as types
+    /// which were created by the typechecker.
+    pub fn add_symbol(&mut self, symbol: Symbol) {
+        self.symbols.entry(symbol.to_string()).or_insert(symbol);
+    }
+
+    pub(crate) fn symbol(&mut self, name: &str) -> Symbol {
+        self.symbols
+            .entry(name.to_string())
+            .or_insert_with(|| Symbol::from(name))
+            .clone()
+    }
+
+    fn find_alias_group(&self, group: &[AliasData<Id, T>]) -> Option<Arc<[AliasData<Id, T>]>>
+    where
+        Id: Any,
+    {
+        let name = |alias: &AliasData<Id, T>| {
+            (&alias.name as &dyn Any)
+                .downcast_ref::<Symbol>()
+                .map(|name| name.to_string())
+        };
+        let existing = self.alias_groups.get(&name(group.first()?)?)?;
+        if existing.len() == group.len()
+            && existing
+                .iter()
+                .zip(group)
+                .all(|(l, r)| name(l).is_some() && name(l) == name(r))
+        {
+            Some(existing.clone())
+        } else {
+            None
+        }
+    }
+}
+
+impl Seed<Symbol, ArcType> {
+    /// Makes any alias group with the same names as `group` deserialize to `group`. Aliases are
+    /// compared by identity so this lets deserialized types unify with the types that the
+    /// aliases were originally created for.
+    pub fn add_alias_group(&mut self, group: &Arc<[AliasData<Symbol, ArcType>]>) {
+        for alias in group.iter() {
+            self.add_symbol(alias.name.clone());
+            self.alias_groups
+                .entry(alias.name.to_string())
+                .or_insert_with(|| group.clone());
+        }
+    }
 }
 
 impl<Id, T> Clone for Seed<Id, T> {
     fn clone(&self) -> Self {
         Seed {
             nodes: self.nodes.clone(),
+            symbols: self.symbols.clone(),
+            alias_groups: self.alias_groups.clone(),
             _marker: PhantomData,
         }
     }
@@ -85,8 +145,10 @@ where
         + ::std::any::Any
         + DeserializeState<'de, Seed<Id, T>>,
 {
-    let seed = SharedSeed::new(seed);
-    DeserializeSeed::deserialize(seed, deserializer).map(|vec: Vec<_>| Arc::from(vec))
+    let group: Vec<_> = DeserializeSeed::deserialize(SharedSeed::new(&mut *seed), deserializer)?;
+    Ok(seed
+        .find_alias_group(&group)
+        .unwrap_or_else(|| Arc::from(group)))
 }
 
 impl<'a, T> Shared for &'a T {
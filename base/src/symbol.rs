@@ -61,9 +61,8 @@ mod serialization {
             use crate::serde::de::DeserializeSeed;
             use crate::serialization::SharedSeed;
 
-            let seed = SharedSeed::new(seed);
-            seed.deserialize(deserializer)
-                .map(|s: String| Symbol::from(&s[..]))
+            let name: String = SharedSeed::new(&mut *seed).deserialize(deserializer)?;
+            Ok(seed.symbol(&name))
         }
     }
 
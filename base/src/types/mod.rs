@@ -853,7 +853,7 @@ pub enum Type<Id, T = ArcType<Id>> {
 
 #[cfg(target_pointer_width = "64")]
 // Safeguard against accidentally growing Type as it is a core type
-const _: [(); 8 * 6] = [(); std::mem::size_of::<Type<Symbol, ArcType>>()];
+const _: [(); 8 * 5] = [(); std::mem::size_of::<Type<Symbol, ArcType>>()];
 
 impl<Id, T> Type<Id, T> {
     pub fn as_variable(&self) -> Option<&TypeVariable> {
@@ -0,0 +1,583 @@
+//! Checks that the alternatives of each `match` expression cover every value of the matched type
+//! and that every alternative can be reached.
+//!
+//! The check runs after typechecking and works on a matrix of simplified patterns where each row
+//! is an alternative (see "Warnings for pattern matching" by Luc Maranget). A pattern vector is
+//! "useful" with respect to a matrix if there is a value which it matches but which none of the
+//! rows matches. An alternative is unreachable if its pattern is not useful with respect to the
+//! alternatives before it and the `match` is exhaustive if the wildcard pattern `_` is not useful
+//! with respect to all of the alternatives. The values which make `_` useful are reported as the
+//! missing patterns.
+use std::{fmt, rc::Rc};
+
+use crate::base::{
+    ast::{self, Alternative, Expr, Literal, Pattern, SpannedExpr, SpannedPattern, Visitor},
+    error::Errors,
+    pos::{self, BytePos, Spanned},
+    resolve::remove_aliases_cow,
+    symbol::Symbol,
+    types::{arg_iter, ArcType, NullInterner, Type, TypeEnv, TypeExt},
+};
+
+/// The maximum number of missing patterns that are reported for a single `match`
+const MAX_MISSING_PATTERNS: usize = 3;
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub enum Error {
+    /// The alternatives do not match the values described by these patterns
+    NonExhaustive { missing: Vec<String> },
+    /// Every value the alternative matches are matched by the alternatives before it
+    UnreachableAlternative,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NonExhaustive { missing } => {
+                write!(f, "Non-exhaustive patterns, ")?;
+                for (i, pattern) in missing.iter().enumerate() {
+                    if i != 0 {
+                        write!(
+                            f,
+                            "{}",
+                            if i + 1 == missing.len() {
+                                " and "
+                            } else {
+                                ", "
+                            }
+                        )?;
+                    }
+                    write!(f, "`{}`", pattern)?;
+                }
+                write!(
+                    f,
+                    " {} not covered",
+                    if missing.len() == 1 { "is" } else { "are" }
+                )
+            }
+            Error::UnreachableAlternative => write!(
+                f,
+                "Unreachable alternative, the alternatives before it match every value it matches"
+            ),
+        }
+    }
+}
+
+pub type ExhaustivenessErrors = Errors<Spanned<Error, BytePos>>;
+
+/// Checks every `match` expression in `expr`. `expr` must have been typechecked successfully.
+pub fn check_expr(
+    env: &dyn TypeEnv<Type = ArcType>,
+    expr: &SpannedExpr<Symbol>,
+) -> Result<(), ExhaustivenessErrors> {
+    let mut checker = Checker {
+        env,
+        errors: Errors::new(),
+    };
+    checker.visit_expr(expr);
+    if checker.errors.has_errors() {
+        Err(checker.errors)
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Constructor {
+    /// A variant constructor along with the name and arity of every constructor of its type (if
+    /// the type could be resolved)
+    Variant {
+        name: String,
+        arity: usize,
+        siblings: Option<Rc<[(String, usize)]>>,
+    },
+    /// A record with the listed fields
+    Record(Rc<[String]>),
+    Tuple(usize),
+    Literal(Literal),
+}
+
+impl Constructor {
+    fn arity(&self) -> usize {
+        match self {
+            Constructor::Variant { arity, .. } => *arity,
+            Constructor::Record(fields) => fields.len(),
+            Constructor::Tuple(arity) => *arity,
+            Constructor::Literal(_) => 0,
+        }
+    }
+
+    fn is_same(&self, other: &Constructor) -> bool {
+        match (self, other) {
+            (Constructor::Variant { name: l, .. }, Constructor::Variant { name: r, .. }) => l == r,
+            (Constructor::Record(_), Constructor::Record(_)) => true,
+            (Constructor::Tuple(l), Constructor::Tuple(r)) => l == r,
+            (Constructor::Literal(l), Constructor::Literal(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Pat {
+    Wildcard,
+    Constructor(Constructor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+impl Pat {
+    fn wildcards(count: usize) -> Vec<Pat> {
+        vec![Pat::Wildcard; count]
+    }
+
+    fn is_wildcard(&self) -> bool {
+        matches!(self, Pat::Wildcard)
+    }
+
+    fn fmt_arg(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pat::Constructor(Constructor::Variant { .. }, args) if !args.is_empty() => {
+                write!(f, "({})", self)
+            }
+            Pat::Or(_) => write!(f, "({})", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pat::Wildcard => write!(f, "_"),
+            Pat::Or(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, " | ")?;
+                    }
+                    alternative.fmt_arg(f)?;
+                }
+                Ok(())
+            }
+            Pat::Constructor(ctor, args) => match ctor {
+                Constructor::Variant { name, .. } => {
+                    write!(f, "{}", name)?;
+                    for arg in args {
+                        write!(f, " ")?;
+                        arg.fmt_arg(f)?;
+                    }
+                    Ok(())
+                }
+                Constructor::Record(fields) => {
+                    if args.iter().all(Pat::is_wildcard) {
+                        return write!(f, "_");
+                    }
+                    write!(f, "{{ ")?;
+                    let mut first = true;
+                    for (field, arg) in fields.iter().zip(args) {
+                        if arg.is_wildcard() {
+                            continue;
+                        }
+                        if !first {
+                            write!(f, ", ")?;
+                        }
+                        first = false;
+                        write!(f, "{} = {}", field, arg)?;
+                    }
+                    write!(f, " }}")
+                }
+                Constructor::Tuple(_) => {
+                    write!(f, "(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i != 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", arg)?;
+                    }
+                    write!(f, ")")
+                }
+                Constructor::Literal(literal) => match literal {
+                    Literal::Byte(b) => write!(f, "{}b", b),
+                    Literal::Int(i) => write!(f, "{}", i),
+                    Literal::Float(x) => write!(f, "{}", x),
+                    Literal::String(s) => write!(f, "{:?}", s),
+                    Literal::Char(c) => write!(f, "{:?}", c),
+                },
+            },
+        }
+    }
+}
+
+struct Checker<'a> {
+    env: &'a dyn TypeEnv<Type = ArcType>,
+    errors: ExhaustivenessErrors,
+}
+
+impl<'a, 'e> Visitor<'e> for Checker<'a> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'e SpannedExpr<Symbol>) {
+        if let Expr::Match(_, ref alts) = expr.value {
+            self.check_match(expr, alts);
+        }
+        ast::walk_expr(self, expr);
+    }
+}
+
+impl<'a> Checker<'a> {
+    fn check_match(&mut self, expr: &SpannedExpr<Symbol>, alts: &[Alternative<Symbol>]) {
+        let mut lowered = Vec::with_capacity(alts.len());
+        for alt in alts {
+            match self.lower(&alt.pattern) {
+                Some(pattern) => lowered.push(vec![pattern]),
+                // Invalid patterns have already been reported by the typechecker
+                None => return,
+            }
+        }
+
+        // The rows of the alternatives without a guard. A guarded alternative may fail to match
+        // so it does not cover any values but it can still be unreachable
+        let mut rows = Vec::with_capacity(alts.len());
+        for (alt, row) in alts.iter().zip(lowered) {
+            if useful(&rows, &row, 1).is_empty() {
+                self.errors.push(pos::spanned(
+                    alt.pattern.span,
+                    Error::UnreachableAlternative,
+                ));
+            }
+            if alt.guard.is_none() {
+                rows.push(row);
+            }
+        }
+
+        let missing = useful(&rows, &[Pat::Wildcard], MAX_MISSING_PATTERNS);
+        if !missing.is_empty() {
+            self.errors.push(pos::spanned(
+                expr.span,
+                Error::NonExhaustive {
+                    missing: missing
+                        .iter()
+                        .map(|witness| witness[0].to_string())
+                        .collect(),
+                },
+            ));
+        }
+    }
+
+    fn lower(&self, pattern: &SpannedPattern<Symbol>) -> Option<Pat> {
+        Some(match pattern.value {
+            Pattern::As(_, ref pattern) => return self.lower(pattern),
+            Pattern::Ident(_) => Pat::Wildcard,
+            Pattern::Literal(ref literal) => {
+                Pat::Constructor(Constructor::Literal(literal.clone()), Vec::new())
+            }
+            Pattern::Constructor(ref id, ref args) => Pat::Constructor(
+                Constructor::Variant {
+                    name: id.name.declared_name().to_string(),
+                    arity: args.len(),
+                    siblings: self.variant_constructors(&id.typ),
+                },
+                args.iter()
+                    .map(|arg| self.lower(arg))
+                    .collect::<Option<_>>()?,
+            ),
+            Pattern::Tuple { ref elems, .. } => Pat::Constructor(
+                Constructor::Tuple(elems.len()),
+                elems
+                    .iter()
+                    .map(|elem| self.lower(elem))
+                    .collect::<Option<_>>()?,
+            ),
+            Pattern::Record {
+                ref typ,
+                ref fields,
+                ..
+            } => {
+                let typ = remove_aliases_cow(self.env, &mut NullInterner, typ);
+                let mut names: Vec<String> = typ
+                    .row_iter()
+                    .map(|field| field.name.declared_name().to_string())
+                    .collect();
+                // Fall back to the fields of the pattern if the type is not a known record
+                for field in fields {
+                    let name = field.name.value.declared_name();
+                    if !names.iter().any(|n| n == name) {
+                        names.push(name.to_string());
+                    }
+                }
+
+                let mut args = Pat::wildcards(names.len());
+                for field in fields {
+                    if let Some(ref value) = field.value {
+                        let name = field.name.value.declared_name();
+                        let i = names.iter().position(|n| n == name).unwrap();
+                        args[i] = self.lower(value)?;
+                    }
+                }
+                Pat::Constructor(Constructor::Record(names.into()), args)
+            }
+            Pattern::Or(ref alternatives) => Pat::Or(
+                alternatives
+                    .iter()
+                    .map(|alternative| self.lower(alternative))
+                    .collect::<Option<_>>()?,
+            ),
+            Pattern::Error => return None,
+        })
+    }
+
+    /// Returns the name and arity of each constructor of the variant type that a constructor
+    /// with the type `ctor_type` constructs
+    fn variant_constructors(&self, ctor_type: &ArcType) -> Option<Rc<[(String, usize)]>> {
+        let mut args = arg_iter(ctor_type.remove_forall());
+        while args.next().is_some() {}
+        let typ = remove_aliases_cow(self.env, &mut NullInterner, args.typ.remove_forall());
+        match **typ.remove_forall() {
+            Type::Variant(ref row) => {
+                let mut fields = row.row_iter();
+                let ctors = fields
+                    .by_ref()
+                    .map(|field| {
+                        (
+                            field.name.declared_name().to_string(),
+                            arg_iter(field.typ.remove_forall()).count(),
+                        )
+                    })
+                    .collect();
+                // Open variants (`.. r`) may hold constructors which are not listed in the type
+                match **fields.current_type() {
+                    Type::EmptyRow => Some(ctors),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the constructors in the first column of `rows` if they make up every constructor of
+/// the column's type
+fn complete_constructors(rows: &[Vec<Pat>]) -> Option<Vec<Constructor>> {
+    let mut heads = rows.iter().filter_map(|row| match row[0] {
+        Pat::Constructor(ref ctor, _) => Some(ctor),
+        Pat::Wildcard | Pat::Or(_) => None,
+    });
+    let first = heads.next()?;
+    match first {
+        // Records and tuples only have a single constructor
+        Constructor::Record(_) | Constructor::Tuple(_) => Some(vec![first.clone()]),
+        Constructor::Variant {
+            siblings: Some(siblings),
+            ..
+        } => {
+            let mut seen = vec![false; siblings.len()];
+            for ctor in Some(first).into_iter().chain(heads) {
+                if let Constructor::Variant { name, .. } = ctor {
+                    if let Some(i) = siblings.iter().position(|(sibling, _)| sibling == name) {
+                        seen[i] = true;
+                    }
+                }
+            }
+            if seen.iter().all(|&seen| seen) {
+                Some(
+                    siblings
+                        .iter()
+                        .map(|(name, arity)| Constructor::Variant {
+                            name: name.clone(),
+                            arity: *arity,
+                            siblings: Some(siblings.clone()),
+                        })
+                        .collect(),
+                )
+            } else {
+                None
+            }
+        }
+        // Literals have too many values to list them all
+        Constructor::Variant { siblings: None, .. } | Constructor::Literal(_) => None,
+    }
+}
+
+/// Returns constructors of the first column's type which none of the `rows` start with. Returns
+/// a wildcard if the constructors are unknown (or there are no constructors in the column).
+fn missing_constructors(rows: &[Vec<Pat>]) -> Vec<Pat> {
+    let heads: Vec<_> = rows
+        .iter()
+        .filter_map(|row| match row[0] {
+            Pat::Constructor(ref ctor, _) => Some(ctor),
+            Pat::Wildcard | Pat::Or(_) => None,
+        })
+        .collect();
+    match heads.first() {
+        Some(Constructor::Variant {
+            siblings: Some(siblings),
+            ..
+        }) => siblings
+            .iter()
+            .filter(|(name, _)| {
+                !heads.iter().any(|head| match head {
+                    Constructor::Variant { name: head, .. } => head == name,
+                    _ => false,
+                })
+            })
+            .map(|(name, arity)| {
+                Pat::Constructor(
+                    Constructor::Variant {
+                        name: name.clone(),
+                        arity: *arity,
+                        siblings: Some(siblings.clone()),
+                    },
+                    Pat::wildcards(*arity),
+                )
+            })
+            .collect(),
+        _ => vec![Pat::Wildcard],
+    }
+}
+
+/// Returns the rows which match `ctor` with the arguments of `ctor` in place of the first column
+fn specialize(rows: &[Vec<Pat>], ctor: &Constructor) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter_map(|row| {
+            let mut args = match row[0] {
+                Pat::Wildcard => Pat::wildcards(ctor.arity()),
+                Pat::Constructor(ref row_ctor, ref args) if row_ctor.is_same(ctor) => {
+                    match (ctor, row_ctor) {
+                        // Records are matched field by field as the rows may list the fields in
+                        // a different order
+                        (Constructor::Record(fields), Constructor::Record(row_fields)) => fields
+                            .iter()
+                            .map(|field| {
+                                row_fields
+                                    .iter()
+                                    .position(|row_field| row_field == field)
+                                    .map_or(Pat::Wildcard, |i| args[i].clone())
+                            })
+                            .collect(),
+                        _ => args.clone(),
+                    }
+                }
+                Pat::Constructor(..) | Pat::Or(_) => return None,
+            };
+            args.extend(row[1..].iter().cloned());
+            Some(args)
+        })
+        .collect()
+}
+
+/// Replaces each row which starts with an or-pattern with one row for each of its alternatives
+fn expand_or_patterns(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut expanded = Vec::with_capacity(rows.len());
+    for row in rows {
+        match row[0] {
+            Pat::Or(ref alternatives) => {
+                let alternative_rows: Vec<_> = alternatives
+                    .iter()
+                    .map(|alternative| {
+                        let mut alternative_row = vec![alternative.clone()];
+                        alternative_row.extend(row[1..].iter().cloned());
+                        alternative_row
+                    })
+                    .collect();
+                expanded.extend(expand_or_patterns(&alternative_rows));
+            }
+            _ => expanded.push(row.clone()),
+        }
+    }
+    expanded
+}
+
+/// Returns the rows which start with a wildcard without the first column
+fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    rows.iter()
+        .filter(|row| row[0].is_wildcard())
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Replaces the `arity` first patterns of `witness` with `ctor` applied to them
+fn rebuild(ctor: &Constructor, mut witness: Vec<Pat>) -> Vec<Pat> {
+    let rest = witness.split_off(ctor.arity());
+    let mut result = vec![Pat::Constructor(ctor.clone(), witness)];
+    result.extend(rest);
+    result
+}
+
+/// Returns (at most `limit`) pattern vectors which match values that `pattern` match but none of
+/// `rows` do. `pattern` is useful with respect to `rows` if the result is non-empty.
+fn useful(rows: &[Vec<Pat>], pattern: &[Pat], limit: usize) -> Vec<Vec<Pat>> {
+    let (first, rest) = match pattern.split_first() {
+        Some(x) => x,
+        None => {
+            return if rows.is_empty() {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            };
+        }
+    };
+
+    if rows.iter().any(|row| matches!(row[0], Pat::Or(_))) {
+        return useful(&expand_or_patterns(rows), pattern, limit);
+    }
+
+    match first {
+        // The or-pattern is useful if any of its alternatives are
+        Pat::Or(alternatives) => {
+            let mut witnesses = Vec::new();
+            for alternative in alternatives {
+                let mut pattern = vec![alternative.clone()];
+                pattern.extend(rest.iter().cloned());
+                witnesses.extend(useful(rows, &pattern, limit - witnesses.len()));
+                if witnesses.len() >= limit {
+                    break;
+                }
+            }
+            witnesses
+        }
+        Pat::Constructor(ctor, args) => {
+            let mut pattern = args.clone();
+            pattern.extend(rest.iter().cloned());
+            useful(&specialize(rows, ctor), &pattern, limit)
+                .into_iter()
+                .map(|witness| rebuild(ctor, witness))
+                .collect()
+        }
+        Pat::Wildcard => match complete_constructors(rows) {
+            Some(ctors) => {
+                let mut witnesses = Vec::new();
+                for ctor in ctors {
+                    let mut pattern = Pat::wildcards(ctor.arity());
+                    pattern.extend(rest.iter().cloned());
+                    witnesses.extend(
+                        useful(&specialize(rows, &ctor), &pattern, limit - witnesses.len())
+                            .into_iter()
+                            .map(|witness| rebuild(&ctor, witness)),
+                    );
+                    if witnesses.len() >= limit {
+                        break;
+                    }
+                }
+                witnesses
+            }
+            None => {
+                let rest_witnesses = useful(&default_rows(rows), rest, limit);
+                if rest_witnesses.is_empty() {
+                    return Vec::new();
+                }
+                let missing = missing_constructors(rows);
+                let mut witnesses = Vec::new();
+                'outer: for witness in &rest_witnesses {
+                    for head in &missing {
+                        if witnesses.len() >= limit {
+                            break 'outer;
+                        }
+                        let mut result = vec![head.clone()];
+                        result.extend(witness.iter().cloned());
+                        witnesses.push(result);
+                    }
+                }
+                witnesses
+            }
+        },
+    }
+}
@@ -166,6 +166,7 @@ pub fn metadata(
                 | Pattern::Tuple { .. }
                 | Pattern::Record { .. }
                 | Pattern::Literal(_)
+                | Pattern::Or(_)
                 | Pattern::Error => self.new_pattern(metadata, &bind.name),
             }
         }
@@ -224,6 +225,7 @@ pub fn metadata(
                 Pattern::Tuple { .. }
                 | Pattern::Constructor(..)
                 | Pattern::Literal(_)
+                | Pattern::Or(_)
                 | Pattern::Error => (),
             }
         }
@@ -262,9 +262,12 @@ impl<'a> Visitor<'a> for Checker {
             Expr::Match(ref expr, ref alts) => {
                 let start = self.uninitialized_free_variables.len();
                 self.visit_expr(expr);
+                for guard in alts.iter().filter_map(|alt| alt.guard.as_ref()) {
+                    self.visit_expr(guard);
+                }
 
-                // Match expressions may not be done on uninitialized values as they could then
-                // view the uninitialized contents
+                // Match expressions (and their guards) may not be done on uninitialized values as
+                // they could then view the uninitialized contents
                 {
                     let used_uninitialized_variables = &self.uninitialized_free_variables[start..];
                     self.errors
@@ -33,6 +33,8 @@ pub fn rename<'s>(
         seen_symbols: FnvMap<Symbol, u32>,
         scope: Vec<Symbol>,
         env: Environment,
+        /// The variables bound by the or-pattern which is currently being renamed
+        or_bindings: Option<FnvMap<Symbol, Symbol>>,
     }
 
     impl<'a, 'b, 's> RenameVisitor<'a, 'b, 's> {
@@ -77,12 +79,36 @@ pub fn rename<'s>(
                         self.new_pattern(arg);
                     }
                 }
+                Pattern::Or(ref mut alternatives) => {
+                    // Every alternative binds the same variables so they must all be renamed to
+                    // the same symbols
+                    let outermost = self.or_bindings.is_none();
+                    if outermost {
+                        self.or_bindings = Some(FnvMap::default());
+                    }
+                    for alternative in alternatives {
+                        self.new_pattern(alternative);
+                    }
+                    if outermost {
+                        self.or_bindings = None;
+                    }
+                }
                 Pattern::Literal(_) | Pattern::Error => (),
             }
         }
 
         // Renames the symbol to be unique in this module
         fn stack_var(&mut self, id: Symbol, span: Span<BytePos>) -> Symbol {
+            if let Some(new_id) = self
+                .or_bindings
+                .as_ref()
+                .and_then(|bindings| bindings.get(&id))
+                .cloned()
+            {
+                self.env.stack.insert(id, (new_id.clone(), span));
+                return new_id;
+            }
+
             let mut location = self
                 .source
                 .location(span.start())
@@ -111,6 +137,10 @@ pub fn rename<'s>(
 
             debug!("Rename binding `{:?}` = `{:?}`", id, new_id);
 
+            if let Some(ref mut bindings) = self.or_bindings {
+                bindings.insert(id.clone(), new_id.clone());
+            }
+
             self.env.stack.insert(id, (new_id.clone(), span));
 
             new_id
@@ -188,6 +218,9 @@ pub fn rename<'s>(
                     for alt in alts {
                         self.env.stack.enter_scope();
                         self.new_pattern(&mut alt.pattern);
+                        if let Some(ref mut guard) = alt.guard {
+                            self.visit_expr(guard);
+                        }
                         self.visit_expr(&mut alt.expr);
                         self.env.stack.exit_scope();
                     }
@@ -379,6 +412,7 @@ pub fn rename<'s>(
         env: Environment {
             stack: ScopedMap::new(),
         },
+        or_bindings: None,
     };
     visitor.visit_expr(expr);
 }
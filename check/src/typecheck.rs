@@ -50,6 +50,12 @@ mod mod_type;
 
 pub(crate) type TcResult<T> = Result<T, TypeError<Symbol, RcType<Symbol>>>;
 
+/// The most patterns that the or-patterns in a single pattern may expand to. The compiler
+/// translates each expanded pattern separately so the expansion is limited to keep the size of
+/// the generated code from growing exponentially (ten fields matching on `A | B` would otherwise
+/// expand to 1024 patterns)
+pub const MAX_OR_PATTERN_EXPANSION: usize = 256;
+
 enum ErrorOrder {
     ExpectedActual,
     ActualExpected,
@@ -396,10 +402,13 @@ impl<'a> Typecheck<'a> {
                 | UndefinedType(_)
                 | DuplicateTypeDefinition(_)
                 | DuplicateField(_)
+                | OrPatternBinding(_)
+                | TooManyOrPatternAlternatives(_)
                 | UndefinedRecord { .. }
                 | EmptyCase
                 | KindError(_)
                 | RecursionCheck(_)
+                | Lint(_)
                 | Message(_) => (),
                 NotAFunction(ref mut typ)
                 | UndefinedField(ref mut typ, _)
@@ -805,12 +814,19 @@ impl<'a> Typecheck<'a> {
                     self.enter_scope();
                     self.refined_variables.enter_scope();
 
+                    self.check_or_pattern_expansion(&alt.pattern);
                     self.typecheck_pattern(
                         &mut alt.pattern,
                         original_scrutinee_type.clone(),
                         scrutinee_type.concrete.clone(),
                     );
 
+                    if let Some(ref mut guard) = alt.guard {
+                        let bool_type = self.bool();
+                        let guard_type = self.typecheck(guard, ModType::rigid(&bool_type));
+                        self.unify_span(expr_check_span(guard), &bool_type, guard_type.concrete);
+                    }
+
                     let mut alt_type = self
                         .typecheck_opt(&mut alt.expr, expected_type.as_ref().map(|t| t.as_ref()));
                     alt_type.concrete = self.instantiate_generics(&alt_type);
@@ -828,11 +844,12 @@ impl<'a> Typecheck<'a> {
                     self.exit_scope();
 
                     // The variant we matched on will not appear in any followup bindings so remove
-                    // this variant from the type we are matching on
+                    // this variant from the type we are matching on. A guarded alternative may
+                    // fall through so the variant may still appear after it.
                     //
                     // TODO Make this more general so it can error when not matching on all the
                     // variants
-                    {
+                    if alt.guard.is_none() {
                         *unaliased_scrutinee_type = self.subs.zonk(&unaliased_scrutinee_type);
                         let replaced = match (&alt.pattern.value, &**unaliased_scrutinee_type) {
                             (Pattern::Constructor(id, _), Type::Variant(row)) => {
@@ -1214,6 +1231,7 @@ impl<'a> Typecheck<'a> {
                 self.unify_span(do_span, &flat_map_type, func_type);
 
                 if let Some(ref mut id) = *id {
+                    self.check_or_pattern_expansion(id);
                     self.typecheck_pattern(id, ModType::wobbly(id_var.clone()), id_var);
                 }
 
@@ -1507,6 +1525,7 @@ impl<'a> Typecheck<'a> {
         pattern: &mut SpannedPattern<Symbol>,
         match_type: RcType,
     ) -> RcType {
+        self.check_or_pattern_expansion(pattern);
         match pattern.value {
             Pattern::Constructor(ref id, _) | Pattern::Ident(ref id)
                 if id.name.declared_name().starts_with(char::is_uppercase) =>
@@ -1520,6 +1539,16 @@ impl<'a> Typecheck<'a> {
         }
     }
 
+    fn check_or_pattern_expansion(&mut self, pattern: &SpannedPattern<Symbol>) {
+        let expansion = pattern.value.or_pattern_expansion();
+        if expansion > MAX_OR_PATTERN_EXPANSION {
+            self.error(
+                pattern.span,
+                TypeError::TooManyOrPatternAlternatives(expansion),
+            );
+        }
+    }
+
     fn typecheck_pattern(
         &mut self,
         pattern: &mut SpannedPattern<Symbol>,
@@ -1717,6 +1746,57 @@ impl<'a> Typecheck<'a> {
                 self.unify_span(span, &match_type, typ);
                 match_type.concrete
             }
+            Pattern::Or(ref mut alternatives) => {
+                let mut first_bindings: Vec<(Symbol, RcType)> = Vec::new();
+                let mut pattern_type = None;
+                for alternative in alternatives {
+                    let alternative_type = self.typecheck_pattern(
+                        alternative,
+                        match_type.clone(),
+                        partial_match_type.clone(),
+                    );
+                    let mut symbols = Vec::new();
+                    pattern_bindings(alternative, &mut symbols);
+                    let bindings: Vec<_> = symbols
+                        .into_iter()
+                        .filter_map(|id| {
+                            let typ = self.environment.stack.get(&id)?.typ.concrete.clone();
+                            Some((id, typ))
+                        })
+                        .collect();
+
+                    if pattern_type.is_none() {
+                        pattern_type = Some(alternative_type);
+                        first_bindings = bindings;
+                        continue;
+                    }
+
+                    for (id, binding_type) in &bindings {
+                        match first_bindings.iter().find(|(first, _)| first == id) {
+                            Some((_, first_type)) => {
+                                self.unify_span(alternative.span, first_type, binding_type.clone());
+                            }
+                            None => {
+                                self.error(
+                                    alternative.span,
+                                    TypeError::OrPatternBinding(id.clone()),
+                                );
+                            }
+                        }
+                    }
+                    for (id, _) in &first_bindings {
+                        if bindings.iter().all(|(binding, _)| binding != id) {
+                            self.error(alternative.span, TypeError::OrPatternBinding(id.clone()));
+                        }
+                    }
+                }
+
+                // Later alternatives may have shadowed the bindings of the first alternative
+                for (id, typ) in first_bindings {
+                    self.stack_var(id, typ);
+                }
+                pattern_type.unwrap_or(partial_match_type)
+            }
             Pattern::Error => self.subs.new_var(),
         }
     }
@@ -2294,6 +2374,11 @@ impl<'a> Typecheck<'a> {
                     self.finish_pattern(level, arg, &arg_type);
                 }
             }
+            Pattern::Or(ref mut alternatives) => {
+                for alternative in alternatives {
+                    self.finish_pattern(level, alternative, final_type);
+                }
+            }
             Pattern::Literal(_) | Pattern::Error => (),
         }
     }
@@ -3164,6 +3249,47 @@ fn with_pattern_types<'a: 'b, 'b>(
     })
 }
 
+/// Collects the variables which `pattern` binds
+fn pattern_bindings(pattern: &SpannedPattern<Symbol>, bindings: &mut Vec<Symbol>) {
+    match pattern.value {
+        Pattern::Ident(ref id) if id.name.declared_name() == "_" => (),
+        Pattern::Ident(ref id) => bindings.push(id.name.clone()),
+        Pattern::As(ref id, ref pat) => {
+            bindings.push(id.value.clone());
+            pattern_bindings(pat, bindings);
+        }
+        Pattern::Record {
+            ref fields,
+            ref implicit_import,
+            ..
+        } => {
+            for field in fields {
+                match field.value {
+                    Some(ref pat) => pattern_bindings(pat, bindings),
+                    None => bindings.push(field.name.value.clone()),
+                }
+            }
+            if let Some(ref implicit_import) = *implicit_import {
+                bindings.push(implicit_import.value.clone());
+            }
+        }
+        Pattern::Tuple {
+            elems: ref args, ..
+        }
+        | Pattern::Constructor(_, ref args) => {
+            for arg in args {
+                pattern_bindings(arg, bindings);
+            }
+        }
+        Pattern::Or(ref alternatives) => {
+            if let Some(alternative) = alternatives.first() {
+                pattern_bindings(alternative, bindings);
+            }
+        }
+        Pattern::Literal(_) | Pattern::Error => (),
+    }
+}
+
 pub fn extract_generics(args: &[RcType]) -> Vec<Generic<Symbol>> {
     args.iter()
         .map(|arg| match **arg {
@@ -40,10 +40,16 @@ pub enum TypeError<I, T> {
     KindError(KindCheckError<I, T>),
     /// Error were found when checking value recursion
     RecursionCheck(crate::recursion_check::Error),
+    /// A warning was found for a lint which is denied
+    Lint(crate::warning::Warning),
     /// Multiple types were declared with the same name in the same expression
     DuplicateTypeDefinition(I),
     /// A field was defined more than once in a record constructor or pattern match
     DuplicateField(I),
+    /// A variable is bound in some, but not all alternatives of an or-pattern
+    OrPatternBinding(I),
+    /// The or-patterns of a pattern expand to more alternatives than the compiler allows
+    TooManyOrPatternAlternatives(usize),
     /// Type is not a type which has any fields
     InvalidProjection(T),
     /// Expected to find a record with the following fields
@@ -84,6 +90,12 @@ impl<I, T> From<crate::recursion_check::Error> for TypeError<I, T> {
     }
 }
 
+impl<I, T> From<crate::warning::Warning> for TypeError<I, T> {
+    fn from(e: crate::warning::Warning) -> Self {
+        TypeError::Lint(e)
+    }
+}
+
 impl<I, T> fmt::Display for TypeError<I, T>
 where
     I: fmt::Display + AsRef<str> + Clone,
@@ -179,7 +191,10 @@ where
                 }
                 write!(f, "{}", errors.last().unwrap())
             }
-            PatternError { constructor_type, pattern_args } => {
+            PatternError {
+                constructor_type,
+                pattern_args,
+            } => {
                 write!(
                     f,
                     "Matching on constructor `{}` requires `{}` arguments but the pattern specifies `{}`",
@@ -190,12 +205,26 @@ where
             }
             KindError(err) => kindcheck::fmt_kind_error(err, f),
             RecursionCheck(err) => write!(f, "{}", err),
+            Lint(warning) => write!(f, "{}", warning),
             DuplicateTypeDefinition(id) => write!(
                 f,
                 "Type '{}' has been already been defined in this module",
                 id
             ),
             DuplicateField(id) => write!(f, "The record has more than one field named '{}'", id),
+            OrPatternBinding(id) => write!(
+                f,
+                "Variable `{}` must be bound in every alternative of the or-pattern",
+                id
+            ),
+            TooManyOrPatternAlternatives(count) => write!(
+                f,
+                "The or-patterns in this pattern can match in {} different ways but at most {} \
+                 are allowed. Split the pattern into multiple alternatives or match on the \
+                 fields in nested `match` expressions",
+                count,
+                crate::typecheck::MAX_OR_PATTERN_EXPANSION
+            ),
             InvalidProjection(typ) => write!(
                 f,
                 "Type '{}' is not a type which allows field accesses",
@@ -0,0 +1,223 @@
+//! Finds bindings which are never used and bindings which shadow another binding that is in scope.
+//!
+//! The expression must have been renamed (see `rename`) so that every binding has a unique symbol.
+use crate::base::{
+    ast::{self, Expr, Pattern, SpannedExpr, SpannedPattern, ValueBinding, Visitor},
+    fnv::FnvSet,
+    pos::{self, BytePos, Span},
+    scoped_map::ScopedMap,
+    symbol::Symbol,
+};
+
+use crate::warning::{SpannedWarning, Warning};
+
+#[derive(Clone, Copy, Debug)]
+enum BindingKind {
+    Variable,
+    /// A binding of the module (or a field of the module) returned by `import!`
+    Import,
+    /// A binding marked with `#[implicit]`
+    Implicit,
+}
+
+struct Checker<'a> {
+    /// All bindings in the order they were declared
+    bindings: Vec<(&'a Symbol, Span<BytePos>, BindingKind)>,
+    used: FnvSet<&'a Symbol>,
+    /// The names of the bindings that are currently in scope
+    scope: ScopedMap<&'a str, ()>,
+    warnings: Vec<SpannedWarning>,
+}
+
+pub fn check_expr(expr: &SpannedExpr<Symbol>) -> Vec<SpannedWarning> {
+    let mut checker = Checker {
+        bindings: Vec::new(),
+        used: FnvSet::default(),
+        scope: ScopedMap::new(),
+        warnings: Vec::new(),
+    };
+    checker.visit_expr(expr);
+
+    let Checker {
+        bindings,
+        used,
+        mut warnings,
+        ..
+    } = checker;
+    warnings.extend(
+        bindings
+            .into_iter()
+            .filter(|(symbol, _, _)| !used.contains(symbol))
+            .map(|(symbol, span, kind)| {
+                let symbol = symbol.clone();
+                pos::spanned(
+                    span,
+                    match kind {
+                        BindingKind::Variable => Warning::UnusedVariable(symbol),
+                        BindingKind::Import => Warning::UnusedImport(symbol),
+                        BindingKind::Implicit => Warning::UnusedImplicit(symbol),
+                    },
+                )
+            }),
+    );
+    warnings
+}
+
+fn binding_kind(bind: &ValueBinding<Symbol>) -> BindingKind {
+    fn is_import(expr: &SpannedExpr<Symbol>) -> bool {
+        match expr.value {
+            Expr::MacroExpansion {
+                ref replacement, ..
+            } => is_import(replacement),
+            // `import!` expands to the global symbol of the module
+            Expr::Ident(ref id) => id.name.is_global(),
+            _ => false,
+        }
+    }
+
+    if bind.metadata.get_attribute("implicit").is_some() {
+        BindingKind::Implicit
+    } else if is_import(&bind.expr) {
+        BindingKind::Import
+    } else {
+        BindingKind::Variable
+    }
+}
+
+impl<'a> Checker<'a> {
+    fn declare(&mut self, symbol: &'a Symbol, span: Span<BytePos>, kind: BindingKind) {
+        let name = symbol.declared_name();
+        if name.starts_with('_') {
+            return;
+        }
+        if self.scope.contains_key(name) {
+            self.warnings.push(pos::spanned(
+                span,
+                Warning::ShadowedVariable(symbol.clone()),
+            ));
+        }
+        self.scope.insert(name, ());
+        self.bindings.push((symbol, span, kind));
+    }
+
+    fn declare_pattern(&mut self, pattern: &'a SpannedPattern<Symbol>, kind: BindingKind) {
+        match pattern.value {
+            Pattern::Ident(ref id) => self.declare(&id.name, pattern.span, kind),
+            Pattern::As(ref id, ref pat) => {
+                self.declare(&id.value, id.span, kind);
+                self.declare_pattern(pat, kind);
+            }
+            Pattern::Record { ref fields, .. } => {
+                for field in fields {
+                    match field.value {
+                        Some(ref pat) => self.declare_pattern(pat, kind),
+                        None => self.declare(&field.name.value, field.name.span, kind),
+                    }
+                }
+            }
+            Pattern::Tuple { ref elems, .. } => {
+                for elem in elems {
+                    self.declare_pattern(elem, kind);
+                }
+            }
+            Pattern::Constructor(_, ref args) => {
+                for arg in args {
+                    self.declare_pattern(arg, kind);
+                }
+            }
+            // Every alternative binds the same symbols
+            Pattern::Or(ref alternatives) => {
+                if let Some(alternative) = alternatives.first() {
+                    self.declare_pattern(alternative, kind);
+                }
+            }
+            Pattern::Literal(_) | Pattern::Error => (),
+        }
+    }
+
+    fn visit_binding(&mut self, bind: &'a ValueBinding<Symbol>) {
+        self.scope.enter_scope();
+        for arg in &bind.args {
+            self.declare(&arg.name.value.name, arg.name.span, BindingKind::Variable);
+        }
+        self.visit_expr(&bind.expr);
+        self.scope.exit_scope();
+    }
+}
+
+impl<'a> Visitor<'a> for Checker<'a> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<Symbol>) {
+        match expr.value {
+            Expr::Ident(ref id) => {
+                self.used.insert(&id.name);
+            }
+            Expr::Infix { ref op, .. } => {
+                self.used.insert(&op.value.name);
+                ast::walk_expr(self, expr);
+            }
+            Expr::Record { ref exprs, .. } => {
+                for field in exprs {
+                    if field.value.is_none() {
+                        self.used.insert(&field.name.value);
+                    }
+                }
+                ast::walk_expr(self, expr);
+            }
+            Expr::LetBindings(ref bindings, ref body) => {
+                self.scope.enter_scope();
+                if bindings.is_recursive() {
+                    for bind in bindings {
+                        self.declare_pattern(&bind.name, binding_kind(bind));
+                    }
+                    for bind in bindings {
+                        self.visit_binding(bind);
+                    }
+                } else {
+                    for bind in bindings {
+                        self.visit_binding(bind);
+                    }
+                    for bind in bindings {
+                        self.declare_pattern(&bind.name, binding_kind(bind));
+                    }
+                }
+                self.visit_expr(body);
+                self.scope.exit_scope();
+            }
+            Expr::Lambda(ref lambda) => {
+                self.scope.enter_scope();
+                for arg in &lambda.args {
+                    self.declare(&arg.name.value.name, arg.name.span, BindingKind::Variable);
+                }
+                self.visit_expr(&lambda.body);
+                self.scope.exit_scope();
+            }
+            Expr::Match(ref scrutinee, ref alts) => {
+                self.visit_expr(scrutinee);
+                for alt in alts {
+                    self.scope.enter_scope();
+                    self.declare_pattern(&alt.pattern, BindingKind::Variable);
+                    if let Some(ref guard) = alt.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&alt.expr);
+                    self.scope.exit_scope();
+                }
+            }
+            Expr::Do(ref do_expr) => {
+                self.visit_expr(&do_expr.bound);
+                if let Some(ref flat_map_id) = do_expr.flat_map_id {
+                    self.visit_expr(flat_map_id);
+                }
+                self.scope.enter_scope();
+                if let Some(ref id) = do_expr.id {
+                    self.declare_pattern(id, BindingKind::Variable);
+                }
+                self.visit_expr(&do_expr.body);
+                self.scope.exit_scope();
+            }
+            _ => ast::walk_expr(self, expr),
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Warnings are problems which do not stop an expression from being compiled such as unused
+//! bindings or `match` expressions which do not cover every value.
+//!
+//! Every warning belongs to a `Lint` whose level decides if the warning is ignored, reported or
+//! turned into an error. The level can be changed for the warnings found inside a `let` or `type`
+//! binding with the `#[allow(..)]`, `#[warn(..)]` and `#[deny(..)]` attributes, which take a
+//! comma separated list of lint or lint group names. Attributes on an inner binding take
+//! precedence over the attributes on the bindings which contain it.
+//!
+//! ```gluon
+//! #[allow(unused)]
+//! let f x y = x
+//! f
+//! ```
+use std::fmt;
+
+use codespan_reporting::Diagnostic;
+
+use crate::base::{
+    ast::{self, Expr, SpannedExpr, Visitor},
+    error::AsDiagnostic,
+    metadata::Metadata,
+    pos::{self, BytePos, Span, Spanned},
+    symbol::Symbol,
+    types::{ArcType, TypeEnv},
+};
+
+use crate::{exhaustiveness, unused};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedImports,
+    UnusedImplicits,
+    Shadowing,
+    NonExhaustivePatterns,
+    UnreachablePatterns,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnusedVariables,
+        Lint::UnusedImports,
+        Lint::UnusedImplicits,
+        Lint::Shadowing,
+        Lint::NonExhaustivePatterns,
+        Lint::UnreachablePatterns,
+    ];
+
+    /// The lints in the `unused` group
+    pub const UNUSED: &'static [Lint] = &[
+        Lint::UnusedVariables,
+        Lint::UnusedImports,
+        Lint::UnusedImplicits,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedImports => "unused_imports",
+            Lint::UnusedImplicits => "unused_implicits",
+            Lint::Shadowing => "shadowing",
+            Lint::NonExhaustivePatterns => "non_exhaustive_patterns",
+            Lint::UnreachablePatterns => "unreachable_patterns",
+        }
+    }
+
+    /// Returns the lints which `name` refers to. `name` is either the name of a lint or one of the
+    /// groups `unused` and `warnings` (every lint).
+    pub fn from_name(name: &str) -> Option<&'static [Lint]> {
+        Some(match name {
+            "warnings" => Lint::ALL,
+            "unused" => Lint::UNUSED,
+            _ => {
+                let i = Lint::ALL.iter().position(|lint| lint.name() == name)?;
+                &Lint::ALL[i..i + 1]
+            }
+        })
+    }
+
+    pub fn default_level(self) -> Level {
+        match self {
+            Lint::Shadowing => Level::Allow,
+            _ => Level::Warn,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Level {
+    /// The warnings are ignored
+    Allow,
+    /// The warnings are reported but compilation continues
+    Warn,
+    /// The warnings are reported as errors
+    Deny,
+}
+
+impl Level {
+    fn from_attribute(name: &str) -> Option<Level> {
+        Some(match name {
+            "allow" => Level::Allow,
+            "warn" => Level::Warn,
+            "deny" => Level::Deny,
+            _ => return None,
+        })
+    }
+}
+
+/// The level of each lint outside of any `allow`, `warn` or `deny` attribute
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LintLevels {
+    levels: [Level; 6],
+}
+
+impl Default for LintLevels {
+    fn default() -> Self {
+        let mut levels = [Level::Warn; 6];
+        for &lint in Lint::ALL {
+            levels[lint as usize] = lint.default_level();
+        }
+        LintLevels { levels }
+    }
+}
+
+impl LintLevels {
+    pub fn get(&self, lint: Lint) -> Level {
+        self.levels[lint as usize]
+    }
+
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels[lint as usize] = level;
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Warning {
+    UnusedVariable(Symbol),
+    UnusedImport(Symbol),
+    UnusedImplicit(Symbol),
+    ShadowedVariable(Symbol),
+    Pattern(exhaustiveness::Error),
+}
+
+impl Warning {
+    pub fn lint(&self) -> Lint {
+        match self {
+            Warning::UnusedVariable(_) => Lint::UnusedVariables,
+            Warning::UnusedImport(_) => Lint::UnusedImports,
+            Warning::UnusedImplicit(_) => Lint::UnusedImplicits,
+            Warning::ShadowedVariable(_) => Lint::Shadowing,
+            Warning::Pattern(exhaustiveness::Error::NonExhaustive { .. }) => {
+                Lint::NonExhaustivePatterns
+            }
+            Warning::Pattern(exhaustiveness::Error::UnreachableAlternative) => {
+                Lint::UnreachablePatterns
+            }
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::UnusedVariable(id) => write!(f, "Unused variable `{}`", id.declared_name()),
+            Warning::UnusedImport(id) => write!(f, "Unused import `{}`", id.declared_name()),
+            Warning::UnusedImplicit(id) => {
+                write!(f, "Unused implicit instance `{}`", id.declared_name())
+            }
+            Warning::ShadowedVariable(id) => write!(
+                f,
+                "`{}` shadows a variable with the same name",
+                id.declared_name()
+            ),
+            Warning::Pattern(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl AsDiagnostic for Warning {
+    fn as_diagnostic(&self) -> Diagnostic {
+        Diagnostic::new_warning(self.to_string())
+    }
+}
+
+pub type SpannedWarning = Spanned<Warning, BytePos>;
+
+/// The warnings found in an expression which were not allowed
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    pub warnings: Vec<SpannedWarning>,
+    /// Warnings of lints which are denied
+    pub errors: Vec<SpannedWarning>,
+}
+
+/// Runs every lint on `expr`. `expr` must have been renamed and typechecked successfully.
+pub fn check_expr(
+    env: &dyn TypeEnv<Type = ArcType>,
+    expr: &SpannedExpr<Symbol>,
+    levels: &LintLevels,
+) -> Report {
+    let mut found = unused::check_expr(expr);
+    if let Err(errors) = exhaustiveness::check_expr(env, expr) {
+        found.extend(
+            errors
+                .into_iter()
+                .map(|err| pos::spanned(err.span, Warning::Pattern(err.value))),
+        );
+    }
+    found.sort_by_key(|warning| warning.span.start());
+
+    let mut scopes = LevelScopes { scopes: Vec::new() };
+    scopes.visit_expr(expr);
+
+    let mut report = Report::default();
+    for warning in found {
+        match scopes.level(levels, warning.span, warning.value.lint()) {
+            Level::Allow => (),
+            Level::Warn => report.warnings.push(warning),
+            Level::Deny => report.errors.push(warning),
+        }
+    }
+    report
+}
+
+/// The lint levels set by the attributes of a binding
+struct Scope {
+    span: Span<BytePos>,
+    levels: Vec<(Lint, Level)>,
+}
+
+struct LevelScopes {
+    /// Scopes are added in the order that the bindings are visited so a scope is always added
+    /// after the scopes which contain it
+    scopes: Vec<Scope>,
+}
+
+impl LevelScopes {
+    fn add(&mut self, span: Span<BytePos>, metadata: &Metadata) {
+        let levels: Vec<_> = metadata
+            .attributes()
+            .filter_map(|attribute| {
+                Some((
+                    Level::from_attribute(&attribute.name)?,
+                    attribute.arguments.as_ref()?,
+                ))
+            })
+            .flat_map(|(level, arguments)| {
+                arguments
+                    .split(',')
+                    .filter_map(|name| Lint::from_name(name.trim()))
+                    .flatten()
+                    .map(move |&lint| (lint, level))
+            })
+            .collect();
+        if !levels.is_empty() {
+            self.scopes.push(Scope { span, levels });
+        }
+    }
+
+    fn level(&self, levels: &LintLevels, span: Span<BytePos>, lint: Lint) -> Level {
+        self.scopes
+            .iter()
+            .rev()
+            .filter(|scope| scope.span.contains(span))
+            .find_map(|scope| {
+                scope
+                    .levels
+                    .iter()
+                    .rev()
+                    .find(|(l, _)| *l == lint)
+                    .map(|&(_, level)| level)
+            })
+            .unwrap_or_else(|| levels.get(lint))
+    }
+}
+
+impl<'a> Visitor<'a> for LevelScopes {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, expr: &'a SpannedExpr<Symbol>) {
+        match expr.value {
+            Expr::LetBindings(ref bindings, _) => {
+                for bind in bindings {
+                    self.add(bind.span(), &bind.metadata);
+                }
+            }
+            Expr::TypeBindings(ref bindings, _) => {
+                for bind in bindings {
+                    // The code generated by `#[derive(..)]` is spanned by the type binding and
+                    // should not produce any warnings
+                    if bind.metadata.get_attribute("derive").is_some() {
+                        self.scopes.push(Scope {
+                            span: bind.span(),
+                            levels: Lint::ALL.iter().map(|&lint| (lint, Level::Allow)).collect(),
+                        });
+                    }
+                    self.add(bind.span(), &bind.metadata);
+                }
+            }
+            _ => (),
+        }
+        ast::walk_expr(self, expr);
+    }
+}
@@ -0,0 +1,285 @@
+#[macro_use]
+extern crate collect_mac;
+extern crate env_logger;
+#[macro_use]
+extern crate quick_error;
+
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_parser as parser;
+
+#[macro_use]
+mod support;
+
+use crate::check::exhaustiveness::{self, Error};
+
+fn check_patterns(text: &str) -> Vec<Error> {
+    let _ = env_logger::try_init();
+
+    let (expr, result) = support::typecheck_expr(text);
+    result.unwrap_or_else(|err| panic!("{}", err));
+
+    match exhaustiveness::check_expr(&support::MockEnv::new(), &expr) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|err| err.value).collect(),
+    }
+}
+
+fn non_exhaustive(missing: &[&str]) -> Error {
+    Error::NonExhaustive {
+        missing: missing.iter().map(|s| s.to_string()).collect(),
+    }
+}
+
+#[test]
+fn exhaustive_variant() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some x -> x
+| None -> 0
+";
+    assert_eq!(check_patterns(text), vec![]);
+}
+
+#[test]
+fn missing_variant() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some x -> x
+";
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["None"])]);
+}
+
+#[test]
+fn missing_nested_variant() {
+    let text = r"
+type Option a = | None | Some a
+type List a = | Nil | Cons a (List a)
+match Some Nil with
+| Some (Cons x Nil) -> x
+| Some Nil -> 0
+| None -> 0
+";
+    assert_eq!(
+        check_patterns(text),
+        vec![non_exhaustive(&["Some (Cons _ (Cons _ _))"])]
+    );
+}
+
+#[test]
+fn missing_tuple_elements() {
+    let text = r"
+type Option a = | None | Some a
+match (Some 1, Some 2) with
+| (Some x, _) -> x
+| (None, Some y) -> y
+";
+    assert_eq!(
+        check_patterns(text),
+        vec![non_exhaustive(&["(None, None)"])]
+    );
+}
+
+#[test]
+fn missing_record_field() {
+    let text = r"
+type Option a = | None | Some a
+match { x = Some 1, y = 2 } with
+| { x = Some x, y } -> x
+";
+    assert_eq!(
+        check_patterns(text),
+        vec![non_exhaustive(&["{ x = None }"])]
+    );
+}
+
+#[test]
+fn record_fields_in_different_order() {
+    let text = r"
+type Option a = | None | Some a
+match { x = Some 1, y = None } with
+| { y = Some y, x = Some x } -> x
+| { x = None } -> 0
+| { y = None } -> 1
+";
+    assert_eq!(check_patterns(text), vec![]);
+}
+
+#[test]
+fn literals_require_a_wildcard() {
+    let text = r#"
+match "a" with
+| "a" -> 1
+| "b" -> 2
+"#;
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["_"])]);
+
+    let text = r#"
+match "a" with
+| "a" -> 1
+| _ -> 2
+"#;
+    assert_eq!(check_patterns(text), vec![]);
+}
+
+#[test]
+fn as_pattern_matches_like_the_inner_pattern() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| x @ (Some _) -> x
+";
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["None"])]);
+}
+
+#[test]
+fn several_missing_variants() {
+    let text = r"
+type Color = | Red | Green | Blue | Alpha Int
+match Red with
+| Green -> 1
+";
+    assert_eq!(
+        check_patterns(text),
+        vec![non_exhaustive(&["Red", "Blue", "Alpha _"])]
+    );
+}
+
+#[test]
+fn unreachable_after_wildcard() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| _ -> 0
+| Some x -> x
+";
+    assert_eq!(check_patterns(text), vec![Error::UnreachableAlternative]);
+}
+
+#[test]
+fn unreachable_when_covered_by_earlier_alternatives() {
+    let text = r"
+type Option a = | None | Some a
+match (Some 1, 2) with
+| (Some x, _) -> x
+| (None, y) -> y
+| (Some 1, 2) -> 3
+";
+    assert_eq!(check_patterns(text), vec![Error::UnreachableAlternative]);
+}
+
+#[test]
+fn duplicate_literal_is_unreachable() {
+    let text = r"
+match 1 with
+| 1 -> 1
+| 1 -> 2
+| _ -> 3
+";
+    assert_eq!(check_patterns(text), vec![Error::UnreachableAlternative]);
+}
+
+#[test]
+fn guarded_alternative_does_not_cover_values() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some x if x #Int== 1 -> x
+| None -> 0
+";
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["Some _"])]);
+
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some x if x #Int== 1 -> x
+| Some x -> x #Int+ 1
+| None -> 0
+";
+    assert_eq!(check_patterns(text), vec![]);
+}
+
+#[test]
+fn unreachable_guarded_alternative() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| _ -> 0
+| Some x if x #Int== 1 -> x
+";
+    assert_eq!(check_patterns(text), vec![Error::UnreachableAlternative]);
+}
+
+#[test]
+fn or_pattern_covers_every_alternative() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some 1 | None -> 0
+| Some 2 | Some 3 -> 1
+";
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["Some _"])]);
+
+    let text = r"
+type Option a = | None | Some a
+match (Some 1, None) with
+| (Some _ | None, None) -> 0
+| (_, Some _) -> 1
+";
+    assert_eq!(check_patterns(text), vec![]);
+}
+
+#[test]
+fn unreachable_or_pattern() {
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some _ -> 0
+| Some 1 | None -> 1
+| None -> 2
+";
+    assert_eq!(check_patterns(text), vec![Error::UnreachableAlternative]);
+}
+
+#[test]
+fn nested_match_is_checked() {
+    let text = r"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some y ->
+        match y with
+        | Some z -> z
+    | None -> 0
+f
+";
+    assert_eq!(check_patterns(text), vec![non_exhaustive(&["None"])]);
+}
+
+#[test]
+fn error_message() {
+    assert_eq!(
+        non_exhaustive(&["None", "Some (Cons _ _)"]).to_string(),
+        "Non-exhaustive patterns, `None` and `Some (Cons _ _)` are not covered"
+    );
+    assert_eq!(
+        non_exhaustive(&["None"]).to_string(),
+        "Non-exhaustive patterns, `None` is not covered"
+    );
+}
+
+#[test]
+fn open_variant_is_never_complete() {
+    let text = r"
+type Eff r = | Get | Put Int .. r
+let f x : Eff r -> Int =
+    match x with
+    | Get -> 0
+    | Put y -> y
+    | rest -> 1
+f
+";
+    assert_eq!(check_patterns(text), vec![]);
+}
@@ -45,6 +45,66 @@ match () with
     assert_unify_err!(result, TypeMismatch(..));
 }
 
+#[test]
+fn match_guard_must_be_bool() {
+    let _ = env_logger::try_init();
+    let text = r#"
+match 1 with
+| x if x -> 1
+| _ -> 2
+"#;
+    let result = support::typecheck(text);
+
+    assert_err!(result, Unification(..));
+}
+
+#[test]
+fn or_pattern_must_bind_the_same_variables() {
+    let _ = env_logger::try_init();
+    let text = r#"
+type Test = | A Int | B Int | C
+match C with
+| A x | C -> 1
+| B y | A x -> 2
+| _ -> 3
+"#;
+    let result = support::typecheck(text);
+
+    assert_err!(
+        result,
+        OrPatternBinding(..),
+        OrPatternBinding(..),
+        OrPatternBinding(..)
+    );
+}
+
+#[test]
+fn or_pattern_variables_must_have_the_same_type() {
+    let _ = env_logger::try_init();
+    let text = r#"
+type Test = | A Int | B String
+match A 1 with
+| A x | B x -> 1
+"#;
+    let result = support::typecheck(text);
+
+    assert_unify_err!(result, TypeMismatch(..));
+}
+
+#[test]
+fn or_pattern_expansion_is_limited() {
+    let _ = env_logger::try_init();
+    let text = r#"
+type Test = | A | B
+let x = A
+match (x, x, x, x, x, x, x, x, x) with
+| (A | B, A | B, A | B, A | B, A | B, A | B, A | B, A | B, A | B) -> 1
+"#;
+    let result = support::typecheck(text);
+
+    assert_err!(result, TooManyOrPatternAlternatives(512));
+}
+
 #[test]
 fn match_different_alt_types_expected() {
     let _ = env_logger::try_init();
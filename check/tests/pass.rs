@@ -295,6 +295,39 @@ in match Some 1 with
     assert_req!(result, expected);
 }
 
+#[test]
+fn case_guard() {
+    let _ = env_logger::try_init();
+
+    let text = r"
+type Option a = | None | Some a
+match Some 1 with
+| Some x if x #Int== 0 -> x
+| Some x -> x #Int+ 1
+| None -> 2
+";
+    let result = support::typecheck(text);
+    let expected = Ok(typ("Int"));
+
+    assert_req!(result, expected);
+}
+
+#[test]
+fn case_or_pattern() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+type Test = | A Int String | B String Int | C
+match C with
+| A x y | B y x -> x
+| C -> 0
+"#;
+    let result = support::typecheck(text);
+    let expected = Ok(typ("Int"));
+
+    assert_req!(result, expected);
+}
+
 #[test]
 fn real_type() {
     let _ = env_logger::try_init();
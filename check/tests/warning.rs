@@ -0,0 +1,230 @@
+#[macro_use]
+extern crate collect_mac;
+extern crate env_logger;
+#[macro_use]
+extern crate quick_error;
+
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_parser as parser;
+
+#[macro_use]
+mod support;
+
+use crate::check::warning::{self, Level, Lint, LintLevels, Report, SpannedWarning};
+
+fn check_warnings_with(text: &str, levels: &LintLevels) -> Report {
+    let _ = env_logger::try_init();
+
+    let (expr, result) = support::typecheck_expr(text);
+    result.unwrap_or_else(|err| panic!("{}", err));
+
+    warning::check_expr(&support::MockEnv::new(), &expr, levels)
+}
+
+fn messages(warnings: &[SpannedWarning]) -> Vec<String> {
+    warnings
+        .iter()
+        .map(|warning| warning.value.to_string())
+        .collect()
+}
+
+fn check_warnings(text: &str) -> Vec<String> {
+    let report = check_warnings_with(text, &LintLevels::default());
+    assert_eq!(messages(&report.errors), Vec::<String>::new());
+    messages(&report.warnings)
+}
+
+#[test]
+fn unused_variable() {
+    let text = r"
+let x = 1
+let y = 2
+x
+";
+    assert_eq!(check_warnings(text), vec!["Unused variable `y`"]);
+}
+
+#[test]
+fn unused_function_argument() {
+    let text = r"
+let f x y = x
+f 1 2
+";
+    assert_eq!(check_warnings(text), vec!["Unused variable `y`"]);
+}
+
+#[test]
+fn variables_used_through_punning_and_operators() {
+    let text = r"
+let x = 1
+#[infix(left, 6)]
+let (+++) l r = l #Int+ r
+let y = x +++ 2
+{ y }
+";
+    assert_eq!(check_warnings(text), Vec::<String>::new());
+}
+
+#[test]
+fn pattern_bindings() {
+    let text = r"
+type Option a = | None | Some a
+match { a = Some 1, b = 2 } with
+| { a = Some x, b } -> x
+| { a = None, b = y } -> 0
+";
+    assert_eq!(
+        check_warnings(text),
+        vec!["Unused variable `b`", "Unused variable `y`"]
+    );
+}
+
+#[test]
+fn variables_used_in_guard() {
+    let text = r"
+match 1 with
+| x if x #Int== 1 -> 2
+| y -> 3
+";
+    assert_eq!(check_warnings(text), vec!["Unused variable `y`"]);
+}
+
+#[test]
+fn or_pattern_bindings() {
+    let text = r"
+type Test = | A Int | B Int
+match A 1 with
+| A x | B x -> 1
+";
+    assert_eq!(check_warnings(text), vec!["Unused variable `x`"]);
+}
+
+#[test]
+fn underscore_prefix_is_not_reported() {
+    let text = r"
+let _x = 1
+let f _y = 2
+f 3
+";
+    assert_eq!(check_warnings(text), Vec::<String>::new());
+}
+
+#[test]
+fn unused_implicit_instance() {
+    let text = r"
+#[implicit]
+let i = 123
+#[implicit]
+let j = 1.0
+let f ?x y : [Int] -> Int -> Int = x
+f 2
+";
+    assert_eq!(
+        check_warnings(text),
+        vec!["Unused implicit instance `j`", "Unused variable `y`"]
+    );
+}
+
+#[test]
+fn shadowing_is_allowed_by_default() {
+    let text = r"
+let x = 1
+let x = x #Int+ 1
+x
+";
+    assert_eq!(check_warnings(text), Vec::<String>::new());
+
+    let text = r"
+let x = 1
+#[warn(shadowing)]
+let x = x #Int+ 1
+x
+";
+    assert_eq!(
+        check_warnings(text),
+        vec!["`x` shadows a variable with the same name"]
+    );
+}
+
+#[test]
+fn allow_attribute() {
+    let text = r"
+#[allow(unused)]
+let f x =
+    let y = 1
+    x
+f 1
+";
+    assert_eq!(check_warnings(text), Vec::<String>::new());
+
+    let text = r"
+#[allow(unused_imports, unused_variables)]
+let f x y = x
+f 1
+";
+    assert_eq!(check_warnings(text), Vec::<String>::new());
+}
+
+#[test]
+fn innermost_attribute_takes_precedence() {
+    let text = r"
+#[allow(unused)]
+let f x =
+    #[warn(unused_variables)]
+    let g y = x
+    g
+f 1
+";
+    assert_eq!(check_warnings(text), vec!["Unused variable `y`"]);
+}
+
+#[test]
+fn deny_attribute() {
+    let text = r"
+#[deny(unused)]
+let f x y = x
+let z = 1
+f 1
+";
+    let report = check_warnings_with(text, &LintLevels::default());
+    assert_eq!(messages(&report.errors), vec!["Unused variable `y`"]);
+    assert_eq!(messages(&report.warnings), vec!["Unused variable `z`"]);
+}
+
+#[test]
+fn default_levels() {
+    let text = r"
+type Option a = | None | Some a
+let z = 1
+match Some 1 with
+| Some x -> x
+";
+    let mut levels = LintLevels::default();
+    levels.set(Lint::NonExhaustivePatterns, Level::Deny);
+    levels.set(Lint::UnusedVariables, Level::Allow);
+    let report = check_warnings_with(text, &levels);
+    assert_eq!(
+        messages(&report.errors),
+        vec!["Non-exhaustive patterns, `None` is not covered"]
+    );
+    assert_eq!(messages(&report.warnings), Vec::<String>::new());
+}
+
+#[test]
+fn lint_names() {
+    for &lint in Lint::ALL {
+        assert_eq!(Lint::from_name(lint.name()), Some(&[lint][..]));
+    }
+    assert_eq!(
+        Lint::from_name("unused"),
+        Some(
+            &[
+                Lint::UnusedVariables,
+                Lint::UnusedImports,
+                Lint::UnusedImplicits
+            ][..]
+        )
+    );
+    assert_eq!(Lint::from_name("unknown"), None);
+}
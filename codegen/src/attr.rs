@@ -34,6 +34,7 @@ pub struct Container {
     pub newtype: bool,
     pub skip: bool,
     pub clone: bool,
+    pub finalize: Option<syn::Path>,
 }
 
 impl Container {
@@ -45,6 +46,7 @@ impl Container {
         let mut newtype = false;
         let mut skip = false;
         let mut clone = false;
+        let mut finalize = None;
 
         for meta_items in item.attrs.iter().filter_map(get_gluon_meta_items) {
             for meta_item in meta_items {
@@ -77,6 +79,13 @@ impl Container {
                         clone = true;
                     }
 
+                    // Parse `#[gluon_userdata(finalize = "path::to::function")]`
+                    Meta(NameValue(ref m)) if m.path.is_ident("finalize") => {
+                        if let Ok(path) = parse_lit_into_path(&m.path, &m.lit) {
+                            finalize = Some(path);
+                        }
+                    }
+
                     _ => panic!("unexpected gluon container attribute: {:?}", meta_item),
                 }
             }
@@ -88,6 +97,7 @@ impl Container {
             newtype,
             skip,
             clone,
+            finalize,
         }
     }
 }
@@ -149,6 +149,37 @@
 //! # fn main() {}
 //! ```
 //!
+//! A finalizer, which runs on the thread that owns the value once the garbage collector has found
+//! the value to be unreachable, is added with the `#[gluon_userdata(finalize = "<function>")]`
+//! attribute. The function is called with the value and the thread (see `Userdata::finalize`).
+//! Fields containing a `gluon::vm::weak::WeakValue` are traced as weak references by the derived
+//! `Trace` implementation so they do not keep the value they refer to alive. Since a `WeakValue`
+//! is only cleared when it is traced, `WeakValue::new` and `WeakValue::upgrade` are `unsafe` and
+//! the reference must stay inside a traced value such as this one:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate gluon_codegen;
+//! extern crate gluon;
+//!
+//! use std::sync::Mutex;
+//!
+//! use gluon::{vm::weak::WeakValue, Thread};
+//!
+//! #[derive(Userdata, Trace, Debug)]
+//! #[gluon_userdata(finalize = "Connection::close")]
+//! struct Connection {
+//!     callbacks: Mutex<Vec<WeakValue>>,
+//! }
+//!
+//! impl Connection {
+//!     fn close(&self, _thread: &Thread) {
+//!         println!("Closing the connection");
+//!     }
+//! }
+//! # fn main() {}
+//! ```
+//!
 
 #![recursion_limit = "128"]
 
@@ -37,16 +37,19 @@ fn gen_impl(container: &Container, ident: Ident, generics: Generics) -> TokenStr
         CrateName::Some(ref ident) => quote! {
             use #ident::api as _gluon_api;
             use #ident::gc as _gluon_gc;
+            use #ident::thread as _gluon_thread;
             use #ident::Result as _gluon_Result;
         },
         CrateName::GluonVm => quote! {
             use api as _gluon_api;
             use thread as _gluon_gc;
+            use thread as _gluon_thread;
             use self::Result as _gluon_Result;
         },
         CrateName::None => quote! {
             use gluon::vm::api as _gluon_api;
             use gluon::vm::gc as _gluon_gc;
+            use gluon::vm::thread as _gluon_thread;
             use gluon::vm::Result as _gluon_Result;
         },
     };
@@ -67,6 +70,19 @@ fn gen_impl(container: &Container, ident: Ident, generics: Generics) -> TokenStr
         quote! {}
     };
 
+    let finalize = match container.finalize {
+        Some(ref finalize) => quote! {
+            fn has_finalizer(&self) -> bool {
+                true
+            }
+
+            fn finalize(&self, thread: &_gluon_thread::Thread) {
+                #finalize(self, thread)
+            }
+        },
+        None => quote! {},
+    };
+
     quote! {
         #[allow(non_upper_case_globals)]
         const #dummy_const: () = {
@@ -78,6 +94,7 @@ fn gen_impl(container: &Container, ident: Ident, generics: Generics) -> TokenStr
             #where_clause #(#trait_bounds,)* #(#lifetime_bounds),*
             {
                 #deep_clone
+                #finalize
             }
         };
     }
@@ -6,12 +6,17 @@ extern crate gluon_vm;
 
 mod init;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use gluon::{
     import,
-    vm::{self, ExternModule},
-    Thread, ThreadExt,
+    vm::{
+        self,
+        api::{generic::A, Generic, Hole, OpaqueValue, ValueRef},
+        weak::WeakValue,
+        ExternModule,
+    },
+    RootedThread, Thread, ThreadExt,
 };
 
 use init::new_vm;
@@ -74,3 +79,130 @@ fn userdata() {
 #[derive(Userdata, Trace, Debug, VmType)]
 #[gluon(vm_type = "Empty")]
 struct Empty;
+
+// The ids of the closed connections along with the thread which closed them
+static CLOSED: Mutex<Vec<(u64, usize)>> = Mutex::new(Vec::new());
+
+#[derive(Userdata, Trace, Debug, VmType)]
+#[gluon(vm_type = "Connection")]
+#[gluon_userdata(finalize = "Connection::close")]
+struct Connection {
+    id: u64,
+    callbacks: Mutex<Vec<WeakValue>>,
+}
+
+impl Connection {
+    fn close(&self, thread: &Thread) {
+        CLOSED
+            .lock()
+            .unwrap()
+            .push((self.id, thread as *const Thread as usize));
+    }
+
+    fn live_callbacks(&self, thread: &Thread) -> usize {
+        self.callbacks
+            .lock()
+            .unwrap()
+            .iter()
+            // SAFETY The callbacks are only stored in `self`, which is traced
+            .filter(|callback| unsafe { callback.upgrade(thread) }.is_some())
+            .count()
+    }
+}
+
+fn connect(id: u64) -> Connection {
+    Connection {
+        id,
+        callbacks: Mutex::new(Vec::new()),
+    }
+}
+
+fn on_close(connection: &Connection, callback: Generic<A>) {
+    connection
+        .callbacks
+        .lock()
+        .unwrap()
+        // SAFETY The callbacks are only stored in `connection`, which is traced
+        .push(unsafe { WeakValue::new(callback.get_variant()) });
+}
+
+fn load_connection(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<Connection>("Connection", &[])?;
+
+    let module = record! {
+        connect => primitive!(1, connect),
+        on_close => primitive!(2, on_close),
+    };
+
+    ExternModule::new(vm, module)
+}
+
+#[test]
+fn finalizer_and_weak_fields() {
+    let vm = new_vm();
+
+    import::add_extern_module(&vm, "connection", load_connection);
+
+    let script = r#"
+        let { connect, on_close } = import! connection
+
+        let kept = connect 1
+        let _ = connect 2
+        let callback = \x -> x
+        on_close kept callback
+        on_close kept (\x -> x + 1)
+        { kept, callback }
+    "#;
+
+    let (value, _) = vm
+        .run_expr::<OpaqueValue<RootedThread, Hole>>("test", script)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    vm.collect();
+    vm.collect();
+
+    let thread = &*vm as *const Thread as usize;
+    assert_eq!(*CLOSED.lock().unwrap(), [(2, thread)]);
+
+    let connection = match value.get_ref() {
+        ValueRef::Data(data) => match data.get_variant(0).map(|field| field.as_ref()) {
+            Some(ValueRef::Userdata(connection)) => connection.downcast_ref::<Connection>(),
+            _ => None,
+        },
+        _ => None,
+    }
+    .expect("Connection");
+    assert_eq!(connection.id, 1);
+    assert_eq!(connection.live_callbacks(&vm), 1);
+}
+
+#[test]
+fn weak_field_is_cleared_when_its_value_is_collected() {
+    let vm = new_vm();
+
+    import::add_extern_module(&vm, "connection", load_connection);
+
+    let script = r#"
+        let { connect, on_close } = import! connection
+
+        let connection = connect 3
+        on_close connection (\x -> x + 1)
+        connection
+    "#;
+
+    let (value, _) = vm
+        .run_expr::<OpaqueValue<RootedThread, Hole>>("test", script)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let connection = match value.get_ref() {
+        ValueRef::Userdata(connection) => connection.downcast_ref::<Connection>(),
+        _ => None,
+    }
+    .expect("Connection");
+    assert_eq!(connection.live_callbacks(&vm), 1);
+
+    // Only the connection is still rooted so the callback is freed
+    vm.collect();
+    assert_eq!(connection.callbacks.lock().unwrap().len(), 1);
+    assert_eq!(connection.live_callbacks(&vm), 0);
+}
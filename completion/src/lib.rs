@@ -8,7 +8,10 @@ extern crate walkdir;
 
 extern crate gluon_base as base;
 
-use std::{borrow::Cow, cmp::Ordering, iter::once, path::PathBuf, sync::Arc};
+use std::{
+    borrow::Cow, cell::Cell, cmp::Ordering, error::Error as StdError, fmt, iter::once,
+    path::PathBuf, sync::Arc,
+};
 
 use codespan::ByteOffset;
 
@@ -18,8 +21,8 @@ use itertools::Itertools;
 
 use crate::base::{
     ast::{
-        walk_expr, walk_pattern, AstType, Expr, Pattern, PatternField, SpannedExpr, SpannedIdent,
-        SpannedPattern, Typed, TypedIdent, Visitor,
+        walk_ast_type, walk_expr, walk_pattern, AstType, Expr, Pattern, PatternField,
+        SpannedAstType, SpannedExpr, SpannedIdent, SpannedPattern, Typed, TypedIdent, Visitor,
     },
     filename_to_module,
     fnv::{FnvMap, FnvSet},
@@ -194,6 +197,12 @@ impl<E: TypeEnv<Type = ArcType>> OnFound for Suggest<E> {
                     self.on_pattern(arg);
                 }
             }
+            // Every alternative binds the same variables
+            Pattern::Or(ref alternatives) => {
+                if let Some(alternative) = alternatives.first() {
+                    self.on_pattern(alternative);
+                }
+            }
             Pattern::Literal(_) | Pattern::Error => (),
         }
     }
@@ -487,6 +496,13 @@ where
                 let (_, field) = self.select_spanned(elems, |elem| elem.span);
                 self.visit_pattern(field.unwrap());
             }
+            Pattern::Or(ref alternatives) => {
+                let (_, alternative) = self.select_spanned(alternatives, |alt| alt.span);
+                match alternative {
+                    Some(alternative) => self.visit_pattern(alternative),
+                    None => self.found = MatchState::Empty,
+                }
+            }
             Pattern::Ident(_) | Pattern::Literal(_) | Pattern::Error => {
                 self.found = if current.span.containment(self.pos) == Ordering::Equal {
                     MatchState::Found(Match::Pattern(current))
@@ -540,8 +556,10 @@ where
                     }
                     Err(alt) => {
                         self.on_found.on_pattern(&alt.pattern);
-                        let iter = [Ok(&alt.pattern), Err(&alt.expr)];
-                        let (_, sel) = self.select_spanned(iter.iter().cloned(), |x| match *x {
+                        let iter = once(Ok(&alt.pattern))
+                            .chain(alt.guard.as_ref().map(Err))
+                            .chain(once(Err(&alt.expr)));
+                        let (_, sel) = self.select_spanned(iter, |x| match *x {
                             Ok(p) => p.span,
                             Err(e) => e.span,
                         });
@@ -979,6 +997,1130 @@ pub fn symbol(
     completion(extract, source_span, expr, pos)
 }
 
+/// Gives `definition_at` and `references` access to the typechecked expressions of other modules
+/// so that definitions can be followed through `import!`
+pub trait ModuleExprs {
+    /// Returns the typechecked expression of `module` (without the leading `@`)
+    fn module_expr(&self, module: &str) -> Option<Arc<SpannedExpr<Symbol>>>;
+
+    /// Returns the modules which `references` searches for uses of symbols from other modules
+    fn module_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl ModuleExprs for () {
+    fn module_expr(&self, _module: &str) -> Option<Arc<SpannedExpr<Symbol>>> {
+        None
+    }
+}
+
+impl<M> ModuleExprs for &M
+where
+    M: ?Sized + ModuleExprs,
+{
+    fn module_expr(&self, module: &str) -> Option<Arc<SpannedExpr<Symbol>>> {
+        (**self).module_expr(module)
+    }
+
+    fn module_names(&self) -> Vec<String> {
+        (**self).module_names()
+    }
+}
+
+/// The location where a symbol is bound
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    /// The module which contains the definition, `None` if it is the expression that was
+    /// searched
+    pub module: Option<String>,
+    pub span: Span<BytePos>,
+}
+
+/// The expression (and the fields projected from it) that a binding refers to
+#[derive(Clone, Debug)]
+struct Bound<'a> {
+    expr: Option<&'a SpannedExpr<Symbol>>,
+    path: Vec<&'a str>,
+}
+
+impl<'a> Bound<'a> {
+    fn unknown() -> Self {
+        Bound {
+            expr: None,
+            path: Vec::new(),
+        }
+    }
+
+    fn expr(expr: &'a SpannedExpr<Symbol>) -> Self {
+        Bound {
+            expr: Some(expr),
+            path: Vec::new(),
+        }
+    }
+
+    fn field(&self, name: &'a str) -> Self {
+        let mut path = self.path.clone();
+        path.push(name);
+        Bound {
+            expr: self.expr,
+            path,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Binding<'a> {
+    span: Span<BytePos>,
+    bound: Bound<'a>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Namespace {
+    Value,
+    Type,
+}
+
+/// Every value and type binding in an expression
+#[derive(Default)]
+struct Bindings<'a> {
+    values: FnvMap<&'a SymbolRef, Binding<'a>>,
+    types: FnvMap<&'a SymbolRef, Binding<'a>>,
+}
+
+impl<'a> Bindings<'a> {
+    fn new(expr: &'a SpannedExpr<Symbol>) -> Self {
+        let mut bindings = Bindings::default();
+        bindings.visit_expr(expr);
+        bindings
+    }
+
+    fn get(&self, namespace: Namespace, symbol: &SymbolRef) -> Option<&Binding<'a>> {
+        match namespace {
+            Namespace::Value => self.values.get(symbol),
+            Namespace::Type => self.types.get(symbol),
+        }
+    }
+
+    fn insert_value(&mut self, symbol: &'a SymbolRef, span: Span<BytePos>, bound: Bound<'a>) {
+        self.values.insert(symbol, Binding { span, bound });
+    }
+
+    fn new_pattern(&mut self, pattern: &'a SpannedPattern<Symbol>, bound: Bound<'a>) {
+        match pattern.value {
+            Pattern::Ident(ref id) => self.insert_value(&id.name, pattern.span, bound),
+            Pattern::As(ref id, ref pat) => {
+                self.insert_value(&id.value, id.span, bound.clone());
+                self.new_pattern(pat, bound);
+            }
+            Pattern::Record {
+                ref types,
+                ref fields,
+                ..
+            } => {
+                for field in types {
+                    let symbol = field.value.as_ref().unwrap_or(&field.name.value);
+                    self.types.insert(
+                        symbol,
+                        Binding {
+                            span: field.name.span,
+                            bound: bound.field(field.name.value.declared_name()),
+                        },
+                    );
+                }
+                for field in fields {
+                    let bound = bound.field(field.name.value.declared_name());
+                    match field.value {
+                        Some(ref pat) => self.new_pattern(pat, bound),
+                        None => self.insert_value(&field.name.value, field.name.span, bound),
+                    }
+                }
+            }
+            Pattern::Tuple { ref elems, .. } => {
+                for elem in elems {
+                    self.new_pattern(elem, Bound::unknown());
+                }
+            }
+            Pattern::Constructor(_, ref args) => {
+                for arg in args {
+                    self.new_pattern(arg, Bound::unknown());
+                }
+            }
+            Pattern::Or(ref alternatives) => {
+                if let Some(alternative) = alternatives.first() {
+                    self.new_pattern(alternative, Bound::unknown());
+                }
+            }
+            Pattern::Literal(_) | Pattern::Error => (),
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Bindings<'a> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, e: &'a SpannedExpr<Self::Ident>) {
+        match e.value {
+            Expr::LetBindings(ref bindings, _) => {
+                for bind in bindings {
+                    let bound = if bind.args.is_empty() {
+                        Bound::expr(&bind.expr)
+                    } else {
+                        Bound::unknown()
+                    };
+                    self.new_pattern(&bind.name, bound);
+                    for arg in &bind.args {
+                        self.insert_value(&arg.name.value.name, arg.name.span, Bound::unknown());
+                    }
+                }
+            }
+            Expr::Lambda(ref lambda) => {
+                for arg in &lambda.args {
+                    self.insert_value(&arg.name.value.name, arg.name.span, Bound::unknown());
+                }
+            }
+            Expr::Match(ref scrutinee, ref alts) => {
+                for alt in alts {
+                    self.new_pattern(&alt.pattern, Bound::expr(scrutinee));
+                }
+            }
+            Expr::Do(ref do_expr) => {
+                if let Some(ref id) = do_expr.id {
+                    self.new_pattern(id, Bound::unknown());
+                }
+            }
+            Expr::TypeBindings(ref bindings, _) => {
+                for bind in bindings {
+                    let binding = Binding {
+                        span: bind.name.span,
+                        bound: Bound::unknown(),
+                    };
+                    self.types.insert(&bind.name.value, binding.clone());
+                    self.types.insert(&bind.alias.value.name, binding);
+                }
+            }
+            _ => (),
+        }
+        walk_expr(self, e)
+    }
+}
+
+struct Scope<'a> {
+    module: Option<String>,
+    bindings: Bindings<'a>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(module: Option<String>, expr: &'a SpannedExpr<Symbol>) -> Self {
+        Scope {
+            module,
+            bindings: Bindings::new(expr),
+        }
+    }
+
+    fn definition(&self, span: Span<BytePos>) -> Definition {
+        Definition {
+            module: self.module.clone(),
+            span,
+        }
+    }
+}
+
+/// Follows bindings, record fields and imports to the place where a symbol is defined
+struct Resolver<M> {
+    modules: M,
+    depth: Cell<u32>,
+}
+
+/// Guards against cyclic bindings such as `rec let x = x.y`
+const MAX_RESOLVE_DEPTH: u32 = 64;
+
+impl<M> Resolver<M>
+where
+    M: ModuleExprs,
+{
+    fn new(modules: M) -> Self {
+        Resolver {
+            modules,
+            depth: Cell::new(0),
+        }
+    }
+
+    fn enter<T>(&self, f: impl FnOnce() -> Option<T>) -> Option<T> {
+        if self.depth.get() >= MAX_RESOLVE_DEPTH {
+            return None;
+        }
+        self.depth.set(self.depth.get() + 1);
+        let result = f();
+        self.depth.set(self.depth.get() - 1);
+        result
+    }
+
+    fn symbol_definition(
+        &self,
+        scope: &Scope,
+        namespace: Namespace,
+        symbol: &SymbolRef,
+    ) -> Option<Definition> {
+        match scope.bindings.get(namespace, symbol) {
+            Some(binding) => {
+                // Bindings which are unpacked from a record are defined where the field is
+                // defined (if we can find it)
+                let field_definition = match binding.bound.expr {
+                    Some(expr) if !binding.bound.path.is_empty() => {
+                        self.field_definition(scope, expr, &binding.bound.path, namespace)
+                    }
+                    _ => None,
+                };
+                Some(field_definition.unwrap_or_else(|| scope.definition(binding.span)))
+            }
+            None if namespace == Namespace::Value && symbol.is_global() => {
+                self.module_definition(symbol.definition_name(), &[], namespace)
+            }
+            None if namespace == Namespace::Type => {
+                // Types keep the symbol of the module that defined them
+                let module = symbol.name().module().as_str();
+                if module.is_empty() || Some(module) == scope.module.as_ref().map(|s| &s[..]) {
+                    return None;
+                }
+                self.enter(|| {
+                    let expr = self.modules.module_expr(module)?;
+                    let module_scope = Scope::new(Some(module.to_string()), &expr);
+                    let binding = module_scope
+                        .bindings
+                        .types
+                        .iter()
+                        .find(|&(id, _)| *id == symbol || id.as_str() == symbol.as_str())
+                        .map(|(_, binding)| binding)?;
+                    Some(module_scope.definition(binding.span))
+                })
+            }
+            None => None,
+        }
+    }
+
+    fn module_definition(
+        &self,
+        module: &str,
+        path: &[&str],
+        namespace: Namespace,
+    ) -> Option<Definition> {
+        self.enter(|| {
+            let expr = self.modules.module_expr(module)?;
+            let scope = Scope::new(Some(module.to_string()), &expr);
+            if path.is_empty() {
+                Some(scope.definition(expr.span))
+            } else {
+                self.field_definition(&scope, &expr, path, namespace)
+            }
+        })
+    }
+
+    /// Finds the definition of `expr.path[0].path[1]...`
+    fn field_definition(
+        &self,
+        scope: &Scope,
+        expr: &SpannedExpr<Symbol>,
+        path: &[&str],
+        namespace: Namespace,
+    ) -> Option<Definition> {
+        self.enter(|| match expr.value {
+            Expr::Ident(ref id) => {
+                if id.name.is_global() {
+                    return self.module_definition(id.name.definition_name(), path, namespace);
+                }
+                let binding = scope.bindings.values.get(&*id.name)?;
+                let bound_expr = binding.bound.expr?;
+                let full_path: Vec<_> = binding.bound.path.iter().chain(path).cloned().collect();
+                self.field_definition(scope, bound_expr, &full_path, namespace)
+            }
+            Expr::Projection(ref inner, ref field, _) => {
+                let full_path: Vec<_> = once(field.declared_name())
+                    .chain(path.iter().cloned())
+                    .collect();
+                self.field_definition(scope, inner, &full_path, namespace)
+            }
+            Expr::Record {
+                ref types,
+                ref exprs,
+                ref base,
+                ..
+            } => {
+                let (name, rest) = path.split_first()?;
+                if rest.is_empty() && namespace == Namespace::Type {
+                    if let Some(field) = types
+                        .iter()
+                        .find(|field| field.name.value.declared_name() == *name)
+                    {
+                        return self
+                            .symbol_definition(scope, Namespace::Type, &field.name.value)
+                            .or_else(|| Some(scope.definition(field.name.span)));
+                    }
+                } else if let Some(field) = exprs
+                    .iter()
+                    .find(|field| field.name.value.declared_name() == *name)
+                {
+                    return match field.value {
+                        Some(ref value) if !rest.is_empty() => {
+                            self.field_definition(scope, value, rest, namespace)
+                        }
+                        Some(_) => Some(scope.definition(field.name.span)),
+                        // Punned fields (`{ x }`) refer to the binding of the same name
+                        None => match scope.bindings.values.get(&*field.name.value) {
+                            Some(binding) if !rest.is_empty() => {
+                                let bound_expr = binding.bound.expr?;
+                                let full_path: Vec<_> =
+                                    binding.bound.path.iter().chain(rest).cloned().collect();
+                                self.field_definition(scope, bound_expr, &full_path, namespace)
+                            }
+                            Some(_) => {
+                                self.symbol_definition(scope, Namespace::Value, &field.name.value)
+                            }
+                            None if rest.is_empty() => Some(scope.definition(field.name.span)),
+                            None => None,
+                        },
+                    };
+                }
+                let base = base.as_ref()?;
+                self.field_definition(scope, base, path, namespace)
+            }
+            Expr::LetBindings(_, ref body)
+            | Expr::TypeBindings(_, ref body)
+            | Expr::Annotated(ref body, _)
+            | Expr::MacroExpansion {
+                replacement: ref body,
+                ..
+            } => self.field_definition(scope, body, path, namespace),
+            Expr::Block(ref exprs) => self.field_definition(scope, exprs.last()?, path, namespace),
+            _ => None,
+        })
+    }
+
+    fn expr_definition(&self, scope: &Scope, expr: &SpannedExpr<Symbol>) -> Option<Definition> {
+        match expr.value {
+            Expr::Ident(ref id) => self.symbol_definition(scope, Namespace::Value, &id.name),
+            Expr::Projection(ref inner, ref field, _) => {
+                self.field_definition(scope, inner, &[field.declared_name()], Namespace::Value)
+            }
+            Expr::App { ref func, .. } => self.expr_definition(scope, func),
+            _ => None,
+        }
+    }
+
+    fn match_definition(&self, scope: &Scope, found: &Found) -> Option<Definition> {
+        match *found.match_.as_ref()? {
+            Match::Expr(expr) => self.expr_definition(scope, expr),
+            Match::Pattern(pattern) => match pattern.value {
+                Pattern::Ident(ref id) => self.symbol_definition(scope, Namespace::Value, &id.name),
+                Pattern::As(ref id, _) => {
+                    self.symbol_definition(scope, Namespace::Value, &id.value)
+                }
+                _ => None,
+            },
+            Match::Ident(_, id, _) => match *found.enclosing_match() {
+                Match::Expr(&Spanned {
+                    value: Expr::Projection(ref inner, ref field, _),
+                    ..
+                }) if field == id => {
+                    self.field_definition(scope, inner, &[field.declared_name()], Namespace::Value)
+                }
+                _ => self.symbol_definition(scope, Namespace::Value, id),
+            },
+            Match::Type(_, id, _) => self.symbol_definition(scope, Namespace::Type, id),
+        }
+    }
+}
+
+/// Returns the location where the value, field or type at `pos` is defined.
+///
+/// Projections (`module.field`), unpacked record fields (`let { field } = module`) and types are
+/// followed through `import!` by looking up the imported modules in `modules`.
+pub fn definition_at<M>(
+    modules: M,
+    source_span: Span<BytePos>,
+    expr: &SpannedExpr<Symbol>,
+    pos: BytePos,
+) -> Option<Definition>
+where
+    M: ModuleExprs,
+{
+    let found = complete_at((), source_span, expr, pos).ok()?;
+    let scope = Scope::new(None, expr);
+    Resolver::new(modules).match_definition(&scope, &found)
+}
+
+/// Returns the locations of the implicit arguments which were passed to the application at `pos`.
+pub fn implicit_definitions_at<M>(
+    modules: M,
+    source_span: Span<BytePos>,
+    expr: &SpannedExpr<Symbol>,
+    pos: BytePos,
+) -> Vec<Definition>
+where
+    M: ModuleExprs,
+{
+    let found = match complete_at((), source_span, expr, pos) {
+        Ok(found) => found,
+        Err(()) => return Vec::new(),
+    };
+    let implicit_args = found
+        .enclosing_matches
+        .iter()
+        .rev()
+        .filter_map(|match_| match *match_ {
+            Match::Expr(&Spanned {
+                value: Expr::App {
+                    ref implicit_args, ..
+                },
+                ..
+            })
+            | Match::Expr(&Spanned {
+                value: Expr::Infix {
+                    ref implicit_args, ..
+                },
+                ..
+            }) if !implicit_args.is_empty() => Some(implicit_args),
+            _ => None,
+        })
+        .next();
+
+    let scope = Scope::new(None, expr);
+    let resolver = Resolver::new(modules);
+    implicit_args
+        .into_iter()
+        .flatten()
+        .filter_map(|arg| resolver.expr_definition(&scope, arg))
+        .collect()
+}
+
+/// Returns the module and span of every binding and use of `symbol` in `expr` (which is the
+/// expression of `module`).
+///
+/// Uses include implicit arguments which were inserted by the typechecker (these are given the
+/// span of the argument which required them), punned record fields, type annotations and record
+/// projections which are resolved to the binding of `symbol`. The modules listed by
+/// `ModuleExprs::module_names` are also searched for values which refer to `symbol` through
+/// `import!`.
+pub fn references<M>(
+    modules: M,
+    module: &str,
+    expr: &SpannedExpr<Symbol>,
+    symbol: &SymbolRef,
+) -> Vec<(String, Span<BytePos>)>
+where
+    M: ModuleExprs,
+{
+    struct References<'a, 'b, M> {
+        resolver: &'b Resolver<M>,
+        scope: &'b Scope<'a>,
+        definition: Option<&'b Definition>,
+        /// The symbol itself is only known in the module it was found in, other modules are
+        /// searched by resolving each identifier to its definition
+        symbol: Option<&'b SymbolRef>,
+        result: Vec<Span<BytePos>>,
+    }
+
+    impl<M> References<'_, '_, M>
+    where
+        M: ModuleExprs,
+    {
+        fn refers_to(&self, id: &SymbolRef) -> bool {
+            match self.symbol {
+                Some(symbol) => id == symbol,
+                None => {
+                    self.definition.is_some()
+                        && self
+                            .resolver
+                            .symbol_definition(self.scope, Namespace::Value, id)
+                            .as_ref()
+                            == self.definition
+                }
+            }
+        }
+
+        fn refers_to_type(&self, id: &SymbolRef) -> bool {
+            self.symbol == Some(id)
+        }
+    }
+
+    impl<'a, 'b, 'c, M> Visitor<'c> for References<'a, 'b, M>
+    where
+        M: ModuleExprs,
+    {
+        type Ident = Symbol;
+
+        fn visit_expr(&mut self, e: &'c SpannedExpr<Self::Ident>) {
+            match e.value {
+                Expr::Ident(ref id) if self.refers_to(&id.name) => self.result.push(e.span),
+                Expr::Projection(ref inner, ref field, _) if self.definition.is_some() => {
+                    let definition = self.resolver.field_definition(
+                        self.scope,
+                        inner,
+                        &[field.declared_name()],
+                        Namespace::Value,
+                    );
+                    if definition.is_some() && definition.as_ref() == self.definition {
+                        self.result.push(e.span);
+                    } else {
+                        walk_expr(self, e)
+                    }
+                }
+                Expr::Infix { ref op, .. } => {
+                    if self.refers_to(&op.value.name) {
+                        self.result.push(op.span);
+                    }
+                    walk_expr(self, e)
+                }
+                Expr::Record { ref exprs, .. } => {
+                    for field in exprs {
+                        if field.value.is_none() && self.refers_to(&field.name.value) {
+                            self.result.push(field.name.span);
+                        }
+                    }
+                    walk_expr(self, e)
+                }
+                Expr::TypeBindings(ref bindings, _) => {
+                    for bind in &**bindings {
+                        if self.refers_to_type(&bind.alias.value.name) {
+                            self.result.push(bind.name.span);
+                        }
+                    }
+                    walk_expr(self, e)
+                }
+                _ => walk_expr(self, e),
+            }
+        }
+
+        fn visit_pattern(&mut self, p: &'c SpannedPattern<Self::Ident>) {
+            match p.value {
+                Pattern::As(ref id, _) if self.refers_to(&id.value) => {
+                    self.result.push(id.span);
+                }
+                Pattern::Ident(ref id) if self.refers_to(&id.name) => {
+                    self.result.push(p.span);
+                }
+                Pattern::Record { ref fields, .. } => {
+                    for field in fields {
+                        if field.value.is_none() && self.refers_to(&field.name.value) {
+                            self.result.push(field.name.span);
+                        }
+                    }
+                }
+                _ => (),
+            }
+            walk_pattern(self, &p.value)
+        }
+
+        fn visit_spanned_typed_ident(&mut self, id: &'c SpannedIdent<Self::Ident>) {
+            if self.refers_to(&id.value.name) {
+                self.result.push(id.span);
+            }
+        }
+
+        fn visit_ast_type(&mut self, typ: &'c SpannedAstType<Self::Ident>) {
+            match typ.value {
+                Type::Ident(ref id) if self.refers_to_type(id) => self.result.push(typ.span),
+                Type::Alias(ref alias) => {
+                    if self.refers_to_type(&alias.name) {
+                        self.result.push(typ.span);
+                    }
+                }
+                _ => walk_ast_type(self, typ),
+            }
+        }
+    }
+
+    let resolver = Resolver::new(modules);
+    let scope = Scope::new(Some(module.to_string()), expr);
+    let definition = resolver.symbol_definition(&scope, Namespace::Value, symbol);
+
+    let mut visitor = References {
+        resolver: &resolver,
+        scope: &scope,
+        definition: definition.as_ref(),
+        symbol: Some(symbol),
+        result: Vec::new(),
+    };
+    visitor.visit_expr(expr);
+    let mut result: Vec<_> = visitor
+        .result
+        .into_iter()
+        .map(|span| (module.to_string(), span))
+        .collect();
+
+    if let Some(definition) = &definition {
+        if let Some(definition_module) = &definition.module {
+            if definition_module != module {
+                result.push((definition_module.clone(), definition.span));
+            }
+        }
+        for other in resolver.modules.module_names() {
+            if other == module {
+                continue;
+            }
+            let other_expr = match resolver.modules.module_expr(&other) {
+                Some(other_expr) => other_expr,
+                None => continue,
+            };
+            let other_scope = Scope::new(Some(other.clone()), &other_expr);
+            let mut visitor = References {
+                resolver: &resolver,
+                scope: &other_scope,
+                definition: Some(definition),
+                symbol: None,
+                result: Vec::new(),
+            };
+            visitor.visit_expr(&other_expr);
+            result.extend(visitor.result.into_iter().map(|span| (other.clone(), span)));
+        }
+    }
+
+    result.sort_by(|l, r| (&l.0, l.1.start(), l.1.end()).cmp(&(&r.0, r.1.start(), r.1.end())));
+    result.dedup();
+    result
+}
+
+/// Replaces the source text in `span` with `new_text`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextEdit {
+    pub span: Span<BytePos>,
+    pub new_text: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RenameError {
+    /// There is no value bound in the expression at the position
+    NoSymbol,
+    /// Only bindings with an identifier as its name can be renamed (not operators)
+    NotRenameable(String),
+    /// The new name is not a valid identifier for a value
+    InvalidName(String),
+    /// Renaming would change which binding the identifier at `span` refers to
+    Shadowing { name: String, span: Span<BytePos> },
+    /// Renaming would clash with another field of the record pattern at `span`
+    FieldCollision { name: String, span: Span<BytePos> },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenameError::NoSymbol => write!(f, "No binding found at the position"),
+            RenameError::NotRenameable(name) => write!(f, "`{}` can't be renamed", name),
+            RenameError::InvalidName(name) => write!(f, "`{}` is not a valid name", name),
+            RenameError::Shadowing { name, .. } => write!(
+                f,
+                "Renaming would change which binding `{}` refers to",
+                name
+            ),
+            RenameError::FieldCollision { name, .. } => write!(
+                f,
+                "Renaming would collide with the field `{}` in the record pattern",
+                name
+            ),
+        }
+    }
+}
+
+impl StdError for RenameError {}
+
+const KEYWORDS: &[&str] = &[
+    "and", "do", "else", "forall", "if", "in", "let", "match", "rec", "seq", "then", "type", "with",
+];
+
+fn is_value_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c == '_' || c.is_lowercase())
+        && chars.all(|c| c == '_' || c.is_alphanumeric())
+        && !KEYWORDS.contains(&name)
+}
+
+/// Checks that renaming `target` to `new_name` does not change what any identifier refers to, by
+/// walking the expression in the same order that `check::rename` resolves bindings
+struct ShadowCheck<'a, 'b> {
+    target: &'b SymbolRef,
+    old_name: &'b str,
+    new_name: &'b str,
+    scope: ScopedMap<&'a str, (&'a SymbolRef, Span<BytePos>, usize)>,
+    order: usize,
+    target_order: Option<usize>,
+    error: Option<RenameError>,
+}
+
+impl<'a, 'b> ShadowCheck<'a, 'b> {
+    fn shadowing(&mut self, name: &str, span: Span<BytePos>) {
+        if self.error.is_none() {
+            self.error = Some(RenameError::Shadowing {
+                name: name.to_string(),
+                span,
+            });
+        }
+    }
+
+    fn bind(&mut self, bindings: Vec<(&'a SymbolRef, Span<BytePos>)>) {
+        // Two bindings in the same pattern or recursive group would get the same name
+        if bindings.iter().any(|&(symbol, _)| symbol == self.target) {
+            if let Some(&(_, span)) = bindings
+                .iter()
+                .find(|&&(symbol, _)| symbol.declared_name() == self.new_name)
+            {
+                self.shadowing(self.new_name, span);
+            }
+        }
+
+        for (symbol, span) in bindings {
+            self.order += 1;
+            if symbol == self.target {
+                self.target_order = Some(self.order);
+            }
+            self.scope
+                .insert(symbol.declared_name(), (symbol, span, self.order));
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &'a SpannedPattern<Symbol>) {
+        let mut bindings = Vec::new();
+        self.pattern_bindings(pattern, &mut bindings);
+        self.bind(bindings);
+    }
+
+    fn pattern_bindings(
+        &mut self,
+        pattern: &'a SpannedPattern<Symbol>,
+        bindings: &mut Vec<(&'a SymbolRef, Span<BytePos>)>,
+    ) {
+        match pattern.value {
+            Pattern::Ident(ref id) => bindings.push((&id.name, pattern.span)),
+            Pattern::As(ref id, ref pat) => {
+                bindings.push((&id.value, id.span));
+                self.pattern_bindings(pat, bindings);
+            }
+            Pattern::Record {
+                ref fields,
+                ref implicit_import,
+                ..
+            } => {
+                let binds_target = fields
+                    .iter()
+                    .any(|field| field.value.is_none() && field.name.value == *self.target);
+                if binds_target && self.error.is_none() {
+                    if let Some(field) = fields
+                        .iter()
+                        .find(|field| field.name.value.declared_name() == self.new_name)
+                    {
+                        self.error = Some(RenameError::FieldCollision {
+                            name: self.new_name.to_string(),
+                            span: field.name.span,
+                        });
+                    }
+                }
+                for field in fields {
+                    match field.value {
+                        Some(ref pat) => self.pattern_bindings(pat, bindings),
+                        None => bindings.push((&field.name.value, field.name.span)),
+                    }
+                }
+                if let Some(ref implicit_import) = *implicit_import {
+                    bindings.push((&implicit_import.value, implicit_import.span));
+                }
+            }
+            Pattern::Tuple { ref elems, .. } => {
+                for elem in elems {
+                    self.pattern_bindings(elem, bindings);
+                }
+            }
+            Pattern::Constructor(_, ref args) => {
+                for arg in args {
+                    self.pattern_bindings(arg, bindings);
+                }
+            }
+            Pattern::Or(ref alternatives) => {
+                if let Some(alternative) = alternatives.first() {
+                    self.pattern_bindings(alternative, bindings);
+                }
+            }
+            Pattern::Literal(_) | Pattern::Error => (),
+        }
+    }
+
+    fn use_symbol(&mut self, symbol: &SymbolRef, span: Span<BytePos>) {
+        let target_order = match self.target_order {
+            Some(order) => order,
+            None => return,
+        };
+        if symbol == self.target {
+            // An inner binding of `new_name` would capture the renamed identifier
+            if let Some(&(_, binding_span, order)) = self.scope.get(self.new_name) {
+                if order > target_order {
+                    self.shadowing(self.new_name, binding_span);
+                }
+            }
+        } else if symbol.declared_name() == self.new_name {
+            // The renamed binding would capture an identifier which refers to an outer binding
+            let target_visible = self
+                .scope
+                .get(self.old_name)
+                .map_or(false, |&(visible, _, _)| visible == self.target);
+            let outer_binding = self
+                .scope
+                .get(self.new_name)
+                .map_or(true, |&(_, _, order)| order < target_order);
+            if target_visible && outer_binding {
+                self.shadowing(self.new_name, span);
+            }
+        }
+    }
+}
+
+impl<'a, 'b> Visitor<'a> for ShadowCheck<'a, 'b> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, e: &'a SpannedExpr<Self::Ident>) {
+        match e.value {
+            Expr::Ident(ref id) => self.use_symbol(&id.name, e.span),
+            Expr::LetBindings(ref bindings, ref body) => {
+                self.scope.enter_scope();
+                if bindings.is_recursive() {
+                    let mut names = Vec::new();
+                    for bind in bindings {
+                        self.pattern_bindings(&bind.name, &mut names);
+                    }
+                    self.bind(names);
+                    for bind in bindings {
+                        self.scope.enter_scope();
+                        self.bind(
+                            bind.args
+                                .iter()
+                                .map(|arg| (&*arg.name.value.name, arg.name.span))
+                                .collect(),
+                        );
+                        self.visit_expr(&bind.expr);
+                        self.scope.exit_scope();
+                    }
+                } else {
+                    for bind in bindings {
+                        self.visit_expr(&bind.expr);
+                        self.bind_pattern(&bind.name);
+                    }
+                }
+                self.visit_expr(body);
+                self.scope.exit_scope();
+            }
+            Expr::Lambda(ref lambda) => {
+                self.scope.enter_scope();
+                self.bind(
+                    lambda
+                        .args
+                        .iter()
+                        .map(|arg| (&*arg.name.value.name, arg.name.span))
+                        .collect(),
+                );
+                self.visit_expr(&lambda.body);
+                self.scope.exit_scope();
+            }
+            Expr::Match(ref scrutinee, ref alts) => {
+                self.visit_expr(scrutinee);
+                for alt in alts {
+                    self.scope.enter_scope();
+                    self.bind_pattern(&alt.pattern);
+                    if let Some(ref guard) = alt.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&alt.expr);
+                    self.scope.exit_scope();
+                }
+            }
+            Expr::Do(ref do_expr) => {
+                self.visit_expr(&do_expr.bound);
+                self.scope.enter_scope();
+                if let Some(ref id) = do_expr.id {
+                    self.bind_pattern(id);
+                }
+                self.visit_expr(&do_expr.body);
+                self.scope.exit_scope();
+            }
+            // Implicit arguments are resolved by type so they are unaffected by renaming
+            Expr::App {
+                ref func, ref args, ..
+            } => {
+                self.visit_expr(func);
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+            }
+            Expr::Infix {
+                ref lhs,
+                ref op,
+                ref rhs,
+                ..
+            } => {
+                self.visit_expr(lhs);
+                self.use_symbol(&op.value.name, op.span);
+                self.visit_expr(rhs);
+            }
+            Expr::Record {
+                ref exprs,
+                ref base,
+                ..
+            } => {
+                for field in exprs {
+                    match field.value {
+                        Some(ref expr) => self.visit_expr(expr),
+                        None => self.use_symbol(&field.name.value, field.name.span),
+                    }
+                }
+                if let Some(ref base) = *base {
+                    self.visit_expr(base);
+                }
+            }
+            _ => walk_expr(self, e),
+        }
+    }
+}
+
+/// Collects the edits which renames every occurence of `target`
+struct RenameEdits<'b> {
+    source_span: Span<BytePos>,
+    target: &'b SymbolRef,
+    old_name: &'b str,
+    new_name: &'b str,
+    edits: Vec<TextEdit>,
+}
+
+impl<'b> RenameEdits<'b> {
+    fn rename(&mut self, span: Span<BytePos>) {
+        self.edit(span, self.new_name.to_string());
+    }
+
+    /// Punned fields (`{ x }`) need to keep the name of the field
+    fn rename_punned(&mut self, span: Span<BytePos>) {
+        self.edit(span, format!("{} = {}", self.old_name, self.new_name));
+    }
+
+    fn edit(&mut self, span: Span<BytePos>, new_text: String) {
+        if self.source_span.contains(span) {
+            self.edits.push(TextEdit { span, new_text });
+        }
+    }
+}
+
+impl<'a, 'b> Visitor<'a> for RenameEdits<'b> {
+    type Ident = Symbol;
+
+    fn visit_expr(&mut self, e: &'a SpannedExpr<Self::Ident>) {
+        match e.value {
+            Expr::Ident(ref id) => {
+                if id.name == *self.target {
+                    self.rename(e.span);
+                }
+            }
+            // Implicit arguments do not appear in the source
+            Expr::App {
+                ref func, ref args, ..
+            } => {
+                self.visit_expr(func);
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+            }
+            Expr::Infix {
+                ref lhs, ref rhs, ..
+            } => {
+                self.visit_expr(lhs);
+                self.visit_expr(rhs);
+            }
+            Expr::Record { ref exprs, .. } => {
+                for field in exprs {
+                    if field.value.is_none() && field.name.value == *self.target {
+                        self.rename_punned(field.name.span);
+                    }
+                }
+                walk_expr(self, e)
+            }
+            _ => walk_expr(self, e),
+        }
+    }
+
+    fn visit_pattern(&mut self, p: &'a SpannedPattern<Self::Ident>) {
+        match p.value {
+            Pattern::Ident(ref id) if id.name == *self.target => self.rename(p.span),
+            Pattern::As(ref id, _) if id.value == *self.target => self.rename(id.span),
+            Pattern::Record { ref fields, .. } => {
+                for field in fields {
+                    if field.value.is_none() && field.name.value == *self.target {
+                        self.rename_punned(field.name.span);
+                    }
+                }
+            }
+            _ => (),
+        }
+        walk_pattern(self, &p.value)
+    }
+
+    fn visit_spanned_typed_ident(&mut self, id: &'a SpannedIdent<Self::Ident>) {
+        if id.value.name == *self.target {
+            self.rename(id.span);
+        }
+    }
+}
+
+/// Returns the edits which renames the value binding at `pos` (and all its uses) to `new_name`.
+///
+/// The rename is refused if it would change which binding any identifier refers to or if it
+/// would collide with a field of a record pattern which binds the value.
+pub fn rename(
+    source_span: Span<BytePos>,
+    expr: &SpannedExpr<Symbol>,
+    pos: BytePos,
+    new_name: &str,
+) -> Result<Vec<TextEdit>, RenameError> {
+    let target = symbol(source_span, expr, pos).map_err(|()| RenameError::NoSymbol)?;
+    let bound_in_source = Bindings::new(expr)
+        .values
+        .get(target)
+        .is_some_and(|binding| source_span.contains(binding.span));
+    if !bound_in_source {
+        return Err(RenameError::NoSymbol);
+    }
+
+    let old_name = target.declared_name();
+    if !is_value_identifier(old_name) {
+        return Err(RenameError::NotRenameable(old_name.to_string()));
+    }
+    if !is_value_identifier(new_name) {
+        return Err(RenameError::InvalidName(new_name.to_string()));
+    }
+    if old_name == new_name {
+        return Ok(Vec::new());
+    }
+
+    let mut check = ShadowCheck {
+        target,
+        old_name,
+        new_name,
+        scope: ScopedMap::new(),
+        order: 0,
+        target_order: None,
+        error: None,
+    };
+    check.visit_expr(expr);
+    if let Some(err) = check.error {
+        return Err(err);
+    }
+
+    let mut visitor = RenameEdits {
+        source_span,
+        target,
+        old_name,
+        new_name,
+        edits: Vec::new(),
+    };
+    visitor.visit_expr(expr);
+    let mut edits = visitor.edits;
+    edits.sort_by_key(|edit| (edit.span.start(), edit.span.end()));
+    edits.dedup_by_key(|edit| edit.span);
+    Ok(edits)
+}
+
 pub type SpCompletionSymbol<'a> = Spanned<CompletionSymbol<'a>, BytePos>;
 
 #[derive(Debug, PartialEq)]
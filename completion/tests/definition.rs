@@ -0,0 +1,232 @@
+#[macro_use]
+extern crate collect_mac;
+extern crate env_logger;
+
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_completion as completion;
+extern crate gluon_parser as parser;
+
+use crate::base::pos::{BytePos, Span};
+
+use crate::completion::Definition;
+
+#[allow(unused)]
+mod support;
+use crate::support::loc;
+
+fn definition_at(s: &str, pos: BytePos) -> Option<Span<BytePos>> {
+    let (expr, result) = support::typecheck_expr(s);
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    completion::definition_at((), expr.span, &expr, pos).map(|definition| {
+        assert_eq!(definition.module, None);
+        definition.span
+    })
+}
+
+fn references(s: &str, pos: BytePos) -> Vec<Span<BytePos>> {
+    let (expr, result) = support::typecheck_expr(s);
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    let symbol = completion::symbol(expr.span, &expr, pos).expect("Symbol");
+    completion::references((), "test", &expr, symbol)
+        .into_iter()
+        .map(|(module, span)| {
+            assert_eq!(module, "test");
+            span
+        })
+        .collect()
+}
+
+fn span(s: &str, line: usize, start: usize, end: usize) -> Span<BytePos> {
+    Span::new(loc(s, line, start), loc(s, line, end))
+}
+
+#[test]
+fn definition_of_let_binding() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let test = 1
+let dummy =
+    let test = 3
+    test
+test
+"#;
+    assert_eq!(
+        definition_at(text, loc(text, 5, 1)),
+        Some(span(text, 1, 4, 8))
+    );
+    assert_eq!(
+        definition_at(text, loc(text, 4, 5)),
+        Some(span(text, 3, 8, 12))
+    );
+}
+
+#[test]
+fn definition_of_function_argument() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let f x y = y
+let g = \z -> z
+f 1 2
+"#;
+    assert_eq!(
+        definition_at(text, loc(text, 1, 12)),
+        Some(span(text, 1, 8, 9))
+    );
+    assert_eq!(
+        definition_at(text, loc(text, 2, 14)),
+        Some(span(text, 2, 9, 10))
+    );
+}
+
+#[test]
+fn definition_of_projected_field() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let record = { x = 1, y = "" }
+let nested = { record }
+nested.record.y
+"#;
+    assert_eq!(
+        definition_at(text, loc(text, 3, 15)),
+        Some(span(text, 1, 22, 23))
+    );
+    assert_eq!(
+        definition_at(text, loc(text, 3, 8)),
+        Some(span(text, 1, 4, 10))
+    );
+}
+
+#[test]
+fn definition_of_unpacked_field() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let inner = 1
+let record = { x = 1, inner }
+let { x, inner = renamed } = record
+match record with
+| { x = y } -> x #Int+ y #Int+ renamed
+"#;
+    // Fields which are unpacked are defined where the field is defined
+    assert_eq!(
+        definition_at(text, loc(text, 5, 15)),
+        Some(span(text, 2, 15, 16))
+    );
+    assert_eq!(
+        definition_at(text, loc(text, 5, 23)),
+        Some(span(text, 2, 15, 16))
+    );
+    // Punned fields are defined by their binding
+    assert_eq!(
+        definition_at(text, loc(text, 5, 32)),
+        Some(span(text, 1, 4, 9))
+    );
+}
+
+#[test]
+fn definition_of_type() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+type Test = Int
+type Wrapper a = { value : a }
+let x : Wrapper Test = { value = 1 }
+x
+"#;
+    assert_eq!(
+        definition_at(text, loc(text, 3, 10)),
+        Some(span(text, 2, 5, 12))
+    );
+    assert_eq!(
+        definition_at(text, loc(text, 3, 18)),
+        Some(span(text, 1, 5, 9))
+    );
+}
+
+#[test]
+fn definition_of_implicit_arguments() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+#[implicit]
+type Show a = { show : a -> String }
+let show ?s : [Show a] -> a -> String = s.show
+let show_int : Show Int = { show = \_ -> "int" }
+show 1
+"#;
+    let (expr, result) = support::typecheck_expr(text);
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    let definitions = completion::implicit_definitions_at((), expr.span, &expr, loc(text, 5, 1));
+    assert_eq!(
+        definitions,
+        vec![Definition {
+            module: None,
+            span: span(text, 4, 4, 12),
+        }]
+    );
+}
+
+#[test]
+fn references_of_let_binding() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let test = 1
+let record = { test }
+let { test = test2 } = record
+test #Int+ record.test #Int+ test2
+"#;
+    assert_eq!(
+        references(text, loc(text, 1, 5)),
+        vec![
+            span(text, 1, 4, 8),
+            span(text, 2, 15, 19),
+            span(text, 4, 0, 4),
+            span(text, 4, 11, 22),
+        ]
+    );
+}
+
+#[test]
+fn references_of_implicit_instance() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+#[implicit]
+type Show a = { show : a -> String }
+let show ?s : [Show a] -> a -> String = s.show
+let show_int : Show Int = { show = \_ -> "int" }
+show 1
+"#;
+    assert_eq!(
+        references(text, loc(text, 4, 5)),
+        vec![span(text, 4, 4, 12), span(text, 5, 5, 6)]
+    );
+}
+
+#[test]
+fn references_of_type() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+type Test = Int
+let x : Test = 1
+let f y : Test -> Int = y
+f x
+"#;
+    assert_eq!(
+        references(text, loc(text, 1, 6)),
+        vec![
+            span(text, 1, 5, 9),
+            span(text, 2, 8, 12),
+            span(text, 3, 10, 14),
+        ]
+    );
+}
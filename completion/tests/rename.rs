@@ -0,0 +1,191 @@
+#[macro_use]
+extern crate collect_mac;
+extern crate env_logger;
+
+extern crate gluon_base as base;
+extern crate gluon_check as check;
+extern crate gluon_completion as completion;
+extern crate gluon_parser as parser;
+
+use crate::base::pos::{BytePos, Span};
+
+use crate::completion::RenameError;
+
+#[allow(unused)]
+mod support;
+use crate::support::loc;
+
+fn rename(s: &str, pos: BytePos, new_name: &str) -> Result<String, RenameError> {
+    let (expr, result) = support::typecheck_expr(s);
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    let edits = completion::rename(expr.span, &expr, pos, new_name)?;
+
+    let mut output = s.to_string();
+    for edit in edits.iter().rev() {
+        let start = edit.span.start().to_usize() - 1;
+        let end = edit.span.end().to_usize() - 1;
+        output.replace_range(start..end, &edit.new_text);
+    }
+    Ok(output)
+}
+
+fn span(s: &str, line: usize, start: usize, end: usize) -> Span<BytePos> {
+    Span::new(loc(s, line, start), loc(s, line, end))
+}
+
+#[test]
+fn rename_let_binding() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let test = 1
+let dummy =
+    let test = 3
+    test
+test #Int+ dummy #Int+ test
+"#;
+    assert_eq!(
+        rename(text, loc(text, 5, 1), "renamed"),
+        Ok(r#"
+let renamed = 1
+let dummy =
+    let test = 3
+    test
+renamed #Int+ dummy #Int+ renamed
+"#
+        .to_string())
+    );
+}
+
+#[test]
+fn rename_function_argument() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let f x y = x #Int+ y
+f 1 2
+"#;
+    assert_eq!(
+        rename(text, loc(text, 1, 6), "first"),
+        Ok(r#"
+let f first y = first #Int+ y
+f 1 2
+"#
+        .to_string())
+    );
+}
+
+#[test]
+fn rename_keeps_punned_field_names() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let x = 1
+let record = { x }
+let { x } = record
+x
+"#;
+    assert_eq!(
+        rename(text, loc(text, 1, 4), "y"),
+        Ok(r#"
+let y = 1
+let record = { x = y }
+let { x } = record
+x
+"#
+        .to_string())
+    );
+    assert_eq!(
+        rename(text, loc(text, 4, 0), "y"),
+        Ok(r#"
+let x = 1
+let record = { x }
+let { x = y } = record
+y
+"#
+        .to_string())
+    );
+}
+
+#[test]
+fn rename_refuses_capture_by_inner_binding() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let x = 1
+let f y = x #Int+ y
+f 2
+"#;
+    assert_eq!(
+        rename(text, loc(text, 1, 4), "y"),
+        Err(RenameError::Shadowing {
+            name: "y".to_string(),
+            span: span(text, 2, 6, 7),
+        })
+    );
+}
+
+#[test]
+fn rename_refuses_capturing_outer_binding() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let y = 1
+let f x = x #Int+ y
+f 2
+"#;
+    assert_eq!(
+        rename(text, loc(text, 2, 6), "y"),
+        Err(RenameError::Shadowing {
+            name: "y".to_string(),
+            span: span(text, 2, 18, 19),
+        })
+    );
+    // Bindings of `y` which are not used inside the scope of `x` are fine to shadow
+    let text = r#"
+let y = 1
+let f x = x
+f y
+"#;
+    assert!(rename(text, loc(text, 2, 6), "y").is_ok());
+}
+
+#[test]
+fn rename_refuses_record_field_collision() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let { x, y = z } = { x = 1, y = 2 }
+x #Int+ z
+"#;
+    assert_eq!(
+        rename(text, loc(text, 2, 0), "y"),
+        Err(RenameError::FieldCollision {
+            name: "y".to_string(),
+            span: span(text, 1, 9, 10),
+        })
+    );
+}
+
+#[test]
+fn rename_refuses_invalid_names() {
+    let _ = env_logger::try_init();
+
+    let text = r#"
+let x = 1
+x
+"#;
+    assert_eq!(
+        rename(text, loc(text, 2, 0), "Upper"),
+        Err(RenameError::InvalidName("Upper".to_string()))
+    );
+    assert_eq!(
+        rename(text, loc(text, 2, 0), "match"),
+        Err(RenameError::InvalidName("match".to_string()))
+    );
+    assert_eq!(
+        rename(text, loc(text, 1, 8), "y"),
+        Err(RenameError::NoSymbol)
+    );
+}
@@ -0,0 +1,500 @@
+//! Debug adapter for the gluon programming language.
+//!
+//! Speaks the [debug adapter protocol](https://microsoft.github.io/debug-adapter-protocol/) over
+//! any `BufRead`/`Write` pair (stdin and stdout for the `gluon_debug_adapter` binary). The
+//! program given to the `launch` request is run with `ThreadExt::run_expr` on a separate thread
+//! with a `vm::debugger::Debugger` attached. Whenever the debugger stops, that thread waits for
+//! the client to inspect the stack and then to resume the execution.
+#![doc(html_root_url = "https://docs.rs/gluon_debug_adapter/0.13.1")] // # GLUON
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+
+pub mod protocol;
+mod runner;
+pub mod source;
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use serde_json::Value;
+
+use gluon::vm::debugger::{Breakpoint, Debugger, Step, Stop, StopReason};
+
+use crate::{
+    protocol::{event, Request},
+    runner::{Command, Program},
+    source::Sources,
+};
+
+/// The id of the only thread reported to the client
+const THREAD_ID: i64 = 1;
+
+/// Messages processed by the main loop of the adapter
+pub(crate) enum Incoming {
+    Request(Request),
+    /// The client closed the input
+    Closed,
+    ReadError(io::Error),
+    /// A message which should be sent as is
+    Message(Value),
+    /// Text printed by the program
+    Output(String),
+    /// All output of the program has been forwarded
+    OutputClosed,
+    Stopped(Stop),
+    Exited(Result<(), String>),
+}
+
+/// State of a running debug adapter
+pub struct DebugAdapter<W> {
+    output: W,
+    seq: i64,
+    sender: Sender<Incoming>,
+    receiver: Receiver<Incoming>,
+    sources: Sources,
+    debugger: Debugger,
+    program: Option<Program>,
+    stop_on_entry: bool,
+    /// A breakpoint on the function of the program's module, which is used for `stopOnEntry`
+    entry_breakpoint: Option<usize>,
+    configured: bool,
+    /// Set while the program is running, used to communicate with it while it is stopped
+    commands: Option<Sender<Command>>,
+    stopped: bool,
+    /// The reason reported when a step finishes
+    step_reason: &'static str,
+    line_breakpoints: HashMap<String, Vec<usize>>,
+    function_breakpoints: Vec<usize>,
+    /// Set if the output of the program is forwarded. Called once the program has finished.
+    close_output: Option<Box<dyn FnOnce() + Send>>,
+    output_open: bool,
+    /// The result of a program which finished before all of its output was forwarded
+    pending_exit: Option<Result<(), String>>,
+}
+
+impl<W> DebugAdapter<W>
+where
+    W: Write,
+{
+    pub fn new(output: W) -> DebugAdapter<W> {
+        let (sender, receiver) = mpsc::channel();
+        DebugAdapter {
+            output,
+            seq: 0,
+            sender,
+            receiver,
+            sources: Sources::default(),
+            debugger: Debugger::new(),
+            program: None,
+            stop_on_entry: false,
+            entry_breakpoint: None,
+            configured: false,
+            commands: None,
+            stopped: false,
+            step_reason: "step",
+            line_breakpoints: HashMap::new(),
+            function_breakpoints: Vec::new(),
+            close_output: None,
+            output_open: false,
+            pending_exit: None,
+        }
+    }
+
+    /// Sends everything that is read from `program_output` to the client as `output` events.
+    ///
+    /// `close_output` is called on the thread of the program once it has finished and must close
+    /// the writing end of `program_output`. The `exited` event is sent after all output has been
+    /// forwarded.
+    pub fn forward_output<R, F>(&mut self, program_output: R, close_output: F)
+    where
+        R: Read + Send + 'static,
+        F: FnOnce() + Send + 'static,
+    {
+        self.close_output = Some(Box::new(close_output));
+        self.output_open = true;
+
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let mut program_output = BufReader::new(program_output);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match program_output.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => {
+                        let _ = sender.send(Incoming::OutputClosed);
+                        return;
+                    }
+                    Ok(_) => {
+                        let output = String::from_utf8_lossy(&line).into_owned();
+                        if sender.send(Incoming::Output(output)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Runs the adapter, reading requests from `input` until the client sends `disconnect` or
+    /// closes `input`
+    pub fn run<R>(mut self, input: R) -> io::Result<()>
+    where
+        R: BufRead + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        thread::spawn(move || read_requests(input, sender));
+
+        while let Ok(incoming) = self.receiver.recv() {
+            match incoming {
+                Incoming::Request(request) => {
+                    if !self.handle_request(request)? {
+                        break;
+                    }
+                }
+                Incoming::Closed => break,
+                Incoming::ReadError(err) => return Err(err),
+                Incoming::Message(message) => self.send(message)?,
+                Incoming::Output(output) => self.send(event(
+                    "output",
+                    json!({ "category": "stdout", "output": output }),
+                ))?,
+                Incoming::OutputClosed => {
+                    self.output_open = false;
+                    if let Some(result) = self.pending_exit.take() {
+                        self.on_exit(result)?;
+                    }
+                }
+                Incoming::Stopped(stop) => self.on_stop(stop)?,
+                Incoming::Exited(result) => {
+                    if self.output_open {
+                        self.pending_exit = Some(result);
+                    } else {
+                        self.on_exit(result)?;
+                    }
+                }
+            }
+        }
+        self.disconnect();
+        Ok(())
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        protocol::write_message(&mut self.output, &message)
+    }
+
+    /// Handles `request`, returning `false` if the adapter should shut down
+    fn handle_request(&mut self, request: Request) -> io::Result<bool> {
+        debug!("Request `{}`", request.command);
+        let arguments = &request.arguments;
+        let result = match &request.command[..] {
+            "initialize" => {
+                self.sources.initialize(arguments);
+                Ok(json!({
+                    "supportsConfigurationDoneRequest": true,
+                    "supportsFunctionBreakpoints": true,
+                }))
+            }
+            "launch" => self.launch(arguments),
+            "configurationDone" => {
+                self.configured = true;
+                Ok(Value::Null)
+            }
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setFunctionBreakpoints" => self.set_function_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(Value::Null),
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            "stackTrace" | "scopes" | "variables" => match self.commands {
+                // Answered by the program thread
+                Some(ref commands) if self.stopped => {
+                    let _ = commands.send(Command::Inspect(request));
+                    return Ok(true);
+                }
+                _ => Err("The program is not stopped".to_string()),
+            },
+            "continue" => self
+                .resume(Step::Continue)
+                .map(|()| json!({ "allThreadsContinued": true })),
+            "next" => self.resume(Step::Over).map(|()| Value::Null),
+            "stepIn" => self.resume(Step::In).map(|()| Value::Null),
+            "stepOut" => self.resume(Step::Out).map(|()| Value::Null),
+            "pause" => self.pause().map(|()| Value::Null),
+            "disconnect" => {
+                self.send(protocol::response(&request, Ok(Value::Null)))?;
+                return Ok(false);
+            }
+            command => Err(format!("Unknown command `{}`", command)),
+        };
+        if let Err(ref err) = result {
+            info!("Request `{}` failed: {}", request.command, err);
+        }
+        let success = result.is_ok();
+        self.send(protocol::response(&request, result))?;
+
+        if success {
+            match &request.command[..] {
+                // Breakpoints are only set once the program (and therefore how paths map to
+                // modules) is known
+                "launch" => {
+                    self.send(event("initialized", Value::Null))?;
+                    self.start()?;
+                }
+                "configurationDone" => self.start()?,
+                _ => (),
+            }
+        }
+        Ok(true)
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<Value, String> {
+        if self.program.is_some() || self.commands.is_some() {
+            return Err("A program has already been launched".to_string());
+        }
+        let path = arguments["program"]
+            .as_str()
+            .ok_or_else(|| "Expected `program` to be a string".to_string())?;
+        let path = Path::new(path);
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read `{}`: {}", path.display(), err))?;
+
+        if let Some(root) = path.parent() {
+            self.sources.set_root(if root.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                root
+            });
+        }
+        self.program = Some(Program {
+            name: self.sources.module_name(path),
+            source,
+        });
+        self.stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+        Ok(Value::Null)
+    }
+
+    /// Starts the program once it has been launched and configured
+    fn start(&mut self) -> io::Result<()> {
+        if !self.configured {
+            return Ok(());
+        }
+        let program = match self.program.take() {
+            Some(program) => program,
+            None => return Ok(()),
+        };
+        if self.stop_on_entry {
+            // Stepping would stop in the first module that is loaded, which may be a module of
+            // the standard library
+            let breakpoint = Breakpoint::Function(program.name.clone());
+            self.entry_breakpoint = Some(self.debugger.add_breakpoint(breakpoint));
+        }
+
+        let (commands, receiver) = mpsc::channel();
+        let debugger = self.debugger.clone();
+        let sender = self.sender.clone();
+        let close_output = self.close_output.take();
+        match runner::spawn(
+            program,
+            self.sources.clone(),
+            debugger,
+            sender,
+            receiver,
+            close_output,
+        ) {
+            Ok(()) => {
+                self.commands = Some(commands);
+                Ok(())
+            }
+            Err(err) => self.on_exit(Err(err.to_string())),
+        }
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let source = &arguments["source"];
+        let path = source["path"]
+            .as_str()
+            .ok_or_else(|| "Expected `source.path` to be a string".to_string())?;
+        let module = self.sources.module_name(Path::new(path));
+        for id in self.line_breakpoints.remove(&module).unwrap_or_default() {
+            self.debugger.remove_breakpoint(id);
+        }
+
+        let lines = match arguments["breakpoints"].as_array() {
+            Some(breakpoints) => breakpoints
+                .iter()
+                .map(|breakpoint| breakpoint["line"].as_i64())
+                .collect::<Option<Vec<_>>>(),
+            // `lines` is deprecated but may still be sent by older clients
+            None => arguments["lines"]
+                .as_array()
+                .map(|lines| lines.iter().filter_map(|line| line.as_i64()).collect()),
+        }
+        .ok_or_else(|| "Expected `breakpoints` to be an array of breakpoints".to_string())?;
+
+        let mut ids = Vec::new();
+        let breakpoints = lines
+            .into_iter()
+            .map(|line| match self.sources.to_line(line) {
+                Some(l) => {
+                    let id = self.debugger.add_breakpoint(Breakpoint::Line {
+                        source_name: module.clone(),
+                        line: l,
+                    });
+                    ids.push(id);
+                    json!({ "id": id, "verified": true, "line": line, "source": source })
+                }
+                None => json!({ "verified": false, "line": line, "message": "Invalid line" }),
+            })
+            .collect::<Vec<_>>();
+        self.line_breakpoints.insert(module, ids);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_function_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let names = arguments["breakpoints"]
+            .as_array()
+            .and_then(|breakpoints| {
+                breakpoints
+                    .iter()
+                    .map(|breakpoint| breakpoint["name"].as_str())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| "Expected `breakpoints` to be an array of breakpoints".to_string())?;
+        for id in self.function_breakpoints.drain(..) {
+            self.debugger.remove_breakpoint(id);
+        }
+
+        let mut breakpoints = Vec::new();
+        for name in names {
+            let id = self
+                .debugger
+                .add_breakpoint(Breakpoint::Function(name.to_string()));
+            self.function_breakpoints.push(id);
+            breakpoints.push(json!({ "id": id, "verified": true }));
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn resume(&mut self, step: Step) -> Result<(), String> {
+        match self.commands {
+            Some(ref commands) if self.stopped => {
+                self.stopped = false;
+                self.step_reason = "step";
+                let _ = commands.send(Command::Resume(step));
+                Ok(())
+            }
+            _ => Err("The program is not stopped".to_string()),
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), String> {
+        if self.commands.is_none() || self.stopped {
+            return Err("The program is not running".to_string());
+        }
+        // Stops at the next line that is executed
+        self.debugger.resume(Step::In);
+        self.step_reason = "pause";
+        Ok(())
+    }
+
+    fn on_stop(&mut self, stop: Stop) -> io::Result<()> {
+        self.stopped = true;
+        let mut body = json!({
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        match stop.reason {
+            StopReason::Breakpoint(id) if self.entry_breakpoint == Some(id) => {
+                self.debugger.remove_breakpoint(id);
+                self.entry_breakpoint = None;
+                body["reason"] = json!("entry");
+                return self.send(event("stopped", body));
+            }
+            StopReason::Breakpoint(id) => {
+                body["reason"] = if self.function_breakpoints.contains(&id) {
+                    json!("function breakpoint")
+                } else {
+                    json!("breakpoint")
+                };
+                body["hitBreakpointIds"] = json!([id]);
+            }
+            StopReason::Step => body["reason"] = json!(self.step_reason),
+        }
+        body["description"] = json!(stop.to_string());
+        self.send(event("stopped", body))
+    }
+
+    fn on_exit(&mut self, result: Result<(), String>) -> io::Result<()> {
+        self.commands = None;
+        self.stopped = false;
+        let exit_code = match result {
+            Ok(()) => 0,
+            Err(err) => {
+                self.send(event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("{}\n", err) }),
+                ))?;
+                1
+            }
+        };
+        self.send(event("exited", json!({ "exitCode": exit_code })))?;
+        self.send(event("terminated", Value::Null))
+    }
+
+    /// Lets a program which is still running finish without stopping again
+    fn disconnect(&mut self) {
+        for (id, _) in self.debugger.breakpoints() {
+            self.debugger.remove_breakpoint(id);
+        }
+        // Dropping the sender resumes a stopped program
+        self.commands = None;
+    }
+}
+
+fn read_requests<R>(mut input: R, sender: Sender<Incoming>)
+where
+    R: BufRead,
+{
+    loop {
+        let incoming = match protocol::read_message(&mut input) {
+            Ok(Some(content)) => {
+                debug!("Received: {}", content);
+                let request = serde_json::from_str(&content)
+                    .map_err(|err| err.to_string())
+                    .and_then(Request::from_json);
+                match request {
+                    Ok(request) => Incoming::Request(request),
+                    Err(err) => {
+                        error!("Invalid message: {}", err);
+                        continue;
+                    }
+                }
+            }
+            Ok(None) => Incoming::Closed,
+            Err(err) => Incoming::ReadError(err),
+        };
+        let done = !matches!(incoming, Incoming::Request(_));
+        if sender.send(incoming).is_err() || done {
+            return;
+        }
+    }
+}
+
+/// Runs the debug adapter, reading requests from `input` and writing responses and events to
+/// `output` until the client sends `disconnect` or closes `input`.
+pub fn start_adapter<R, W>(input: R, output: W) -> io::Result<()>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+{
+    DebugAdapter::new(output).run(input)
+}
@@ -0,0 +1,84 @@
+//! Debug adapter for the gluon programming language, communicating over stdin and stdout
+#![doc(html_root_url = "https://docs.rs/gluon_debug_adapter/0.13.1")] // # GLUON
+
+use std::{
+    fs::File,
+    io::{self, BufReader, Write},
+};
+
+use gluon_debug_adapter::DebugAdapter;
+
+/// The read end of the pipe which replaces stdout and a function which closes its write end
+type ProgramOutput = (File, fn());
+
+/// Moves the standard output of the process to a pipe so that anything printed by the debugged
+/// program is sent to the client as `output` events instead of corrupting the protocol. Returns
+/// the original standard output together with the pipe.
+#[cfg(unix)]
+fn capture_stdout() -> io::Result<(Box<dyn Write>, Option<ProgramOutput>)> {
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    // SAFETY Only file descriptors created here are wrapped in `File`s. Each descriptor is owned
+    // by a `File` as soon as it is created so that it is closed if a later call fails
+    unsafe {
+        let protocol = libc::dup(libc::STDOUT_FILENO);
+        if protocol < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let protocol = File::from_raw_fd(protocol);
+        let mut pipe = [0; 2];
+        if libc::pipe(pipe.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let (read, write) = (File::from_raw_fd(pipe[0]), File::from_raw_fd(pipe[1]));
+        if libc::dup2(write.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        drop(write);
+        Ok((
+            Box::new(io::BufWriter::new(protocol)),
+            Some((read, close_stdout)),
+        ))
+    }
+}
+
+/// Replaces the standard output with `/dev/null`, closing the last write end of the pipe
+#[cfg(unix)]
+fn close_stdout() {
+    let _ = io::stdout().flush();
+    // SAFETY Only replaces the standard output file descriptor
+    unsafe {
+        let null = libc::open(
+            b"/dev/null\0".as_ptr() as *const libc::c_char,
+            libc::O_WRONLY,
+        );
+        if null >= 0 {
+            libc::dup2(null, libc::STDOUT_FILENO);
+            libc::close(null);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn capture_stdout() -> io::Result<(Box<dyn Write>, Option<ProgramOutput>)> {
+    Ok((Box::new(io::stdout()), None))
+}
+
+fn run() -> io::Result<()> {
+    let (output, program_output) = capture_stdout()?;
+    let mut adapter = DebugAdapter::new(output);
+    if let Some((program_output, close_output)) = program_output {
+        adapter.forward_output(program_output, close_output);
+    }
+    adapter.run(BufReader::new(io::stdin()))
+}
+
+fn main() {
+    #[cfg(feature = "env_logger")]
+    env_logger::init();
+
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
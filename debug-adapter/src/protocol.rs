@@ -0,0 +1,197 @@
+//! Reading and writing of debug adapter protocol messages. The framing is the same as the one used
+//! by the language server protocol, a `Content-Length` header followed by a JSON body.
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+/// A request sent by the client
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    pub arguments: Value,
+}
+
+impl Request {
+    pub fn from_json(mut value: Value) -> Result<Request, String> {
+        let object = value
+            .as_object_mut()
+            .ok_or_else(|| "Expected a JSON object".to_string())?;
+        match object.get("type").and_then(|typ| typ.as_str()) {
+            Some("request") => (),
+            Some(typ) => return Err(format!("Expected a request, found `{}`", typ)),
+            None => return Err("Message is missing its `type`".to_string()),
+        }
+        let seq = object
+            .get("seq")
+            .and_then(|seq| seq.as_i64())
+            .ok_or_else(|| "Expected `seq` to be an integer".to_string())?;
+        let command = match object.remove("command") {
+            Some(Value::String(command)) => command,
+            _ => return Err("Expected `command` to be a string".to_string()),
+        };
+        Ok(Request {
+            seq,
+            command,
+            arguments: object.remove("arguments").unwrap_or(Value::Null),
+        })
+    }
+}
+
+/// Reads the content of the next message in `input`.
+///
+/// Returns `Ok(None)` if the input was closed before a new message started.
+pub fn read_message<R>(input: &mut R) -> io::Result<Option<String>>
+where
+    R: BufRead,
+{
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return if content_length.is_none() {
+                Ok(None)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Input ended in the middle of a message header",
+                ))
+            };
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        let mut iter = header.splitn(2, ':');
+        let name = iter.next().unwrap_or("").trim();
+        let value = iter.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(value.parse::<usize>().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid Content-Length `{}`: {}", value, err),
+                )
+            })?);
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes `message` to `output` prefixed by the `Content-Length` header
+pub fn write_message<W>(output: &mut W, message: &Value) -> io::Result<()>
+where
+    W: Write,
+{
+    let content = message.to_string();
+    debug!("Sending: {}", content);
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+/// Creates the response to `request`. The `seq` of the message is assigned when it is sent.
+pub fn response(request: &Request, result: Result<Value, String>) -> Value {
+    match result {
+        Ok(body) => {
+            let mut message = json!({
+                "type": "response",
+                "request_seq": request.seq,
+                "success": true,
+                "command": request.command,
+            });
+            if !body.is_null() {
+                message["body"] = body;
+            }
+            message
+        }
+        Err(message) => json!({
+            "type": "response",
+            "request_seq": request.seq,
+            "success": false,
+            "command": request.command,
+            "message": message,
+        }),
+    }
+}
+
+/// Creates an event. The `seq` of the message is assigned when it is sent.
+pub fn event(event: &str, body: Value) -> Value {
+    let mut message = json!({
+        "type": "event",
+        "event": event,
+    });
+    if !body.is_null() {
+        message["body"] = body;
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_framed_messages() {
+        let input = "Content-Length: 2\r\n\r\n{}Content-Length: 8\r\n\r\n{\"a\":12}";
+        let mut input = input.as_bytes();
+        assert_eq!(read_message(&mut input).unwrap(), Some("{}".to_string()));
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some("{\"a\":12}".to_string())
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_request() {
+        assert_eq!(
+            Request::from_json(json!({
+                "seq": 3,
+                "type": "request",
+                "command": "threads",
+            })),
+            Ok(Request {
+                seq: 3,
+                command: "threads".into(),
+                arguments: Value::Null,
+            })
+        );
+        assert!(Request::from_json(json!({ "seq": 1, "type": "event" })).is_err());
+        assert!(Request::from_json(json!({ "type": "request", "command": "a" })).is_err());
+    }
+
+    #[test]
+    fn encode_response() {
+        let request = Request {
+            seq: 2,
+            command: "next".into(),
+            arguments: Value::Null,
+        };
+        assert_eq!(
+            response(&request, Err("Not stopped".into())),
+            json!({
+                "type": "response",
+                "request_seq": 2,
+                "success": false,
+                "command": "next",
+                "message": "Not stopped",
+            })
+        );
+    }
+}
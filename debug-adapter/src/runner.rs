@@ -0,0 +1,277 @@
+//! Runs the debugged program on its own thread. While the program is stopped that thread answers
+//! the requests which inspect the stack since the stack can only be accessed from the stop
+//! handler.
+
+use std::{
+    io,
+    sync::{
+        mpsc::{Receiver, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+use serde_json::Value;
+
+use gluon::{
+    base::types::ArcType,
+    import::Import,
+    vm::{
+        api::{Hole, OpaqueValue},
+        debugger::{self, Debugger, Step},
+        thread::DebugInfo,
+        Variants,
+    },
+    RootedThread, Thread, ThreadExt,
+};
+
+use crate::{
+    protocol::{self, Request},
+    source::Sources,
+    Incoming,
+};
+
+/// Sent to the program thread while it is stopped
+pub(crate) enum Command {
+    /// A `stackTrace`, `scopes` or `variables` request
+    Inspect(Request),
+    Resume(Step),
+}
+
+pub(crate) struct Program {
+    pub name: String,
+    pub source: String,
+}
+
+/// Starts running `program` with `debugger` attached
+pub(crate) fn spawn(
+    program: Program,
+    sources: Sources,
+    debugger: Debugger,
+    events: Sender<Incoming>,
+    commands: Receiver<Command>,
+    close_output: Option<Box<dyn FnOnce() + Send>>,
+) -> io::Result<()> {
+    thread::Builder::new()
+        .name("gluon_program".to_string())
+        .spawn(move || {
+            let thread = gluon::new_vm();
+            thread.run_io(true);
+            // Optimizations may inline or remove the code that breakpoints refer to
+            thread.get_database_mut().set_optimize(false);
+            if let Some(import) = thread.get_macros().get("import") {
+                if let Some(import) = import.downcast_ref::<Import>() {
+                    import.add_path(sources.root());
+                }
+            }
+
+            debugger.attach(&thread);
+            {
+                let events = Mutex::new(events.clone());
+                let commands = Mutex::new(commands);
+                debugger.set_stop_handler(Some(Box::new(move |_, thread, info, stop| {
+                    let send = |incoming| {
+                        let _ = events.lock().unwrap().send(incoming);
+                    };
+                    send(Incoming::Stopped(stop.clone()));
+
+                    let mut inspector = Inspector {
+                        thread,
+                        info,
+                        sources: &sources,
+                        references: Vec::new(),
+                    };
+                    let commands = commands.lock().unwrap();
+                    loop {
+                        match commands.recv() {
+                            Ok(Command::Inspect(request)) => {
+                                let result = inspector.inspect(&request);
+                                send(Incoming::Message(protocol::response(&request, result)));
+                            }
+                            Ok(Command::Resume(step)) => return step,
+                            // The client disconnected, let the program run to completion
+                            Err(_) => return Step::Continue,
+                        }
+                    }
+                })));
+            }
+
+            let result = thread
+                .run_expr::<OpaqueValue<RootedThread, Hole>>(&program.name, &program.source)
+                .map(|_| ())
+                .map_err(|err| err.to_string());
+            if let Some(close_output) = close_output {
+                close_output();
+            }
+            let _ = events.send(Incoming::Exited(result));
+        })
+        .map(|_| ())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Scope {
+    Locals,
+    Upvars,
+}
+
+/// Locates a list of variables in a stopped program. The list is found by taking the variables of
+/// `scope` and then repeatedly taking the children of the variable at each index in `path`.
+#[derive(Clone, Debug)]
+struct Reference {
+    frame: usize,
+    scope: Scope,
+    path: Vec<usize>,
+}
+
+struct Inspector<'a, 'b> {
+    thread: &'a Thread,
+    info: &'a DebugInfo<'b>,
+    sources: &'a Sources,
+    // `variablesReference` values are indexes into this vector, offset by one since `0` means
+    // that a variable does not have any children
+    references: Vec<Reference>,
+}
+
+impl Inspector<'_, '_> {
+    fn inspect(&mut self, request: &Request) -> Result<Value, String> {
+        match &request.command[..] {
+            "stackTrace" => self.stack_trace(&request.arguments),
+            "scopes" => self.scopes(&request.arguments),
+            "variables" => self.variables(&request.arguments),
+            command => Err(format!("Unknown command `{}`", command)),
+        }
+    }
+
+    fn add_reference(&mut self, reference: Reference) -> usize {
+        self.references.push(reference);
+        self.references.len()
+    }
+
+    fn stack_trace(&mut self, arguments: &Value) -> Result<Value, String> {
+        // Frames without any debug information (such as the frame which started the execution)
+        // are not shown
+        let levels = (0..self.info.stack_info_len())
+            .filter(|&level| {
+                self.info
+                    .stack_info(level)
+                    .is_some_and(|frame| frame.function_name().is_some())
+            })
+            .collect::<Vec<_>>();
+        let start = arguments["startFrame"].as_u64().unwrap_or(0) as usize;
+        let count = match arguments["levels"].as_u64() {
+            None | Some(0) => levels.len(),
+            Some(count) => count as usize,
+        };
+        let frames = levels
+            .iter()
+            .skip(start)
+            .take(count)
+            .filter_map(|&level| {
+                let frame = self.info.stack_info(level)?;
+                Some(json!({
+                    "id": level,
+                    "name": frame.function_name()?,
+                    "source": self.sources.source(frame.source_name()),
+                    "line": frame.line().map_or(0, |line| self.sources.from_line(line)),
+                    "column": self.sources.first_column(),
+                }))
+            })
+            .collect::<Vec<_>>();
+        Ok(json!({
+            "stackFrames": frames,
+            "totalFrames": levels.len(),
+        }))
+    }
+
+    fn scopes(&mut self, arguments: &Value) -> Result<Value, String> {
+        let level = arguments["frameId"]
+            .as_u64()
+            .ok_or_else(|| "Expected `frameId` to be an integer".to_string())?
+            as usize;
+        let info = self.info;
+        let frame = info
+            .stack_info(level)
+            .ok_or_else(|| format!("Unknown frame `{}`", level))?;
+
+        let mut scopes = vec![json!({
+            "name": "Locals",
+            "presentationHint": "locals",
+            "variablesReference": self.add_reference(Reference {
+                frame: level,
+                scope: Scope::Locals,
+                path: Vec::new(),
+            }),
+            "expensive": false,
+        })];
+        if frame.upvar_values().next().is_some() {
+            scopes.push(json!({
+                "name": "Upvars",
+                "variablesReference": self.add_reference(Reference {
+                    frame: level,
+                    scope: Scope::Upvars,
+                    path: Vec::new(),
+                }),
+                "expensive": false,
+            }));
+        }
+        Ok(json!({ "scopes": scopes }))
+    }
+
+    fn variables(&mut self, arguments: &Value) -> Result<Value, String> {
+        let id = arguments["variablesReference"]
+            .as_u64()
+            .ok_or_else(|| "Expected `variablesReference` to be an integer".to_string())?;
+        let reference = (id as usize)
+            .checked_sub(1)
+            .and_then(|index| self.references.get(index))
+            .cloned()
+            .ok_or_else(|| format!("Unknown variablesReference `{}`", id))?;
+
+        let (thread, info) = (self.thread, self.info);
+        let frame = info
+            .stack_info(reference.frame)
+            .ok_or_else(|| format!("Unknown frame `{}`", reference.frame))?;
+        let mut variables: Vec<(String, ArcType, Variants)> = match reference.scope {
+            Scope::Locals => frame
+                .local_values()
+                .map(|(local, value)| {
+                    let name = local.name.declared_name().to_string();
+                    (name, local.typ.clone(), value)
+                })
+                .collect(),
+            Scope::Upvars => frame
+                .upvar_values()
+                .map(|(upvar, value)| (upvar.name.clone(), upvar.typ.clone(), value))
+                .collect(),
+        };
+        for &index in &reference.path {
+            let (_, typ, value) = variables
+                .into_iter()
+                .nth(index)
+                .ok_or_else(|| "The variable does not exist anymore".to_string())?;
+            variables = debugger::children(thread, &typ, value);
+        }
+
+        let mut result = Vec::with_capacity(variables.len());
+        for (i, (name, typ, value)) in variables.into_iter().enumerate() {
+            // Children are only computed once the client asks for them
+            let variables_reference = if !debugger::has_children(value.clone()) {
+                0
+            } else {
+                let mut path = reference.path.clone();
+                path.push(i);
+                self.add_reference(Reference {
+                    path,
+                    ..reference.clone()
+                })
+            };
+            result.push(json!({
+                "name": name,
+                "value": debugger::format_value(thread, &typ, value),
+                "type": typ.to_string(),
+                "variablesReference": variables_reference,
+            }));
+        }
+        Ok(json!({ "variables": result }))
+    }
+}
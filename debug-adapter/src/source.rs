@@ -0,0 +1,90 @@
+//! Conversions between the file paths and lines used by the client and the module names and lines
+//! used by gluon.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use gluon::base::{filename_to_module, pos::Line};
+
+#[derive(Clone, Debug)]
+pub struct Sources {
+    /// The directory which module names are relative to
+    root: PathBuf,
+    lines_start_at1: bool,
+    columns_start_at1: bool,
+}
+
+impl Default for Sources {
+    fn default() -> Sources {
+        Sources {
+            root: PathBuf::from("."),
+            lines_start_at1: true,
+            columns_start_at1: true,
+        }
+    }
+}
+
+impl Sources {
+    /// Reads the `linesStartAt1` and `columnsStartAt1` arguments of the `initialize` request
+    pub fn initialize(&mut self, arguments: &Value) {
+        let flag = |name| {
+            arguments
+                .get(name)
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true)
+        };
+        self.lines_start_at1 = flag("linesStartAt1");
+        self.columns_start_at1 = flag("columnsStartAt1");
+    }
+
+    /// Makes module names relative to `root`
+    pub fn set_root(&mut self, root: &Path) {
+        self.root = root.canonicalize().unwrap_or_else(|_| root.to_owned());
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the name of the module stored at `path`
+    pub fn module_name(&self, path: &Path) -> String {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+        let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+        filename_to_module(&relative.to_string_lossy())
+    }
+
+    /// Returns a `Source` object for `module`, with a path if the module exists as a file
+    pub fn source(&self, module: &str) -> Value {
+        let path = self.root.join(format!("{}.glu", module.replace('.', "/")));
+        if path.is_file() {
+            json!({ "name": module, "path": path })
+        } else {
+            json!({ "name": module })
+        }
+    }
+
+    /// Converts a line number sent by the client
+    pub fn to_line(&self, line: i64) -> Option<Line> {
+        let line = if self.lines_start_at1 { line - 1 } else { line };
+        if line < 0 {
+            None
+        } else {
+            Some(Line::from(line as u32))
+        }
+    }
+
+    /// Converts `line` to the line number expected by the client
+    pub fn from_line(&self, line: Line) -> i64 {
+        i64::from(line.to_usize() as u32) + if self.lines_start_at1 { 1 } else { 0 }
+    }
+
+    /// Returns the first column of a line, which is the only column reported by the adapter
+    pub fn first_column(&self) -> i64 {
+        if self.columns_start_at1 {
+            1
+        } else {
+            0
+        }
+    }
+}
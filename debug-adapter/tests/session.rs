@@ -0,0 +1,149 @@
+//! Replays the recorded sessions in `tests/sessions` against the `gluon_debug_adapter` binary.
+//!
+//! A session is a JSON array where each entry either sends a request (`{ "send": REQUEST }`) or
+//! expects a message from the adapter (`{ "receive": MESSAGE }`). Received messages only need to
+//! contain the fields of the expected message, so uninteresting fields can be left out. `output`
+//! events are skipped unless an `output` event is expected since they may arrive at any time.
+//!
+//! Running the tests with `RECORD_DAP_SESSIONS=1` overwrites the expected messages with the
+//! messages that were actually received.
+
+#[macro_use]
+extern crate serde_json;
+
+use std::{
+    env, fs,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::Value;
+
+use gluon_debug_adapter::protocol::{read_message, write_message};
+
+fn adapter_path() -> PathBuf {
+    let path = env::args().next().unwrap();
+    PathBuf::from(path)
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("folder")
+        .join("gluon_debug_adapter")
+}
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    seq: i64,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(adapter_path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|err| panic!("{}: {}", err, adapter_path().display()));
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Client {
+            child,
+            stdin,
+            stdout,
+            seq: 0,
+        }
+    }
+
+    fn send(&mut self, mut request: Value) {
+        self.seq += 1;
+        request["seq"] = json!(self.seq);
+        request["type"] = json!("request");
+        write_message(&mut self.stdin, &request).unwrap();
+    }
+
+    /// Reads the next message, without its `seq` since it depends on how many `output` events
+    /// were sent
+    fn read(&mut self) -> Value {
+        let content = read_message(&mut self.stdout)
+            .unwrap()
+            .expect("The adapter closed its output");
+        let mut message: Value = serde_json::from_str(&content).unwrap();
+        message.as_object_mut().unwrap().remove("seq");
+        message
+    }
+}
+
+fn is_output(message: &Value) -> bool {
+    message["type"] == "event" && message["event"] == "output"
+}
+
+/// Returns `true` if `actual` contains everything in `expected`
+fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| matches(value, a))),
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| matches(e, a))
+        }
+        _ => expected == actual,
+    }
+}
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+fn replay(name: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sessions")
+        .join(name);
+    let fixtures = fixtures().to_string_lossy().into_owned();
+    let session = fs::read_to_string(&path).unwrap();
+    let mut session: Vec<Value> =
+        serde_json::from_str(&session.replace("${fixtures}", &fixtures)).unwrap();
+    let record = env::var_os("RECORD_DAP_SESSIONS").is_some();
+
+    let mut client = Client::start();
+    for entry in &mut session {
+        if let Some(request) = entry.get("send") {
+            client.send(request.clone());
+            continue;
+        }
+        let expected = &mut entry["receive"];
+        let mut actual = client.read();
+        while is_output(&actual) && !is_output(expected) {
+            actual = client.read();
+        }
+        if record {
+            *expected = actual;
+        } else if !matches(expected, &actual) {
+            pretty_assertions::assert_eq!(*expected, actual);
+        }
+    }
+
+    let status = client.child.wait().unwrap();
+    assert!(status.success());
+
+    if record {
+        let session = serde_json::to_string_pretty(&session).unwrap();
+        fs::write(&path, session.replace(&fixtures, "${fixtures}") + "\n").unwrap();
+    }
+}
+
+#[test]
+fn breakpoints_and_variables() {
+    replay("breakpoints.json");
+}
+
+#[test]
+fn stop_on_entry_and_output() {
+    replay("entry.json");
+}
+
+#[test]
+fn launch_errors() {
+    replay("errors.json");
+}
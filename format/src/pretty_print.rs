@@ -342,6 +342,10 @@ where
                     chain![arena;
                         "| ",
                         self.pretty_pattern(&alt.pattern),
+                        match alt.guard {
+                            Some(ref guard) => chain![arena; " if ", pretty(guard).group()],
+                            None => arena.nil(),
+                        },
                         " ->",
                         self.hang(arena.nil(), &alt.expr).group()
                     ]
@@ -834,6 +838,16 @@ where
                 ")"
             ]
             .group(),
+            Pattern::Or(ref alternatives) => prec.enclose(
+                Prec::Constructor,
+                arena,
+                arena.concat(alternatives.iter().enumerate().map(|(i, alternative)| {
+                    chain![arena;
+                        if i == 0 { arena.nil() } else { arena.text(" | ") },
+                        self.pretty_pattern_(alternative, Prec::Top)
+                    ]
+                })),
+            ),
             Pattern::Error => arena.text("<error>"),
             Pattern::Literal(_) => arena.text(self.source.src_slice(pattern.span)),
         }
@@ -261,6 +261,26 @@ match None with
     assert_eq!(&format_expr(expr).unwrap(), expr);
 }
 
+#[test]
+fn pattern_guard() {
+    let expr = r#"
+match x with
+| Some y if y > 0 -> y
+| _ -> 123
+"#;
+    assert_eq!(&format_expr(expr).unwrap(), expr);
+}
+
+#[test]
+fn or_pattern() {
+    let expr = r#"
+match x with
+| Some (1 | 2) | None -> 1
+| Some _ -> 123
+"#;
+    assert_eq!(&format_expr(expr).unwrap(), expr);
+}
+
 #[test]
 fn long_pattern_match() {
     let expr = r#"
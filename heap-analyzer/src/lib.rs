@@ -0,0 +1,325 @@
+//! Analysis of heap snapshots taken from a gluon virtual machine.
+//!
+//! Snapshots are written by `gluon::vm::gc::snapshot::HeapSnapshot::write_json`, the format is
+//! described in the documentation of the `gluon::vm::gc::snapshot` module. The analysis computes
+//! the dominator tree of the snapshot, where a value dominates another value if every path from the
+//! roots to the other value goes through it. The retained size of a value is the number of bytes
+//! which would be freed if the value was freed, that is, the size of the value and of every value
+//! it dominates.
+#![doc(html_root_url = "https://docs.rs/gluon_heap_analyzer/0.13.1")] // # GLUON
+
+#[macro_use]
+extern crate serde_derive;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Read, Write},
+};
+
+/// The snapshot format versions which can be analyzed
+pub const SUPPORTED_VERSION: u32 = 1;
+
+/// The id of the node which represents the roots
+pub const ROOT: usize = 0;
+
+#[derive(Debug, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Node {
+    pub id: usize,
+    pub name: String,
+    pub size: usize,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub fields: Vec<String>,
+    #[serde(default)]
+    pub external: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    UnsupportedVersion(u32),
+    InvalidNode { index: usize, id: usize },
+    InvalidEdge { from: usize, to: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Json(err) => write!(f, "Invalid snapshot: {}", err),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "Snapshots of version {} are not supported (expected version {})",
+                version, SUPPORTED_VERSION
+            ),
+            Error::InvalidNode { index, id } => write!(
+                f,
+                "The node at index {} has the id {}, ids must be equal to the index of the node",
+                index, id
+            ),
+            Error::InvalidEdge { from, to } => write!(
+                f,
+                "The edge from {} to {} refers to a node which does not exist",
+                from, to
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl Snapshot {
+    /// Reads and validates a JSON snapshot
+    pub fn from_reader<R>(reader: R) -> Result<Snapshot, Error>
+    where
+        R: Read,
+    {
+        let snapshot: Snapshot = serde_json::from_reader(reader)?;
+        if snapshot.version != SUPPORTED_VERSION {
+            return Err(Error::UnsupportedVersion(snapshot.version));
+        }
+        for (index, node) in snapshot.nodes.iter().enumerate() {
+            if node.id != index {
+                return Err(Error::InvalidNode { index, id: node.id });
+            }
+        }
+        for edge in &snapshot.edges {
+            if edge.from >= snapshot.nodes.len() || edge.to >= snapshot.nodes.len() {
+                return Err(Error::InvalidEdge {
+                    from: edge.from,
+                    to: edge.to,
+                });
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Returns a short description of a node, such as `#12 { x, y }`
+    pub fn describe(&self, id: usize) -> String {
+        format!("#{} {}", id, self.nodes[id].name)
+    }
+}
+
+/// The dominator tree and retained sizes of a snapshot.
+#[derive(Debug)]
+pub struct Analysis {
+    /// The immediate dominator of each node. `None` for the root and for unreachable nodes
+    pub dominators: Vec<Option<usize>>,
+    /// The retained size of each node. `0` for unreachable nodes
+    pub retained: Vec<usize>,
+}
+
+impl Analysis {
+    /// Computes the dominator tree with the algorithm from "A Simple, Fast Dominance Algorithm" by
+    /// Cooper, Harvey and Kennedy
+    pub fn new(snapshot: &Snapshot) -> Analysis {
+        let len = snapshot.nodes.len();
+        if len == 0 {
+            return Analysis {
+                dominators: Vec::new(),
+                retained: Vec::new(),
+            };
+        }
+
+        let mut successors = vec![Vec::new(); len];
+        let mut predecessors = vec![Vec::new(); len];
+        for edge in &snapshot.edges {
+            successors[edge.from].push(edge.to);
+            predecessors[edge.to].push(edge.from);
+        }
+
+        // Depth first search from the root, recording the post order of each reachable node
+        let mut postorder = Vec::with_capacity(len);
+        let mut postorder_index = vec![None; len];
+        let mut visited = vec![false; len];
+        let mut stack = vec![(ROOT, 0)];
+        visited[ROOT] = true;
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            match successors[node].get(*next) {
+                Some(&successor) => {
+                    *next += 1;
+                    if !visited[successor] {
+                        visited[successor] = true;
+                        stack.push((successor, 0));
+                    }
+                }
+                None => {
+                    postorder_index[node] = Some(postorder.len());
+                    postorder.push(node);
+                    stack.pop();
+                }
+            }
+        }
+
+        let mut dominators = vec![None; len];
+        dominators[ROOT] = Some(ROOT);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in postorder.iter().rev().skip(1) {
+                let mut new_dominator = None;
+                for &predecessor in &predecessors[node] {
+                    if dominators[predecessor].is_none() {
+                        continue;
+                    }
+                    new_dominator = Some(match new_dominator {
+                        None => predecessor,
+                        Some(current) => {
+                            intersect(&dominators, &postorder_index, predecessor, current)
+                        }
+                    });
+                }
+                if new_dominator.is_some() && dominators[node] != new_dominator {
+                    dominators[node] = new_dominator;
+                    changed = true;
+                }
+            }
+        }
+        dominators[ROOT] = None;
+
+        // A node always comes after the nodes it dominates in the post order
+        let mut retained = vec![0; len];
+        for &node in &postorder {
+            retained[node] += snapshot.nodes[node].size;
+            if let Some(dominator) = dominators[node] {
+                retained[dominator] += retained[node];
+            }
+        }
+
+        Analysis {
+            dominators,
+            retained,
+        }
+    }
+
+    /// Returns the nodes which dominate `node`, starting from the root and ending with `node`
+    /// itself. Every path from the roots to `node` goes through all of these nodes.
+    pub fn dominator_path(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        let mut current = node;
+        while let Some(dominator) = self.dominators[current] {
+            path.push(dominator);
+            current = dominator;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns the reachable nodes, excluding the root, ordered by their retained size
+    pub fn largest_retained(&self) -> Vec<usize> {
+        let mut nodes: Vec<_> = (0..self.retained.len())
+            .filter(|&node| self.dominators[node].is_some())
+            .collect();
+        nodes.sort_by(|&l, &r| self.retained[r].cmp(&self.retained[l]).then(l.cmp(&r)));
+        nodes
+    }
+}
+
+fn intersect(
+    dominators: &[Option<usize>],
+    postorder_index: &[Option<usize>],
+    mut l: usize,
+    mut r: usize,
+) -> usize {
+    let index = |node: usize| postorder_index[node].expect("Reachable node");
+    while l != r {
+        while index(l) < index(r) {
+            l = dominators[l].expect("Processed node");
+        }
+        while index(r) < index(l) {
+            r = dominators[r].expect("Processed node");
+        }
+    }
+    l
+}
+
+/// Writes a report of the `top` values with the largest retained sizes, including the path of
+/// dominators leading to each of them, followed by the `top` types using the most memory.
+pub fn report<W>(snapshot: &Snapshot, analysis: &Analysis, top: usize, mut out: W) -> io::Result<()>
+where
+    W: Write,
+{
+    let reachable: Vec<_> = (0..snapshot.nodes.len())
+        .filter(|&node| analysis.dominators[node].is_some())
+        .collect();
+    let total: usize = reachable
+        .iter()
+        .map(|&node| snapshot.nodes[node].size)
+        .sum();
+    let external = reachable
+        .iter()
+        .filter(|&&node| snapshot.nodes[node].external)
+        .count();
+    writeln!(
+        out,
+        "{} values ({} external) using {} bytes are reachable from the roots",
+        reachable.len(),
+        external,
+        total
+    )?;
+
+    writeln!(out)?;
+    writeln!(out, "Largest retained sizes")?;
+    writeln!(out, "{:>12} {:>12}  Value", "Retained", "Shallow")?;
+    for node in analysis.largest_retained().into_iter().take(top) {
+        writeln!(
+            out,
+            "{:>12} {:>12}  {}",
+            analysis.retained[node],
+            snapshot.nodes[node].size,
+            snapshot.describe(node)
+        )?;
+        let path: Vec<_> = analysis
+            .dominator_path(node)
+            .into_iter()
+            .map(|node| snapshot.describe(node))
+            .collect();
+        writeln!(out, "{:>27}{}", "", path.join(" -> "))?;
+    }
+
+    let mut types = BTreeMap::new();
+    for &node in &reachable {
+        let node = &snapshot.nodes[node];
+        let entry = types.entry(&node.name[..]).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += node.size;
+    }
+    let mut types: Vec<_> = types.into_iter().collect();
+    types.sort_by_key(|&(_, (_, size))| std::cmp::Reverse(size));
+
+    writeln!(out)?;
+    writeln!(out, "Types by shallow size")?;
+    writeln!(out, "{:>12} {:>12}  Type", "Count", "Bytes")?;
+    for (name, (count, size)) in types.into_iter().take(top) {
+        writeln!(out, "{:>12} {:>12}  {}", count, size, name)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+//! Reports the retained sizes and dominator paths of a heap snapshot taken from a gluon virtual
+//! machine.
+//!
+//! Usage: `gluon_heap_analyzer [--top N] SNAPSHOT`
+#![doc(html_root_url = "https://docs.rs/gluon_heap_analyzer/0.13.1")] // # GLUON
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufReader},
+};
+
+use gluon_heap_analyzer::{report, Analysis, Snapshot};
+
+const USAGE: &str = "Usage: gluon_heap_analyzer [--top N] SNAPSHOT";
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut top = 20;
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--top" => {
+                top = args
+                    .next()
+                    .ok_or("Expected a number after `--top`")?
+                    .parse()?;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+
+    let snapshot = Snapshot::from_reader(BufReader::new(File::open(&path)?))?;
+    let analysis = Analysis::new(&snapshot);
+
+    let stdout = io::stdout();
+    report(&snapshot, &analysis, top, stdout.lock())?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
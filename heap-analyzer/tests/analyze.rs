@@ -0,0 +1,135 @@
+use gluon::{
+    new_vm,
+    vm::api::{Hole, OpaqueValue},
+    Thread, ThreadExt,
+};
+
+use gluon_heap_analyzer::{report, Analysis, Error, Snapshot, ROOT};
+
+// (roots) -> a -> b -> d
+//         |    \-> c -/
+//         \-> e
+// f is unreachable
+const DIAMOND: &str = r#"
+{
+    "version": 1,
+    "nodes": [
+        { "id": 0, "name": "(roots)", "size": 0, "tag": null, "fields": [], "external": false },
+        { "id": 1, "name": "a", "size": 10, "tag": null, "fields": [], "external": false },
+        { "id": 2, "name": "b", "size": 20, "tag": null, "fields": [], "external": false },
+        { "id": 3, "name": "c", "size": 30, "tag": null, "fields": [], "external": false },
+        { "id": 4, "name": "d", "size": 40, "tag": null, "fields": [], "external": false },
+        { "id": 5, "name": "e", "size": 5, "tag": null, "fields": [], "external": true },
+        { "id": 6, "name": "f", "size": 7, "tag": null, "fields": [], "external": false }
+    ],
+    "edges": [
+        { "from": 0, "to": 1 },
+        { "from": 1, "to": 2 },
+        { "from": 1, "to": 3 },
+        { "from": 2, "to": 4 },
+        { "from": 3, "to": 4 },
+        { "from": 0, "to": 5 }
+    ]
+}
+"#;
+
+#[test]
+fn dominators_and_retained_sizes() {
+    let snapshot = Snapshot::from_reader(DIAMOND.as_bytes()).unwrap();
+    let analysis = Analysis::new(&snapshot);
+
+    assert_eq!(
+        analysis.dominators,
+        [None, Some(0), Some(1), Some(1), Some(1), Some(0), None]
+    );
+    assert_eq!(analysis.retained, [105, 100, 20, 30, 40, 5, 0]);
+    assert_eq!(analysis.dominator_path(4), [0, 1, 4]);
+    assert_eq!(analysis.largest_retained(), [1, 4, 3, 2, 5]);
+}
+
+#[test]
+fn report_lists_largest_values_and_types() {
+    let snapshot = Snapshot::from_reader(DIAMOND.as_bytes()).unwrap();
+    let analysis = Analysis::new(&snapshot);
+
+    let mut out = Vec::new();
+    report(&snapshot, &analysis, 2, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    let expected = r#"5 values (1 external) using 105 bytes are reachable from the roots
+
+Largest retained sizes
+    Retained      Shallow  Value
+         100           10  #1 a
+                           #0 (roots) -> #1 a
+          40           40  #4 d
+                           #0 (roots) -> #1 a -> #4 d
+
+Types by shallow size
+       Count        Bytes  Type
+           1           40  d
+           1           30  c
+"#;
+    assert_eq!(out, expected);
+}
+
+#[test]
+fn unsupported_version() {
+    let json = DIAMOND.replace(r#""version": 1"#, r#""version": 2"#);
+    match Snapshot::from_reader(json.as_bytes()) {
+        Err(Error::UnsupportedVersion(2)) => (),
+        result => panic!("Expected an unsupported version error, got {:?}", result),
+    }
+}
+
+#[test]
+fn invalid_edge() {
+    let json = DIAMOND.replace(r#"{ "from": 0, "to": 5 }"#, r#"{ "from": 0, "to": 7 }"#);
+    match Snapshot::from_reader(json.as_bytes()) {
+        Err(Error::InvalidEdge { from: 0, to: 7 }) => (),
+        result => panic!("Expected an invalid edge error, got {:?}", result),
+    }
+}
+
+#[test]
+fn analyze_vm_snapshot() {
+    let vm = new_vm();
+    vm.get_database_mut().set_implicit_prelude(false);
+    let (value, _) = vm
+        .run_expr::<OpaqueValue<&Thread, Hole>>(
+            "test",
+            r#"
+                let make x = { x, y = x #Int+ 1 }
+                [make 1, make 2]
+            "#,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let mut json = Vec::new();
+    vm.heap_snapshot().write_json(&mut json).unwrap();
+    drop(value);
+
+    let snapshot = Snapshot::from_reader(&json[..]).unwrap();
+    let analysis = Analysis::new(&snapshot);
+
+    let reachable_size: usize = snapshot
+        .nodes
+        .iter()
+        .filter(|node| analysis.dominators[node.id].is_some())
+        .map(|node| node.size)
+        .sum();
+    assert_eq!(analysis.retained[ROOT], reachable_size);
+
+    let records: Vec<_> = snapshot
+        .nodes
+        .iter()
+        .filter(|node| node.name == "{ x, y }")
+        .collect();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        let path = analysis.dominator_path(record.id);
+        assert_eq!(path[0], ROOT);
+        assert_eq!(*path.last().unwrap(), record.id);
+        assert_eq!(analysis.retained[record.id], record.size);
+    }
+}
@@ -0,0 +1,89 @@
+//! Conversion of gluon errors and warnings into language server diagnostics.
+
+use codespan::FileMap;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+
+use gluon::{
+    base::{
+        error::InFile,
+        pos::{BytePos, Span, Spanned},
+    },
+    check::warning::SpannedWarning,
+    Error,
+};
+
+use crate::location::span_to_range;
+
+/// Converts `error` into diagnostics for the document in `file_map`.
+///
+/// Errors which do not originate in `file_map` (such as errors in an imported module) are reported
+/// at the start of the document.
+pub fn error_to_diagnostics(file_map: &FileMap, error: &Error) -> Vec<Diagnostic> {
+    let mut errors = Vec::new();
+    collect_errors(error, &mut errors);
+
+    errors
+        .into_iter()
+        .map(|(span, message)| {
+            let range = span
+                .filter(|span| file_map.span().contains(*span))
+                .and_then(|span| span_to_range(file_map, span).ok());
+            let message = match (range.is_some(), span) {
+                (false, Some(_)) => format!("Error in imported module: {}", message),
+                _ => message,
+            };
+            Diagnostic {
+                range: range.unwrap_or_else(|| Range {
+                    start: Position::new(0, 0),
+                    end: Position::new(0, 0),
+                }),
+                severity: Some(DiagnosticSeverity::Error),
+                source: Some("gluon".into()),
+                message,
+                ..Diagnostic::default()
+            }
+        })
+        .collect()
+}
+
+/// Converts the `warnings` found in the document in `file_map` into diagnostics.
+pub fn warnings_to_diagnostics(file_map: &FileMap, warnings: &[SpannedWarning]) -> Vec<Diagnostic> {
+    warnings
+        .iter()
+        .filter_map(|warning| {
+            Some(Diagnostic {
+                range: span_to_range(file_map, warning.span).ok()?,
+                severity: Some(DiagnosticSeverity::Warning),
+                source: Some("gluon".into()),
+                message: warning.value.to_string(),
+                ..Diagnostic::default()
+            })
+        })
+        .collect()
+}
+
+fn collect_errors(error: &Error, errors: &mut Vec<(Option<Span<BytePos>>, String)>) {
+    fn in_file<E>(err: &InFile<E>, errors: &mut Vec<(Option<Span<BytePos>>, String)>)
+    where
+        E: std::fmt::Display,
+    {
+        errors.extend(
+            err.errors()
+                .iter()
+                .map(|err: &Spanned<E, BytePos>| (Some(err.span), err.value.to_string())),
+        );
+    }
+
+    match error {
+        Error::Parse(err) => in_file(err, errors),
+        Error::Typecheck(err) => in_file(err, errors),
+        Error::Macro(err) => in_file(err, errors),
+        Error::Multiple(multiple) => {
+            for err in multiple {
+                collect_errors(err, errors);
+            }
+        }
+        err => errors.push((None, err.to_string())),
+    }
+}
@@ -0,0 +1,405 @@
+//! Request handlers which query the typechecked expression of a document through
+//! `gluon_completion`.
+
+use std::sync::Arc;
+
+use gluon::either::Either;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, DocumentSymbol, Hover, HoverContents, Location,
+    MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, Position, SignatureHelp,
+    SignatureInformation, SymbolInformation, SymbolKind,
+};
+
+use url::Url;
+
+use gluon::{
+    base::{
+        ast::SpannedExpr,
+        pos::{BytePos, Span},
+        symbol::Symbol,
+        types::{ArcType, TypeEnv, TypeExt},
+    },
+    compiler_pipeline::TypecheckValue,
+    import::{DatabaseSnapshot, Import},
+    query::Compilation,
+    Thread,
+};
+
+use completion::{
+    CompletionSymbol, CompletionSymbolContent, Definition, ModuleExprs, SpCompletionSymbol,
+    SuggestionQuery,
+};
+
+use crate::{
+    location::{position_to_byte_pos, span_to_range},
+    rpc::ServerError,
+};
+
+/// A document which has been (possibly partially) typechecked
+pub struct CheckedDocument {
+    pub uri: Url,
+    pub module: String,
+    pub file_map: Arc<codespan::FileMap>,
+    pub value: TypecheckValue<Arc<SpannedExpr<Symbol>>>,
+}
+
+impl CheckedDocument {
+    fn span(&self) -> Span<BytePos> {
+        self.file_map.span()
+    }
+
+    fn expr(&self) -> &SpannedExpr<Symbol> {
+        &self.value.expr
+    }
+
+    fn byte_pos(&self, position: &Position) -> Result<BytePos, ServerError> {
+        position_to_byte_pos(&self.file_map, position)
+    }
+}
+
+/// Lets `gluon_completion` follow definitions into the other modules of the database
+struct DatabaseModules<'a>(&'a DatabaseSnapshot);
+
+impl ModuleExprs for DatabaseModules<'_> {
+    fn module_expr(&self, module: &str) -> Option<Arc<SpannedExpr<Symbol>>> {
+        match self.0.typechecked_module(module.to_string(), None) {
+            Ok(value) | Err((Some(value), _)) => Some(value.expr),
+            Err((None, _)) => None,
+        }
+    }
+}
+
+/// Lets `references` search the modules of the open documents for uses of a symbol
+struct OpenModules<'a> {
+    db: &'a DatabaseSnapshot,
+    modules: Vec<String>,
+}
+
+impl ModuleExprs for OpenModules<'_> {
+    fn module_expr(&self, module: &str) -> Option<Arc<SpannedExpr<Symbol>>> {
+        DatabaseModules(self.db).module_expr(module)
+    }
+
+    fn module_names(&self) -> Vec<String> {
+        self.modules.clone()
+    }
+}
+
+fn is_function(typ: &ArcType) -> bool {
+    typ.remove_forall_and_implicit_args()
+        .as_function()
+        .is_some()
+}
+
+pub fn completion<T>(
+    thread: &Thread,
+    env: &T,
+    document: &CheckedDocument,
+    position: &Position,
+) -> Result<Vec<CompletionItem>, ServerError>
+where
+    T: TypeEnv<Type = ArcType>,
+{
+    let pos = document.byte_pos(position)?;
+
+    let query = match thread
+        .get_macros()
+        .get("import")
+        .as_ref()
+        .and_then(|import| import.downcast_ref::<Import>())
+    {
+        Some(import) => SuggestionQuery {
+            paths: import.paths.read().unwrap().clone(),
+            modules: import.modules(),
+            ..SuggestionQuery::default()
+        },
+        None => SuggestionQuery::default(),
+    };
+
+    let suggestions = query.suggest(env, document.span(), document.expr(), pos);
+    Ok(suggestions
+        .into_iter()
+        .map(|suggestion| {
+            let (kind, detail) = match suggestion.typ {
+                Either::Left(kind) => (CompletionItemKind::Class, kind.to_string()),
+                Either::Right(typ) => (
+                    if is_function(&typ) {
+                        CompletionItemKind::Function
+                    } else {
+                        CompletionItemKind::Variable
+                    },
+                    typ.to_string(),
+                ),
+            };
+            CompletionItem {
+                label: suggestion.name,
+                kind: Some(kind),
+                detail: Some(detail),
+                ..CompletionItem::default()
+            }
+        })
+        .collect())
+}
+
+pub fn hover<T>(
+    env: &T,
+    document: &CheckedDocument,
+    position: &Position,
+) -> Result<Option<Hover>, ServerError>
+where
+    T: TypeEnv<Type = ArcType>,
+{
+    let pos = document.byte_pos(position)?;
+    let (span, expr) = (document.span(), document.expr());
+
+    let typ = match completion::find(env, span, expr, pos) {
+        Ok(typ) => typ,
+        Err(()) => return Ok(None),
+    };
+
+    let mut value = format!(
+        "```gluon\n{}\n```",
+        match typ {
+            Either::Left(kind) => kind.to_string(),
+            Either::Right(typ) => typ.to_string(),
+        }
+    );
+    if let Some(comment) = completion::get_metadata(&document.value.metadata_map, span, expr, pos)
+        .and_then(|metadata| metadata.comment.as_ref())
+    {
+        value.push_str("\n\n");
+        value.push_str(&comment.content);
+    }
+
+    let range = completion::completion(completion::SpanAt, span, expr, pos)
+        .ok()
+        .and_then(|span| span_to_range(&document.file_map, span).ok());
+
+    Ok(Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range,
+    }))
+}
+
+pub fn signature_help(
+    env: &dyn TypeEnv<Type = ArcType>,
+    document: &CheckedDocument,
+    position: &Position,
+) -> Result<Option<SignatureHelp>, ServerError> {
+    let pos = document.byte_pos(position)?;
+
+    Ok(
+        completion::signature_help(env, document.span(), document.expr(), pos).map(|help| {
+            let parameters = help
+                .typ
+                .remove_forall_and_implicit_args()
+                .arg_iter()
+                .map(|arg| ParameterInformation {
+                    label: ParameterLabel::Simple(arg.to_string()),
+                    documentation: None,
+                })
+                .collect();
+            SignatureHelp {
+                signatures: vec![SignatureInformation {
+                    label: if help.name.is_empty() {
+                        help.typ.to_string()
+                    } else {
+                        format!("{} : {}", help.name, help.typ)
+                    },
+                    documentation: None,
+                    parameters: Some(parameters),
+                }],
+                active_signature: Some(0),
+                active_parameter: help.index.map(i64::from),
+            }
+        }),
+    )
+}
+
+fn symbol_kind(symbol: &CompletionSymbol) -> SymbolKind {
+    match symbol.content {
+        CompletionSymbolContent::Value { typ, .. } if is_function(typ) => SymbolKind::Function,
+        CompletionSymbolContent::Value { .. } => SymbolKind::Variable,
+        CompletionSymbolContent::Type { .. } => SymbolKind::Class,
+    }
+}
+
+fn symbol_detail(symbol: &CompletionSymbol) -> Option<String> {
+    match symbol.content {
+        CompletionSymbolContent::Value { typ, .. } => Some(typ.to_string()),
+        CompletionSymbolContent::Type { .. } => None,
+    }
+}
+
+fn document_symbol(
+    document: &CheckedDocument,
+    symbol: &SpCompletionSymbol,
+) -> Result<DocumentSymbol, ServerError> {
+    let range = span_to_range(&document.file_map, symbol.span)?;
+    let children = symbol
+        .value
+        .children
+        .iter()
+        .map(|child| document_symbol(document, child))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(DocumentSymbol {
+        name: symbol.value.name.declared_name().to_string(),
+        detail: symbol_detail(&symbol.value),
+        kind: symbol_kind(&symbol.value),
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    })
+}
+
+pub fn document_symbols(document: &CheckedDocument) -> Result<Vec<DocumentSymbol>, ServerError> {
+    completion::all_symbols(document.span(), document.expr())
+        .iter()
+        .map(|symbol| document_symbol(document, symbol))
+        .collect()
+}
+
+/// Returns all symbols in `document` whose name contains `query`
+pub fn workspace_symbols(
+    document: &CheckedDocument,
+    query: &str,
+) -> Result<Vec<SymbolInformation>, ServerError> {
+    fn add_symbols(
+        document: &CheckedDocument,
+        query: &str,
+        symbols: &[SpCompletionSymbol],
+        output: &mut Vec<SymbolInformation>,
+    ) -> Result<(), ServerError> {
+        for symbol in symbols {
+            let name = symbol.value.name.declared_name();
+            if name.contains(query) {
+                output.push(SymbolInformation {
+                    name: name.to_string(),
+                    kind: symbol_kind(&symbol.value),
+                    deprecated: None,
+                    location: Location {
+                        uri: document.uri.clone(),
+                        range: span_to_range(&document.file_map, symbol.span)?,
+                    },
+                    container_name: Some(document.module.clone()),
+                });
+            }
+            add_symbols(document, query, &symbol.value.children, output)?;
+        }
+        Ok(())
+    }
+
+    let mut output = Vec::new();
+    add_symbols(
+        document,
+        query,
+        &completion::all_symbols(document.span(), document.expr()),
+        &mut output,
+    )?;
+    Ok(output)
+}
+
+/// Returns the location of the definition at `position`.
+///
+/// Definitions in other modules are only returned if `module_uri` can find the file of the module.
+pub fn definition(
+    db: &DatabaseSnapshot,
+    document: &CheckedDocument,
+    position: &Position,
+    module_uri: impl Fn(&str) -> Option<Url>,
+) -> Result<Option<Location>, ServerError> {
+    let pos = document.byte_pos(position)?;
+    let definition =
+        match completion::definition_at(DatabaseModules(db), document.span(), document.expr(), pos)
+        {
+            Some(definition) => definition,
+            None => return Ok(None),
+        };
+
+    match definition {
+        Definition { module: None, span } => Ok(Some(Location {
+            uri: document.uri.clone(),
+            range: span_to_range(&document.file_map, span)?,
+        })),
+        Definition {
+            module: Some(module),
+            span,
+        } => {
+            let file_map = match db.get_filemap(&module) {
+                Some(file_map) => file_map,
+                None => return Ok(None),
+            };
+            Ok(match module_uri(&module) {
+                Some(uri) => Some(Location {
+                    uri,
+                    range: span_to_range(&file_map, span)?,
+                }),
+                None => None,
+            })
+        }
+    }
+}
+
+/// Returns every use of the symbol at `position` in the document and in the `open_modules` which
+/// refer to it through `import!`.
+///
+/// Uses in other modules are only returned if `module_uri` can find the file of the module.
+pub fn references(
+    db: &DatabaseSnapshot,
+    document: &CheckedDocument,
+    position: &Position,
+    include_declaration: bool,
+    open_modules: Vec<String>,
+    module_uri: impl Fn(&str) -> Option<Url>,
+) -> Result<Vec<Location>, ServerError> {
+    let pos = document.byte_pos(position)?;
+    let (span, expr) = (document.span(), document.expr());
+
+    let symbol = match completion::symbol(span, expr, pos) {
+        Ok(symbol) => symbol,
+        Err(()) => return Ok(Vec::new()),
+    };
+    let modules = OpenModules {
+        db,
+        modules: open_modules,
+    };
+    let declaration = if include_declaration {
+        None
+    } else {
+        completion::definition_at(&modules, span, expr, pos).map(|definition| {
+            (
+                definition.module.unwrap_or_else(|| document.module.clone()),
+                definition.span,
+            )
+        })
+    };
+
+    let mut locations = Vec::new();
+    for reference in completion::references(&modules, &document.module, expr, symbol) {
+        if Some(&reference) == declaration.as_ref() {
+            continue;
+        }
+        let (module, span) = reference;
+        if module == document.module {
+            locations.push(Location {
+                uri: document.uri.clone(),
+                range: span_to_range(&document.file_map, span)?,
+            });
+        } else if let (Some(uri), Some(file_map)) = (module_uri(&module), db.get_filemap(&module)) {
+            locations.push(Location {
+                uri,
+                range: span_to_range(&file_map, span)?,
+            });
+        }
+    }
+    Ok(locations)
+}
@@ -0,0 +1,487 @@
+//! Language server for the gluon programming language.
+//!
+//! Speaks the [language server protocol](https://microsoft.github.io/language-server-protocol/)
+//! over any `BufRead`/`Write` pair (stdin and stdout for the `gluon_language_server` binary).
+//! Documents are stored as inline modules of the incremental `CompilerDatabase` so each edit only
+//! retypechecks what changed, and the typechecked expressions are queried using
+//! `gluon_completion`.
+#![doc(html_root_url = "https://docs.rs/gluon_language_server/0.13.1")] // # GLUON
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_json;
+
+extern crate gluon_completion as completion;
+
+mod diagnostics;
+mod document;
+pub mod location;
+pub mod rpc;
+
+use std::{
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Initialized,
+        Notification, PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, GotoDefinition, GotoDefinitionResponse, HoverRequest,
+        Initialize, References, Request, Shutdown, SignatureHelpRequest, WorkspaceSymbol,
+    },
+    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentSymbolParams,
+    DocumentSymbolResponse, Hover, InitializeParams, InitializeResult, Location,
+    PublishDiagnosticsParams, ReferenceParams, ServerCapabilities, SignatureHelp,
+    SignatureHelpOptions, SymbolInformation, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, WorkspaceSymbolParams,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use url::Url;
+
+use gluon::{
+    base::{filename_to_module, fnv::FnvMap},
+    import::Import,
+    query::{Compilation, CompilationBase},
+    RootedThread, ThreadExt,
+};
+
+use crate::{
+    document::CheckedDocument,
+    rpc::{error_code, Message, ServerError},
+};
+
+struct Document {
+    module: String,
+    text: String,
+}
+
+/// State of a running language server
+pub struct LanguageServer {
+    thread: RootedThread,
+    documents: FnvMap<Url, Document>,
+    root: Option<PathBuf>,
+    initialized: bool,
+    outgoing: Vec<Value>,
+}
+
+impl LanguageServer {
+    pub fn new(thread: RootedThread) -> LanguageServer {
+        LanguageServer {
+            thread,
+            documents: FnvMap::default(),
+            root: None,
+            initialized: false,
+            outgoing: Vec::new(),
+        }
+    }
+
+    /// Handles a single decoded message, returning the messages which should be sent back to the
+    /// client. Returns `None` once the client has sent the `exit` notification.
+    pub fn handle_message(&mut self, message: Message) -> Option<Vec<Value>> {
+        match message {
+            Message::Request { id, method, params } => {
+                debug!("Request `{}`", method);
+                let result = self.handle_request(&method, params);
+                if let Err(ref err) = result {
+                    info!("Request `{}` failed: {}", method, err);
+                }
+                self.outgoing.push(rpc::response(id, result));
+            }
+            Message::Notification { method, params } => {
+                debug!("Notification `{}`", method);
+                if method == Exit::METHOD {
+                    return None;
+                }
+                if let Err(err) = self.handle_notification(&method, params) {
+                    info!("Notification `{}` failed: {}", method, err);
+                }
+            }
+            // The server never sends requests so there is nothing to do with any responses
+            Message::Response { .. } => (),
+        }
+        Some(self.outgoing.drain(..).collect())
+    }
+
+    fn handle_request(&mut self, method: &str, params: Value) -> Result<Value, ServerError> {
+        if !self.initialized && method != Initialize::METHOD {
+            return Err(ServerError::new(
+                error_code::SERVER_NOT_INITIALIZED,
+                "The server has not been initialized",
+            ));
+        }
+        match method {
+            Initialize::METHOD => self.request::<Initialize, _>(params, Self::initialize),
+            Shutdown::METHOD => self.request::<Shutdown, _>(params, |_, ()| Ok(())),
+            Completion::METHOD => self.request::<Completion, _>(params, Self::completion),
+            HoverRequest::METHOD => self.request::<HoverRequest, _>(params, Self::hover),
+            GotoDefinition::METHOD => self.request::<GotoDefinition, _>(params, Self::definition),
+            References::METHOD => self.request::<References, _>(params, Self::references),
+            SignatureHelpRequest::METHOD => {
+                self.request::<SignatureHelpRequest, _>(params, Self::signature_help)
+            }
+            DocumentSymbolRequest::METHOD => {
+                self.request::<DocumentSymbolRequest, _>(params, Self::document_symbols)
+            }
+            WorkspaceSymbol::METHOD => {
+                self.request::<WorkspaceSymbol, _>(params, Self::workspace_symbols)
+            }
+            _ => Err(ServerError::new(
+                error_code::METHOD_NOT_FOUND,
+                format!("Unknown method `{}`", method),
+            )),
+        }
+    }
+
+    fn request<R, F>(&mut self, params: Value, f: F) -> Result<Value, ServerError>
+    where
+        R: Request,
+        R::Params: DeserializeOwned,
+        R::Result: Serialize,
+        F: FnOnce(&mut Self, R::Params) -> Result<R::Result, ServerError>,
+    {
+        let params = serde_json::from_value(params).map_err(ServerError::invalid_params)?;
+        let result = f(self, params)?;
+        serde_json::to_value(result).map_err(ServerError::internal)
+    }
+
+    fn handle_notification(&mut self, method: &str, params: Value) -> Result<(), ServerError> {
+        match method {
+            Initialized::METHOD => Ok(()),
+            DidOpenTextDocument::METHOD => {
+                self.notification::<DidOpenTextDocument, _>(params, |server, params| {
+                    let DidOpenTextDocumentParams { text_document } = params;
+                    server.update_document(text_document.uri, text_document.text)
+                })
+            }
+            DidChangeTextDocument::METHOD => {
+                self.notification::<DidChangeTextDocument, _>(params, Self::did_change)
+            }
+            DidCloseTextDocument::METHOD => {
+                self.notification::<DidCloseTextDocument, _>(params, |server, params| {
+                    let DidCloseTextDocumentParams { text_document } = params;
+                    server.close_document(text_document.uri);
+                    Ok(())
+                })
+            }
+            // Unknown notifications are allowed to be ignored
+            _ => Ok(()),
+        }
+    }
+
+    fn notification<N, F>(&mut self, params: Value, f: F) -> Result<(), ServerError>
+    where
+        N: Notification,
+        N::Params: DeserializeOwned,
+        F: FnOnce(&mut Self, N::Params) -> Result<(), ServerError>,
+    {
+        let params = serde_json::from_value(params).map_err(ServerError::invalid_params)?;
+        f(self, params)
+    }
+
+    fn send_notification<N>(&mut self, params: N::Params)
+    where
+        N: Notification,
+        N::Params: Serialize,
+    {
+        match serde_json::to_value(params) {
+            Ok(params) => self.outgoing.push(rpc::notification(N::METHOD, params)),
+            Err(err) => error!("Unable to serialize `{}`: {}", N::METHOD, err),
+        }
+    }
+
+    fn initialize(&mut self, params: InitializeParams) -> Result<InitializeResult, ServerError> {
+        let InitializeParams {
+            root_uri,
+            root_path,
+            ..
+        } = params;
+        self.root = root_uri
+            .and_then(|uri| uri.to_file_path().ok())
+            .or_else(|| root_path.map(PathBuf::from));
+
+        if let Some(ref root) = self.root {
+            if let Some(import) = self.thread.get_macros().get("import") {
+                if let Some(import) = import.downcast_ref::<Import>() {
+                    import.add_path(root.clone());
+                }
+            }
+        }
+
+        self.initialized = true;
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::Full,
+                )),
+                completion_provider: Some(CompletionOptions {
+                    resolve_provider: Some(false),
+                    trigger_characters: Some(vec![".".into()]),
+                }),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec![" ".into(), "(".into()]),
+                }),
+                hover_provider: Some(true),
+                definition_provider: Some(true),
+                references_provider: Some(true),
+                document_symbol_provider: Some(true),
+                workspace_symbol_provider: Some(true),
+                ..ServerCapabilities::default()
+            },
+        })
+    }
+
+    fn did_change(&mut self, params: DidChangeTextDocumentParams) -> Result<(), ServerError> {
+        let DidChangeTextDocumentParams {
+            text_document,
+            content_changes,
+        } = params;
+        // Only full synchronization is supported so the last change contains the entire text
+        match content_changes.into_iter().last() {
+            Some(change) => self.update_document(text_document.uri, change.text),
+            None => Ok(()),
+        }
+    }
+
+    fn module_name(&self, uri: &Url) -> String {
+        match uri.to_file_path() {
+            Ok(path) => {
+                let relative = self
+                    .root
+                    .as_ref()
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .or_else(|| path.file_name().map(|name| name.as_ref()))
+                    .unwrap_or(&path);
+                filename_to_module(&relative.to_string_lossy())
+            }
+            Err(()) => filename_to_module(uri.path().trim_start_matches('/')),
+        }
+    }
+
+    /// Returns the uri of `module`, either from the open documents or by searching the import
+    /// paths for its file
+    fn module_uri(&self, module: &str) -> Option<Url> {
+        if let Some((uri, _)) = self.documents.iter().find(|(_, doc)| doc.module == module) {
+            return Some(uri.clone());
+        }
+        let import = self.thread.get_macros().get("import")?;
+        let import = import.downcast_ref::<Import>()?;
+        let filename = format!("{}.glu", module.replace('.', "/"));
+        let paths = import.paths.read().unwrap();
+        paths
+            .iter()
+            .map(|path| path.join(&filename))
+            .find(|path| path.exists())
+            .and_then(|path| Url::from_file_path(path.canonicalize().unwrap_or(path)).ok())
+    }
+
+    fn update_document(&mut self, uri: Url, text: String) -> Result<(), ServerError> {
+        let module = self.module_name(&uri);
+        {
+            let mut db = self.thread.get_database_mut();
+            // Keep the positions of the old file map so that spans stay stable between edits
+            db.update_filemap(&module, &text[..]);
+            db.add_module(module.clone(), &text);
+        }
+        self.documents
+            .insert(uri.clone(), Document { module, text });
+
+        self.publish_diagnostics(uri)
+    }
+
+    fn close_document(&mut self, uri: Url) {
+        if let Some(document) = self.documents.remove(&uri) {
+            // Any unsaved text must stop shadowing the file so modules importing it see the
+            // contents on disk again
+            self.thread
+                .get_database_mut()
+                .remove_module(&document.module);
+        }
+        self.send_notification::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+            uri,
+            Vec::new(),
+        ));
+    }
+
+    fn publish_diagnostics(&mut self, uri: Url) -> Result<(), ServerError> {
+        let module = self.document(&uri)?.module.clone();
+        let db = self.thread.get_database();
+        let result = db.typechecked_module(module.clone(), None);
+        let file_map = db
+            .get_filemap(&module)
+            .ok_or_else(|| ServerError::internal("Missing file map"))?;
+        let diagnostics = match result {
+            Ok(value) => diagnostics::warnings_to_diagnostics(&file_map, &value.warnings),
+            Err((_, err)) => diagnostics::error_to_diagnostics(&file_map, &err),
+        };
+        drop(db);
+
+        self.send_notification::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+            uri,
+            diagnostics,
+        ));
+        Ok(())
+    }
+
+    fn document(&self, uri: &Url) -> Result<&Document, ServerError> {
+        self.documents
+            .get(uri)
+            .ok_or_else(|| ServerError::invalid_params(format!("Document `{}` is not open", uri)))
+    }
+
+    /// Runs `f` with the typechecked expression of the document at `uri`.
+    ///
+    /// If the document could not be parsed well enough to produce an expression then `f` is not
+    /// called and `T::default()` is returned.
+    fn with_document<T, F>(&self, uri: &Url, f: F) -> Result<T, ServerError>
+    where
+        T: Default,
+        F: FnOnce(&gluon::import::DatabaseSnapshot, &CheckedDocument) -> Result<T, ServerError>,
+    {
+        let document = self.document(uri)?;
+        let db = self.thread.get_database();
+        let value = match db.typechecked_module(document.module.clone(), None) {
+            Ok(value) => value,
+            Err((Some(value), _)) => value,
+            Err((None, _)) => return Ok(T::default()),
+        };
+        let file_map = db.get_filemap(&document.module).ok_or_else(|| {
+            ServerError::internal(format!("Missing file map for `{}`", document.module))
+        })?;
+        debug_assert_eq!(file_map.src(), document.text);
+
+        let document = CheckedDocument {
+            uri: uri.clone(),
+            module: document.module.clone(),
+            file_map,
+            value,
+        };
+        f(&db, &document)
+    }
+
+    fn completion(
+        &mut self,
+        params: CompletionParams,
+    ) -> Result<Option<CompletionResponse>, ServerError> {
+        let position = params.text_document_position;
+        let thread = self.thread.clone();
+        self.with_document(&position.text_document.uri, |db, document| {
+            document::completion(&thread, &**db, document, &position.position)
+                .map(|items| Some(CompletionResponse::Array(items)))
+        })
+    }
+
+    fn hover(&mut self, params: TextDocumentPositionParams) -> Result<Option<Hover>, ServerError> {
+        self.with_document(&params.text_document.uri, |db, document| {
+            document::hover(&**db, document, &params.position)
+        })
+    }
+
+    fn definition(
+        &mut self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<GotoDefinitionResponse>, ServerError> {
+        self.with_document(&params.text_document.uri, |db, document| {
+            document::definition(db, document, &params.position, |module| {
+                self.module_uri(module)
+            })
+            .map(|location| location.map(GotoDefinitionResponse::Scalar))
+        })
+    }
+
+    fn references(
+        &mut self,
+        params: ReferenceParams,
+    ) -> Result<Option<Vec<Location>>, ServerError> {
+        let ReferenceParams {
+            text_document_position: position,
+            context,
+        } = params;
+        let open_modules = self
+            .documents
+            .values()
+            .map(|document| document.module.clone())
+            .collect();
+        self.with_document(&position.text_document.uri, |db, document| {
+            document::references(
+                db,
+                document,
+                &position.position,
+                context.include_declaration,
+                open_modules,
+                |module| self.module_uri(module),
+            )
+            .map(Some)
+        })
+    }
+
+    fn signature_help(
+        &mut self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<SignatureHelp>, ServerError> {
+        self.with_document(&params.text_document.uri, |db, document| {
+            document::signature_help(&**db, document, &params.position)
+        })
+    }
+
+    fn document_symbols(
+        &mut self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>, ServerError> {
+        self.with_document(&params.text_document.uri, |_, document| {
+            document::document_symbols(document)
+                .map(|symbols| Some(DocumentSymbolResponse::Nested(symbols)))
+        })
+    }
+
+    fn workspace_symbols(
+        &mut self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>, ServerError> {
+        let uris: Vec<_> = self.documents.keys().cloned().collect();
+        let mut symbols = Vec::new();
+        for uri in uris {
+            symbols.extend(self.with_document(&uri, |_, document| {
+                document::workspace_symbols(document, &params.query)
+            })?);
+        }
+        Ok(Some(symbols))
+    }
+}
+
+/// Runs the language server on `thread`, reading messages from `input` and writing responses and
+/// notifications to `output` until the client sends `exit` or closes `input`.
+pub fn start_server<R, W>(thread: RootedThread, mut input: R, mut output: W) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut server = LanguageServer::new(thread);
+    while let Some(content) = rpc::read_message(&mut input)? {
+        debug!("Received: {}", content);
+
+        let message = serde_json::from_str(&content)
+            .map_err(|err| ServerError::new(error_code::PARSE_ERROR, err.to_string()))
+            .and_then(Message::from_json);
+        let replies = match message {
+            Ok(message) => match server.handle_message(message) {
+                Some(replies) => replies,
+                None => return Ok(()),
+            },
+            Err(err) => vec![rpc::response(Value::Null, Err(err))],
+        };
+
+        for reply in &replies {
+            rpc::write_message(&mut output, reply)?;
+        }
+    }
+    Ok(())
+}
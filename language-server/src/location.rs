@@ -0,0 +1,103 @@
+//! Conversions between the byte positions used by gluon and the line/UTF-16 character positions
+//! used by the language server protocol.
+
+use codespan::{ByteOffset, FileMap};
+
+use lsp_types::{Position, Range};
+
+use gluon::base::pos::{BytePos, Line, Span};
+
+use crate::rpc::ServerError;
+
+/// Converts `position` into a `BytePos` inside `file_map`.
+///
+/// Characters past the end of the line are clamped to the end of the line.
+pub fn position_to_byte_pos(
+    file_map: &FileMap,
+    position: &Position,
+) -> Result<BytePos, ServerError> {
+    let line_span = file_map
+        .line_span(Line(position.line as u32))
+        .map_err(ServerError::invalid_params)?;
+    let line = file_map
+        .src_slice(line_span)
+        .map_err(ServerError::invalid_params)?;
+    let line = line.trim_end_matches(&['\n', '\r'][..]);
+
+    let mut utf16_offset = 0;
+    let mut byte_offset = line.len();
+    for (i, c) in line.char_indices() {
+        if utf16_offset >= position.character {
+            byte_offset = i;
+            break;
+        }
+        utf16_offset += c.len_utf16() as u64;
+    }
+
+    Ok(line_span.start() + ByteOffset::from(byte_offset as i64))
+}
+
+/// Converts `pos` into a `Position` inside `file_map`
+pub fn byte_pos_to_position(file_map: &FileMap, pos: BytePos) -> Result<Position, ServerError> {
+    let line = file_map.find_line(pos).map_err(ServerError::internal)?;
+    let line_span = file_map.line_span(line).map_err(ServerError::internal)?;
+    let prefix = file_map
+        .src_slice(Span::new(line_span.start(), pos))
+        .map_err(ServerError::internal)?;
+    Ok(Position {
+        line: line.to_usize() as u64,
+        character: prefix.encode_utf16().count() as u64,
+    })
+}
+
+pub fn span_to_range(file_map: &FileMap, span: Span<BytePos>) -> Result<Range, ServerError> {
+    Ok(Range {
+        start: byte_pos_to_position(file_map, span.start())?,
+        end: byte_pos_to_position(file_map, span.end())?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use codespan::{CodeMap, FileName};
+
+    #[test]
+    fn roundtrip_positions() {
+        let mut code_map = CodeMap::new();
+        let file_map = code_map.add_filemap(
+            FileName::virtual_("test"),
+            "let x = 1\nlet åäö = \"𝄞\"\nx".to_string(),
+        );
+
+        let position = Position {
+            line: 1,
+            character: 11,
+        };
+        let pos = position_to_byte_pos(&file_map, &position).unwrap();
+        assert_eq!(
+            file_map
+                .src_slice(Span::new(pos, file_map.span().end()))
+                .unwrap(),
+            "𝄞\"\nx"
+        );
+        assert_eq!(byte_pos_to_position(&file_map, pos).unwrap(), position);
+
+        let end_of_line = position_to_byte_pos(
+            &file_map,
+            &Position {
+                line: 0,
+                character: 100,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            byte_pos_to_position(&file_map, end_of_line).unwrap(),
+            Position {
+                line: 0,
+                character: 9,
+            }
+        );
+    }
+}
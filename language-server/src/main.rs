@@ -0,0 +1,18 @@
+//! Language server for the gluon programming language, communicating over stdin and stdout
+#![doc(html_root_url = "https://docs.rs/gluon_language_server/0.13.1")] // # GLUON
+
+use std::io;
+
+fn main() {
+    #[cfg(feature = "env_logger")]
+    env_logger::init();
+
+    let thread = gluon::new_vm();
+
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(err) = gluon_language_server::start_server(thread, stdin.lock(), stdout.lock()) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
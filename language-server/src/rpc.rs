@@ -0,0 +1,240 @@
+//! Reading and writing of JSON-RPC messages using the framing of the language server protocol.
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use serde_json::Value;
+
+/// Error codes defined by JSON-RPC and the language server protocol
+pub mod error_code {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    pub const SERVER_NOT_INITIALIZED: i64 = -32002;
+}
+
+/// An error which is reported back to the client as the `error` field of a response
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl ServerError {
+    pub fn new<S>(code: i64, message: S) -> ServerError
+    where
+        S: Into<String>,
+    {
+        ServerError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn internal<E>(err: E) -> ServerError
+    where
+        E: fmt::Display,
+    {
+        ServerError::new(error_code::INTERNAL_ERROR, err.to_string())
+    }
+
+    pub fn invalid_params<E>(err: E) -> ServerError
+    where
+        E: fmt::Display,
+    {
+        ServerError::new(error_code::INVALID_PARAMS, err.to_string())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "code": self.code,
+            "message": self.message,
+        })
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+/// A decoded message sent by the client
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    /// A request which must be answered with a response carrying the same `id`
+    Request {
+        id: Value,
+        method: String,
+        params: Value,
+    },
+    /// A notification which does not expect a response
+    Notification { method: String, params: Value },
+    /// A response to a request sent by the server
+    Response { id: Value, result: Value },
+}
+
+impl Message {
+    pub fn from_json(mut value: Value) -> Result<Message, ServerError> {
+        let object = value.as_object_mut().ok_or_else(|| {
+            ServerError::new(error_code::INVALID_REQUEST, "Expected a JSON object")
+        })?;
+        let params = object.remove("params").unwrap_or(Value::Null);
+        let id = object.remove("id");
+        match object.remove("method") {
+            Some(Value::String(method)) => Ok(match id {
+                Some(id) => Message::Request { id, method, params },
+                None => Message::Notification { method, params },
+            }),
+            Some(_) => Err(ServerError::new(
+                error_code::INVALID_REQUEST,
+                "Expected `method` to be a string",
+            )),
+            None => match id {
+                Some(id) => Ok(Message::Response {
+                    id,
+                    result: object
+                        .remove("result")
+                        .or_else(|| object.remove("error"))
+                        .unwrap_or(Value::Null),
+                }),
+                None => Err(ServerError::new(
+                    error_code::INVALID_REQUEST,
+                    "Message is missing both `method` and `id`",
+                )),
+            },
+        }
+    }
+}
+
+/// Reads the content of the next message in `input`.
+///
+/// Returns `Ok(None)` if the input was closed before a new message started.
+pub fn read_message<R>(input: &mut R) -> io::Result<Option<String>>
+where
+    R: BufRead,
+{
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return if content_length.is_none() {
+                Ok(None)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Input ended in the middle of a message header",
+                ))
+            };
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            // Tolerate stray newlines between messages
+            continue;
+        }
+
+        let mut iter = header.splitn(2, ':');
+        let name = iter.next().unwrap_or("").trim();
+        let value = iter.next().unwrap_or("").trim();
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(value.parse::<usize>().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid Content-Length `{}`: {}", value, err),
+                )
+            })?);
+        }
+    }
+
+    let mut content = vec![0; content_length.unwrap()];
+    input.read_exact(&mut content)?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Writes `message` to `output` prefixed by the `Content-Length` header
+pub fn write_message<W>(output: &mut W, message: &Value) -> io::Result<()>
+where
+    W: Write,
+{
+    let content = message.to_string();
+    debug!("Sending: {}", content);
+    write!(
+        output,
+        "Content-Length: {}\r\n\r\n{}",
+        content.len(),
+        content
+    )?;
+    output.flush()
+}
+
+pub fn response(id: Value, result: Result<Value, ServerError>) -> Value {
+    match result {
+        Ok(result) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result,
+        }),
+        Err(err) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": err.to_json(),
+        }),
+    }
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_framed_messages() {
+        let input = "Content-Length: 2\r\n\r\n{}Content-Length: 14\r\nContent-Type: \
+                     application/vscode-jsonrpc; charset=utf-8\r\n\r\n{\"id\":1,\"a\":2}";
+        let mut input = input.as_bytes();
+        assert_eq!(read_message(&mut input).unwrap(), Some("{}".to_string()));
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some("{\"id\":1,\"a\":2}".to_string())
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn decode_message_kinds() {
+        assert_eq!(
+            Message::from_json(json!({ "id": 1, "method": "initialize", "params": {} })),
+            Ok(Message::Request {
+                id: json!(1),
+                method: "initialize".into(),
+                params: json!({}),
+            })
+        );
+        assert_eq!(
+            Message::from_json(json!({ "method": "exit" })),
+            Ok(Message::Notification {
+                method: "exit".into(),
+                params: Value::Null,
+            })
+        );
+        assert!(Message::from_json(json!([])).is_err());
+    }
+}
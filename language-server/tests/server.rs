@@ -0,0 +1,424 @@
+#[macro_use]
+extern crate serde_json;
+
+use std::{
+    collections::VecDeque,
+    env,
+    io::BufReader,
+    path::PathBuf,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::Value;
+
+use gluon_language_server::rpc::{read_message, write_message};
+
+const URI: &str = "file:///gluon_test/test.glu";
+
+fn server_path() -> PathBuf {
+    let path = env::args().next().unwrap();
+    PathBuf::from(path)
+        .parent()
+        .and_then(|p| p.parent())
+        .expect("folder")
+        .join("gluon_language_server")
+}
+
+/// A scripted JSON-RPC client driving a `gluon_language_server` process
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    notifications: VecDeque<Value>,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(server_path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|err| panic!("{}: {}", err, server_path().display()));
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+            notifications: VecDeque::new(),
+        };
+
+        let capabilities = client.request(
+            "initialize",
+            json!({
+                "processId": null,
+                "rootUri": "file:///gluon_test",
+                "capabilities": {},
+            }),
+        );
+        assert_eq!(capabilities["capabilities"]["hoverProvider"], json!(true));
+        client.notify("initialized", json!({}));
+
+        client
+    }
+
+    fn read(&mut self) -> Value {
+        let content = read_message(&mut self.stdout)
+            .unwrap()
+            .expect("Server closed its output");
+        serde_json::from_str(&content).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        write_message(
+            &mut self.stdin,
+            &json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+        )
+        .unwrap();
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        write_message(
+            &mut self.stdin,
+            &json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }),
+        )
+        .unwrap();
+
+        loop {
+            let mut message = self.read();
+            if message.get("id") == Some(&json!(id)) {
+                if let Some(error) = message.get("error") {
+                    panic!("Request `{}` failed: {}", method, error);
+                }
+                return message["result"].take();
+            }
+            self.notifications.push_back(message);
+        }
+    }
+
+    fn diagnostics(&mut self, uri: &str) -> Value {
+        let mut message = match self.notifications.pop_front() {
+            Some(message) => message,
+            None => self.read(),
+        };
+        assert_eq!(message["method"], "textDocument/publishDiagnostics");
+        assert_eq!(message["params"]["uri"], uri);
+        message["params"]["diagnostics"].take()
+    }
+
+    fn open(&mut self, text: &str) -> Value {
+        self.open_uri(URI, text)
+    }
+
+    fn open_uri(&mut self, uri: &str, text: &str) -> Value {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": "gluon",
+                    "version": 1,
+                    "text": text,
+                }
+            }),
+        );
+        self.diagnostics(uri)
+    }
+
+    fn change(&mut self, version: u64, text: &str) -> Value {
+        self.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": version },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+        self.diagnostics(URI)
+    }
+
+    fn position_request(&mut self, method: &str, line: u64, character: u64) -> Value {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    fn exit(mut self) {
+        assert_eq!(self.request("shutdown", Value::Null), Value::Null);
+        self.notify("exit", Value::Null);
+        let status = self.child.wait().unwrap();
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn publish_diagnostics_on_open_and_change() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open("let x = 1\nlet y : String = x\ny");
+    let diagnostics = diagnostics.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{:#?}", diagnostics);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    let diagnostics = client.change(2, "let x = 1\nlet y : Int = x\ny");
+    assert_eq!(diagnostics, json!([]));
+
+    client.exit();
+}
+
+#[test]
+fn publish_warnings() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open("let x = 1\nlet y = 2\nx");
+    let diagnostics = diagnostics.as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{:#?}", diagnostics);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert_eq!(diagnostics[0]["message"], "Unused variable `y`");
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], 1);
+
+    let diagnostics = client.change(2, "let x = 1\n#[allow(unused)]\nlet y = 2\nx");
+    assert_eq!(diagnostics, json!([]));
+
+    client.exit();
+}
+
+#[test]
+fn hover_shows_type_and_documentation() {
+    let mut client = Client::start();
+
+    let diagnostics = client.open("/// The answer\nlet answer = 42\nanswer");
+    assert_eq!(diagnostics, json!([]));
+
+    let hover = client.position_request("textDocument/hover", 2, 2);
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("Int"), "{}", contents);
+    assert!(contents.contains("The answer"), "{}", contents);
+    assert_eq!(
+        hover["range"],
+        json!({
+            "start": { "line": 2, "character": 0 },
+            "end": { "line": 2, "character": 6 },
+        })
+    );
+
+    client.exit();
+}
+
+#[test]
+fn completion_suggests_bindings_in_scope() {
+    let mut client = Client::start();
+
+    client.open("let abc = 1\nlet abd = \"\"\nlet other = 3\nab");
+
+    let completion = client.request(
+        "textDocument/completion",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 3, "character": 2 },
+        }),
+    );
+    let mut labels: Vec<_> = completion
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| {
+            (
+                item["label"].as_str().unwrap().to_string(),
+                item["detail"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    labels.sort();
+    assert_eq!(
+        labels,
+        vec![
+            ("abc".to_string(), "Int".to_string()),
+            ("abd".to_string(), "String".to_string()),
+        ]
+    );
+
+    client.exit();
+}
+
+#[test]
+fn signature_help_in_application() {
+    let mut client = Client::start();
+
+    client.open("let f x y : Int -> String -> Int = x\nf 1 ");
+
+    let help = client.position_request("textDocument/signatureHelp", 1, 4);
+    assert_eq!(help["signatures"][0]["label"], "f : Int -> String -> Int");
+    assert_eq!(help["activeParameter"], 1);
+
+    client.exit();
+}
+
+#[test]
+fn document_and_workspace_symbols() {
+    let mut client = Client::start();
+
+    client.open("type Test = Int\nlet test x : Int -> Int =\n    let inner = x\n    inner\ntest 1");
+
+    let symbols = client.request(
+        "textDocument/documentSymbol",
+        json!({ "textDocument": { "uri": URI } }),
+    );
+    let symbols = symbols.as_array().unwrap();
+    assert_eq!(symbols.len(), 2, "{:#?}", symbols);
+    assert_eq!(symbols[0]["name"], "Test");
+    assert_eq!(symbols[1]["name"], "test");
+    assert_eq!(symbols[1]["detail"], "Int -> Int");
+    assert_eq!(symbols[1]["children"][0]["name"], "inner");
+
+    let symbols = client.request("workspace/symbol", json!({ "query": "inn" }));
+    assert_eq!(symbols[0]["name"], "inner");
+    assert_eq!(symbols[0]["location"]["uri"], URI);
+    assert_eq!(symbols[0]["containerName"], "test");
+
+    client.exit();
+}
+
+#[test]
+fn definition_in_imported_module() {
+    let mut client = Client::start();
+
+    let other = "file:///gluon_test/other.glu";
+    let diagnostics = client.open_uri(other, "let value = 1\n{ value }");
+    assert_eq!(diagnostics, json!([]));
+
+    let diagnostics = client.open("let other = import! other\nlet x = other.value\nx");
+    assert_eq!(diagnostics, json!([]));
+
+    let definition = client.position_request("textDocument/definition", 1, 16);
+    assert_eq!(
+        definition,
+        json!({
+            "uri": other,
+            "range": {
+                "start": { "line": 0, "character": 4 },
+                "end": { "line": 0, "character": 9 },
+            },
+        })
+    );
+
+    let definition = client.position_request("textDocument/definition", 2, 0);
+    assert_eq!(definition["uri"], URI);
+    assert_eq!(
+        definition["range"]["start"],
+        json!({ "line": 1, "character": 4 })
+    );
+
+    client.exit();
+}
+
+#[test]
+fn references_in_document() {
+    let mut client = Client::start();
+
+    client.open("let x = 1\nlet y = x\nx");
+
+    let references = client.request(
+        "textDocument/references",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 2, "character": 0 },
+            "context": { "includeDeclaration": true },
+        }),
+    );
+    let lines: Vec<_> = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| location["range"]["start"]["line"].clone())
+        .collect();
+    assert_eq!(lines, vec![json!(0), json!(1), json!(2)]);
+
+    let references = client.request(
+        "textDocument/references",
+        json!({
+            "textDocument": { "uri": URI },
+            "position": { "line": 2, "character": 0 },
+            "context": { "includeDeclaration": false },
+        }),
+    );
+    assert_eq!(references.as_array().unwrap().len(), 2, "{:#?}", references);
+
+    client.exit();
+}
+
+#[test]
+fn closing_a_document_clears_its_module() {
+    let mut client = Client::start();
+
+    let other = "file:///gluon_test/other.glu";
+    client.open_uri(other, "let value = 1\n{ value }");
+    let diagnostics = client.open("let other = import! other\nother.value");
+    assert_eq!(diagnostics, json!([]));
+
+    client.notify(
+        "textDocument/didClose",
+        json!({ "textDocument": { "uri": other } }),
+    );
+    assert_eq!(client.diagnostics(other), json!([]));
+
+    // `other.glu` does not exist on disk so the import fails once the open document is gone
+    let diagnostics = client.change(2, "let other = import! other\nother.value");
+    assert_eq!(
+        diagnostics.as_array().unwrap().len(),
+        1,
+        "{:#?}",
+        diagnostics
+    );
+
+    client.exit();
+}
+
+#[test]
+fn references_in_importing_module() {
+    let mut client = Client::start();
+
+    let other = "file:///gluon_test/other.glu";
+    client.open_uri(other, "let value = 1\n{ value }");
+    let diagnostics = client.open("let other = import! other\nlet x = other.value\nx");
+    assert_eq!(diagnostics, json!([]));
+
+    let references = client.request(
+        "textDocument/references",
+        json!({
+            "textDocument": { "uri": other },
+            "position": { "line": 0, "character": 4 },
+            "context": { "includeDeclaration": true },
+        }),
+    );
+    let mut locations: Vec<_> = references
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|location| {
+            (
+                location["uri"].as_str().unwrap().to_string(),
+                location["range"]["start"]["line"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    locations.sort();
+    assert_eq!(
+        locations,
+        vec![
+            (other.to_string(), 0),
+            (other.to_string(), 1),
+            (URI.to_string(), 1),
+        ]
+    );
+
+    client.exit();
+}
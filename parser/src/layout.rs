@@ -515,6 +515,8 @@ where
                 Token::Type => Some(Context::Type),
                 Token::Let => Some(Context::Let),
                 Token::Do | Token::Seq => Some(Context::Let),
+                // An `if` directly in a match clause is a guard which is not closed by an `else`
+                Token::If if offside.context == Context::MatchClause => None,
                 Token::If => Some(Context::If),
                 Token::Match => Some(Context::Expr),
                 Token::Lambda => Some(Context::Lambda),
@@ -252,6 +252,75 @@ match None with
     );
 }
 
+#[test]
+fn case_guard_expr() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+match x with
+    | Some y if y > 0 -> y
+    | _ -> 0"#;
+    let e = parse_clear_span!(text);
+    assert_eq!(
+        e,
+        no_loc(Expr::Match(
+            Box::new(id("x")),
+            vec![
+                Alternative {
+                    pattern: no_loc(Pattern::Constructor(
+                        TypedIdent::new(intern("Some")),
+                        vec![no_loc(Pattern::Ident(TypedIdent::new(intern("y"))))],
+                    )),
+                    guard: Some(binop(id("y"), ">", int(0))),
+                    expr: id("y"),
+                },
+                Alternative {
+                    pattern: no_loc(Pattern::Ident(TypedIdent::new(intern("_")))),
+                    guard: None,
+                    expr: int(0),
+                },
+            ],
+        ))
+    );
+}
+
+#[test]
+fn case_or_pattern_expr() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+match x with
+    | Some (1 | 2) | None -> 1
+    | _ -> 0"#;
+    let e = parse_clear_span!(text);
+    let int_pattern = |i| no_loc(Pattern::Literal(Literal::Int(i)));
+    assert_eq!(
+        e,
+        no_loc(Expr::Match(
+            Box::new(id("x")),
+            vec![
+                Alternative {
+                    pattern: no_loc(Pattern::Or(vec![
+                        no_loc(Pattern::Constructor(
+                            TypedIdent::new(intern("Some")),
+                            vec![no_loc(Pattern::Or(vec![int_pattern(1), int_pattern(2)]))],
+                        )),
+                        no_loc(Pattern::Constructor(
+                            TypedIdent::new(intern("None")),
+                            vec![],
+                        )),
+                    ])),
+                    guard: None,
+                    expr: int(1),
+                },
+                Alternative {
+                    pattern: no_loc(Pattern::Ident(TypedIdent::new(intern("_")))),
+                    guard: None,
+                    expr: int(0),
+                },
+            ],
+        ))
+    );
+}
+
 #[test]
 fn array_expr() {
     let _ = ::env_logger::try_init();
@@ -160,6 +160,32 @@ match x with
     }
 }
 
+#[test]
+fn match_guard_on_multiple_lines() {
+    let _ = ::env_logger::try_init();
+
+    let result = parse(
+        r#"
+match x with
+    | Some y
+        if y > 0
+            && y < 10 ->
+        if y == 1 then 1 else 2
+    | _ -> 0
+"#,
+    );
+
+    assert!(result.is_ok(), "{}", result.unwrap_err());
+
+    match result.as_ref().unwrap().value {
+        Expr::Match(_, ref alts) => {
+            assert_eq!(alts.len(), 2);
+            assert!(alts[0].guard.is_some());
+        }
+        ref x => panic!("{:?}", x),
+    }
+}
+
 #[test]
 fn allow_unindented_lambda() {
     let _ = ::env_logger::try_init();
@@ -287,6 +287,7 @@ pub fn case(e: SpExpr, alts: Vec<(Pattern<String>, SpExpr)>) -> SpExpr {
         alts.into_iter()
             .map(|(p, e)| Alternative {
                 pattern: no_loc(p),
+                guard: None,
                 expr: e,
             })
             .collect(),
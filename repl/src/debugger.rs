@@ -0,0 +1,125 @@
+//! The commands of the REPL's debugger. `:break` and `:step` are available from the REPL itself
+//! while `:locals` (and resuming the execution) is available from the prompt which is shown when
+//! the execution stops.
+
+use gluon::vm::{
+    debugger::{self, Breakpoint, Debugger, Step, Stop},
+    thread::{DebugInfo, Thread},
+};
+
+const STOP_HELP: &str = "Execution is stopped. Available commands
+
+    :step (st) [in|over|out] Steps to the next line, into or over calls or out of the function
+    :continue (c) Continues to the next breakpoint
+    :locals (lo) Prints the local variables of the current function
+    :break (b) [SOURCE:LINE|FUNCTION] Adds a breakpoint or lists the breakpoints";
+
+pub fn parse_step(arg: &str) -> Result<Step, String> {
+    match arg.trim() {
+        "" | "in" => Ok(Step::In),
+        "over" => Ok(Step::Over),
+        "out" => Ok(Step::Out),
+        arg => Err(format!("Expected `in`, `over` or `out`, found `{}`", arg)),
+    }
+}
+
+/// Adds the breakpoint described by `arg` or lists all breakpoints if `arg` is empty
+pub fn break_command(debugger: &Debugger, arg: &str) -> Result<String, String> {
+    if arg.trim().is_empty() {
+        let breakpoints = debugger.breakpoints();
+        if breakpoints.is_empty() {
+            return Ok("No breakpoints".to_string());
+        }
+        Ok(breakpoints
+            .iter()
+            .map(|(id, breakpoint)| format!("{}: {}", id, breakpoint))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    } else {
+        let breakpoint = arg.parse::<Breakpoint>()?;
+        let message = format!("at {}", breakpoint);
+        let id = debugger.add_breakpoint(breakpoint);
+        Ok(format!("Breakpoint {} {}", id, message))
+    }
+}
+
+/// Formats the locals and upvars of the function that execution stopped in
+pub fn locals(thread: &Thread, info: &DebugInfo) -> String {
+    let frame = match info.stack_info(0) {
+        Some(frame) => frame,
+        None => return "No function is executing".to_string(),
+    };
+    let variables = debugger::locals(thread, &frame)
+        .into_iter()
+        .chain(debugger::upvars(thread, &frame))
+        .map(|variable| format!("{} : {} = {}", variable.name, variable.typ, variable.value))
+        .collect::<Vec<_>>();
+    if variables.is_empty() {
+        "No variables".to_string()
+    } else {
+        variables.join("\n")
+    }
+}
+
+/// Reads debugger commands until the user resumes the execution
+pub fn stop_prompt(debugger: &Debugger, thread: &Thread, info: &DebugInfo, stop: &Stop) -> Step {
+    println!("{}", stop);
+
+    let mut editor = rustyline::Editor::<()>::new();
+    loop {
+        let line = match editor.readline("(debug) ") {
+            Ok(line) => line,
+            Err(_) => return Step::Continue,
+        };
+        let mut iter = line.trim().splitn(2, char::is_whitespace);
+        let (command, arg) = (iter.next().unwrap_or(""), iter.next().unwrap_or(""));
+        let result = match command {
+            "" => continue,
+            ":step" | ":st" => match parse_step(arg) {
+                Ok(step) => return step,
+                Err(err) => Err(err),
+            },
+            ":continue" | ":c" => return Step::Continue,
+            ":locals" | ":lo" => Ok(locals(thread, info)),
+            ":break" | ":b" => break_command(debugger, arg),
+            _ => Ok(STOP_HELP.to_string()),
+        };
+        match result {
+            Ok(output) | Err(output) => println!("{}", output),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakpoints() {
+        let debugger = Debugger::new();
+        assert_eq!(
+            break_command(&debugger, ""),
+            Ok("No breakpoints".to_string())
+        );
+        assert_eq!(
+            break_command(&debugger, "line:2"),
+            Ok("Breakpoint 1 at line:2".to_string())
+        );
+        assert_eq!(
+            break_command(&debugger, " f "),
+            Ok("Breakpoint 2 at f".to_string())
+        );
+        assert_eq!(
+            break_command(&debugger, ""),
+            Ok("1: line:2\n2: f".to_string())
+        );
+        assert!(break_command(&debugger, "line:0").is_err());
+    }
+
+    #[test]
+    fn steps() {
+        assert_eq!(parse_step(""), Ok(Step::In));
+        assert_eq!(parse_step("over"), Ok(Step::Over));
+        assert!(parse_step("sideways").is_err());
+    }
+}
@@ -31,9 +31,15 @@ use gluon::{base, parser, vm};
 use crate::base::filename_to_module;
 
 use gluon::{
-    new_vm, vm::thread::ThreadInternal, vm::Error as VMError, Error, Result, Thread, ThreadExt,
+    new_vm,
+    vm::profiler::{Metric, Profiler},
+    vm::thread::ThreadInternal,
+    vm::Error as VMError,
+    Error, Result, Thread, ThreadExt,
 };
 
+mod debugger;
+mod rename;
 mod repl;
 
 const APP_INFO: app_dirs::AppInfo = app_dirs::AppInfo {
@@ -86,6 +92,38 @@ impl ::std::str::FromStr for Color {
 pub struct FmtOpt {
     #[structopt(name = "FILE", parse(from_os_str), help = "Formats each file")]
     input: Vec<PathBuf>,
+
+    #[structopt(
+        long = "rename",
+        help = "Renames the binding at `LINE:COLUMN=NEW_NAME` (and all its uses) before formatting"
+    )]
+    rename: Option<rename::RenameOpt>,
+}
+
+#[derive(StructOpt)]
+#[structopt(about = "Compiles a gluon program and every module it imports into a single file")]
+pub struct BuildOpt {
+    #[structopt(name = "FILE", parse(from_os_str), help = "The program to compile")]
+    input: PathBuf,
+
+    #[structopt(
+        long = "output",
+        short = "o",
+        parse(from_os_str),
+        help = "File to write the compiled program to (default: FILE with the extension `gluc`)"
+    )]
+    output: Option<PathBuf>,
+}
+
+#[derive(StructOpt)]
+#[structopt(about = "Runs a program compiled with `gluon build`")]
+pub struct RunOpt {
+    #[structopt(
+        name = "ARTIFACT",
+        parse(from_os_str),
+        help = "The file written by `gluon build`"
+    )]
+    artifact: PathBuf,
 }
 
 #[derive(StructOpt)]
@@ -94,6 +132,13 @@ pub enum SubOpt {
     Fmt(FmtOpt),
     #[structopt(name = "doc", about = "Documents gluon source code")]
     Doc(::gluon_doc::Opt),
+    #[structopt(
+        name = "build",
+        about = "Compiles a gluon program and every module it imports into a single file"
+    )]
+    Build(BuildOpt),
+    #[structopt(name = "run", about = "Runs a program compiled with `gluon build`")]
+    Run(RunOpt),
 }
 
 const LONG_VERSION: &str = concat!(clap::crate_version!(), "\n", "commit: ", env!("GIT_HASH"));
@@ -132,6 +177,28 @@ pub struct Opt {
     )]
     no_std: bool,
 
+    #[structopt(
+        long = "profile",
+        help = "Profiles the executed files and prints a report to stderr"
+    )]
+    profile: bool,
+
+    #[structopt(
+        long = "profile-output",
+        default_value = "gluon.folded",
+        parse(from_os_str),
+        help = "File which `--profile` writes the folded stacks of the profile to"
+    )]
+    profile_output: PathBuf,
+
+    #[structopt(
+        long = "profile-sample",
+        value_name = "INSTRUCTIONS",
+        help = "Makes `--profile` sample the stack every INSTRUCTIONS instructions instead of \
+                recording every call"
+    )]
+    profile_sample: Option<u64>,
+
     #[structopt(name = "FILE", help = "Executes each file as a gluon program")]
     input: Vec<String>,
 
@@ -139,17 +206,57 @@ pub struct Opt {
     subcommand_opt: Option<SubOpt>,
 }
 
-fn run_files<I>(vm: &Thread, files: I) -> Result<()>
+fn run_files<I>(vm: &Thread, files: I, color: Color) -> Result<()>
 where
     I: IntoIterator,
     I::Item: AsRef<str>,
 {
     for file in files {
+        if let Some(warnings) = vm.file_warnings(file.as_ref())? {
+            let mut stderr = termcolor::StandardStream::stderr(color.into());
+            warnings.emit(&mut stderr, &vm.get_database().code_map())?;
+            writeln!(stderr)?;
+        }
         vm.load_file(file.as_ref())?;
     }
     Ok(())
 }
 
+fn profile_files<I>(
+    vm: &Thread,
+    files: I,
+    output: &Path,
+    sample_interval: Option<u64>,
+    color: Color,
+) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let profiler = Profiler::new();
+    let metric = match sample_interval {
+        Some(interval) => {
+            profiler.attach_sampling(vm, interval);
+            Metric::Samples
+        }
+        None => {
+            profiler.attach(vm);
+            Metric::Instructions
+        }
+    };
+    let result = run_files(vm, files, color);
+    Profiler::detach(vm);
+
+    // Write the profile even if the program failed
+    let profile = profiler.profile();
+    let mut folded = io::BufWriter::new(fs::File::create(output)?);
+    profile.write_folded(&mut folded, metric)?;
+    folded.flush()?;
+    eprint!("{}", profile);
+    eprintln!("Wrote the folded stacks to {}", output.display());
+    result
+}
+
 #[cfg(feature = "env_logger")]
 fn init_env_logger() {
     let _ = ::env_logger::try_init();
@@ -158,18 +265,28 @@ fn init_env_logger() {
 #[cfg(not(feature = "env_logger"))]
 fn init_env_logger() {}
 
-fn format(file: &str, file_map: Arc<codespan::FileMap>, opt: &Opt) -> Result<String> {
+fn format(
+    file: &str,
+    file_map: Arc<codespan::FileMap>,
+    opt: &Opt,
+    fmt_opt: &FmtOpt,
+) -> Result<String> {
+    let renamed;
+    let mut source = file_map.src();
+    if let Some(ref rename_opt) = fmt_opt.rename {
+        let thread = new_vm();
+        thread.get_database_mut().use_standard_lib(!opt.no_std);
+        renamed = rename::rename_source(&thread, file, source, rename_opt)?;
+        source = &renamed;
+    }
+
     let thread = new_vm();
     thread.get_database_mut().use_standard_lib(!opt.no_std);
 
-    Ok(thread.format_expr(
-        &mut gluon_format::Formatter::default(),
-        file,
-        file_map.src(),
-    )?)
+    thread.format_expr(&mut gluon_format::Formatter::default(), file, source)
 }
 
-fn fmt_file(name: &Path, opt: &Opt) -> Result<()> {
+fn fmt_file(name: &Path, opt: &Opt, fmt_opt: &FmtOpt) -> Result<()> {
     use std::fs::File;
     use std::io::Read;
 
@@ -182,7 +299,7 @@ fn fmt_file(name: &Path, opt: &Opt) -> Result<()> {
     let module_name = filename_to_module(&name.display().to_string());
     let mut code_map = codespan::CodeMap::new();
     let file_map = code_map.add_filemap(module_name.clone().into(), buffer);
-    let formatted = format(&module_name, file_map.clone(), opt)?;
+    let formatted = format(&module_name, file_map.clone(), opt, fmt_opt)?;
 
     // Avoid touching the .glu file if it did not change
     if file_map.src() != formatted {
@@ -198,7 +315,7 @@ fn fmt_file(name: &Path, opt: &Opt) -> Result<()> {
     Ok(())
 }
 
-fn fmt_stdio(opt: &Opt) -> Result<()> {
+fn fmt_stdio(opt: &Opt, fmt_opt: &FmtOpt) -> Result<()> {
     use std::io::{stdin, stdout, Read};
 
     let mut buffer = String::new();
@@ -207,11 +324,33 @@ fn fmt_stdio(opt: &Opt) -> Result<()> {
     let mut code_map = codespan::CodeMap::new();
     let file_map = code_map.add_filemap("STDIN".into(), buffer);
 
-    let formatted = format("STDIN", file_map, opt)?;
+    let formatted = format("STDIN", file_map, opt, fmt_opt)?;
     stdout().write_all(formatted.as_bytes())?;
     Ok(())
 }
 
+fn build(vm: &Thread, build_opt: &BuildOpt) -> Result<()> {
+    let input = &build_opt.input;
+    let source = fs::read_to_string(input)?;
+    let output = build_opt
+        .output
+        .clone()
+        .unwrap_or_else(|| input.with_extension("gluc"));
+
+    // Only the imported modules are run while compiling, never the program itself
+    vm.get_database_mut().run_io(false);
+    let mut writer = io::BufWriter::new(fs::File::create(&output)?);
+    vm.compile_to_artifact(&input.display().to_string(), &source, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn run_artifact(vm: &Thread, run_opt: &RunOpt) -> Result<()> {
+    let reader = io::BufReader::new(fs::File::open(&run_opt.artifact)?);
+    vm.load_artifact(reader)?;
+    Ok(())
+}
+
 fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), gluon::Error> {
     vm.global_env().set_debug_level(opt.debug_level.clone());
     match opt.subcommand_opt {
@@ -238,10 +377,10 @@ fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), gluon::E
                 gluon_files.dedup();
 
                 for file in gluon_files {
-                    fmt_file(&file, opt)?;
+                    fmt_file(&file, opt, fmt_opt)?;
                 }
             } else {
-                fmt_stdio(opt)?;
+                fmt_stdio(opt, fmt_opt)?;
             }
         }
         Some(SubOpt::Doc(ref doc_opt)) => {
@@ -250,6 +389,8 @@ fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), gluon::E
             gluon_doc::generate_for_path(&new_vm(), input, output)
                 .map_err(|err| format!("{}\n{}", err, err.backtrace()))?;
         }
+        Some(SubOpt::Build(ref build_opt)) => build(vm, build_opt)?,
+        Some(SubOpt::Run(ref run_opt)) => run_artifact(vm, run_opt)?,
         None => {
             if opt.interactive {
                 let mut runtime = Runtime::new()?;
@@ -260,7 +401,17 @@ fn run(opt: &Opt, color: Color, vm: &Thread) -> std::result::Result<(), gluon::E
                     repl::run(color, &prompt, debug_level, use_std_lib)
                 }))?;
             } else if !opt.input.is_empty() {
-                run_files(&vm, &opt.input)?;
+                if opt.profile {
+                    profile_files(
+                        vm,
+                        &opt.input,
+                        &opt.profile_output,
+                        opt.profile_sample,
+                        color,
+                    )?;
+                } else {
+                    run_files(vm, &opt.input, color)?;
+                }
             } else {
                 writeln!(io::stderr(), "{}", Opt::clap().get_matches().usage())
                     .expect("Error writing help to stderr");
@@ -0,0 +1,148 @@
+//! Renaming of bindings in gluon source files, shared by `gluon fmt --rename` and the `:rename`
+//! command of the REPL.
+
+use std::{fs, path::Path, str::FromStr};
+
+use codespan::{ColumnIndex, LineIndex};
+
+use gluon_completion::RenameError;
+
+use gluon::{
+    base::{filename_to_module, pos::Span},
+    vm::macros,
+    Result, Thread, ThreadExt,
+};
+
+/// Which binding to rename and what to rename it to, written as `LINE:COLUMN=NEW_NAME` (the line
+/// and column starts at 1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameOpt {
+    pub line: u32,
+    pub column: u32,
+    pub new_name: String,
+}
+
+impl FromStr for RenameOpt {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        const USAGE: &str = "Expected `LINE:COLUMN=NEW_NAME`";
+
+        let mut iter = s.splitn(2, '=');
+        let position = iter.next().unwrap_or("");
+        let new_name = iter.next().ok_or(USAGE)?.trim();
+
+        let mut iter = position.splitn(2, ':');
+        let mut parse_index = || {
+            iter.next()
+                .and_then(|index| index.trim().parse::<u32>().ok())
+                .filter(|&index| index > 0)
+                .ok_or(USAGE)
+        };
+        let line = parse_index()?;
+        let column = parse_index()?;
+
+        Ok(RenameOpt {
+            line,
+            column,
+            new_name: new_name.to_string(),
+        })
+    }
+}
+
+/// Typechecks `source` as `module` and returns the source with the binding at the position
+/// described by `opt` renamed.
+pub fn rename_source(
+    thread: &Thread,
+    module: &str,
+    source: &str,
+    opt: &RenameOpt,
+) -> Result<String> {
+    let (expr, _) = thread.typecheck_str(module, source, None)?;
+    let file_map = thread
+        .get_database()
+        .get_filemap(module)
+        .expect("Typechecked module has a file map");
+
+    let pos = file_map
+        .byte_index(LineIndex(opt.line - 1), ColumnIndex(opt.column - 1))
+        .map_err(|err| macros::Error::message(err.to_string()))?;
+    let edits =
+        gluon_completion::rename(file_map.span(), &expr, pos, &opt.new_name).map_err(|err| {
+            let span = match err {
+                RenameError::Shadowing { span, .. } | RenameError::FieldCollision { span, .. } => {
+                    Some(span)
+                }
+                _ => None,
+            };
+            match span.and_then(|span| file_map.location(span.start()).ok()) {
+                Some((line, column)) => macros::Error::message(format!(
+                    "{} (at {}:{})",
+                    err,
+                    line.number(),
+                    column.number()
+                )),
+                None => macros::Error::message(err.to_string()),
+            }
+        })?;
+
+    let mut output = String::with_capacity(source.len());
+    let mut last = file_map.span().start();
+    for edit in edits {
+        output.push_str(
+            file_map
+                .src_slice(Span::new(last, edit.span.start()))
+                .expect("Edit is inside the file"),
+        );
+        output.push_str(&edit.new_text);
+        last = edit.span.end();
+    }
+    output.push_str(
+        file_map
+            .src_slice(Span::new(last, file_map.span().end()))
+            .expect("Edit is inside the file"),
+    );
+    Ok(output)
+}
+
+/// Renames the binding described by `opt` in the file at `path`, writing the result back to the
+/// file
+pub fn rename_file(thread: &Thread, path: &Path, opt: &RenameOpt) -> Result<()> {
+    let source = fs::read_to_string(path)?;
+    let module = filename_to_module(&path.display().to_string());
+    let renamed = rename_source(thread, &module, &source, opt)?;
+    if renamed != source {
+        fs::write(path, renamed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rename_opt() {
+        assert_eq!(
+            "3:5=renamed".parse(),
+            Ok(RenameOpt {
+                line: 3,
+                column: 5,
+                new_name: "renamed".to_string(),
+            })
+        );
+        assert!("3=renamed".parse::<RenameOpt>().is_err());
+        assert!("0:1=renamed".parse::<RenameOpt>().is_err());
+        assert!("3:5".parse::<RenameOpt>().is_err());
+    }
+
+    #[test]
+    fn rename_source_applies_edits() {
+        let thread = gluon::new_vm();
+        let opt = "1:5=y".parse().unwrap();
+        let renamed = rename_source(&thread, "test", "let x = 1\nlet r = { x }\nx", &opt);
+        assert_eq!(
+            renamed.unwrap_or_else(|err| panic!("{}", err)),
+            "let y = 1\nlet r = { x = y }\ny"
+        );
+    }
+}
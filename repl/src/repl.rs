@@ -1,6 +1,12 @@
 extern crate gluon_completion as completion;
 
-use std::{borrow::Cow, error::Error as StdError, path::PathBuf, str::FromStr, sync::Mutex};
+use std::{
+    borrow::Cow,
+    error::Error as StdError,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
 
 use futures::{
     future::{self, Either},
@@ -36,7 +42,7 @@ use gluon::{
 
 use codespan_reporting::termcolor;
 
-use crate::Color;
+use crate::{debugger, rename::RenameOpt, Color};
 
 macro_rules! try_future {
     ($e:expr, $f:expr) => {
@@ -125,6 +131,24 @@ fn switch_debug_level(args: WithVM<&str>) -> IO<Result<String, String>> {
     IO::Value(Ok(vm.global_env().get_debug_level().to_string()))
 }
 
+fn rename_in_file(args: WithVM<&str>) -> IO<Result<String, String>> {
+    let WithVM { vm, value: args } = args;
+    let mut iter = args.trim().splitn(2, ' ');
+    let (file, rename_opt) = match (iter.next(), iter.next()) {
+        (Some(file), Some(rename_opt)) if !file.is_empty() => (file, rename_opt),
+        _ => return IO::Value(Err("Expected `FILE LINE:COLUMN=NEW_NAME`".to_string())),
+    };
+    let rename_opt = match rename_opt.parse::<RenameOpt>() {
+        Ok(rename_opt) => rename_opt,
+        Err(err) => return IO::Value(Err(err)),
+    };
+    IO::Value(
+        crate::rename::rename_file(vm, Path::new(file), &rename_opt)
+            .map(|()| format!("Renamed to `{}` in {}", rename_opt.new_name, file))
+            .map_err(|err| err.to_string()),
+    )
+}
+
 fn complete(thread: &Thread, name: &str, fileinput: &str, pos: usize) -> GluonResult<Vec<String>> {
     use gluon::compiler_pipeline::*;
 
@@ -262,6 +286,13 @@ struct CpuPool(futures_cpupool::CpuPool);
 
 impl_userdata! { CpuPool }
 
+#[derive(Userdata, Trace, VmType)]
+#[gluon(vm_type = "Debugger")]
+#[gluon_trace(skip)]
+struct Debugger(vm::debugger::Debugger);
+
+impl_userdata! { Debugger }
+
 #[derive(Serialize, Deserialize)]
 pub enum ReadlineError {
     Eof,
@@ -339,12 +370,34 @@ fn new_cpu_pool(size: usize) -> IO<CpuPool> {
     IO::Value(CpuPool(futures_cpupool::CpuPool::new(size)))
 }
 
+fn new_debugger(_: ()) -> IO<Debugger> {
+    let debugger = vm::debugger::Debugger::new();
+    debugger.set_stop_handler(Some(Box::new(debugger::stop_prompt)));
+    IO::Value(Debugger(debugger))
+}
+
+fn set_breakpoint(debugger: &Debugger, arg: &str) -> IO<Result<String, String>> {
+    IO::Value(debugger::break_command(&debugger.0, arg))
+}
+
+fn step(debugger: &Debugger, arg: &str) -> IO<Result<String, String>> {
+    IO::Value(debugger::parse_step(arg).map(|step| {
+        debugger.0.resume(step);
+        "Execution stops at the first line of the next evaluated expression".to_string()
+    }))
+}
+
 fn eval_line(
     De(color): De<crate::Color>,
+    debugger: &Debugger,
     WithVM { vm, value: line }: WithVM<&str>,
 ) -> impl Future<Item = IO<()>, Error = vm::Error> {
     let vm = vm.root_thread();
+    if debugger.0.is_active() {
+        debugger.0.attach(&vm);
+    }
     eval_line_(vm.root_thread(), line).then(move |result| {
+        vm::debugger::Debugger::detach(&vm);
         Ok(match result {
             Ok(x) => IO::Value(x),
             Err(err) => {
@@ -504,7 +557,7 @@ fn set_globals(
             )?;
             set_globals(vm, pattern, typ, value)
         }
-        Pattern::Constructor(..) | Pattern::Literal(_) | Pattern::Error => {
+        Pattern::Constructor(..) | Pattern::Literal(_) | Pattern::Or(_) | Pattern::Error => {
             Err(VMError::Message("The repl cannot bind variables from this pattern".into()).into())
         }
     }
@@ -594,17 +647,24 @@ struct Settings<'a> {
 }
 
 fn load_repl(vm: &Thread) -> vm::Result<vm::ExternModule> {
+    vm.register_type::<Debugger>("Debugger", &[])?;
+
     vm::ExternModule::new(
         vm,
         record!(
             type Color => Color,
             type Settings => Settings<'static>,
+            type Debugger => Debugger,
             type_of_expr => primitive!(1, type_of_expr),
             find_info => primitive!(1, find_info),
             find_kind => primitive!(1, find_kind),
             parse_color => primitive!(1, "parse_color", |s: &str| s.parse::<Color>()),
             switch_debug_level => primitive!(1, switch_debug_level),
-            eval_line => primitive!(2, async fn eval_line),
+            rename_in_file => primitive!(1, rename_in_file),
+            new_debugger => primitive!(1, new_debugger),
+            set_breakpoint => primitive!(2, set_breakpoint),
+            step => primitive!(2, step),
+            eval_line => primitive!(3, async fn eval_line),
             finish_or_interrupt => primitive!(3, async fn finish_or_interrupt),
             new_cpu_pool => primitive!(1, new_cpu_pool)
         ),
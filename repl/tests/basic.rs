@@ -61,3 +61,165 @@ fn issue_365_run_io_from_command_line() {
     }
     assert_eq!(String::from_utf8_lossy(&output.stdout), "123\n");
 }
+
+#[test]
+fn fmt_rename() {
+    use std::io::Write;
+
+    let run = |rename: &str, input: &str| {
+        let mut child = Command::new("../target/debug/gluon")
+            .args(["fmt", "--rename", rename])
+            .env("GLUON_PATH", "..")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Could not find gluon executable");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = run("1:5=renamed", "let x = 1\nlet f y = x #Int+ y\nf x\n");
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "let renamed = 1\nlet f y = renamed #Int+ y\nf renamed\n"
+    );
+
+    let output = run("1:5=y", "let x = 1\nlet f y = x #Int+ y\nf x\n");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Renaming would change which binding `y` refers to"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn profile_writes_report_and_folded_stacks() {
+    let output_path = env::temp_dir().join("gluon_repl_profile_test.folded");
+    let output = Command::new("../target/debug/gluon")
+        .arg("--profile")
+        .arg("--profile-output")
+        .arg(&output_path)
+        .arg("tests/print.glu")
+        .env("GLUON_PATH", "..")
+        .output()
+        .expect("Could not find gluon executable");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "123\n");
+    assert!(stderr.contains("instructions"), "{}", stderr);
+    assert!(stderr.contains("print (tests.print:1)"), "{}", stderr);
+
+    let mut folded = String::new();
+    File::open(&output_path)
+        .unwrap()
+        .read_to_string(&mut folded)
+        .unwrap();
+    assert!(
+        folded
+            .lines()
+            .any(|line| line.starts_with("print (tests.print:1) ")),
+        "{}",
+        folded
+    );
+}
+
+#[test]
+fn profile_samples_stacks() {
+    let output_path = env::temp_dir().join("gluon_repl_profile_sample_test.folded");
+    let output = Command::new("../target/debug/gluon")
+        .arg("--profile")
+        .arg("--profile-sample")
+        .arg("1")
+        .arg("--profile-output")
+        .arg(&output_path)
+        .arg("tests/print.glu")
+        .env("GLUON_PATH", "..")
+        .output()
+        .expect("Could not find gluon executable");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("Sampled every 1 instructions"),
+        "{}",
+        stderr
+    );
+
+    let mut folded = String::new();
+    File::open(&output_path)
+        .unwrap()
+        .read_to_string(&mut folded)
+        .unwrap();
+    assert!(
+        folded
+            .lines()
+            .any(|line| line.starts_with("print (tests.print:1) ")),
+        "{}",
+        folded
+    );
+}
+
+#[test]
+fn build_and_run_artifact() {
+    let artifact_path = env::temp_dir().join("gluon_repl_build_test.gluc");
+    let output = Command::new("../target/debug/gluon")
+        .arg("build")
+        .arg("tests/print.glu")
+        .arg("-o")
+        .arg(&artifact_path)
+        .env("GLUON_PATH", "..")
+        .output()
+        .expect("Could not find gluon executable");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // Building must not run the program
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+
+    // The artifact contains every module so it runs without access to any source
+    let gluon_path = env::current_dir().unwrap().join("../target/debug/gluon");
+    let output = Command::new(&gluon_path)
+        .arg("run")
+        .arg(&artifact_path)
+        .current_dir(env::temp_dir())
+        .output()
+        .expect("Could not find gluon executable");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "123\n");
+}
+
+#[test]
+fn warnings_are_printed_to_stderr() {
+    let output = Command::new("../target/debug/gluon")
+        .arg("--color")
+        .arg("never")
+        .arg("tests/unused.glu")
+        .env("GLUON_PATH", "..")
+        .output()
+        .expect("Could not find gluon executable");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "123\n");
+    assert!(
+        stderr.contains("warning: Unused variable `unused`"),
+        "{}",
+        stderr
+    );
+}
@@ -170,3 +170,25 @@ fn assert() {
     repl.test("let { assert } = import! std.test", None);
     repl.test("assert False", None);
 }
+
+#[test]
+fn debugger() {
+    let mut repl = REPL::new();
+
+    repl.test("let f x = x + 1", None);
+    repl.test(":break f", Some("Breakpoint 1 at f"));
+
+    let debug_prompt = "(debug) ";
+    let mut test_stopped = |send: &str, expect: &str| -> Result<()> {
+        repl.session.send_line(send)?;
+        repl.session.exp_string(send)?;
+        repl.session.exp_string(expect)?;
+        repl.session.exp_string(debug_prompt)?;
+        Ok(())
+    };
+    test_stopped("f 41", "Stopped at line:1 in f (breakpoint 1)")
+        .unwrap_or_else(|err| panic!("{}", err));
+    test_stopped(":locals", "x : Int = 41").unwrap_or_else(|err| panic!("{}", err));
+
+    repl.test(":continue", Some("42"));
+}
@@ -13,6 +13,8 @@ declare -a PROJECTS=(
     c-api
     doc
     repl
+    language-server
+    debug-adapter
 )
 
 for PROJECT in "${PROJECTS[@]}"
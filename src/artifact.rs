@@ -0,0 +1,136 @@
+//! Self-contained artifacts which store a compiled program together with every module it imports.
+//!
+//! An artifact starts with a header line listing the extern modules the program needs and the
+//! modules stored in the artifact. Each module is then stored on its own line as a serialized
+//! `compiler_pipeline::VersionedModule` (the same format that `compile_to` writes). The modules
+//! are stored in the order they must be loaded in and the last module is the program itself.
+
+use std::io::{BufRead, Write};
+
+use futures::Future;
+
+use crate::base::fnv::FnvSet;
+
+use crate::vm::{serialization::SeSeed, thread::Thread};
+
+use crate::{
+    compiler_pipeline::{Executable, Module, Precompiled, VersionedModule},
+    import::ImportApi,
+    query::{self, Compilation, CompilerDatabase},
+    serde::ser::SerializeState,
+    Error, ModuleCompiler, Result,
+};
+
+struct Artifact {
+    externs: Vec<String>,
+    modules: Vec<(String, VersionedModule)>,
+    visited: FnvSet<String>,
+}
+
+impl Artifact {
+    fn add_module(
+        &mut self,
+        db: &CompilerDatabase,
+        import: &dyn ImportApi,
+        module: &str,
+    ) -> Result<()> {
+        if !self.visited.insert(module.to_string()) {
+            return Ok(());
+        }
+        if import.is_extern_module(module) {
+            self.externs.push(module.to_string());
+            return Ok(());
+        }
+
+        let value = db
+            .typechecked_module(module.into(), None)
+            .map_err(|(_, err)| err)?;
+        // Imports must be loaded before the modules that import them
+        for dependency in query::module_imports(&value.expr) {
+            self.add_module(db, import, &dependency)?;
+        }
+
+        let compiled_module = VersionedModule(Module {
+            typ: value.typ.clone(),
+            metadata: value.metadata.clone(),
+            module: query::compile_module(db, module)?,
+        });
+        self.modules.push((module.to_string(), compiled_module));
+        Ok(())
+    }
+}
+
+/// Compiles `module`, which must have been added to `db`, and every module it imports and writes
+/// them to `writer`
+pub(crate) fn compile(
+    db: &CompilerDatabase,
+    vm: &Thread,
+    module: &str,
+    mut writer: impl Write,
+) -> Result<()> {
+    let import = crate::get_import(vm);
+    let mut artifact = Artifact {
+        externs: Vec::new(),
+        modules: Vec::new(),
+        visited: FnvSet::default(),
+    };
+    artifact.add_module(db, &*import, module)?;
+
+    let names: Vec<_> = artifact.modules.iter().map(|(name, _)| name).collect();
+    serde_json::to_writer(&mut writer, &(&artifact.externs, &names))
+        .map_err(std::io::Error::from)?;
+    writer.write_all(b"\n")?;
+    for (_, module) in &artifact.modules {
+        module
+            .serialize_state(
+                &mut serde_json::Serializer::new(&mut writer),
+                &SeSeed::new(),
+            )
+            .map_err(std::io::Error::from)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+
+    info!(
+        "Compiled `{}` with {} modules into an artifact",
+        module,
+        artifact.modules.len()
+    );
+    Ok(())
+}
+
+/// Loads every module stored in the artifact in `reader` and returns the name of the program
+pub(crate) fn load(
+    compiler: &mut ModuleCompiler,
+    vm: &Thread,
+    mut reader: impl BufRead,
+) -> Result<String> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let (externs, modules): (Vec<String>, Vec<String>) = serde_json::from_str(&line)
+        .map_err(|err| Error::from(format!("Invalid artifact header: {}", err)))?;
+
+    for module in externs {
+        compiler.database.import(module)?;
+    }
+
+    let program = modules
+        .last()
+        .cloned()
+        .ok_or_else(|| Error::from("The artifact does not contain any modules".to_string()))?;
+    for module in modules {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(format!("The artifact ended before `{}` was loaded", module).into());
+        }
+        // Modules which the vm has already loaded are shared with the program instead of being
+        // loaded again
+        if vm.global_env().global_exists(&module) {
+            continue;
+        }
+        Precompiled(&mut serde_json::Deserializer::from_str(&line))
+            .load_script(compiler, vm, &module, "", ())
+            .wait()?;
+    }
+    Ok(program)
+}
@@ -21,12 +21,12 @@ use crate::base::{
     error::{Errors, InFile},
     fnv::FnvMap,
     metadata::Metadata,
-    resolve,
+    pos, resolve,
     symbol::{Name, NameBuf, Symbol, SymbolData, SymbolModule},
     types::{ArcType, NullInterner, Type, TypeCache},
 };
 
-use crate::check::{metadata, rename};
+use crate::check::{metadata, rename, warning::SpannedWarning};
 
 use crate::vm::{
     compiler::CompiledModule,
@@ -35,7 +35,7 @@ use crate::vm::{
     thread::{RootedThread, RootedValue, Thread, ThreadInternal, VmRoot},
 };
 
-use crate::{query::Compilation, Error, ModuleCompiler, Result};
+use crate::{query::Compilation, Error, ModuleCompiler, Result, Severity};
 
 pub type BoxFuture<'vm, T, E> = Box<dyn Future<Item = T, Error = E> + Send + 'vm>;
 
@@ -447,6 +447,8 @@ pub struct TypecheckValue<E> {
     pub typ: ArcType,
     pub metadata_map: FnvMap<Symbol, Arc<Metadata>>,
     pub metadata: Arc<Metadata>,
+    /// Warnings found in the expression which were not allowed
+    pub warnings: Vec<SpannedWarning>,
 }
 
 impl<E> TypecheckValue<E> {
@@ -456,12 +458,14 @@ impl<E> TypecheckValue<E> {
             typ,
             metadata_map,
             metadata,
+            warnings,
         } = self;
         TypecheckValue {
             expr: f(expr),
             typ,
             metadata_map,
             metadata,
+            warnings,
         }
     }
 }
@@ -531,8 +535,11 @@ fn typecheck_expr(
     file: &str,
     expected_type: Option<&ArcType>,
     metadata_map: &mut FnvMap<Symbol, Arc<Metadata>>,
-) -> Result<ArcType> {
-    use crate::check::typecheck::Typecheck;
+) -> Result<(ArcType, Vec<SpannedWarning>)> {
+    use crate::check::{
+        typecheck::{self, TypeError, Typecheck},
+        warning::{self, Level, Lint, LintLevels},
+    };
     let env = compiler.database;
     let mut tc = Typecheck::new(
         file.into(),
@@ -542,8 +549,37 @@ fn typecheck_expr(
         metadata_map,
     );
 
-    tc.typecheck_expr_expected(expr.borrow_mut(), expected_type)
-        .map_err(|err| InFile::new(compiler.database.state().code_map.clone(), err).into())
+    let typ = tc
+        .typecheck_expr_expected(expr.borrow_mut(), expected_type)
+        .map_err(|err| InFile::new(compiler.database.state().code_map.clone(), err))?;
+
+    let mut levels = LintLevels::default();
+    if env.compiler_settings().exhaustiveness == Severity::Error {
+        levels.set(Lint::NonExhaustivePatterns, Level::Deny);
+        levels.set(Lint::UnreachablePatterns, Level::Deny);
+    }
+    let mut report = warning::check_expr(&env, expr, &levels);
+
+    // The implicit prelude is parsed from a separate source so only report what is found in
+    // `file` itself
+    if let Some(file_map) = env.get_filemap(file) {
+        let span = file_map.span();
+        report
+            .warnings
+            .retain(|warning| span.contains(warning.span));
+        report.errors.retain(|warning| span.contains(warning.span));
+    }
+
+    if !report.errors.is_empty() {
+        let errors: typecheck::Error = report
+            .errors
+            .into_iter()
+            .map(|err| pos::spanned(err.span, TypeError::from(err.value).into()))
+            .collect();
+        return Err(InFile::new(compiler.database.state().code_map.clone(), errors).into());
+    }
+
+    Ok((typ, report.warnings))
 }
 
 impl<E> Typecheckable for InfixReparsed<E>
@@ -568,7 +604,7 @@ where
             metadata,
         } = self;
 
-        let typ = match typecheck_expr(
+        let (typ, warnings) = match typecheck_expr(
             expr.borrow_mut(),
             compiler,
             thread,
@@ -576,7 +612,7 @@ where
             expected_type,
             &mut metadata_map,
         ) {
-            Ok(typ) => typ,
+            Ok(result) => result,
             Err(err) => {
                 return Err((
                     Some(TypecheckValue {
@@ -587,6 +623,7 @@ where
                         expr,
                         metadata_map,
                         metadata,
+                        warnings: Vec::new(),
                     }),
                     err,
                 ))
@@ -606,6 +643,7 @@ where
             typ,
             metadata_map,
             metadata,
+            warnings,
         })
     }
 }
@@ -766,6 +804,7 @@ where
                 filename.to_string(),
                 settings.emit_debug_info,
             );
+            compiler.set_wrapping_int_arithmetic(settings.wrapping_int_arithmetic);
             compiler.compile_expr(core_expr.value.expr())?
         };
         module.function.id = Symbol::from(filename);
@@ -960,6 +999,164 @@ pub struct Module {
     pub module: CompiledModule,
 }
 
+#[cfg(feature = "serde")]
+impl Module {
+    /// Runs the module and stores the resulting value in the global `module_id`, returning the
+    /// type of the global
+    pub(crate) fn load(self, vm: &Thread, module_id: Symbol, run_io: bool) -> Result<ArcType> {
+        let Module {
+            typ,
+            metadata,
+            module,
+        } = self;
+        let closure = vm.global_env().new_global_thunk(vm, module)?;
+        let ExecuteValue {
+            typ,
+            metadata,
+            value,
+            ..
+        } = vm
+            .call_thunk_top(&closure)
+            .map(|value| ExecuteValue {
+                id: module_id.clone(),
+                expr: (),
+                typ,
+                metadata,
+                value,
+            })
+            .map_err(Error::from)
+            .and_then(move |v| {
+                if run_io {
+                    future::Either::B(crate::compiler_pipeline::run_io(vm, v))
+                } else {
+                    future::Either::A(future::ok(v))
+                }
+            })
+            .wait()?;
+
+        vm.set_global(module_id, typ.clone(), metadata, value.get_value())?;
+        Ok(typ)
+    }
+}
+
+/// Written in front of every module serialized by `compile_to` so that modules which were written
+/// by an incompatible version of gluon are rejected with a clear error
+#[cfg(feature = "serde")]
+const PRECOMPILED_FORMAT: &str = "gluon-module";
+
+/// Must be incremented whenever the serialized form of `Module` changes
+#[cfg(feature = "serde")]
+const PRECOMPILED_VERSION: u32 = 1;
+
+/// A `Module` which is serialized together with a header containing `PRECOMPILED_FORMAT` and
+/// `PRECOMPILED_VERSION`
+#[cfg(feature = "serde")]
+pub(crate) struct VersionedModule(pub Module);
+
+#[cfg(feature = "serde")]
+impl crate::serde::ser::SerializeState<crate::vm::serialization::SeSeed> for VersionedModule {
+    fn serialize_state<S>(
+        &self,
+        serializer: S,
+        seed: &crate::vm::serialization::SeSeed,
+    ) -> StdResult<S::Ok, S::Error>
+    where
+        S: crate::serde::Serializer,
+    {
+        use crate::serde::ser::{Seeded, SerializeTuple};
+
+        let mut tuple = serializer.serialize_tuple(3)?;
+        tuple.serialize_element(PRECOMPILED_FORMAT)?;
+        tuple.serialize_element(&PRECOMPILED_VERSION)?;
+        tuple.serialize_element(&Seeded::new(seed, &self.0))?;
+        tuple.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'gc> crate::serde::de::DeserializeState<'de, crate::vm::serialization::DeSeed<'gc>>
+    for VersionedModule
+{
+    fn deserialize_state<D>(
+        seed: &mut crate::vm::serialization::DeSeed<'gc>,
+        deserializer: D,
+    ) -> StdResult<Self, D::Error>
+    where
+        D: crate::serde::Deserializer<'de>,
+    {
+        use std::fmt;
+
+        use crate::serde::de::{self, Error as _, SeqAccess};
+        use crate::vm::serialization::DeSeed;
+
+        const OLD_FORMAT: &str = "The module was precompiled by an older version of gluon \
+                                  which did not record a format version, compile it again";
+
+        struct Visitor<'s, 'gc>(&'s mut DeSeed<'gc>);
+
+        impl<'de, 's, 'gc> de::Visitor<'de> for Visitor<'s, 'gc> {
+            type Value = VersionedModule;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                // Self describing formats report modules from older versions (which were
+                // serialized as a struct) as an invalid type so the hint must be in this message
+                write!(
+                    f,
+                    "a precompiled gluon module with a format version (modules precompiled by \
+                     an older version of gluon must be compiled again)"
+                )
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> StdResult<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let format: String = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                if format != PRECOMPILED_FORMAT {
+                    return Err(A::Error::custom(OLD_FORMAT));
+                }
+                let version: u32 = seq
+                    .next_element()?
+                    .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+                if version != PRECOMPILED_VERSION {
+                    return Err(A::Error::custom(format!(
+                        "The module was precompiled with format version {} but this version of \
+                         gluon only loads version {}, compile it again",
+                        version, PRECOMPILED_VERSION
+                    )));
+                }
+                let module = seq
+                    .next_element_seed(de::Seed::<_, Module>::new(&mut *self.0))?
+                    .ok_or_else(|| A::Error::invalid_length(2, &self))?;
+                Ok(VersionedModule(module))
+            }
+        }
+
+        deserializer.deserialize_tuple(3, Visitor(seed))
+    }
+}
+
+/// Returns the types of every global and every registered type of `vm` so that a deserialized
+/// module can refer to the same aliases as the modules that are already loaded
+#[cfg(feature = "serde")]
+pub(crate) fn global_types(vm: &Thread) -> Vec<ArcType> {
+    let globals = vm.global_env().get_globals();
+    globals
+        .globals
+        .values()
+        .map(|global| global.typ.clone())
+        .chain(
+            globals
+                .type_infos
+                .id_to_type
+                .values()
+                .map(|alias| alias.as_type().clone()),
+        )
+        .collect()
+}
+
 #[cfg(feature = "serde")]
 impl<'vm, 'de, D> Executable<'vm, ()> for Precompiled<D>
 where
@@ -980,9 +1177,13 @@ where
     {
         use crate::vm::serialization::DeSeed;
 
-        let module: Module = try_future!(DeSeed::new(&vm, &mut vm.current_context())
-            .deserialize(self.0)
-            .map_err(|err| err.to_string()));
+        let types = global_types(&vm);
+        let VersionedModule(module) = {
+            let mut context = vm.current_context();
+            let mut seed = DeSeed::new(&vm, &mut context);
+            seed.add_aliases(&types);
+            try_future!(seed.deserialize(self.0).map_err(|err| err.to_string()))
+        };
         let module_id = module.module.function.id.clone();
         if filename != module_id.as_ref() {
             return Box::new(future::err(
@@ -1017,23 +1218,22 @@ where
     where
         T: Send + VmRoot<'vm>,
     {
-        use crate::vm::internal::Global;
         use crate::vm::serialization::DeSeed;
 
-        let Global {
-            metadata,
-            typ,
-            value,
-            id: _,
-        } = try_future!(DeSeed::new(&vm, &mut vm.current_context())
-            .deserialize(self.0)
-            .map_err(|err| err.to_string()));
+        let types = global_types(&vm);
+        let VersionedModule(module) = {
+            let mut context = vm.current_context();
+            let mut seed = DeSeed::new(&vm, &mut context);
+            seed.add_aliases(&types);
+            try_future!(seed.deserialize(self.0).map_err(|err| err.to_string()))
+        };
         let id = compiler.symbols.symbol(SymbolData {
             global: true,
             location: None,
             name: name,
         });
-        try_future!(vm.set_global(id, typ, metadata.clone(), &value,));
+        let run_io = compiler.database.compiler_settings().run_io;
+        try_future!(module.load(&vm, id, run_io));
         info!("Loaded module `{}`", name);
         Box::new(future::ok(()))
     }
@@ -1066,11 +1266,11 @@ where
         .compile(compiler, thread, file, expr_str, arg)
         .map_err(Error::from)
         .map_err(Either::Left)?;
-    let module = Module {
+    let module = VersionedModule(Module {
         typ,
         metadata,
         module,
-    };
+    });
     module
         .serialize_state(serializer, &SeSeed::new())
         .map_err(Either::Right)
@@ -35,9 +35,16 @@ use crate::vm::{
 
 use crate::{
     query::{Compilation, CompilerDatabase},
+    sandbox::SandboxPolicy,
     IoError, ModuleCompiler,
 };
 
+#[cfg(feature = "serialization")]
+mod cache;
+
+#[cfg(feature = "serialization")]
+pub use self::cache::ModuleCache;
+
 quick_error! {
     /// Error type for the import macro
     #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -56,6 +63,11 @@ quick_error! {
             description(message)
             display("{}", message)
         }
+        /// The sandbox policy does not allow the module to be imported
+        Blocked(module: String) {
+            description("The sandbox policy does not allow the module to be imported")
+            display("The sandbox policy does not allow `{}` to be imported", module)
+        }
         /// The importer could not load the imported file
         IO(err: IoError) {
             description(err.description())
@@ -158,6 +170,8 @@ pub(crate) trait ImportApi {
         module_id: &Symbol,
     ) -> Result<ArcType, (Option<ArcType>, MacroError)>;
     fn snapshot(&self, thread: RootedThread) -> DatabaseSnapshot;
+    fn sandbox_policy(&self) -> Arc<SandboxPolicy>;
+    fn is_extern_module(&self, module: &str) -> bool;
 }
 
 impl<I> ImportApi for Import<I>
@@ -183,8 +197,17 @@ where
     fn snapshot(&self, thread: RootedThread) -> DatabaseSnapshot {
         Self::snapshot(self, thread)
     }
+    fn sandbox_policy(&self) -> Arc<SandboxPolicy> {
+        Self::sandbox_policy(self)
+    }
+    fn is_extern_module(&self, module: &str) -> bool {
+        Self::is_extern_module(self, module)
+    }
 }
 
+// Extern modules which are needed by the prelude and are therefore exempt from the sandbox policy
+const REQUIRED_EXTERN_MODULES: &[&str] = &["std.prim", "std.io.prim"];
+
 /// Macro which rewrites occurances of `import! "filename"` to a load of that file if it is not
 /// already loaded and then a global access to the loaded module
 pub struct Import<I = DefaultImporter> {
@@ -193,6 +216,9 @@ pub struct Import<I = DefaultImporter> {
     pub importer: I,
 
     compiler: Mutex<CompilerDatabase>,
+    sandbox: RwLock<Arc<SandboxPolicy>>,
+    #[cfg(feature = "serialization")]
+    module_cache: RwLock<Option<Arc<ModuleCache>>>,
 }
 
 impl<I> Import<I> {
@@ -203,6 +229,9 @@ impl<I> Import<I> {
             loaders: RwLock::default(),
             compiler: CompilerDatabase::new_base(None).into(),
             importer: importer,
+            sandbox: RwLock::default(),
+            #[cfg(feature = "serialization")]
+            module_cache: RwLock::default(),
         }
     }
 
@@ -215,6 +244,33 @@ impl<I> Import<I> {
         *self.paths.write().unwrap() = paths;
     }
 
+    /// Sets the policy which decides which modules may be imported. Modules which have already
+    /// been imported are not affected.
+    pub fn set_sandbox_policy(&self, policy: SandboxPolicy) {
+        *self.sandbox.write().unwrap() = Arc::new(policy);
+    }
+
+    pub fn sandbox_policy(&self) -> Arc<SandboxPolicy> {
+        self.sandbox.read().unwrap().clone()
+    }
+
+    /// Sets the cache which compiled modules are loaded from and stored in. Modules which have
+    /// already been imported are not stored in the cache.
+    #[cfg(feature = "serialization")]
+    pub fn set_module_cache(&self, module_cache: Option<ModuleCache>) {
+        *self.module_cache.write().unwrap() = module_cache.map(Arc::new);
+    }
+
+    #[cfg(feature = "serialization")]
+    pub fn module_cache(&self) -> Option<Arc<ModuleCache>> {
+        self.module_cache.read().unwrap().clone()
+    }
+
+    /// Returns true if `module` is loaded by an extern loader instead of from source
+    pub fn is_extern_module(&self, module: &str) -> bool {
+        self.loaders.read().unwrap().contains_key(module)
+    }
+
     pub fn add_loader(&self, module: &str, loader: ExternLoader) {
         self.loaders
             .write()
@@ -270,6 +326,11 @@ impl<I> Import<I> {
         {
             let mut loaders = self.loaders.write().unwrap();
             if let Some(loader) = loaders.get_mut(module) {
+                if !REQUIRED_EXTERN_MODULES.contains(&module)
+                    && !self.sandbox_policy().allows_extern_module(module)
+                {
+                    return Err(MacroError::new(Error::Blocked(module.to_string())));
+                }
                 let value = loader(vm).map_err(MacroError::new)?;
                 return Ok(UnloadedModule::Extern(value));
             }
@@ -356,10 +417,45 @@ impl<I> Import<I> {
                 .map_err(|err| (None, MacroError::new(err)))?;
                 typ
             }
-            UnloadedModule::Source => self
-                .importer
-                .import(compiler, vm, &modulename)
-                .map_err(|(t, err)| (t, MacroError::new(err)))?,
+            UnloadedModule::Source => {
+                if !self.sandbox_policy().allows_import(modulename) {
+                    return Err((
+                        None,
+                        MacroError::new(Error::Blocked(modulename.to_string())),
+                    ));
+                }
+
+                #[cfg(feature = "serialization")]
+                let module_cache = self.module_cache();
+                #[cfg(feature = "serialization")]
+                let is_extern = |module: &str| self.is_extern_module(module);
+
+                #[cfg(feature = "serialization")]
+                {
+                    if let Some(module_cache) = &module_cache {
+                        if let Some(typ) = module_cache
+                            .load(compiler, vm, module_id, &is_extern)
+                            .map_err(|err| (None, MacroError::new(err)))?
+                        {
+                            return Ok(typ);
+                        }
+                    }
+                }
+
+                let typ = self
+                    .importer
+                    .import(compiler, vm, modulename)
+                    .map_err(|(t, err)| (t, MacroError::new(err)))?;
+
+                #[cfg(feature = "serialization")]
+                {
+                    if let Some(module_cache) = &module_cache {
+                        module_cache.store(compiler, modulename, &is_extern);
+                    }
+                }
+
+                typ
+            }
         })
     }
 }
@@ -0,0 +1,311 @@
+//! An on-disk cache of compiled modules.
+//!
+//! Each module is stored in its own file, `<module>.gluc`, which starts with a header line
+//! describing the key of the module and the keys of the modules it imports, followed by the
+//! serialized `compiler_pipeline::Module`. The key of a module is a hash of the compiler version,
+//! the compiler settings, the source of the module and the keys of its imports, so a module is
+//! recompiled whenever it or any module it (transitively) imports changes.
+
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::base::{ast::Expr, fnv::FnvMap, symbol::Symbol, types::ArcType};
+
+use crate::vm::{
+    serialization::{DeSeed, SeSeed},
+    thread::Thread,
+};
+
+use crate::{
+    compiler_pipeline::Module,
+    query::{self, Compilation, CompilerDatabase},
+    serde::ser::SerializeState,
+    Error, ModuleCompiler,
+};
+
+const EXTENSION: &str = "gluc";
+
+// Key recorded for imports of extern modules, these do not have any source to hash
+const EXTERN_KEY: &str = "extern";
+
+/// Stores compiled modules in a directory so that they do not need to be parsed, typechecked and
+/// compiled again the next time they are imported.
+///
+/// The cache is installed with `VmBuilder::module_cache` (or `Import::set_module_cache`).
+/// Modules are loaded directly from the cache without going through the `Importer` of the
+/// `Import` macro.
+#[derive(Clone, Debug)]
+pub struct ModuleCache {
+    dir: PathBuf,
+}
+
+struct Header {
+    key: String,
+    dependencies: Vec<(String, String)>,
+}
+
+impl ModuleCache {
+    /// Creates a cache which stores modules in `dir`. The directory is created when the first
+    /// module is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> ModuleCache {
+        ModuleCache { dir: dir.into() }
+    }
+
+    /// The directory the modules are stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes every module stored in the cache
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::read_dir(&self.dir) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry?.path();
+                    if path.extension() == Some(EXTENSION.as_ref()) {
+                        fs::remove_file(path)?;
+                    }
+                }
+                Ok(())
+            }
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn path(&self, module: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", module, EXTENSION))
+    }
+
+    /// Loads `module` from the cache and sets it as a global of `vm`. Returns `None` if the module
+    /// is not stored in the cache or if it (or one of its imports) has changed since it was
+    /// stored.
+    pub(crate) fn load(
+        &self,
+        compiler: &mut ModuleCompiler,
+        vm: &Thread,
+        module_id: &Symbol,
+        is_extern: &dyn Fn(&str) -> bool,
+    ) -> Result<Option<ArcType>, Error> {
+        let db = compiler.database;
+        let module = module_id.definition_name();
+
+        let mut keys = FnvMap::default();
+        if self.current_key(db, module, is_extern, &mut keys).is_none() {
+            return Ok(None);
+        }
+
+        let contents = match fs::read(self.path(module)) {
+            Ok(contents) => contents,
+            Err(err) => {
+                debug!("Unable to read `{}` from the module cache: {}", module, err);
+                return Ok(None);
+            }
+        };
+        let (header, body) = match contents.iter().position(|&b| b == b'\n') {
+            Some(i) => (&contents[..i], &contents[i + 1..]),
+            None => return Ok(None),
+        };
+        let header = match parse_header(header) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        // Import the dependencies first so that the globals the module refers to exist when the
+        // module is loaded
+        let mut dependency_types = Vec::new();
+        for (dependency, _) in &header.dependencies {
+            match db.import(dependency.clone())? {
+                Expr::Ident(id) => dependency_types.push(id.typ),
+                _ => ice!("Import did not return an identifier"),
+            }
+        }
+
+        let module_value: Module = {
+            let mut context = vm.current_context();
+            let mut seed = DeSeed::new(vm, &mut context);
+            seed.add_aliases(&dependency_types);
+            match seed.deserialize(&mut serde_json::Deserializer::from_slice(body)) {
+                Ok(module) => module,
+                Err(err) => {
+                    warn!("Unable to load `{}` from the module cache: {}", module, err);
+                    return Ok(None);
+                }
+            }
+        };
+        info!("Loading `{}` from the module cache", module);
+
+        let run_io = db.compiler_settings().run_io;
+        let typ = module_value.load(vm, module_id.clone(), run_io)?;
+
+        Ok(Some(typ))
+    }
+
+    /// Compiles `module`, which must already have been imported from source, and stores it in the
+    /// cache. Failing to store the module is not an error as it just means that it needs to be
+    /// compiled the next time.
+    pub(crate) fn store(
+        &self,
+        compiler: &mut ModuleCompiler,
+        module: &str,
+        is_extern: &dyn Fn(&str) -> bool,
+    ) {
+        if let Err(err) = self.store_(compiler.database, module, is_extern) {
+            warn!("Unable to store `{}` in the module cache: {}", module, err);
+        }
+    }
+
+    fn store_(
+        &self,
+        db: &CompilerDatabase,
+        module: &str,
+        is_extern: &dyn Fn(&str) -> bool,
+    ) -> Result<(), Error> {
+        let value = db
+            .typechecked_module(module.into(), None)
+            .map_err(|(_, err)| err)?;
+
+        let mut keys = FnvMap::default();
+        let dependencies = query::module_imports(&value.expr)
+            .into_iter()
+            .filter(|dependency| dependency != module)
+            .map(|dependency| {
+                let key = if is_extern(&dependency) {
+                    Some(EXTERN_KEY.to_string())
+                } else {
+                    self.current_key(db, &dependency, is_extern, &mut keys)
+                };
+                key.map(|key| (dependency.clone(), key)).ok_or_else(|| {
+                    Error::from(format!("`{}` is not in the module cache", dependency))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = module_key(db, module, &dependencies)?;
+
+        let module_value = Module {
+            typ: value.typ.clone(),
+            metadata: value.metadata.clone(),
+            module: query::compile_module(db, module)?,
+        };
+
+        fs::create_dir_all(&self.dir)?;
+
+        // Write to a temporary file first so that other processes never see a partially written
+        // module
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let temp_path = self.dir.join(format!(
+            "{}.{}.{}.tmp",
+            module,
+            process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let result = (|| -> Result<(), Error> {
+            let mut file = std::io::BufWriter::new(File::create(&temp_path)?);
+            serde_json::to_writer(&mut file, &(&key, &dependencies))
+                .map_err(std::io::Error::from)?;
+            file.write_all(b"\n")?;
+            module_value
+                .serialize_state(&mut serde_json::Serializer::new(&mut file), &SeSeed::new())
+                .map_err(std::io::Error::from)?;
+            file.flush()?;
+            Ok(())
+        })()
+        .and_then(|()| Ok(fs::rename(&temp_path, self.path(module))?));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result?;
+
+        info!("Stored `{}` in the module cache", module);
+        Ok(())
+    }
+
+    fn read_header(&self, module: &str) -> Option<Header> {
+        let file = File::open(self.path(module)).ok()?;
+        let mut line = Vec::new();
+        BufReader::new(file).read_until(b'\n', &mut line).ok()?;
+        parse_header(&line)
+    }
+
+    /// Returns the key of the cached `module` if it and all of its imports are up to date
+    fn current_key(
+        &self,
+        db: &CompilerDatabase,
+        module: &str,
+        is_extern: &dyn Fn(&str) -> bool,
+        keys: &mut FnvMap<String, Option<String>>,
+    ) -> Option<String> {
+        if let Some(key) = keys.get(module) {
+            return key.clone();
+        }
+        // Guards against cycles in corrupted headers
+        keys.insert(module.to_string(), None);
+
+        let key = self.read_header(module).and_then(|header| {
+            let dependencies = header
+                .dependencies
+                .iter()
+                .map(|(dependency, _)| {
+                    let key = if is_extern(dependency) {
+                        Some(EXTERN_KEY.to_string())
+                    } else {
+                        self.current_key(db, dependency, is_extern, keys)
+                    };
+                    key.map(|key| (dependency.clone(), key))
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            let key = module_key(db, module, &dependencies).ok()?;
+            if key == header.key && dependencies == header.dependencies {
+                Some(key)
+            } else {
+                debug!("`{}` in the module cache is out of date", module);
+                None
+            }
+        });
+
+        keys.insert(module.to_string(), key.clone());
+        key
+    }
+}
+
+fn parse_header(line: &[u8]) -> Option<Header> {
+    let (key, dependencies) = serde_json::from_slice(line).ok()?;
+    Some(Header { key, dependencies })
+}
+
+fn module_key(
+    db: &CompilerDatabase,
+    module: &str,
+    dependencies: &[(String, String)],
+) -> Result<String, Error> {
+    let source = db.module_text(module.into())?;
+
+    let mut hasher = Sha256::new();
+    let mut input = |bytes: &[u8]| {
+        hasher.input((bytes.len() as u64).to_le_bytes());
+        hasher.input(bytes);
+    };
+    input(env!("CARGO_PKG_VERSION").as_bytes());
+    input(format!("{:?}", db.compiler_settings()).as_bytes());
+    input(module.as_bytes());
+    input(source.as_bytes());
+    for (dependency, key) in dependencies {
+        input(dependency.as_bytes());
+        input(key.as_bytes());
+    }
+
+    Ok(hasher
+        .result()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
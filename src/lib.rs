@@ -43,12 +43,15 @@ macro_rules! try_future {
     };
 }
 
+#[cfg(feature = "serialization")]
+mod artifact;
 pub mod compiler_pipeline;
 #[macro_use]
 pub mod import;
 pub mod lift_io;
 #[doc(hidden)]
 pub mod query;
+pub mod sandbox;
 pub mod std_lib;
 
 pub use crate::vm::thread::{RootedThread, Thread};
@@ -72,11 +75,14 @@ use crate::base::{
     types::{ArcType, TypeCache},
 };
 
+use crate::check::warning::Warning;
+
 use crate::format::Formatter;
 
 use crate::vm::{
     api::{Getable, Hole, OpaqueValue, VmType},
     compiler::CompiledModule,
+    gc::GcMode,
     macros,
 };
 
@@ -84,6 +90,7 @@ use crate::{
     compiler_pipeline::*,
     import::{add_extern_module, DefaultImporter, Import},
     query::{Compilation, CompilationBase},
+    sandbox::SandboxPolicy,
 };
 
 quick_error! {
@@ -285,6 +292,15 @@ impl Error {
 /// Type alias for results returned by gluon
 pub type Result<T> = StdResult<T, Error>;
 
+/// How the problems found by a check are reported
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Severity {
+    /// The problems are reported as warnings and compilation continues
+    Warning,
+    /// The problems are errors which stop compilation
+    Error,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct Settings {
     pub implicit_prelude: bool,
@@ -293,6 +309,8 @@ pub struct Settings {
     pub use_standard_lib: bool,
     pub optimize: bool,
     pub run_io: bool,
+    pub exhaustiveness: Severity,
+    pub wrapping_int_arithmetic: bool,
 }
 
 impl Default for Settings {
@@ -304,6 +322,8 @@ impl Default for Settings {
             use_standard_lib: true,
             optimize: true,
             run_io: false,
+            exhaustiveness: Severity::Warning,
+            wrapping_int_arithmetic: false,
         }
     }
 }
@@ -387,6 +407,19 @@ impl import::DatabaseMut {
         /// (default: false)
         run_io set_run_io: bool
     }
+
+    runtime_option! {
+        /// Sets how non-exhaustive `match` expressions and unreachable alternatives are reported.
+        /// (default: Severity::Warning)
+        exhaustiveness set_exhaustiveness: Severity
+    }
+
+    runtime_option! {
+        /// Sets whether `Int` arithmetic wraps around on overflow instead of returning an error
+        /// carrying the source location of the operation
+        /// (default: false)
+        wrapping_int_arithmetic set_wrapping_int_arithmetic: bool
+    }
 }
 
 /// Extension trait which provides methods to load and execute gluon code
@@ -455,6 +488,20 @@ pub trait ThreadExt {
         .map_err(|t| t.1)
     }
 
+    /// Typechecks the file `filename` and returns the warnings which were found in it, if any
+    fn file_warnings(&self, filename: &str) -> Result<Option<InFile<Warning>>> {
+        let vm = self.thread();
+        let db = vm.get_database();
+        let TypecheckValue { warnings, .. } = db
+            .typechecked_module(filename_to_module(filename), None)
+            .map_err(|t| t.1)?;
+        Ok(if warnings.is_empty() {
+            None
+        } else {
+            Some(InFile::new(db.code_map(), warnings.into_iter().collect()))
+        })
+    }
+
     fn typecheck_str(
         &self,
         file: &str,
@@ -487,6 +534,7 @@ pub trait ThreadExt {
             typ: vm.global_env().type_cache().hole(),
             metadata: Default::default(),
             metadata_map: Default::default(),
+            warnings: Vec::new(),
         }
         .compile(
             &mut self.module_compiler(&vm.get_database()),
@@ -541,6 +589,36 @@ pub trait ThreadExt {
         ))
     }
 
+    /// Compiles `input` together with every module it (transitively) imports and writes them to
+    /// `writer` as a single artifact. The artifact can be loaded with `load_artifact` without
+    /// access to the source code of any of the modules.
+    #[cfg(feature = "serialization")]
+    fn compile_to_artifact<W>(&self, filename: &str, input: &str, writer: W) -> Result<()>
+    where
+        W: std::io::Write,
+    {
+        let module_name = filename_to_module(filename);
+
+        let vm = self.thread();
+        {
+            let mut db = vm.get_database_mut();
+            db.add_module(module_name.clone(), input);
+        }
+        artifact::compile(&vm.get_database(), vm, &module_name, writer)
+    }
+
+    /// Loads an artifact written by `compile_to_artifact`, running each module in it and storing
+    /// the resulting values in the vm. Returns the name of the module that the program itself is
+    /// stored in, so `get_global` can be used to retrieve values from it.
+    #[cfg(feature = "serialization")]
+    fn load_artifact<R>(&self, reader: R) -> Result<String>
+    where
+        R: std::io::BufRead,
+    {
+        let vm = self.thread();
+        artifact::load(&mut self.module_compiler(&vm.get_database()), vm, reader)
+    }
+
     /// Parses and typechecks `expr_str` followed by extracting metadata from the created
     /// expression
     fn extract_metadata(
@@ -571,7 +649,7 @@ pub trait ThreadExt {
         let vm = self.thread();
         {
             let mut db = vm.get_database_mut();
-            db.add_module(module_name.clone(), input.into());
+            db.add_module(module_name.clone(), input);
         }
         let db = vm.get_database();
         Box::new(future::result(db.global(module_name).map(|_| ())))
@@ -818,6 +896,10 @@ in ()
 #[derive(Default)]
 pub struct VmBuilder {
     import_paths: Option<Vec<PathBuf>>,
+    sandbox: Option<SandboxPolicy>,
+    #[cfg(feature = "serialization")]
+    module_cache: Option<PathBuf>,
+    gc_mode: GcMode,
 }
 
 impl VmBuilder {
@@ -830,9 +912,29 @@ impl VmBuilder {
         import_paths set_import_paths: Option<Vec<PathBuf>>
     }
 
+    option! {
+        /// Restricts which modules the virtual machine can import (default: None)
+        sandbox set_sandbox: Option<SandboxPolicy>
+    }
+
+    #[cfg(feature = "serialization")]
+    option! {
+        /// Stores compiled modules in, and loads them from, the directory (default: None)
+        module_cache set_module_cache: Option<PathBuf>
+    }
+
+    option! {
+        /// Selects between stop-the-world and incremental garbage collection, where incremental
+        /// collection bounds the pauses of each thread (default: GcMode::StopTheWorld)
+        gc_mode set_gc_mode: GcMode
+    }
+
     pub fn build(self) -> RootedThread {
-        let vm =
-            RootedThread::with_global_state(crate::vm::vm::GlobalVmStateBuilder::new().build());
+        let vm = RootedThread::with_global_state(
+            crate::vm::vm::GlobalVmStateBuilder::new()
+                .gc_mode(self.gc_mode)
+                .build(),
+        );
 
         {
             let macros = vm.get_macros();
@@ -846,6 +948,13 @@ impl VmBuilder {
                 if let Ok(gluon_path) = env::var("GLUON_PATH") {
                     import.add_path(gluon_path);
                 }
+
+                #[cfg(feature = "serialization")]
+                {
+                    if let Some(module_cache) = self.module_cache {
+                        import.set_module_cache(Some(import::ModuleCache::new(module_cache)));
+                    }
+                }
                 macros.insert(String::from("import"), import);
             }
 
@@ -864,6 +973,15 @@ impl VmBuilder {
         )
         .unwrap_or_else(|err| panic!("{}", err));
 
+        // Applied after the modules above are loaded as every program depends on them
+        if let Some(sandbox) = self.sandbox {
+            vm.get_macros()
+                .get("import")
+                .and_then(|import| import.downcast_arc::<Import<DefaultImporter>>().ok())
+                .expect("Import macro")
+                .set_sandbox_policy(sandbox);
+        }
+
         add_extern_module(&vm, "std.byte.prim", crate::vm::primitives::load_byte);
         add_extern_module(&vm, "std.int.prim", crate::vm::primitives::load_int);
         add_extern_module(&vm, "std.float.prim", crate::vm::primitives::load_float);
@@ -872,14 +990,18 @@ impl VmBuilder {
         add_extern_module(&vm, "std.path.prim", crate::vm::primitives::load_path);
         add_extern_module(&vm, "std.char.prim", crate::vm::primitives::load_char);
         add_extern_module(&vm, "std.array.prim", crate::vm::primitives::load_array);
+        add_extern_module(&vm, "std.hash.prim", crate::vm::primitives::load_hash);
 
         add_extern_module(&vm, "std.lazy.prim", crate::vm::lazy::load);
         add_extern_module(&vm, "std.reference.prim", crate::vm::reference::load);
+        add_extern_module(&vm, "std.weak.prim", crate::vm::weak::load);
 
         add_extern_module(&vm, "std.channel.prim", crate::vm::channel::load_channel);
         add_extern_module(&vm, "std.thread.prim", crate::vm::channel::load_thread);
+        add_extern_module(&vm, "std.scheduler.prim", crate::vm::scheduler::load);
         add_extern_module(&vm, "std.debug.prim", crate::vm::debug::load);
         add_extern_module(&vm, "std.io.prim", crate::std_lib::io::load);
+        add_extern_module(&vm, "std.bytes.prim", crate::std_lib::bytes::load);
         add_extern_module(&vm, "std.process.prim", crate::std_lib::process::load);
         add_extern_module(&vm, "std.env.prim", crate::std_lib::env::load);
 
@@ -895,6 +1017,18 @@ impl VmBuilder {
             args(&vm, "std.json.prim", crate::vm::api::json::load)
         );
 
+        add_extern_module_if!(
+            #[cfg(feature = "bigint")],
+            available_if = "gluon is compiled with the 'bigint' feature",
+            args(&vm, "std.bigint.prim", crate::std_lib::bigint::load)
+        );
+
+        add_extern_module_if!(
+            #[cfg(feature = "decimal")],
+            available_if = "gluon is compiled with the 'decimal' feature",
+            args(&vm, "std.decimal.prim", crate::vm::api::decimal::load)
+        );
+
         add_extern_module_if!(
             #[cfg(feature = "regex")],
             available_if = "gluon is compiled with the 'regex' feature",
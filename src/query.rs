@@ -11,7 +11,7 @@ use {
 
 use {
     base::{
-        ast::{self, Expr, SpannedExpr, TypedIdent},
+        ast::{self, walk_expr, Expr, SpannedExpr, TypedIdent, Visitor},
         fnv::FnvMap,
         kind::{ArcKind, KindEnv},
         metadata::{Metadata, MetadataEnv},
@@ -77,6 +77,9 @@ pub type DatabaseGlobal = vm::vm::Global<RootedValue<RootedThread>>;
 pub(crate) struct State {
     pub(crate) code_map: codespan::CodeMap,
     pub(crate) inline_modules: FnvMap<String, String>,
+    /// The text of the modules which were read through the importer instead of from
+    /// `inline_modules`
+    pub(crate) imported_modules: FnvMap<String, Arc<Cow<'static, str>>>,
     pub(crate) index_map: FnvMap<String, BytePos>,
 }
 
@@ -160,11 +163,15 @@ impl crate::query::CompilationBase for CompilerDatabase {
         let state = self.state.clone();
         let mut state = state.lock().unwrap();
         state.add_filemap(&module, &contents[..]);
-        if state
-            .inline_modules
-            .insert(module.clone(), contents.into())
-            .is_some()
-        {
+        // The module may already have been read from disk (or inserted with different contents)
+        // so any change to the text must invalidate the previous value. Invalidating waits for
+        // every snapshot of the database to be dropped so it is only done if the text changed.
+        let imported = state.imported_modules.remove(&module);
+        let changed = match state.inline_modules.insert(module.clone(), contents.into()) {
+            Some(previous) => previous != contents,
+            None => imported.is_some_and(|previous| previous[..] != *contents),
+        };
+        if changed {
             self.query_mut(ModuleTextQuery).invalidate(&module);
         }
     }
@@ -212,6 +219,24 @@ impl CompilerDatabase {
         self.state().update_filemap(file, source)
     }
 
+    /// Removes a module added through `add_module` so that it is read through the importer again
+    /// the next time it is compiled.
+    pub fn remove_module(&mut self, module: &str) {
+        let removed = {
+            let mut state = self.state();
+            let removed = state.inline_modules.remove(module).is_some();
+            if removed {
+                // The text on disk gets a new file map once it is read
+                state.index_map.remove(module);
+            }
+            removed
+        };
+        if removed {
+            self.query_mut(ModuleTextQuery)
+                .invalidate(&module.to_string());
+        }
+    }
+
     pub fn get_filemap(&self, file: &str) -> Option<Arc<codespan::FileMap>> {
         self.state().get_filemap(file)
     }
@@ -322,17 +347,24 @@ fn module_text(db: &impl Compilation, module: String) -> StdResult<Arc<Cow<'stat
     db.salsa_runtime()
         .report_synthetic_read(salsa::Durability::LOW);
 
-    let contents = if let Some(contents) = db.compiler().state().inline_modules.get(&module) {
-        Arc::new(contents.to_string().into()) // FIXME Avoid copying
+    // The lock on the state must be released before the importer is called
+    let inline_contents = db.compiler().state().inline_modules.get(&module).cloned();
+    let contents = if let Some(contents) = inline_contents {
+        Arc::new(contents.into())
     } else {
         let mut filename = module.replace(".", "/");
         filename.push_str(".glu");
 
-        Arc::new(
+        let contents = Arc::new(
             crate::get_import(db.thread())
                 .get_module_source(db.compiler_settings().use_standard_lib, &module, &filename)
                 .map_err(macros::Error::new)?,
-        )
+        );
+        db.compiler()
+            .state()
+            .imported_modules
+            .insert(module, contents.clone());
+        contents
     };
 
     Ok(contents)
@@ -400,7 +432,21 @@ fn compiled_module(
     db: &impl Compilation,
     module: String,
 ) -> StdResult<OpaqueValue<RootedThread, GcPtr<ClosureData>>, Error> {
-    let core_expr = db.core_expr(module.clone())?;
+    let compiled_module = compile_module(db, &module)?;
+    let thread = db.thread();
+    let closure = thread
+        .global_env()
+        .new_global_thunk(thread, compiled_module)?;
+
+    Ok(closure)
+}
+
+/// Compiles `module` to bytecode
+pub(crate) fn compile_module(
+    db: &impl Compilation,
+    module: &str,
+) -> StdResult<vm::compiler::CompiledModule, Error> {
+    let core_expr = db.core_expr(module.into())?;
     let settings = db.compiler_settings();
 
     let thread = db.thread();
@@ -409,10 +455,10 @@ fn compiled_module(
     let mut compiler = thread.module_compiler(db.compiler());
 
     let source = compiler
-        .get_filemap(&module)
+        .get_filemap(module)
         .expect("Filemap does not exist");
 
-    let name = Name::new(&module);
+    let name = Name::new(module);
     let symbols = SymbolModule::new(
         String::from(AsRef::<str>::as_ref(name.module())),
         &mut compiler.symbols,
@@ -423,18 +469,40 @@ fn compiled_module(
         thread.global_env(),
         symbols,
         &source,
-        module.clone(),
+        module.into(),
         settings.emit_debug_info,
     );
+    compiler.set_wrapping_int_arithmetic(settings.wrapping_int_arithmetic);
 
     let mut compiled_module = compiler.compile_expr(core_expr.value.expr())?;
     let module_id = Symbol::from(format!("@{}", name));
-    compiled_module.function.id = module_id.clone();
-    let closure = thread
-        .global_env()
-        .new_global_thunk(&thread, compiled_module)?;
+    compiled_module.function.id = module_id;
 
-    Ok(closure)
+    Ok(compiled_module)
+}
+
+/// Returns the names of the modules imported by `expr`
+pub(crate) fn module_imports(expr: &SpannedExpr<Symbol>) -> Vec<String> {
+    struct Imports(Vec<String>);
+
+    impl<'a> Visitor<'a> for Imports {
+        type Ident = Symbol;
+
+        fn visit_expr(&mut self, e: &'a SpannedExpr<Self::Ident>) {
+            if let Expr::Ident(ref id) = e.value {
+                if id.name.is_global() {
+                    self.0.push(id.name.definition_name().to_string());
+                }
+            }
+            walk_expr(self, e);
+        }
+    }
+
+    let mut visitor = Imports(Vec::new());
+    visitor.visit_expr(expr);
+    visitor.0.sort();
+    visitor.0.dedup();
+    visitor.0
 }
 
 fn import(db: &impl Compilation, modulename: String) -> StdResult<Expr<Symbol>, Error> {
@@ -577,6 +645,18 @@ impl PrimitiveEnv for CompilerDatabase {
 impl MetadataEnv for CompilerDatabase {
     fn get_metadata(&self, id: &SymbolRef) -> Option<Arc<Metadata>> {
         if id.is_global() {
+            // Extern modules and modules loaded from the module cache are stored in the globals
+            // of the thread instead of in the database
+            let metadata = self
+                .thread()
+                .global_env()
+                .get_globals()
+                .globals
+                .get(id.definition_name())
+                .map(|global| global.metadata.clone());
+            if metadata.is_some() {
+                return metadata;
+            }
             self.typechecked_module(id.definition_name().into(), None)
                 .ok()
                 .map(|v| v.metadata.clone())
@@ -589,6 +669,14 @@ impl MetadataEnv for CompilerDatabase {
 impl OptimizeEnv for CompilerDatabase {
     fn find_expr(&self, id: &Symbol) -> Option<interpreter::Global<CoreExpr>> {
         if id.is_global() {
+            // Modules in the globals of the thread do not have an expression in the database
+            if self
+                .thread()
+                .global_env()
+                .global_exists(id.definition_name())
+            {
+                return None;
+            }
             self.core_expr(id.definition_name().into()).ok()
         } else {
             None
@@ -695,22 +783,25 @@ impl CompilerDatabase {
         // Test: "x.y"
         // Test: "x"
         // Test: -> Error
-        let globals = self.thread().global_env().get_globals();
         let global = loop {
             if module.as_str() == "" {
                 return None;
             }
-            if let Some(g) = globals
+            // The lock on the globals must be released before `global` is queried as that may
+            // import the module
+            let global = self
+                .thread()
+                .global_env()
+                .get_globals()
                 .globals
-                .get(name)
+                .get(module.as_str())
                 .map(|global| DatabaseGlobal {
                     id: global.id.clone(),
                     typ: global.typ.clone(),
                     metadata: global.metadata.clone(),
                     value: self.thread().root_value(global.value.get_variants()),
-                })
-                .or_else(|| self.global(module.as_str().into()).ok())
-            {
+                });
+            if let Some(g) = global.or_else(|| self.global(module.as_str().into()).ok()) {
                 break g;
             }
             module = module.module();
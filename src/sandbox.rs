@@ -0,0 +1,175 @@
+//! Policies which restrict what gluon code is able to access.
+//!
+//! A `SandboxPolicy` is installed with `VmBuilder::sandbox` (or `Import::set_sandbox_policy`) and
+//! decides which modules `import!` is able to load. Modules are matched against patterns which are
+//! either a module name (`std.list`) or a module name followed by `.*` (`std.http.*`) which matches
+//! every module below it. A module is available if it matches an allowed pattern (or no patterns
+//! are allowed) and does not match a denied pattern.
+//!
+//! Source modules and extern modules (the modules implemented in Rust, such as `std.fs.prim`) are
+//! restricted separately. The extern modules needed by the prelude (`std.prim` and `std.io.prim`)
+//! can not be denied, though if `std.io.prim` is denied it only exposes the `IO` type and the
+//! functions which do not perform any IO.
+
+/// Decides which modules gluon code may import and whether it may compile and run code at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    imports: Filter,
+    extern_modules: Filter,
+    eval: bool,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> SandboxPolicy {
+        SandboxPolicy {
+            imports: Filter::default(),
+            extern_modules: Filter::default(),
+            eval: true,
+        }
+    }
+}
+
+impl SandboxPolicy {
+    /// Returns a policy which does not restrict anything
+    pub fn new() -> SandboxPolicy {
+        SandboxPolicy::default()
+    }
+
+    /// Returns a policy which allows the standard library except for the modules that access the
+    /// file system, processes, environment variables, the network or the `IO` functions of
+    /// `std.io`. Compiling code at runtime is denied as well.
+    ///
+    /// Modules outside of the standard library can be allowed with `allow_import`.
+    pub fn restricted() -> SandboxPolicy {
+        SandboxPolicy::new()
+            .allow_import("std.*")
+            .deny_import("std.io.*")
+            .deny_import("std.fs.*")
+            .deny_import("std.process.*")
+            .deny_import("std.env.*")
+            .deny_import("std.http.*")
+            .deny_import("std.effect.io.*")
+            .allow_extern_module("std.*")
+            .deny_extern_module("std.io.prim")
+            .deny_extern_module("std.fs.prim")
+            .deny_extern_module("std.process.prim")
+            .deny_extern_module("std.env.prim")
+            .deny_extern_module("std.http.*")
+            .eval(false)
+    }
+
+    /// Allows source modules matching `pattern` to be imported. Once a pattern is allowed only
+    /// modules matching an allowed pattern can be imported.
+    pub fn allow_import(mut self, pattern: &str) -> SandboxPolicy {
+        self.imports.allow(pattern);
+        self
+    }
+
+    /// Denies source modules matching `pattern` from being imported
+    pub fn deny_import(mut self, pattern: &str) -> SandboxPolicy {
+        self.imports.deny(pattern);
+        self
+    }
+
+    /// Allows extern modules matching `pattern` to be imported. Once a pattern is allowed only
+    /// extern modules matching an allowed pattern can be imported.
+    pub fn allow_extern_module(mut self, pattern: &str) -> SandboxPolicy {
+        self.extern_modules.allow(pattern);
+        self
+    }
+
+    /// Denies extern modules matching `pattern` from being imported
+    pub fn deny_extern_module(mut self, pattern: &str) -> SandboxPolicy {
+        self.extern_modules.deny(pattern);
+        self
+    }
+
+    /// Sets whether `std.io.run_expr` and `std.io.load_script` may be used (default: true)
+    pub fn eval(mut self, eval: bool) -> SandboxPolicy {
+        self.eval = eval;
+        self
+    }
+
+    /// Returns `true` if the source module `module` may be imported
+    pub fn allows_import(&self, module: &str) -> bool {
+        self.imports.allows(module)
+    }
+
+    /// Returns `true` if the extern module `module` may be imported
+    pub fn allows_extern_module(&self, module: &str) -> bool {
+        self.extern_modules.allows(module)
+    }
+
+    /// Returns `true` if code may be compiled and run at runtime
+    pub fn allows_eval(&self) -> bool {
+        self.eval
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Filter {
+    // `None` allows every module
+    allowed: Option<Vec<String>>,
+    denied: Vec<String>,
+}
+
+impl Filter {
+    fn allow(&mut self, pattern: &str) {
+        self.allowed
+            .get_or_insert_with(Vec::new)
+            .push(pattern.to_string());
+    }
+
+    fn deny(&mut self, pattern: &str) {
+        self.denied.push(pattern.to_string());
+    }
+
+    fn allows(&self, module: &str) -> bool {
+        let allowed = self
+            .allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|pattern| matches(pattern, module)));
+        allowed && !self.denied.iter().any(|pattern| matches(pattern, module))
+    }
+}
+
+fn matches(pattern: &str, module: &str) -> bool {
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => {
+            module == prefix
+                || (module.starts_with(prefix) && module[prefix.len()..].starts_with('.'))
+        }
+        None => pattern == module,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        assert!(matches("std.list", "std.list"));
+        assert!(!matches("std.list", "std.list.prim"));
+        assert!(matches("std.http.*", "std.http"));
+        assert!(matches("std.http.*", "std.http.types"));
+        assert!(!matches("std.http.*", "std.httpx"));
+    }
+
+    #[test]
+    fn restricted() {
+        let policy = SandboxPolicy::restricted();
+        assert!(policy.allows_import("std.list"));
+        assert!(!policy.allows_import("std.io"));
+        assert!(!policy.allows_import("std.io.read"));
+        assert!(!policy.allows_import("my_module"));
+        assert!(policy.allows_extern_module("std.int.prim"));
+        assert!(!policy.allows_extern_module("std.fs.prim"));
+        assert!(!policy.allows_extern_module("std.http.prim_types"));
+        assert!(!policy.allows_eval());
+
+        let policy = policy.allow_import("my_module");
+        assert!(policy.allows_import("my_module"));
+        assert!(policy.allows_import("std.list"));
+    }
+}
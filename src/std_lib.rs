@@ -1,3 +1,6 @@
+#[cfg(feature = "bigint")]
+pub mod bigint;
+pub mod bytes;
 pub mod env;
 #[cfg(feature = "http")]
 pub mod http;
@@ -0,0 +1,118 @@
+//! Module containing bindings to the `num-bigint` library.
+
+extern crate num_bigint;
+extern crate num_traits;
+
+use crate::real_std::cmp::Ordering;
+
+use self::num_bigint::BigInt as Inner;
+use self::num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::vm::{self, api::RuntimeResult, thread::Thread, types::VmInt, ExternModule};
+
+#[derive(Clone, Debug, Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.bigint.BigInt")]
+#[gluon(crate_name = "vm")]
+#[gluon_userdata(clone)]
+#[gluon_trace(skip)]
+struct BigInt(Inner);
+
+fn from_int(i: VmInt) -> BigInt {
+    BigInt(Inner::from(i))
+}
+
+fn to_int(i: &BigInt) -> Option<VmInt> {
+    i.0.to_i64()
+}
+
+fn parse(s: &str) -> Result<BigInt, ()> {
+    s.parse().map(BigInt).map_err(|_| ())
+}
+
+fn show(i: &BigInt) -> String {
+    i.0.to_string()
+}
+
+fn add(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 + &r.0)
+}
+
+fn sub(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 - &r.0)
+}
+
+fn mul(l: &BigInt, r: &BigInt) -> BigInt {
+    BigInt(&l.0 * &r.0)
+}
+
+fn div(l: &BigInt, r: &BigInt) -> RuntimeResult<BigInt, &'static str> {
+    if r.0.is_zero() {
+        RuntimeResult::Panic("Division by zero")
+    } else {
+        RuntimeResult::Return(BigInt(&l.0 / &r.0))
+    }
+}
+
+fn rem(l: &BigInt, r: &BigInt) -> RuntimeResult<BigInt, &'static str> {
+    if r.0.is_zero() {
+        RuntimeResult::Panic("Division by zero")
+    } else {
+        RuntimeResult::Return(BigInt(&l.0 % &r.0))
+    }
+}
+
+fn negate(i: &BigInt) -> BigInt {
+    BigInt(-&i.0)
+}
+
+fn abs(i: &BigInt) -> BigInt {
+    BigInt(i.0.abs())
+}
+
+fn pow(i: &BigInt, exponent: VmInt) -> RuntimeResult<BigInt, &'static str> {
+    if exponent < 0 {
+        RuntimeResult::Panic("Negative exponent")
+    } else {
+        RuntimeResult::Return(BigInt(num_traits::pow(i.0.clone(), exponent as usize)))
+    }
+}
+
+fn eq(l: &BigInt, r: &BigInt) -> bool {
+    l.0 == r.0
+}
+
+fn compare(l: &BigInt, r: &BigInt) -> Ordering {
+    l.0.cmp(&r.0)
+}
+
+mod std {
+    pub mod bigint {
+        pub use crate::std_lib::bigint as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<BigInt>("std.bigint.BigInt", &[])?;
+
+    ExternModule::new(
+        vm,
+        record! {
+            type BigInt => BigInt,
+
+            from_int => primitive!(1, std::bigint::prim::from_int),
+            to_int => primitive!(1, std::bigint::prim::to_int),
+            parse => primitive!(1, std::bigint::prim::parse),
+            show => primitive!(1, std::bigint::prim::show),
+            add => primitive!(2, std::bigint::prim::add),
+            sub => primitive!(2, std::bigint::prim::sub),
+            mul => primitive!(2, std::bigint::prim::mul),
+            div => primitive!(2, std::bigint::prim::div),
+            rem => primitive!(2, std::bigint::prim::rem),
+            negate => primitive!(1, std::bigint::prim::negate),
+            abs => primitive!(1, std::bigint::prim::abs),
+            pow => primitive!(2, std::bigint::prim::pow),
+            eq => primitive!(2, std::bigint::prim::eq),
+            compare => primitive!(2, std::bigint::prim::compare)
+        },
+    )
+}
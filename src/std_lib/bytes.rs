@@ -0,0 +1,384 @@
+//! Module containing a mutable, growable byte buffer and functions for encoding and decoding
+//! binary data.
+
+use crate::real_std::{
+    fmt, mem, str,
+    sync::{Arc, Mutex},
+};
+
+use crate::vm::{
+    self,
+    api::{RuntimeResult, IO},
+    thread::Thread,
+    types::VmInt,
+    ExternModule,
+};
+
+/// A byte buffer which can be modified in place. Slices of a buffer share its storage, so writes
+/// through a slice are visible in the buffer and vice versa.
+#[derive(Clone, Userdata, Trace, VmType)]
+#[gluon(vm_type = "std.bytes.Bytes")]
+#[gluon(crate_name = "vm")]
+#[gluon_userdata(clone)]
+#[gluon_trace(skip)]
+struct Bytes {
+    data: Arc<Mutex<Vec<u8>>>,
+    start: usize,
+    /// Where the slice ends, or `None` if this is the whole buffer (which may grow)
+    end: Option<usize>,
+}
+
+impl fmt::Debug for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.with(|data| write!(f, "Bytes({:?})", data))
+    }
+}
+
+impl Bytes {
+    fn new(data: Vec<u8>) -> Bytes {
+        Bytes {
+            data: Arc::new(Mutex::new(data)),
+            start: 0,
+            end: None,
+        }
+    }
+
+    /// Returns the range of the underlying buffer that this value refers to. The buffer may have
+    /// been truncated after a slice was created so the range is clamped to its current length.
+    fn bounds(&self, len: usize) -> (usize, usize) {
+        let end = self.end.map_or(len, |end| end.min(len));
+        (self.start.min(end), end)
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        let data = self.data.lock().unwrap();
+        let (start, end) = self.bounds(data.len());
+        f(&data[start..end])
+    }
+
+    fn grow(&self, f: impl FnOnce(&mut Vec<u8>)) -> IO<()> {
+        if self.end.is_some() {
+            return IO::Exception("A slice of a byte buffer can not grow".to_string());
+        }
+        f(&mut self.data.lock().unwrap());
+        IO::Value(())
+    }
+
+    /// Writes `buf` at `offset`, growing the buffer if `buf` does not fit.
+    fn write_at(&self, offset: usize, buf: &[u8]) -> IO<()> {
+        let mut data = self.data.lock().unwrap();
+        let (start, end) = self.bounds(data.len());
+        if offset > end - start {
+            return IO::Exception(format!(
+                "Offset {} is out of range for a byte buffer of length {}",
+                offset,
+                end - start
+            ));
+        }
+
+        let write_end = start + offset + buf.len();
+        if write_end > end {
+            if self.end.is_some() {
+                return IO::Exception("A slice of a byte buffer can not grow".to_string());
+            }
+            data.resize(write_end, 0);
+        }
+        data[start + offset..write_end].copy_from_slice(buf);
+        IO::Value(())
+    }
+}
+
+#[derive(Getable, VmType, Clone, Copy)]
+#[gluon(crate_name = "vm")]
+enum Endian {
+    Big,
+    Little,
+}
+
+#[derive(Pushable, VmType)]
+#[gluon(crate_name = "vm")]
+struct Utf8Error {
+    valid_up_to: usize,
+    error_len: Option<usize>,
+}
+
+fn with_capacity(capacity: usize) -> Bytes {
+    Bytes::new(Vec::with_capacity(capacity))
+}
+
+fn from_array(array: &[u8]) -> Bytes {
+    Bytes::new(array.to_owned())
+}
+
+fn to_array(bytes: &Bytes) -> Vec<u8> {
+    bytes.with(|data| data.to_owned())
+}
+
+fn copy(bytes: &Bytes) -> Bytes {
+    Bytes::new(to_array(bytes))
+}
+
+fn len(bytes: &Bytes) -> usize {
+    bytes.with(|data| data.len())
+}
+
+fn get(bytes: &Bytes, index: usize) -> Option<u8> {
+    bytes.with(|data| data.get(index).cloned())
+}
+
+fn set(bytes: &Bytes, index: usize, value: u8) -> IO<()> {
+    let mut data = bytes.data.lock().unwrap();
+    let (start, end) = bytes.bounds(data.len());
+    if index < end - start {
+        data[start + index] = value;
+        IO::Value(())
+    } else {
+        IO::Exception(format!(
+            "Index {} is out of range for a byte buffer of length {}",
+            index,
+            end - start
+        ))
+    }
+}
+
+fn push(bytes: &Bytes, value: u8) -> IO<()> {
+    bytes.grow(|data| data.push(value))
+}
+
+fn extend(bytes: &Bytes, array: &[u8]) -> IO<()> {
+    bytes.grow(|data| data.extend_from_slice(array))
+}
+
+fn append(bytes: &Bytes, other: &Bytes) -> IO<()> {
+    // Copy first as `other` may share the storage of `bytes`
+    let other = to_array(other);
+    extend(bytes, &other)
+}
+
+fn truncate(bytes: &Bytes, len: usize) -> IO<()> {
+    bytes.grow(|data| data.truncate(len))
+}
+
+fn slice(bytes: &Bytes, start: usize, end: usize) -> RuntimeResult<Bytes, String> {
+    let len = len(bytes);
+    if start > end || end > len {
+        return RuntimeResult::Panic(format!(
+            "Slice {}..{} is out of range for a byte buffer of length {}",
+            start, end, len
+        ));
+    }
+    RuntimeResult::Return(Bytes {
+        data: bytes.data.clone(),
+        start: bytes.start + start,
+        end: Some(bytes.start + end),
+    })
+}
+
+fn eq(l: &Bytes, r: &Bytes) -> bool {
+    to_array(l) == to_array(r)
+}
+
+fn read_u8(bytes: &Bytes, offset: usize) -> Option<VmInt> {
+    get(bytes, offset).map(VmInt::from)
+}
+
+fn read_i8(bytes: &Bytes, offset: usize) -> Option<VmInt> {
+    get(bytes, offset).map(|b| VmInt::from(b as i8))
+}
+
+fn write_u8(bytes: &Bytes, offset: usize, value: VmInt) -> IO<()> {
+    bytes.write_at(offset, &[value as u8])
+}
+
+macro_rules! define_number_functions {
+    ($($read: ident, $write: ident, $ty: ty, $gluon_ty: ty;)*) => {$(
+        fn $read(endian: Endian, bytes: &Bytes, offset: usize) -> Option<$gluon_ty> {
+            bytes.with(|data| {
+                let mut buf = [0; mem::size_of::<$ty>()];
+                let end = offset.checked_add(buf.len())?;
+                buf.copy_from_slice(data.get(offset..end)?);
+                let value = match endian {
+                    Endian::Big => <$ty>::from_be_bytes(buf),
+                    Endian::Little => <$ty>::from_le_bytes(buf),
+                };
+                Some(value as $gluon_ty)
+            })
+        }
+
+        fn $write(
+            endian: Endian,
+            bytes: &Bytes,
+            offset: usize,
+            value: $gluon_ty,
+        ) -> IO<()> {
+            let value = value as $ty;
+            let buf = match endian {
+                Endian::Big => value.to_be_bytes(),
+                Endian::Little => value.to_le_bytes(),
+            };
+            bytes.write_at(offset, &buf)
+        }
+    )*};
+}
+
+define_number_functions! {
+    read_u16, write_u16, u16, VmInt;
+    read_i16, write_i16, i16, VmInt;
+    read_u32, write_u32, u32, VmInt;
+    read_i32, write_i32, i32, VmInt;
+    read_i64, write_i64, i64, VmInt;
+    read_f32, write_f32, f32, f64;
+    read_f64, write_f64, f64, f64;
+}
+
+fn decode_utf8(bytes: &Bytes) -> Result<String, Utf8Error> {
+    bytes.with(|data| match str::from_utf8(data) {
+        Ok(s) => Ok(s.to_owned()),
+        Err(err) => Err(Utf8Error {
+            valid_up_to: err.valid_up_to(),
+            error_len: err.error_len(),
+        }),
+    })
+}
+
+fn encode_utf8(s: &str) -> Bytes {
+    Bytes::new(s.as_bytes().to_owned())
+}
+
+fn to_hex(bytes: &Bytes) -> String {
+    bytes.with(|data| data.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn from_hex(s: &str) -> Result<Bytes, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err("Hex string must have an even number of digits".to_string());
+    }
+    s.as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| format!("Invalid hex digits at {}", 2 * i))
+        })
+        .collect::<Result<_, _>>()
+        .map(Bytes::new)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_digit(c: u8) -> Option<u32> {
+    let digit = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    };
+    Some(u32::from(digit))
+}
+
+fn to_base64(bytes: &Bytes) -> String {
+    bytes.with(|data| {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+        for chunk in data.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(char::from(
+                        BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize],
+                    ));
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    })
+}
+
+fn from_base64(s: &str) -> Result<Bytes, String> {
+    let input = s.as_bytes();
+    if !input.len().is_multiple_of(4) {
+        return Err("Base64 string must have a length which is a multiple of 4".to_string());
+    }
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    for (i, chunk) in input.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && (i + 1) * 4 != input.len()) {
+            return Err(format!("Invalid base64 padding at {}", 4 * i));
+        }
+
+        let mut n = 0;
+        for (j, &c) in chunk[..4 - padding].iter().enumerate() {
+            let digit = base64_digit(c)
+                .ok_or_else(|| format!("Invalid base64 character at {}", 4 * i + j))?;
+            n |= digit << (18 - 6 * j);
+        }
+        out.extend_from_slice(&[(n >> 16) as u8, (n >> 8) as u8, n as u8][..3 - padding]);
+    }
+    Ok(Bytes::new(out))
+}
+
+mod std {
+    pub mod bytes {
+        pub use crate::std_lib::bytes as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> vm::Result<ExternModule> {
+    vm.register_type::<Bytes>("std.bytes.Bytes", &[])?;
+
+    ExternModule::new(
+        vm,
+        record! {
+            type Bytes => Bytes,
+            type Endian => Endian,
+            type Utf8Error => Utf8Error,
+
+            with_capacity => primitive!(1, std::bytes::prim::with_capacity),
+            from_array => primitive!(1, std::bytes::prim::from_array),
+            to_array => primitive!(1, std::bytes::prim::to_array),
+            copy => primitive!(1, std::bytes::prim::copy),
+            len => primitive!(1, std::bytes::prim::len),
+            get => primitive!(2, std::bytes::prim::get),
+            set => primitive!(3, std::bytes::prim::set),
+            push => primitive!(2, std::bytes::prim::push),
+            extend => primitive!(2, std::bytes::prim::extend),
+            append => primitive!(2, std::bytes::prim::append),
+            truncate => primitive!(2, std::bytes::prim::truncate),
+            slice => primitive!(3, std::bytes::prim::slice),
+            eq => primitive!(2, std::bytes::prim::eq),
+            read_u8 => primitive!(2, std::bytes::prim::read_u8),
+            read_i8 => primitive!(2, std::bytes::prim::read_i8),
+            write_u8 => primitive!(3, std::bytes::prim::write_u8),
+            read_u16 => primitive!(3, std::bytes::prim::read_u16),
+            write_u16 => primitive!(4, std::bytes::prim::write_u16),
+            read_i16 => primitive!(3, std::bytes::prim::read_i16),
+            write_i16 => primitive!(4, std::bytes::prim::write_i16),
+            read_u32 => primitive!(3, std::bytes::prim::read_u32),
+            write_u32 => primitive!(4, std::bytes::prim::write_u32),
+            read_i32 => primitive!(3, std::bytes::prim::read_i32),
+            write_i32 => primitive!(4, std::bytes::prim::write_i32),
+            read_i64 => primitive!(3, std::bytes::prim::read_i64),
+            write_i64 => primitive!(4, std::bytes::prim::write_i64),
+            read_f32 => primitive!(3, std::bytes::prim::read_f32),
+            write_f32 => primitive!(4, std::bytes::prim::write_f32),
+            read_f64 => primitive!(3, std::bytes::prim::read_f64),
+            write_f64 => primitive!(4, std::bytes::prim::write_f64),
+            decode_utf8 => primitive!(1, std::bytes::prim::decode_utf8),
+            encode_utf8 => primitive!(1, std::bytes::prim::encode_utf8),
+            to_hex => primitive!(1, std::bytes::prim::to_hex),
+            from_hex => primitive!(1, std::bytes::prim::from_hex),
+            to_base64 => primitive!(1, std::bytes::prim::to_base64),
+            from_base64 => primitive!(1, std::bytes::prim::from_base64)
+        },
+    )
+}
@@ -291,51 +291,68 @@ field_decl! { value, typ }
 #[allow(dead_code)]
 type RunExpr = record_type! { value => String, typ => String };
 
+fn eval_denied<T>(function: &str) -> IO<T> {
+    IO::Exception(format!(
+        "The sandbox policy does not allow `{}` to be used",
+        function
+    ))
+}
+
 fn run_expr(
     WithVM { vm, value: expr }: WithVM<&str>,
 ) -> impl Future<Item = IO<RunExpr>, Error = vm::Error> {
+    if !crate::get_import(vm).sandbox_policy().allows_eval() {
+        return Either::A(future::ok(eval_denied("std.io.run_expr")));
+    }
     let vm = vm.root_thread();
 
     let vm1 = vm.clone();
     let db = vm.get_database();
-    expr.run_expr(&mut vm.module_compiler(&db), vm1, "<top>", expr, None)
-        .then(move |run_result| {
-            let mut context = vm.context();
-            let stack = context.stack_frame::<stack::State>();
-            Ok(match run_result {
-                Ok(execute_value) => {
-                    let env = vm.get_env();
-                    let typ = execute_value.typ;
-                    let debug_level = vm.global_env().get_debug_level();
-                    IO::Value(record_no_decl!{
-                        value => ValuePrinter::new(&env, &typ, execute_value.value.get_variant(), &debug_level).width(80).to_string(),
-                        typ => typ.to_string()
-                    })
-                }
-                Err(err) => clear_frames(err, stack),
-            })
-        })
+    Either::B(
+        expr.run_expr(&mut vm.module_compiler(&db), vm1, "<top>", expr, None)
+            .then(move |run_result| {
+                let mut context = vm.context();
+                let stack = context.stack_frame::<stack::State>();
+                Ok(match run_result {
+                    Ok(execute_value) => {
+                        let env = vm.get_env();
+                        let typ = execute_value.typ;
+                        let debug_level = vm.global_env().get_debug_level();
+                        IO::Value(record_no_decl!{
+                            value => ValuePrinter::new(&env, &typ, execute_value.value.get_variant(), &debug_level).width(80).to_string(),
+                            typ => typ.to_string()
+                        })
+                    }
+                    Err(err) => clear_frames(err, stack),
+                })
+            }),
+    )
 }
 
 fn load_script(
     WithVM { vm, value: name }: WithVM<&str>,
     expr: &str,
 ) -> impl Future<Item = IO<String>, Error = vm::Error> {
+    if !crate::get_import(vm).sandbox_policy().allows_eval() {
+        return Either::A(future::ok(eval_denied("std.io.load_script")));
+    }
     let vm1 = vm.root_thread();
     let vm = vm.root_thread();
     let name = name.to_string();
 
     let db = vm.get_database();
-    expr.load_script(&mut vm.module_compiler(&db), vm1, &name, expr, None)
-        .then(move |run_result| {
-            let mut context = vm.context();
-            let stack = context.stack_frame::<stack::State>();
-            let io = match run_result {
-                Ok(()) => IO::Value(format!("Loaded {}", name)),
-                Err(err) => clear_frames(err, stack),
-            };
-            Ok(io)
-        })
+    Either::B(
+        expr.load_script(&mut vm.module_compiler(&db), vm1, &name, expr, None)
+            .then(move |run_result| {
+                let mut context = vm.context();
+                let stack = context.stack_frame::<stack::State>();
+                let io = match run_result {
+                    Ok(()) => IO::Value(format!("Loaded {}", name)),
+                    Err(err) => clear_frames(err, stack),
+                };
+                Ok(io)
+            }),
+    )
 }
 
 mod std {
@@ -362,6 +379,27 @@ pub fn load(vm: &Thread) -> Result<ExternModule> {
 
     let wrap = vec![Pop(1), Return];
 
+    // Without access to `std.io.prim` only the functions which do not perform any IO are
+    // available, the prelude still needs the `IO` type
+    if !crate::get_import(vm)
+        .sandbox_policy()
+        .allows_extern_module("std.io.prim")
+    {
+        return ExternModule::new(
+            vm,
+            record! {
+                type std::io::File => GluonFile,
+                type OpenOptions => OpenOptions,
+                type std::io::IO a => IO<A>,
+                flat_map => TypedBytecode::<FlatMap>::new("std.io.prim.flat_map", 3, flat_map),
+                wrap => TypedBytecode::<Wrap>::new("std.io.prim.wrap", 2, wrap),
+                catch => primitive!(2, async fn std::io::prim::catch),
+                throw => primitive!(1, std::io::prim::throw),
+                default_buf_len => 8192,
+            },
+        );
+    }
+
     // IO functions
     ExternModule::new(
         vm,
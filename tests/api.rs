@@ -149,6 +149,117 @@ fn array() {
     assert_eq!(result, expected);
 }
 
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal() {
+    use gluon::vm::api::decimal::Decimal;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let { unwrap_ok } = import! std.result
+        let decimal @ { ? } = import! std.decimal
+        let add_vat = import! add_vat
+        add_vat (unwrap_ok (decimal.parse "19.99"))
+    "#;
+    fn add_vat(price: Decimal) -> Decimal {
+        (price * Decimal::new(125, 2)).round_dp(2)
+    }
+
+    let vm = make_vm();
+    add_extern_module(&vm, "add_vat", |thread| {
+        ExternModule::new(thread, primitive!(1, add_vat))
+    });
+
+    let (result, _) = vm
+        .run_expr::<Decimal>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, Decimal::new(2499, 2));
+
+    let mut show: FunctionRef<fn(Decimal) -> String> = vm
+        .get_global("std.decimal.show.show")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(show.call(Decimal::new(-1050, 3)).unwrap(), "-1.050");
+}
+
+#[cfg(feature = "decimal")]
+#[test]
+fn decimal_round_to_negative_places_is_an_error() {
+    use gluon::vm::api::decimal::Decimal;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let decimal = import! std.decimal
+        decimal.round_dp (-1) (decimal.from_int 123)
+    "#;
+
+    let vm = make_vm();
+    let err = vm.run_expr::<Decimal>("<top>", expr).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("the number of places must not be negative"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn marshal_map() {
+    use std::collections::BTreeMap;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let map = import! std.map
+        let { foldl } = import! std.foldable
+        let m = foldl (\m i -> map.insert i (i * 2) m) map.empty [1, 2, 3, 4, 5]
+        map.delete 3 m
+    "#;
+
+    let vm = make_vm();
+    vm.load_file("std/map.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (map, _) = vm
+        .run_expr::<BTreeMap<VmInt, VmInt>>("<top>", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let expected: BTreeMap<VmInt, VmInt> =
+        vec![(1, 2), (2, 4), (4, 8), (5, 10)].into_iter().collect();
+    assert_eq!(map, expected);
+}
+
+#[test]
+fn marshal_hashmap() {
+    use std::collections::HashMap;
+
+    let _ = ::env_logger::try_init();
+
+    let expr = r#"
+        let hashmap = import! std.hashmap
+        \m -> hashmap.insert "new" 0 (hashmap.remove "1" m)
+    "#;
+
+    let vm = make_vm();
+    vm.load_file("std/hashmap.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let (mut update, _) = vm
+        .run_expr::<OwnedFunction<fn(HashMap<String, VmInt>) -> HashMap<String, VmInt>>>(
+            "<top>", expr,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    // Enough entries to get branches several levels deep
+    let map: HashMap<String, VmInt> = (0..1000).map(|i| (i.to_string(), i)).collect();
+    let mut expected = map.clone();
+    expected.remove("1");
+    expected.insert("new".to_string(), 0);
+
+    assert_eq!(update.call(map).unwrap(), expected);
+}
+
 #[test]
 fn return_finished_future() {
     let _ = ::env_logger::try_init();
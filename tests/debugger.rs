@@ -0,0 +1,250 @@
+extern crate env_logger;
+extern crate futures;
+extern crate gluon;
+
+use std::sync::{Arc, Mutex};
+
+use futures::{Async, Future};
+
+use gluon::{
+    base::pos::Line,
+    vm::{
+        debugger::{self, Breakpoint, Debugger, Step, Stop, StopReason},
+        thread::ThreadInternal,
+    },
+    RootedThread, ThreadExt,
+};
+
+fn new_vm() -> RootedThread {
+    let thread = gluon::new_vm();
+    thread.get_database_mut().set_optimize(false);
+    thread.get_database_mut().implicit_prelude(false);
+    thread
+}
+
+const SIMPLE_EXPR: &'static str = r#"
+    let f x = x
+
+    let g x = f x
+    g 1
+    "#;
+
+/// Runs `source` without a stop handler, resuming with the next step in `steps` after each stop
+fn run_steps(debugger: &Debugger, source: &str, steps: &[Step]) -> Vec<Stop> {
+    let thread = new_vm();
+    debugger.attach(&thread);
+
+    // Execution starts directly so it may already be stopped when the future is returned
+    let mut execute = thread.run_expr_async::<i32>("test", source).map(|_| ());
+    let mut steps = steps.iter();
+    let mut stops = Vec::new();
+    loop {
+        if let Some(stop) = debugger.stopped() {
+            stops.push(stop);
+            debugger.resume(steps.next().cloned().unwrap_or(Step::Continue));
+        }
+        match execute.poll() {
+            Ok(Async::Ready(())) => break,
+            Ok(Async::NotReady) => assert!(debugger.stopped().is_some()),
+            Err(err) => panic!("{}", err),
+        }
+    }
+    stops
+}
+
+fn lines(stops: &[Stop]) -> Vec<(StopReason, Option<Line>)> {
+    stops.iter().map(|stop| (stop.reason, stop.line)).collect()
+}
+
+#[test]
+fn parse_breakpoint() {
+    assert_eq!(
+        "test:3".parse(),
+        Ok(Breakpoint::Line {
+            source_name: "test".to_string(),
+            line: Line::from(2),
+        })
+    );
+    assert_eq!(
+        "std.list:1".parse::<Breakpoint>().unwrap().to_string(),
+        "std.list:1"
+    );
+    assert_eq!("f".parse(), Ok(Breakpoint::Function("f".to_string())));
+    assert!("test:0".parse::<Breakpoint>().is_err());
+    assert!("".parse::<Breakpoint>().is_err());
+}
+
+#[test]
+fn line_breakpoint() {
+    let _ = env_logger::try_init();
+
+    let debugger = Debugger::new();
+    let id = debugger.add_breakpoint("test:4".parse().unwrap());
+    let stops = run_steps(&debugger, SIMPLE_EXPR, &[]);
+    assert_eq!(
+        lines(&stops),
+        vec![
+            (StopReason::Breakpoint(id), Some(Line::from(3))),
+            (StopReason::Breakpoint(id), Some(Line::from(3))),
+        ]
+    );
+    assert_eq!(
+        stops[0].function_name.as_ref().map(|s| &s[..]),
+        Some("test")
+    );
+    assert_eq!(stops[1].function_name.as_ref().map(|s| &s[..]), Some("g"));
+
+    assert!(debugger.remove_breakpoint(id));
+    assert!(!debugger.remove_breakpoint(id));
+    assert_eq!(run_steps(&debugger, SIMPLE_EXPR, &[]), vec![]);
+}
+
+#[test]
+fn function_breakpoint_step_in() {
+    let _ = env_logger::try_init();
+
+    let debugger = Debugger::new();
+    let id = debugger.add_breakpoint(Breakpoint::Function("g".to_string()));
+    let stops = run_steps(&debugger, SIMPLE_EXPR, &[Step::In, Step::In, Step::In]);
+    assert_eq!(
+        lines(&stops),
+        vec![
+            (StopReason::Breakpoint(id), Some(Line::from(3))),
+            (StopReason::Step, Some(Line::from(3))),
+            (StopReason::Step, Some(Line::from(1))),
+        ]
+    );
+    assert_eq!(stops[2].function_name.as_ref().map(|s| &s[..]), Some("f"));
+}
+
+#[test]
+fn step_over_and_out() {
+    let _ = env_logger::try_init();
+
+    let expr = r#"
+    let f x =
+        let y = x #Int+ 1
+        y
+    let z = f 1
+    z
+    "#;
+
+    let debugger = Debugger::new();
+    let id = debugger.add_breakpoint("test:5".parse().unwrap());
+    let stops = run_steps(&debugger, expr, &[Step::Over]);
+    assert_eq!(
+        lines(&stops),
+        vec![
+            (StopReason::Breakpoint(id), Some(Line::from(4))),
+            (StopReason::Step, Some(Line::from(5))),
+        ]
+    );
+
+    debugger.remove_breakpoint(id);
+    let id = debugger.add_breakpoint("test:3".parse().unwrap());
+    let stops = run_steps(&debugger, expr, &[Step::Out]);
+    assert_eq!(
+        lines(&stops),
+        vec![
+            (StopReason::Breakpoint(id), Some(Line::from(2))),
+            (StopReason::Step, Some(Line::from(5))),
+        ]
+    );
+}
+
+#[test]
+fn stop_handler_reads_variables() {
+    let _ = env_logger::try_init();
+
+    let debugger = Debugger::new();
+    debugger.add_breakpoint("test:5".parse().unwrap());
+
+    let variables = Arc::new(Mutex::new(Vec::new()));
+    {
+        let variables = variables.clone();
+        debugger.set_stop_handler(Some(Box::new(move |_, thread, info, _| {
+            let frame = info.stack_info(0).unwrap();
+            let mut variables = variables.lock().unwrap();
+            for variable in debugger::locals(thread, &frame)
+                .into_iter()
+                .chain(debugger::upvars(thread, &frame))
+            {
+                variables.push((variable.name, variable.value));
+            }
+            Step::Continue
+        })));
+    }
+
+    let thread = new_vm();
+    debugger.attach(&thread);
+    let expr = r#"
+    let a = "abc"
+    let f x =
+        let y = x #Int+ 1
+        a
+    f 2
+    "#;
+    let (value, _) = thread
+        .run_expr::<String>("test", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, "abc");
+    assert_eq!(debugger.stopped(), None);
+
+    assert_eq!(
+        *variables.lock().unwrap(),
+        vec![
+            ("x".to_string(), "2".to_string()),
+            ("y".to_string(), "3".to_string()),
+            ("a".to_string(), "\"abc\"".to_string()),
+        ]
+    );
+
+    Debugger::detach(&thread);
+    assert!(thread.context().debug_info().stack_info(0).is_some());
+}
+
+#[test]
+fn children_of_records_variants_and_arrays() {
+    let _ = env_logger::try_init();
+
+    let debugger = Debugger::new();
+    debugger.add_breakpoint("test:5".parse().unwrap());
+
+    let children = Arc::new(Mutex::new(Vec::new()));
+    {
+        let children = children.clone();
+        debugger.set_stop_handler(Some(Box::new(move |_, thread, info, _| {
+            let frame = info.stack_info(0).unwrap();
+            let mut children = children.lock().unwrap();
+            for (local, value) in frame.local_values() {
+                for (name, typ, value) in debugger::children(thread, &local.typ, value) {
+                    let value = debugger::format_value(thread, &typ, value);
+                    children.push(format!("{}.{} : {} = {}", local.name, name, typ, value));
+                }
+            }
+            Step::Continue
+        })));
+    }
+
+    let thread = new_vm();
+    debugger.attach(&thread);
+    let expr = r#"
+    type Shape = | Circle Float | Rect Int Int
+    let r = { x = 1, shape = Rect 2 3, values = ["a", "b"] }
+    let s = Circle 1.5
+    r.x
+    "#;
+    thread
+        .run_expr::<i32>("test", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(
+        *children.lock().unwrap(),
+        vec![
+            "r.x : Int = 1",
+            "r.shape : test.Shape = Rect 2 3",
+            "r.values : Array String = [\"a\", \"b\"]",
+            "s.0 : Float = 1.5",
+        ]
+    );
+}
@@ -92,3 +92,60 @@ fn undefined_infix() {
         _ => panic!(),
     }
 }
+
+#[test]
+fn derive_default_for_variant() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+#[derive(Default)]
+type Test = | A | B
+()
+"#;
+    let error = vm.load_script("test", text).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Unable to derive Default for `Test` as it is not a record"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn derive_functor_without_type_parameters() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+#[derive(Functor)]
+type Test = { x : Int }
+()
+"#;
+    let error = vm.load_script("test", text).unwrap_err();
+    assert!(
+        error.to_string().contains(
+            "Unable to derive Functor for `Test` as it does not have any type parameters"
+        ),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn derive_functor_contravariant_parameter() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+#[derive(Functor)]
+type Test a = { f : a -> Int }
+()
+"#;
+    let error = vm.load_script("test", text).unwrap_err();
+    assert!(
+        error.to_string().contains(
+            "Unable to derive Functor for `Test` as `a` is used in a position which can't be mapped over"
+        ),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,194 @@
+#[macro_use]
+extern crate gluon_vm;
+
+use gluon::{
+    import::add_extern_module,
+    vm::{
+        api::{Hole, OpaqueValue, WithVM},
+        gc::{GcMode, GcStats},
+        ExternModule,
+    },
+    RootedThread, ThreadExt, VmBuilder,
+};
+
+const TREE: &str = r#"
+type Tree = | Leaf | Node Tree Tree
+
+rec let build depth =
+    if depth #Int== 0 then Leaf
+    else Node (build (depth #Int- 1)) (build (depth #Int- 1))
+
+rec let size tree =
+    match tree with
+    | Leaf -> 0
+    | Node l r -> 1 #Int+ size l #Int+ size r
+in
+"#;
+
+const LIVE_DEPTH: u32 = 17;
+const ITERATIONS: i32 = 20000;
+
+fn make_vm(gc_mode: GcMode) -> RootedThread {
+    let _ = ::env_logger::try_init();
+
+    let vm = VmBuilder::new().gc_mode(gc_mode).build();
+    vm.get_database_mut().set_implicit_prelude(false);
+    vm
+}
+
+// Keeps a large tree alive while allocating many small, short lived trees
+fn run_allocation_load(gc_mode: GcMode) -> GcStats {
+    let vm = make_vm(gc_mode);
+    let expr = format!(
+        r#"{}
+let live = build {}
+
+rec let churn n acc =
+    if n #Int== 0 then acc
+    else churn (n #Int- 1) (acc #Int+ size (build 4))
+
+size live #Int+ churn {} 0
+"#,
+        TREE, LIVE_DEPTH, ITERATIONS
+    );
+    let (value, _) = vm
+        .run_expr::<i32>("allocation_load", &expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, (1 << LIVE_DEPTH) - 1 + ITERATIONS * 15);
+    vm.gc_stats()
+}
+
+#[test]
+fn incremental_collection_bounds_pauses() {
+    let step_size = 100;
+    let stop_the_world = run_allocation_load(GcMode::StopTheWorld);
+    let incremental = run_allocation_load(GcMode::Incremental { step_size });
+
+    assert!(stop_the_world.collections > 0);
+    assert!(incremental.collections > 0);
+    // Compares the number of values visited in the longest pauses instead of their durations which
+    // depend on the machine running the test. A step may visit the values referenced by the values
+    // it traces and the last step of marking visits every root again, so the longest pause can do
+    // somewhat more than `step_size` work.
+    assert!(
+        incremental.max_pause_work < 10 * step_size,
+        "Incremental max pause work: {}",
+        incremental.max_pause_work
+    );
+    assert!(
+        incremental.max_pause_work * 100 < stop_the_world.max_pause_work,
+        "Incremental max pause work: {}, stop the world max pause work: {}",
+        incremental.max_pause_work,
+        stop_the_world.max_pause_work
+    );
+}
+
+#[test]
+fn incremental_collection_keeps_written_values() {
+    let vm = make_vm(GcMode::Incremental { step_size: 2 });
+    vm.run_expr::<()>("load", "let _ = import! std.reference in ()")
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    // Stores new trees into a reference and into recursive closures while the collector is
+    // marking, relying on the write barrier to keep them alive
+    let expr = format!(
+        r#"
+let {{ ref, (<-), load }} = import! std.reference
+{}
+let current = ref Leaf
+
+rec let churn n acc =
+    if n #Int== 0 then acc
+    else
+        let tree = build 3
+        current <- Node tree (build 2)
+        rec
+        let count_current x = size (load current) #Int+ x #Int+ count_tree 0
+        let count_tree x = size tree #Int+ x
+        churn (n #Int- 1) (count_current acc)
+
+churn 5000 0
+"#,
+        TREE
+    );
+    let (value, _) = vm
+        .run_expr::<i32>("written_values", &expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 5000 * (1 + 7 + 3 + 7));
+    assert!(vm.gc_stats().collections > 0);
+}
+
+fn collect(WithVM { vm, .. }: WithVM<()>) {
+    vm.collect();
+}
+
+#[test]
+fn weak_references_are_cleared_when_collected() {
+    for &gc_mode in &[GcMode::StopTheWorld, GcMode::Incremental { step_size: 2 }] {
+        let vm = make_vm(gc_mode);
+        add_extern_module(&vm, "collect", |vm| {
+            ExternModule::new(vm, primitive!(1, collect))
+        });
+
+        let expr = r#"
+let { Bool, Option } = import! std.types
+let { downgrade, upgrade } = import! std.weak
+let collect = import! collect
+
+let is_some x =
+    match x with
+    | Some _ -> True
+    | None -> False
+
+let kept = [1, 2, 3]
+let kept_weak = downgrade kept
+let dropped_weak = downgrade [4, 5, 6]
+let int_weak = downgrade 1
+collect ()
+[is_some (upgrade kept_weak), is_some (upgrade dropped_weak), is_some (upgrade int_weak)]
+"#;
+        let (value, _) = vm
+            .run_expr::<Vec<bool>>("weak", expr)
+            .unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(value, [true, false, true], "{:?}", gc_mode);
+    }
+}
+
+#[test]
+fn incremental_collection_marks_child_heaps_in_one_pause() {
+    let step_size = 100;
+    let child_depth = 12;
+    let vm = make_vm(GcMode::Incremental { step_size });
+
+    // The tree is only rooted by the child thread so it is traced and swept as part of the pauses
+    // of the parent
+    let child = vm.new_thread().unwrap();
+    let (tree, _) = child
+        .run_expr::<OpaqueValue<RootedThread, Hole>>(
+            "child_tree",
+            &format!("{}build {}", TREE, child_depth),
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let expr = format!(
+        r#"{}
+rec let churn n acc =
+    if n #Int== 0 then acc
+    else churn (n #Int- 1) (acc #Int+ size (build 4))
+
+churn {} 0
+"#,
+        TREE, ITERATIONS
+    );
+    vm.run_expr::<i32>("allocation_load", &expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let stats = vm.gc_stats();
+    assert!(stats.collections > 0);
+    assert!(
+        stats.max_pause_work > 1 << child_depth,
+        "Max pause work: {}",
+        stats.max_pause_work
+    );
+    drop(tree);
+}
@@ -1,9 +1,13 @@
 mod support;
 
+use std::time::{Duration, Instant};
+
+use futures::Future;
+
 use gluon::{
     vm::{
-        api::{Hole, OpaqueValue},
-        thread::ThreadInternal,
+        api::{FunctionRef, Hole, OpaqueValue, ValueRef},
+        thread::{Execute, ThreadInternal},
         Error as VMError,
     },
     Error, Thread, ThreadExt,
@@ -47,3 +51,115 @@ fn stack_overflow() {
         Ok(_) => panic!("Expected an error"),
     }
 }
+
+const LOOP: &str = r#"
+let loop x = loop (x #Int+ 1)
+loop 0
+"#;
+
+#[test]
+fn out_of_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.set_fuel(Some(1000));
+    vm.get_database_mut().implicit_prelude(false);
+
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("example", LOOP);
+
+    match result {
+        Err(Error::VM(VMError::OutOfFuel)) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+    assert_eq!(vm.fuel(), Some(0));
+}
+
+#[test]
+fn deadline_exceeded() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.get_database_mut().implicit_prelude(false);
+    vm.set_deadline(Some(Instant::now() + Duration::from_millis(50)));
+
+    let result = vm.run_expr::<OpaqueValue<&Thread, Hole>>("example", LOOP);
+
+    match result {
+        Err(Error::VM(VMError::DeadlineExceeded)) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn resume_after_adding_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.get_database_mut().implicit_prelude(false);
+
+    let expr = r#"
+let sum n = if n #Int== 0 then 0 else n #Int+ sum (n #Int- 1)
+sum
+"#;
+    let (mut sum, _) = vm
+        .run_expr::<FunctionRef<fn(i32) -> i32>>("example", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    vm.set_fuel(Some(100));
+    match sum.call_async(100).wait() {
+        Err(VMError::OutOfFuel) => (),
+        result => panic!("Expected an error, got `{:?}`", result),
+    }
+
+    let mut attempts = 0;
+    let mut execute = Execute::new(vm.root_thread());
+    let value = loop {
+        attempts += 1;
+        vm.add_fuel(100);
+        match execute.poll() {
+            Ok(futures::Async::Ready(value)) => break value,
+            Err(VMError::OutOfFuel) => (),
+            result => panic!("Unexpected result `{:?}`", result.map(|_| ())),
+        }
+    };
+    assert!(attempts > 1);
+    assert_eq!(value.get_variant().as_ref(), ValueRef::Int(5050));
+}
+
+#[test]
+fn child_threads_inherit_limits() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.set_fuel(Some(1000));
+    let deadline = Instant::now() + Duration::from_secs(60);
+    vm.set_deadline(Some(deadline));
+
+    let child = vm.new_thread().unwrap();
+    assert_eq!(child.fuel(), Some(1000));
+    assert_eq!(child.deadline(), Some(deadline));
+}
+
+#[test]
+fn child_threads_share_fuel() {
+    let _ = ::env_logger::try_init();
+
+    let vm = make_vm();
+    vm.get_database_mut().implicit_prelude(false);
+    vm.set_fuel(Some(1000));
+
+    let child = vm.new_thread().unwrap();
+    let result = child.run_expr::<OpaqueValue<&Thread, Hole>>("example", LOOP);
+    match result {
+        Err(Error::VM(VMError::OutOfFuel)) => (),
+        Err(err) => panic!("Unexpected error `{:?}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+    // The fuel spent by the child is gone from the parent as well
+    assert_eq!(vm.fuel(), Some(0));
+
+    vm.add_fuel(10);
+    assert_eq!(child.fuel(), Some(10));
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "serialization")]
+
+use std::{fs, path::Path, time::SystemTime};
+
+use gluon::{import::Import, vm::thread::RootedThread, ThreadExt, VmBuilder};
+
+fn make_vm(cache_dir: &Path, source_dir: &Path) -> RootedThread {
+    let _ = ::env_logger::try_init();
+
+    let vm = VmBuilder::new()
+        .module_cache(Some(cache_dir.to_owned()))
+        .build();
+    vm.get_macros()
+        .get("import")
+        .as_ref()
+        .and_then(|import| import.downcast_ref::<Import>())
+        .expect("Import macro")
+        .add_path(source_dir);
+    vm
+}
+
+fn modified(cache_dir: &Path, module: &str) -> SystemTime {
+    fs::metadata(cache_dir.join(format!("{}.gluc", module)))
+        .and_then(|metadata| metadata.modified())
+        .unwrap_or_else(|err| panic!("`{}` is not cached: {}", module, err))
+}
+
+const PROGRAM: &str = r#"
+let list @ { ? } = import! std.list
+let { foldl } = import! std.foldable
+let { value } = import! a
+let total = foldl (+) 0 (list.of [1, 2, 3])
+if total == 6 then total + value else 0
+"#;
+
+fn write_modules(source_dir: &Path, b_value: i32) {
+    fs::write(
+        source_dir.join("a.glu"),
+        "let { value } = import! b\n{ value = value + 1 }\n",
+    )
+    .unwrap();
+    fs::write(
+        source_dir.join("b.glu"),
+        format!("{{ value = {} }}\n", b_value),
+    )
+    .unwrap();
+}
+
+#[test]
+fn modules_are_loaded_from_the_cache() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    write_modules(source_dir.path(), 10);
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    let (value, _) = vm
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 17);
+
+    let stored: Vec<_> = ["std.types", "std.list", "a", "b"]
+        .iter()
+        .map(|module| modified(cache_dir.path(), module))
+        .collect();
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    let (value, _) = vm
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 17);
+
+    // Modules loaded from the cache are not stored again
+    let loaded: Vec<_> = ["std.types", "std.list", "a", "b"]
+        .iter()
+        .map(|module| modified(cache_dir.path(), module))
+        .collect();
+    assert_eq!(stored, loaded);
+}
+
+#[test]
+fn cached_modules_can_be_used_from_rust() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    write_modules(source_dir.path(), 10);
+
+    for _ in 0..2 {
+        let vm = make_vm(cache_dir.path(), source_dir.path());
+        vm.load_script("test", "let { value } = import! a in value")
+            .unwrap_or_else(|err| panic!("{}", err));
+        let value: i32 = vm
+            .get_global("a.value")
+            .unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(value, 11);
+    }
+}
+
+#[test]
+fn changing_an_import_invalidates_the_importing_modules() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    write_modules(source_dir.path(), 10);
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    let (value, _) = vm
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 17);
+
+    let list_stored = modified(cache_dir.path(), "std.list");
+    let a_stored = modified(cache_dir.path(), "a");
+
+    write_modules(source_dir.path(), 20);
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    let (value, _) = vm
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 27);
+
+    assert_eq!(list_stored, modified(cache_dir.path(), "std.list"));
+    assert_ne!(a_stored, modified(cache_dir.path(), "a"));
+}
+
+#[test]
+fn corrupted_modules_are_compiled_again() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let source_dir = tempfile::tempdir().unwrap();
+    write_modules(source_dir.path(), 10);
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    vm.run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let path = cache_dir.path().join("b.gluc");
+    let contents = fs::read_to_string(&path).unwrap();
+    let header_end = contents.find('\n').unwrap();
+    fs::write(&path, format!("{}\n{{", &contents[..header_end])).unwrap();
+
+    let vm = make_vm(cache_dir.path(), source_dir.path());
+    let (value, _) = vm
+        .run_expr::<i32>("test", PROGRAM)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 17);
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+}
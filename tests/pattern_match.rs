@@ -1,4 +1,4 @@
-use gluon::ThreadExt;
+use gluon::{Error, Severity, ThreadExt};
 
 use crate::support::*;
 
@@ -28,6 +28,51 @@ match A with
     assert!(result.is_err());
 }
 
+#[test]
+fn non_exhaustive_pattern_is_a_compile_error() {
+    let _ = ::env_logger::try_init();
+    let text = r"
+type AB = | A | B in
+match A with
+| B -> True
+| B -> False
+";
+    let vm = make_vm();
+    vm.get_database_mut().set_exhaustiveness(Severity::Error);
+    let result = vm.run_expr::<bool>("<top>", text);
+    match result {
+        Err(Error::Typecheck(err)) => {
+            let err = err.to_string();
+            assert!(
+                err.contains("Non-exhaustive patterns, `A` is not covered"),
+                "{}",
+                err
+            );
+            assert!(err.contains("Unreachable alternative"), "{}", err);
+        }
+        _ => panic!("Expected a typecheck error: {:?}", result),
+    }
+}
+
+#[test]
+fn exhaustive_nested_patterns_compile() {
+    let _ = ::env_logger::try_init();
+    let text = r"
+let { Option } = import! std.option
+match (Some True, None) with
+| (Some True, _) -> 1
+| (Some False, Some x) -> x
+| (Some False, None) -> 2
+| (None, _) -> 3
+";
+    let vm = make_vm();
+    vm.get_database_mut().set_exhaustiveness(Severity::Error);
+    let (result, _) = vm
+        .run_expr::<i32>("<top>", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(result, 1);
+}
+
 test_expr! { match_record_pattern,
 r#"
 let string_prim = import! std.string.prim
@@ -199,3 +244,151 @@ getName ("abc", 123)
 "#,
 "abc".to_string()
 }
+
+test_expr! { match_guard,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some y if y #Int< 0 -> 0
+    | Some y if y #Int< 10 -> y #Int* 2
+    | Some y -> y
+    | None -> 100
+f (Some (0 #Int- 5)) #Int+ f (Some 3) #Int+ f (Some 20) #Int+ f None
+"#,
+126i32
+}
+
+test_expr! { match_guard_falls_through_to_other_constructor,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | Some 1 if 1 #Int== 2 -> 1
+    | None -> 2
+    | y -> 3
+f (Some 1) #Int+ f None
+"#,
+5i32
+}
+
+test_expr! { match_guard_on_variable,
+r#"
+let f x =
+    match x with
+    | a if a #Int== 1 -> 10
+    | b if b #Int== 2 -> b
+    | c -> c #Int+ 100
+f 1 #Int+ f 2 #Int+ f 3
+"#,
+115i32
+}
+
+test_expr! { match_guard_with_nested_record,
+r#"
+match { x = 1, y = (2, "abc") } with
+| { x, y = (a, _) } if x #Int== a -> ""
+| { y = (_, s) } -> s
+"#,
+String::from("abc")
+}
+
+#[test]
+fn match_guard_falls_through_to_unmatched_pattern_error() {
+    let _ = ::env_logger::try_init();
+    let text = r"
+match 1 with
+| 1 if 1 #Int== 2 -> 1
+| 2 -> 2
+| _ if 1 #Int== 2 -> 3
+";
+    let vm = make_vm();
+    let result = vm.run_expr::<i32>("<top>", text);
+    assert!(result.is_err());
+}
+
+test_expr! { match_record_patterns_with_different_fields,
+r#"
+match { x = 1, y = (2, "abc") } with
+| { x, y = (a, _) } -> ""
+| { y = (_, s) } -> s
+"#,
+String::from("")
+}
+
+test_expr! { match_or_pattern,
+r#"
+type Shape = | Circle Int | Square Int | Rect Int Int | Empty
+let area s =
+    match s with
+    | Circle r | Square r -> r #Int* r
+    | Rect w 0 | Rect 0 w -> w
+    | Rect w h -> w #Int* h
+    | Empty -> 0
+area (Circle 3) #Int+ area (Square 2) #Int+ area (Rect 5 0) #Int+ area (Rect 0 6) #Int+ area (Rect 2 3) #Int+ area Empty
+"#,
+30i32
+}
+
+test_expr! { match_or_pattern_without_bindings,
+r#"
+type Color = | Red | Green | Blue
+let warm c =
+    match c with
+    | Red | Green -> 1
+    | Blue -> 10
+warm Red #Int+ warm Green #Int+ warm Blue
+"#,
+12i32
+}
+
+test_expr! { match_or_pattern_with_guard,
+r#"
+type Test = | A Int String | B String Int | C
+let f e =
+    match e with
+    | A n s | B s n if 10 #Int< n -> s
+    | A _ _ | B _ _ -> "small"
+    | C -> "c"
+(f (A 20 "a"), f (B "b" 30), f (B "b" 3), f C)
+"#,
+(
+    String::from("a"),
+    String::from("b"),
+    String::from("small"),
+    String::from("c")
+)
+}
+
+test_expr! { match_nested_or_pattern_with_literals,
+r#"
+type Option a = | None | Some a
+let f x =
+    match x with
+    | (Some (1 | 2), "a" | "b") -> 1
+    | (Some _, "c") | (None, _) -> 2
+    | _ -> 3
+f (Some 2, "b") #Int+ f (Some 7, "c") #Int+ f (None, "x") #Int+ f (Some 1, "x")
+"#,
+8i32
+}
+
+test_expr! { match_or_pattern_in_record,
+r#"
+let f r =
+    match r with
+    | { x, y = 1 | 2 } | { y = 3, x } -> x
+    | { x = _, y } -> "other"
+(f { x = "a", y = 2 }, f { x = "b", y = 3 }, f { x = "c", y = 4 })
+"#,
+(String::from("a"), String::from("b"), String::from("other"))
+}
+
+test_expr! { let_or_pattern,
+r#"
+type Test = | A Int | B Int
+let (A x | B x) = B 42
+x
+"#,
+42i32
+}
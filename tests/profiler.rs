@@ -0,0 +1,213 @@
+extern crate env_logger;
+extern crate gluon;
+
+use gluon::{
+    vm::profiler::{Metric, Profile, Profiler},
+    RootedThread, ThreadExt,
+};
+
+fn new_vm() -> RootedThread {
+    let thread = gluon::new_vm();
+    thread.get_database_mut().set_optimize(false);
+    thread.get_database_mut().implicit_prelude(false);
+    thread
+}
+
+fn run_profiled(source: &str) -> Profile {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let profiler = Profiler::new();
+    profiler.attach(&thread);
+    thread
+        .run_expr::<i32>("test", source)
+        .unwrap_or_else(|err| panic!("{}", err));
+    Profiler::detach(&thread);
+    profiler.profile()
+}
+
+fn run_sampled(source: &str, interval: u64) -> Profile {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let profiler = Profiler::new();
+    profiler.attach_sampling(&thread, interval);
+    thread
+        .run_expr::<i32>("test", source)
+        .unwrap_or_else(|err| panic!("{}", err));
+    Profiler::detach(&thread);
+    profiler.profile()
+}
+
+const CALLS: &'static str = r#"
+let f x = x #Int+ 1
+
+let g x =
+    let y = f x
+    f y #Int+ 1
+
+let h x = [x, x]
+
+let _ = h 1
+g 1 #Int+ g 2
+"#;
+
+#[test]
+fn counts_calls_and_instructions() {
+    let profile = run_profiled(CALLS);
+
+    let f = profile.function("f").expect("f");
+    let g = profile.function("g").expect("g");
+    assert_eq!(f.calls, 4);
+    assert_eq!(g.calls, 2);
+    assert_eq!(profile.function("h").expect("h").calls, 1);
+
+    assert!(f.exclusive_instructions > 0);
+    assert_eq!(f.inclusive_instructions, f.exclusive_instructions);
+    assert_eq!(
+        g.inclusive_instructions,
+        g.exclusive_instructions + f.inclusive_instructions
+    );
+
+    let test = profile.function("test").expect("test");
+    assert_eq!(test.calls, 1);
+    assert!(test.inclusive_instructions > g.inclusive_instructions);
+}
+
+#[test]
+fn counts_allocated_bytes() {
+    let profile = run_profiled(CALLS);
+
+    let h = profile.function("h").expect("h");
+    assert!(h.exclusive_allocated > 0);
+    assert_eq!(profile.function("f").expect("f").inclusive_allocated, 0);
+}
+
+#[test]
+fn recursive_calls_are_counted_once() {
+    let profile = run_profiled(
+        r#"
+let sum n = if n #Int== 0 then 0 else n #Int+ sum (n #Int- 1)
+sum 10
+"#,
+    );
+
+    let sum = profile.function("sum").expect("sum");
+    assert_eq!(sum.calls, 11);
+    assert_eq!(sum.inclusive_instructions, sum.exclusive_instructions);
+}
+
+#[test]
+fn folded_stacks() {
+    let profile = run_profiled(CALLS);
+
+    let mut folded = Vec::new();
+    profile
+        .write_folded(&mut folded, Metric::Instructions)
+        .unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    let stacks = folded
+        .lines()
+        .map(|line| {
+            let (stack, count) = line.split_at(line.rfind(' ').unwrap());
+            assert!(count.trim().parse::<u64>().unwrap() > 0, "{}", line);
+            stack
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stacks,
+        [
+            "test (test:2)",
+            "test (test:2);g (test:5)",
+            "test (test:2);g (test:5);f (test:2)",
+            "test (test:2);h (test:8)",
+        ],
+        "{}",
+        folded
+    );
+
+    let total = folded
+        .lines()
+        .map(|line| line[line.rfind(' ').unwrap() + 1..].parse::<u64>().unwrap())
+        .sum::<u64>();
+    assert_eq!(
+        total,
+        profile.function("test").unwrap().inclusive_instructions
+    );
+}
+
+#[test]
+fn report_lists_every_function() {
+    let profile = run_profiled(CALLS);
+
+    let report = profile.to_string();
+    let mut lines = report.lines();
+    assert!(lines.next().unwrap().contains("instructions"));
+    let functions = lines
+        .map(|line| line.split("  ").last().unwrap().trim())
+        .collect::<Vec<_>>();
+    assert_eq!(functions.len(), 4, "{}", report);
+    for name in &["test (test:2)", "g (test:5)", "f (test:2)", "h (test:8)"] {
+        assert!(functions.contains(name), "{}", report);
+    }
+}
+
+#[test]
+fn tail_calls_replace_the_caller() {
+    let profile = run_profiled(
+        r#"
+let f x = x #Int+ 1
+let g x = f x
+g 1 #Int+ 1
+"#,
+    );
+
+    let mut folded = Vec::new();
+    profile
+        .write_folded(&mut folded, Metric::Instructions)
+        .unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    assert!(folded.contains("\ntest (test:2);f (test:2) "), "{}", folded);
+    assert!(!folded.contains(";g (test:3);f"), "{}", folded);
+    assert_eq!(profile.function("g").expect("g").calls, 1);
+}
+
+#[test]
+fn sampling_records_stacks_instead_of_calls() {
+    let profile = run_sampled(
+        r#"
+let f x = x #Int+ 1
+let sum n = if n #Int== 0 then 0 else f n #Int+ sum (n #Int- 1)
+sum 1000 #Int+ 0
+"#,
+        7,
+    );
+    assert_eq!(profile.sample_interval(), Some(7));
+
+    let sum = profile.function("sum").expect("sum");
+    let test = profile.function("test").expect("test");
+    assert_eq!(sum.calls, 0);
+    assert_eq!(sum.inclusive_instructions, 0);
+    assert!(sum.exclusive_samples > 0);
+    assert!(sum.inclusive_samples > sum.exclusive_samples, "{}", profile);
+
+    let mut folded = Vec::new();
+    profile.write_folded(&mut folded, Metric::Samples).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    assert!(
+        folded
+            .lines()
+            .any(|line| line.starts_with("test (test:2);sum (test:3);f (test:2) ")),
+        "{}",
+        folded
+    );
+    // Every sample is taken while `test` is on the stack
+    let total = folded
+        .lines()
+        .map(|line| line[line.rfind(' ').unwrap() + 1..].parse::<u64>().unwrap())
+        .sum::<u64>();
+    assert_eq!(total, test.inclusive_samples);
+
+    let report = profile.to_string();
+    assert!(report.contains("samples"), "{}", report);
+}
@@ -0,0 +1,138 @@
+mod support;
+
+use gluon::{
+    import::Import,
+    sandbox::SandboxPolicy,
+    vm::{api::IO, thread::RootedThread},
+    Error, ThreadExt, VmBuilder,
+};
+
+fn make_sandboxed_vm(policy: SandboxPolicy) -> RootedThread {
+    let _ = ::env_logger::try_init();
+
+    let vm = VmBuilder::new().sandbox(Some(policy)).build();
+    vm.get_macros()
+        .get("import")
+        .as_ref()
+        .and_then(|import| import.downcast_ref::<Import>())
+        .expect("Import macro")
+        .add_path("..");
+    vm
+}
+
+fn expect_blocked(vm: &RootedThread, expr: &str, module: &str) {
+    match vm.run_expr::<()>("test", expr) {
+        Err(err) => {
+            let message = err.to_string();
+            assert!(
+                message.contains(&format!(
+                    "The sandbox policy does not allow `{}` to be imported",
+                    module
+                )),
+                "{}",
+                message
+            );
+        }
+        Ok(_) => panic!("Expected `{}` to be blocked", module),
+    }
+}
+
+#[test]
+fn restricted_prelude_is_available() {
+    let vm = make_sandboxed_vm(SandboxPolicy::restricted());
+    let (value, _) = vm
+        .run_expr::<i32>(
+            "test",
+            r#"
+let list @ { ? } = import! std.list
+let { foldl } = import! std.foldable
+foldl (+) 0 (list.of [1, 2, 3])
+"#,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 6);
+}
+
+#[test]
+fn restricted_blocks_source_modules() {
+    let vm = make_sandboxed_vm(SandboxPolicy::restricted());
+    expect_blocked(&vm, "let _ = import! std.fs in ()", "std.fs");
+    expect_blocked(&vm, "let _ = import! std.io in ()", "std.io");
+}
+
+#[test]
+fn restricted_blocks_extern_modules() {
+    let vm = make_sandboxed_vm(SandboxPolicy::restricted());
+    expect_blocked(&vm, "let _ = import! std.fs.prim in ()", "std.fs.prim");
+    expect_blocked(&vm, "let _ = import! std.env.prim in ()", "std.env.prim");
+}
+
+#[test]
+fn restricted_io_prim_does_not_perform_io() {
+    let vm = make_sandboxed_vm(SandboxPolicy::restricted());
+    match vm.run_expr::<()>("test", "let { println } = import! std.io.prim in ()") {
+        Err(Error::Typecheck(_)) => (),
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected `println` to be missing"),
+    }
+
+    let (value, _) = vm
+        .run_expr::<i32>(
+            "test",
+            "let { default_buf_len } = import! std.io.prim in default_buf_len",
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, 8192);
+}
+
+#[test]
+fn whitelisted_modules_can_be_imported() {
+    let vm = make_sandboxed_vm(SandboxPolicy::restricted().allow_import("tests.sandbox.*"));
+    let (value, _) = vm
+        .run_expr::<String>(
+            "test",
+            "let { greeting } = import! tests.sandbox.greeting in greeting",
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value, "hello");
+
+    expect_blocked(&vm, "let _ = import! tests.main in ()", "tests.main");
+}
+
+#[test]
+fn transitive_imports_are_checked() {
+    let vm = make_sandboxed_vm(SandboxPolicy::new().deny_import("std.list"));
+    expect_blocked(
+        &vm,
+        "let _ = import! tests.sandbox.greeting in ()",
+        "std.list",
+    );
+}
+
+#[test]
+fn eval_can_be_denied() {
+    let vm = make_sandboxed_vm(SandboxPolicy::new().eval(false));
+    vm.get_database_mut().run_io(true);
+    let text = r#"
+        let io = import! std.io
+        let { flat_map } = io.monad
+        do result = io.run_expr "123"
+        io.applicative.wrap result.value
+    "#;
+    match vm.run_expr::<IO<String>>("test", text) {
+        Err(err) => assert!(
+            err.to_string()
+                .contains("The sandbox policy does not allow `std.io.run_expr` to be used"),
+            "{}",
+            err
+        ),
+        Ok((value, _)) => panic!("Expected an error, got `{:?}`", value),
+    }
+}
+
+#[test]
+fn unrestricted_by_default() {
+    let vm = make_sandboxed_vm(SandboxPolicy::new());
+    vm.run_expr::<()>("test", "let _ = import! std.fs in ()")
+        .unwrap_or_else(|err| panic!("{}", err));
+}
@@ -0,0 +1,293 @@
+#[macro_use]
+extern crate gluon_vm;
+
+use std::sync::Mutex;
+
+use futures::{sync::oneshot, Future};
+
+use gluon::{
+    import::add_extern_module,
+    new_vm,
+    vm::{
+        api::{FutureResult, OpaqueValue, OwnedFunction, ValueRef, IO},
+        scheduler::{Priority, SchedulerBuilder, TaskState},
+        thread::RootedValue,
+        types::VmInt,
+        Error, ExternModule,
+    },
+    RootedThread, Thread, ThreadExt,
+};
+
+type Task = OwnedFunction<fn(()) -> VmInt>;
+
+// Each test records events in its own log as the tests run in parallel
+static PREEMPTION_LOG: Mutex<Vec<VmInt>> = Mutex::new(Vec::new());
+static PRIORITY_LOG: Mutex<Vec<VmInt>> = Mutex::new(Vec::new());
+static SPAWN_LOG: Mutex<Vec<VmInt>> = Mutex::new(Vec::new());
+
+static SIGNAL: Mutex<Option<oneshot::Receiver<VmInt>>> = Mutex::new(None);
+
+fn record_preemption(x: VmInt) {
+    PREEMPTION_LOG.lock().unwrap().push(x);
+}
+
+fn record_priority(x: VmInt) -> VmInt {
+    PRIORITY_LOG.lock().unwrap().push(x);
+    x
+}
+
+fn record_spawn(x: VmInt) -> IO<()> {
+    SPAWN_LOG.lock().unwrap().push(x);
+    IO::Value(())
+}
+
+fn wait_for_signal(_: ()) -> FutureResult<impl Future<Item = VmInt, Error = Error>> {
+    let receiver = SIGNAL.lock().unwrap().take().expect("signal");
+    FutureResult(receiver.map_err(|err| Error::Message(err.to_string())))
+}
+
+fn load_test(vm: &Thread) -> gluon::vm::Result<ExternModule> {
+    ExternModule::new(
+        vm,
+        record! {
+            record_preemption => primitive!(1, record_preemption),
+            record_priority => primitive!(1, record_priority),
+            record_spawn => primitive!(1, record_spawn),
+            wait_for_signal => primitive!(1, wait_for_signal),
+        },
+    )
+}
+
+fn make_vm() -> RootedThread {
+    let _ = ::env_logger::try_init();
+
+    let vm = new_vm();
+    add_extern_module(&vm, "test", load_test);
+    vm
+}
+
+fn int(value: RootedValue<RootedThread>) -> VmInt {
+    match value.get_variant().as_ref() {
+        ValueRef::Int(i) => i,
+        value => panic!("Expected an integer, got {:?}", value),
+    }
+}
+
+fn task(vm: &Thread, expr: &str) -> Task {
+    vm.run_expr::<Task>("task", expr)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .0
+}
+
+fn counter(vm: &Thread, id: VmInt) -> Task {
+    let expr = format!(
+        r#"
+let {{ record_preemption }} = import! test
+rec let count n =
+    if n #Int== 0 then {}
+    else
+        let _ = record_preemption {}
+        count (n #Int- 1)
+\_ -> count 100
+"#,
+        id, id
+    );
+    task(vm, &expr)
+}
+
+fn run_counters(quantum: Option<u64>) -> Vec<VmInt> {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new()
+        .deterministic(true)
+        .quantum(quantum)
+        .build();
+    let tasks: Vec<_> = (1..=2)
+        .map(|id| {
+            scheduler
+                .spawn(&counter(&vm, id), Priority::Normal)
+                .unwrap()
+        })
+        .collect();
+    for (id, task) in (1..=2).zip(tasks) {
+        let value = scheduler.join(task).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(int(value), id);
+    }
+    std::mem::replace(&mut *PREEMPTION_LOG.lock().unwrap(), Vec::new())
+}
+
+#[test]
+fn preemption_interleaves_threads_deterministically() {
+    let sequential = run_counters(None);
+    assert_eq!(sequential.len(), 200);
+    assert!(sequential[..100].iter().all(|&id| id == 1));
+
+    let interleaved = run_counters(Some(200));
+    assert_eq!(interleaved.len(), 200);
+    assert!(interleaved[..100].contains(&2), "{:?}", interleaved);
+    assert_eq!(run_counters(Some(200)), interleaved);
+}
+
+#[test]
+fn higher_priority_tasks_run_first() {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new().deterministic(true).build();
+
+    let priorities = [Priority::Low, Priority::High, Priority::Normal];
+    let tasks: Vec<_> = priorities
+        .iter()
+        .enumerate()
+        .map(|(i, &priority)| {
+            let expr = format!(
+                "let {{ record_priority }} = import! test in \\_ -> record_priority {}",
+                i
+            );
+            scheduler.spawn(&task(&vm, &expr), priority).unwrap()
+        })
+        .collect();
+    assert!(scheduler
+        .tasks()
+        .iter()
+        .all(|task| task.state == TaskState::Runnable));
+
+    scheduler.run_until_idle();
+    assert_eq!(*PRIORITY_LOG.lock().unwrap(), [1, 2, 0]);
+    for task in tasks {
+        assert_eq!(scheduler.task(task).unwrap().state, TaskState::Finished);
+        scheduler.join(task).unwrap();
+    }
+    assert!(scheduler.tasks().is_empty());
+}
+
+#[test]
+fn ids_of_removed_tasks_do_not_refer_to_later_tasks() {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new().deterministic(true).build();
+
+    let first = scheduler
+        .spawn(&task(&vm, "\\_ -> 1"), Priority::Normal)
+        .unwrap();
+    assert_eq!(int(scheduler.join(first).unwrap()), 1);
+
+    // The second task is stored where the first task was
+    let second = scheduler
+        .spawn(&task(&vm, "\\_ -> 2"), Priority::Normal)
+        .unwrap();
+    assert_ne!(first, second);
+    assert!(scheduler.task(first).is_none());
+
+    scheduler.set_priority(first, Priority::High);
+    scheduler.detach(first);
+    assert!(scheduler.join(first).is_err());
+
+    let info = scheduler.task(second).unwrap();
+    assert_eq!(info.id, second);
+    assert_eq!(info.priority, Priority::Normal);
+    assert_eq!(int(scheduler.join(second).unwrap()), 2);
+}
+
+#[test]
+fn blocked_tasks_are_woken_by_their_future() {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new().deterministic(true).build();
+
+    let (sender, receiver) = oneshot::channel();
+    *SIGNAL.lock().unwrap() = Some(receiver);
+
+    let waiting = task(
+        &vm,
+        "let { wait_for_signal } = import! test in \\_ -> wait_for_signal () #Int+ 1",
+    );
+    let id = scheduler.spawn(&waiting, Priority::Normal).unwrap();
+    scheduler.run_until_idle();
+
+    let info = scheduler.task(id).unwrap();
+    assert_eq!(info.state, TaskState::Blocked);
+    assert_eq!(info.polls, 1);
+
+    sender.send(41).unwrap();
+    let value = scheduler.join(id).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(int(value), 42);
+}
+
+#[test]
+fn threads_spawn_threads_from_gluon() {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new().deterministic(true).build();
+
+    let expr = r#"
+let { record_spawn } = import! test
+let io @ { ? } = import! std.io
+let { (*>) } = import! std.applicative
+let scheduler @ { Priority } = import! std.scheduler
+
+record_spawn 1
+    *> scheduler.spawn Low (record_spawn 3)
+    *> scheduler.spawn High (record_spawn 2)
+"#;
+    let (action, _) = vm
+        .run_expr::<OpaqueValue<RootedThread, IO<()>>>("spawn", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    let id = scheduler.spawn_io(&action, Priority::Normal).unwrap();
+    scheduler.join(id).unwrap_or_else(|err| panic!("{}", err));
+    scheduler.run_until_idle();
+    assert_eq!(*SPAWN_LOG.lock().unwrap(), [1, 2, 3]);
+    // Detached tasks are removed once they finish
+    assert!(scheduler.tasks().is_empty());
+}
+
+#[test]
+fn looping_threads_do_not_starve_the_pool() {
+    let vm = make_vm();
+    let scheduler = SchedulerBuilder::new()
+        .worker_threads(2)
+        .quantum(Some(1000))
+        .build();
+
+    // Both workers are kept busy by threads which never finish
+    let looping = task(&vm, "rec let loop x = loop (x #Int+ 1) in \\_ -> loop 0");
+    for _ in 0..2 {
+        scheduler.spawn(&looping, Priority::Normal).unwrap();
+    }
+
+    let sum = task(
+        &vm,
+        r#"
+rec let sum n acc = if n #Int== 0 then acc else sum (n #Int- 1) (acc #Int+ n)
+\_ -> sum 10000 0
+"#,
+    );
+    let tasks: Vec<_> = (0..8)
+        .map(|_| scheduler.spawn(&sum, Priority::Normal).unwrap())
+        .collect();
+    for task in tasks {
+        let value = scheduler.join(task).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(int(value), 50005000);
+    }
+
+    let tasks = scheduler.tasks();
+    assert_eq!(tasks.len(), 2);
+    assert!(tasks.iter().all(|task| task.polls > 1));
+    // Dropping the scheduler stops the looping threads
+    drop(scheduler);
+}
+
+#[test]
+fn spawn_outside_of_a_scheduler_is_an_error() {
+    let vm = make_vm();
+    vm.get_database_mut().run_io(true);
+
+    let expr = r#"
+let io @ { ? } = import! std.io
+let { wrap, (*>) } = import! std.applicative
+let scheduler @ { Priority } = import! std.scheduler
+
+io.catch (scheduler.spawn Normal (io.println "unreachable") *> wrap "") wrap
+"#;
+    let (value, _) = vm
+        .run_expr::<IO<String>>("spawn", expr)
+        .unwrap_or_else(|err| panic!("{}", err));
+    match value {
+        IO::Value(msg) => assert!(msg.contains("scheduler"), "{}", msg),
+        IO::Exception(err) => panic!("{}", err),
+    }
+}
@@ -194,6 +194,37 @@ fn precompile() {
     );
 }
 
+fn load_precompiled(thread: &Thread, json: &str) -> Result<(), gluon::Error> {
+    use gluon::compiler_pipeline::*;
+
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    Precompiled(&mut deserializer)
+        .load_script(
+            &mut thread.module_compiler(&thread.get_database()),
+            thread,
+            "test",
+            "",
+            (),
+        )
+        .wait()
+}
+
+#[test]
+fn precompiled_modules_must_have_a_known_version() {
+    let thread = new_vm();
+
+    // Modules used to be serialized without a header
+    let err = load_precompiled(&thread, r#"{ "id": "test", "typ": null }"#).unwrap_err();
+    assert!(
+        err.to_string().contains("older version of gluon"),
+        "{}",
+        err
+    );
+
+    let err = load_precompiled(&thread, r#"["gluon-module", 0, null]"#).unwrap_err();
+    assert!(err.to_string().contains("format version 0"), "{}", err);
+}
+
 #[test]
 fn roundtrip_reference() {
     let thread = new_vm();
@@ -235,3 +266,45 @@ fn roundtrip_thread() {
         .unwrap_or_else(|err| panic!("{}", err));
     roundtrip(&thread, &Into::<Result<_, _>>::into(value).unwrap());
 }
+
+#[test]
+fn artifact_globals_keep_their_types() {
+    let _ = env_logger::try_init();
+
+    let thread = new_vm();
+    let expr = r#"
+        let list = import! std.list
+        { value = 1 + 2, opt = Some 1, len = list.foldable.foldl (\n _ -> n + 1) 0 (list.of [1, 2, 3]) }
+    "#;
+    let mut artifact = Vec::new();
+    thread
+        .compile_to_artifact("test", expr, &mut artifact)
+        .unwrap_or_else(|err| panic!("{}", err));
+
+    let thread = new_vm();
+    let program = thread
+        .load_artifact(&artifact[..])
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(program, "test");
+
+    assert_eq!(thread.get_global::<i32>("test.value").unwrap(), 3);
+    assert_eq!(
+        thread.get_global::<Option<i32>>("test.opt").unwrap(),
+        Some(1)
+    );
+    assert_eq!(thread.get_global::<i32>("test.len").unwrap(), 3);
+    assert!(thread.get_global::<String>("test.value").is_err());
+}
+
+#[test]
+fn truncated_artifact_is_an_error() {
+    let thread = new_vm();
+    let mut artifact = Vec::new();
+    thread
+        .compile_to_artifact("test", "1", &mut artifact)
+        .unwrap_or_else(|err| panic!("{}", err));
+    artifact.truncate(artifact.len() / 2);
+
+    let thread = new_vm();
+    assert!(thread.load_artifact(&artifact[..]).is_err());
+}
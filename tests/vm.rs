@@ -15,6 +15,7 @@ use gluon::{
         api::{FunctionRef, Hole, OpaqueValue, ValueRef, IO},
         channel::Sender,
         thread::{RootedThread, Thread, ThreadInternal},
+        types::VmInt,
     },
     Error, ThreadExt,
 };
@@ -693,7 +694,62 @@ int.max_value * 2
     let vm = make_vm();
     let result = vm.run_expr::<i32>("<top>", text);
     match result {
-        Err(Error::VM(vm::Error::Message(ref err))) if err.contains("overflow") => (),
+        Err(Error::VM(vm::Error::ArithmeticOverflow(..))) => (),
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+#[test]
+fn wrapping_int_arithmetic() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let int = import! std.int
+(
+    int.max_value #Int+ 1,
+    9223372036854775807 #Int+ 1,
+    int.max_value * 2,
+    int.min_value #Int/ (0 #Int- 1)
+)
+"#;
+    let vm = make_vm();
+    vm.get_database_mut().set_wrapping_int_arithmetic(true);
+    let (result, _) = vm
+        .run_expr::<(VmInt, VmInt, VmInt, VmInt)>("<top>", text)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(
+        result,
+        (
+            VmInt::min_value(),
+            VmInt::min_value(),
+            -2,
+            VmInt::min_value()
+        )
+    );
+}
+
+#[test]
+fn int_overflow_reports_the_location() {
+    let _ = ::env_logger::try_init();
+    let text = r#"
+let int = import! std.int
+let x = int.max_value
+x #Int+ 1
+"#;
+    let vm = make_vm();
+    let result = vm.run_expr::<VmInt>("test", text);
+    match result {
+        Err(Error::VM(err @ vm::Error::ArithmeticOverflow(..))) => {
+            assert_eq!(err.to_string(), "Arithmetic overflow in `test` at line 4")
+        }
+        Err(err) => panic!("Unexpected error `{}`", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+
+    // Constant expressions are not folded if they would overflow
+    let result = vm.run_expr::<VmInt>("test2", "9223372036854775807 #Int* 2");
+    match result {
+        Err(Error::VM(vm::Error::ArithmeticOverflow(..))) => (),
         Err(err) => panic!("Unexpected error `{}`", err),
         Ok(_) => panic!("Expected an error"),
     }
@@ -921,6 +977,89 @@ fn deep_clone_partial_application() {
     );
 }
 
+#[test]
+fn gc_stats() {
+    let _ = ::env_logger::try_init();
+    let vm = make_vm();
+    let child = vm.new_thread().unwrap();
+
+    assert_eq!(child.gc_stats().collections, 0);
+
+    child.get_database_mut().set_implicit_prelude(false);
+    let result = child.run_expr::<OpaqueValue<&Thread, Hole>>(
+        "test",
+        r#"
+            let make x = { x, y = x #Int+ 1 }
+            [make 1, make 2]
+        "#,
+    );
+    assert!(result.is_ok(), "{}", result.err().unwrap());
+
+    let collections = child.gc_stats().collections;
+    child.collect();
+
+    let stats = child.gc_stats();
+    assert_eq!(stats.collections, collections + 1);
+    assert_eq!(stats.live_bytes, child.allocated_memory());
+    assert_eq!(
+        stats.allocated_bytes,
+        stats.live_bytes as u64 + stats.freed_bytes
+    );
+
+    let record = stats
+        .types
+        .iter()
+        .find(|typ| typ.name == "{ x, y }")
+        .unwrap_or_else(|| panic!("Missing record in {:#?}", stats.types));
+    assert_eq!(record.live_objects, 2);
+    assert!(record.allocations >= 2);
+}
+
+#[test]
+fn heap_snapshot() {
+    let _ = ::env_logger::try_init();
+    let vm = make_vm();
+    let child = vm.new_thread().unwrap();
+
+    child.get_database_mut().set_implicit_prelude(false);
+    let result = child.run_expr::<OpaqueValue<&Thread, Hole>>(
+        "test",
+        r#"
+            let make x = { x, y = x #Int+ 1 }
+            [make 1, make 2]
+        "#,
+    );
+    assert!(result.is_ok(), "{}", result.err().unwrap());
+
+    let snapshot = child.heap_snapshot();
+    assert_eq!(snapshot.nodes[0].name, "(roots)");
+
+    let records: Vec<_> = snapshot
+        .nodes
+        .iter()
+        .filter(|node| node.name == "{ x, y }")
+        .collect();
+    assert_eq!(records.len(), 2, "{:#?}", snapshot.nodes);
+    for record in records {
+        assert_eq!(record.fields, ["x", "y"]);
+        assert!(!record.external);
+        assert!(snapshot.edges.iter().any(|edge| edge.to == record.id));
+    }
+
+    let mut json = Vec::new();
+    snapshot.write_json(&mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["version"], 1);
+    assert_eq!(
+        json["nodes"].as_array().unwrap().len(),
+        snapshot.nodes.len()
+    );
+    assert_eq!(
+        json["edges"].as_array().unwrap().len(),
+        snapshot.edges.len()
+    );
+}
+
 test_expr! { prelude issue_601,
 r"
 let { wrap } = import! std.applicative
@@ -0,0 +1,87 @@
+use gluon::{check::typecheck::TypeError, query::CompilationBase, Error, ThreadExt};
+
+mod support;
+
+#[test]
+fn warnings_are_reported_for_the_file() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+let list = import! std.list
+let { map } = import! std.functor
+let x = 1
+let y = 2
+x
+"#;
+    vm.get_database_mut().add_module("test".into(), text);
+    let warnings = vm
+        .file_warnings("test.glu")
+        .unwrap_or_else(|err| panic!("{}", err))
+        .expect("Expected warnings");
+
+    let messages: Vec<_> = warnings
+        .errors()
+        .iter()
+        .map(|warning| warning.value.to_string())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Unused import `list`",
+            "Unused import `map`",
+            "Unused variable `y`",
+        ]
+    );
+
+    let output = warnings.emit_string(&vm.get_database().code_map()).unwrap();
+    assert!(
+        output.contains("warning: Unused variable `y`"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn derived_bindings_do_not_produce_warnings() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+let { Hash } = import! std.hash
+let { Default } = import! std.default
+#[derive(Eq, Show, Ord, Hash)]
+type Test = | Test
+#[derive(Functor, Default)]
+type Wrapper a = { value : Option a }
+Test
+"#;
+    vm.get_database_mut().add_module("test".into(), text);
+    let warnings = vm
+        .file_warnings("test.glu")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(warnings.is_none(), "{}", warnings.unwrap());
+}
+
+#[test]
+fn denied_warnings_are_errors() {
+    let _ = ::env_logger::try_init();
+    let vm = support::make_vm();
+    let text = r#"
+#[deny(unused_variables)]
+let f x y = x
+f 1 2
+"#;
+    let result = vm.run_expr::<i32>("test", text);
+    match result {
+        Err(Error::Typecheck(err)) => {
+            let errors: Vec<_> = err.into_errors().into();
+            assert_eq!(errors.len(), 1);
+            match errors[0].value.error {
+                TypeError::Lint(ref warning) => {
+                    assert_eq!(warning.to_string(), "Unused variable `y`")
+                }
+                ref err => panic!("Unexpected error: {}", err),
+            }
+        }
+        _ => panic!("Expected a typecheck error: {:?}", result),
+    }
+}
@@ -0,0 +1,228 @@
+//! Marshalling of `rust_decimal::Decimal` and the primitives of the `std.decimal` module.
+//!
+//! `Decimal` is a fixed-point number with 96 bits of mantissa and a scale of up to 28 decimal
+//! places. In gluon it is an opaque `std.decimal.Decimal` value which can be passed to and
+//! returned from Rust functions directly.
+extern crate rust_decimal;
+
+use std::{cmp::Ordering, convert::TryFrom, result::Result as StdResult, str::FromStr};
+
+use self::rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+pub use self::rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::base::types::ArcType;
+
+use crate::{
+    api::{Getable, RuntimeResult, VmType},
+    gc::{GcRef, Move, Trace},
+    thread::Thread,
+    types::VmInt,
+    value::{Cloner, Userdata},
+    ExternModule, Result, Variants,
+};
+
+impl VmType for Decimal {
+    type Type = Self;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        // `run_expr::<Decimal>` needs the type before the expression (and with it `std.decimal`)
+        // has been loaded so the type is registered by whichever comes first
+        vm.get_type::<Decimal>()
+            .unwrap_or_else(|| register_type(vm))
+    }
+}
+
+fn register_type(vm: &Thread) -> ArcType {
+    vm.register_type::<Decimal>("std.decimal.Decimal", &[])
+        .ok()
+        .or_else(|| vm.get_type::<Decimal>())
+        .expect("std.decimal.Decimal is registered")
+}
+
+unsafe impl Trace for Decimal {
+    impl_trace! { self, _gc, { } }
+}
+
+impl Userdata for Decimal {
+    fn deep_clone<'gc>(
+        &self,
+        deep_cloner: &'gc mut Cloner,
+    ) -> Result<GcRef<'gc, Box<dyn Userdata>>> {
+        let data: Box<dyn Userdata> = Box::new(*self);
+        deep_cloner.gc().alloc(Move(data))
+    }
+}
+
+impl<'vm, 'value> Getable<'vm, 'value> for Decimal {
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        *<&Decimal>::from_value(vm, value)
+    }
+}
+
+/// How a `Decimal` is rounded to fewer decimal places. Mirrors `std.decimal.RoundingMode`.
+#[derive(Getable, VmType, Clone, Copy, Debug)]
+#[gluon(gluon_vm)]
+pub enum RoundingMode {
+    /// Round to the nearest number, breaking ties towards the even number ("bankers rounding")
+    HalfEven,
+    /// Round to the nearest number, breaking ties away from zero
+    HalfAwayFromZero,
+    /// Round to the nearest number, breaking ties towards zero
+    HalfTowardZero,
+    /// Round towards zero (truncation)
+    TowardZero,
+    /// Round away from zero
+    AwayFromZero,
+    /// Round towards negative infinity
+    Floor,
+    /// Round towards positive infinity
+    Ceiling,
+}
+
+impl From<RoundingMode> for RoundingStrategy {
+    fn from(mode: RoundingMode) -> RoundingStrategy {
+        match mode {
+            RoundingMode::HalfEven => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfAwayFromZero => RoundingStrategy::MidpointAwayFromZero,
+            RoundingMode::HalfTowardZero => RoundingStrategy::MidpointTowardZero,
+            RoundingMode::TowardZero => RoundingStrategy::ToZero,
+            RoundingMode::AwayFromZero => RoundingStrategy::AwayFromZero,
+            RoundingMode::Floor => RoundingStrategy::ToNegativeInfinity,
+            RoundingMode::Ceiling => RoundingStrategy::ToPositiveInfinity,
+        }
+    }
+}
+
+mod prim {
+    use super::*;
+
+    pub fn from_int(i: VmInt) -> Decimal {
+        Decimal::from(i)
+    }
+
+    pub fn from_float(f: f64) -> Option<Decimal> {
+        Decimal::from_f64(f)
+    }
+
+    pub fn to_int(d: Decimal) -> Option<VmInt> {
+        d.to_i64()
+    }
+
+    pub fn to_float(d: Decimal) -> Option<f64> {
+        d.to_f64()
+    }
+
+    pub fn parse(s: &str) -> StdResult<Decimal, String> {
+        Decimal::from_str(s).map_err(|err| err.to_string())
+    }
+
+    pub fn show(d: Decimal) -> String {
+        d.to_string()
+    }
+
+    fn checked(
+        result: Option<Decimal>,
+        message: &'static str,
+    ) -> RuntimeResult<Decimal, &'static str> {
+        match result {
+            Some(d) => RuntimeResult::Return(d),
+            None => RuntimeResult::Panic(message),
+        }
+    }
+
+    pub fn add(l: Decimal, r: Decimal) -> RuntimeResult<Decimal, &'static str> {
+        checked(l.checked_add(r), "Decimal overflow")
+    }
+
+    pub fn sub(l: Decimal, r: Decimal) -> RuntimeResult<Decimal, &'static str> {
+        checked(l.checked_sub(r), "Decimal overflow")
+    }
+
+    pub fn mul(l: Decimal, r: Decimal) -> RuntimeResult<Decimal, &'static str> {
+        checked(l.checked_mul(r), "Decimal overflow")
+    }
+
+    pub fn div(l: Decimal, r: Decimal) -> RuntimeResult<Decimal, &'static str> {
+        if r.is_zero() {
+            RuntimeResult::Panic("Division by zero")
+        } else {
+            checked(l.checked_div(r), "Decimal overflow")
+        }
+    }
+
+    pub fn rem(l: Decimal, r: Decimal) -> RuntimeResult<Decimal, &'static str> {
+        if r.is_zero() {
+            RuntimeResult::Panic("Division by zero")
+        } else {
+            checked(l.checked_rem(r), "Decimal overflow")
+        }
+    }
+
+    pub fn negate(d: Decimal) -> Decimal {
+        -d
+    }
+
+    pub fn abs(d: Decimal) -> Decimal {
+        d.abs()
+    }
+
+    pub fn eq(l: Decimal, r: Decimal) -> bool {
+        l == r
+    }
+
+    pub fn compare(l: Decimal, r: Decimal) -> Ordering {
+        l.cmp(&r)
+    }
+
+    pub fn scale(d: Decimal) -> VmInt {
+        d.scale() as VmInt
+    }
+
+    pub fn round(mode: RoundingMode, places: VmInt, d: Decimal) -> RuntimeResult<Decimal, String> {
+        match u32::try_from(places) {
+            Ok(places) => RuntimeResult::Return(d.round_dp_with_strategy(places, mode.into())),
+            Err(_) => RuntimeResult::Panic(format!(
+                "Can't round to {} decimal places, the number of places must not be negative",
+                places
+            )),
+        }
+    }
+
+    pub fn normalize(d: Decimal) -> Decimal {
+        d.normalize()
+    }
+}
+
+pub fn load(vm: &Thread) -> Result<ExternModule> {
+    register_type(vm);
+
+    ExternModule::new(
+        vm,
+        record! {
+            type Decimal => Decimal,
+            type RoundingMode => RoundingMode,
+
+            from_int => primitive!(1, "std.decimal.prim.from_int", prim::from_int),
+            from_float => primitive!(1, "std.decimal.prim.from_float", prim::from_float),
+            to_int => primitive!(1, "std.decimal.prim.to_int", prim::to_int),
+            to_float => primitive!(1, "std.decimal.prim.to_float", prim::to_float),
+            parse => primitive!(1, "std.decimal.prim.parse", prim::parse),
+            show => primitive!(1, "std.decimal.prim.show", prim::show),
+            add => primitive!(2, "std.decimal.prim.add", prim::add),
+            sub => primitive!(2, "std.decimal.prim.sub", prim::sub),
+            mul => primitive!(2, "std.decimal.prim.mul", prim::mul),
+            div => primitive!(2, "std.decimal.prim.div", prim::div),
+            rem => primitive!(2, "std.decimal.prim.rem", prim::rem),
+            negate => primitive!(1, "std.decimal.prim.negate", prim::negate),
+            abs => primitive!(1, "std.decimal.prim.abs", prim::abs),
+            eq => primitive!(2, "std.decimal.prim.eq", prim::eq),
+            compare => primitive!(2, "std.decimal.prim.compare", prim::compare),
+            scale => primitive!(1, "std.decimal.prim.scale", prim::scale),
+            round => primitive!(3, "std.decimal.prim.round", prim::round),
+            normalize => primitive!(1, "std.decimal.prim.normalize", prim::normalize)
+        },
+    )
+}
@@ -126,6 +126,14 @@ macro_rules! stringify_inner {
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! stringify_path_inner {
+    ($name:ident $( :: $suffix: ident)*) => {
+        concat!(stringify!($name) $(, "::", stringify!($suffix))*)
+    };
+}
+
 #[doc(hidden)]
 #[macro_export(local_inner_macros)]
 macro_rules! record_field_inner_struct {
@@ -177,7 +185,7 @@ macro_rules! field_decl_inner {
         record_field_inner_struct!{ $field $($suffix)* }
         impl $crate::api::record::Field for last_ident!($field $($suffix)*) {
             fn name() -> &'static str {
-                stringify_inner!($field $( :: $suffix)* )
+                stringify_path_inner!($field $( :: $suffix)* )
             }
             fn args() -> &'static [&'static str] {
                 &[$(stringify_inner!($args)),*]
@@ -4,9 +4,10 @@ use std::{
     borrow::Borrow,
     cell::Ref,
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     ffi::{OsStr, OsString},
     fmt,
+    hash::{BuildHasher, Hash},
     marker::PhantomData,
     ops::Deref,
     path::{Path, PathBuf},
@@ -63,6 +64,8 @@ macro_rules! impl_getable_simple {
 
 #[macro_use]
 pub mod mac;
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
 pub mod function;
 mod opaque;
 pub mod record;
@@ -578,8 +581,15 @@ impl<'vm, T: vm::Userdata> Pushable<'vm> for T {
     fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
         let mut context = context.context();
         let data: Box<dyn vm::Userdata> = Box::new(self);
-        let userdata = alloc!(context, Move(data))?;
-        context.stack.push(Variants::from(userdata));
+        let has_finalizer = data.has_finalizer();
+        // SAFETY The value is rooted on the stack before anything else is allocated
+        let userdata = unsafe { alloc!(context, Move(data))?.unrooted() };
+        if has_finalizer {
+            context
+                .gc
+                .register_finalizer(unsafe { userdata.clone_unrooted() });
+        }
+        context.stack.push(Variants::from(&userdata));
         Ok(())
     }
 }
@@ -1295,12 +1305,35 @@ where
     K::Type: Sized,
     V: for<'vm2> Pushable<'vm2> + VmType,
     V::Type: Sized,
+{
+    to_gluon_map_with::<BTreeMap<String, V>, _, _>(
+        map_iter,
+        context,
+        "std.map.empty",
+        "std.json.de.insert_string",
+    )
+}
+
+/// Builds the gluon map `M` by calling the gluon function `insert` (which must have the type
+/// `String -> V -> M -> M`) for each entry, starting from `empty`.
+fn to_gluon_map_with<'vm, M, K, V>(
+    map_iter: impl IntoIterator<Item = (K, V)>,
+    context: &mut ActiveThread<'vm>,
+    empty: &str,
+    insert: &str,
+) -> Result<()>
+where
+    M: VmType,
+    M::Type: Sized,
+    K: Borrow<str> + VmType,
+    K::Type: Sized,
+    V: for<'vm2> Pushable<'vm2> + VmType,
+    V::Type: Sized,
 {
     let thread = context.thread();
-    type Map<V2> = OpaqueValue<RootedThread, BTreeMap<String, V2>>;
-    let mut map: Map<V> = thread.get_global("std.map.empty")?;
-    let mut insert: OwnedFunction<fn(String, V, Map<V>) -> Map<V>> =
-        thread.get_global("std.json.de.insert_string")?;
+    type Map<M> = OpaqueValue<RootedThread, M>;
+    let mut map: Map<M> = thread.get_global(empty)?;
+    let mut insert: OwnedFunction<fn(String, V, Map<M>) -> Map<M>> = thread.get_global(insert)?;
 
     context.drop();
     for (key, value) in map_iter {
@@ -1319,15 +1352,16 @@ where
 {
     match value.as_ref() {
         ValueRef::Data(data) => {
+            // Bin size key value left right
             if data.tag() == 1 {
-                let key = K2::from_value(vm, data.get_variant(0).expect("key"));
-                let value = V2::from_value(vm, data.get_variant(1).expect("value"));
+                let key = K2::from_value(vm, data.get_variant(1).expect("key"));
+                let value = V2::from_value(vm, data.get_variant(2).expect("value"));
                 map.extend(Some((key, value)));
 
-                let left = data.get_variant(2).expect("left");
+                let left = data.get_variant(3).expect("left");
                 from_gluon_map(map, vm, left);
 
-                let right = data.get_variant(3).expect("right");
+                let right = data.get_variant(4).expect("right");
                 from_gluon_map(map, vm, right);
             }
         }
@@ -1335,6 +1369,111 @@ where
     }
 }
 
+impl<K, V, S> VmType for HashMap<K, V, S>
+where
+    K: VmType,
+    K::Type: Sized,
+    V: VmType,
+    V::Type: Sized,
+    S: 'static,
+{
+    type Type = HashMap<K::Type, V::Type>;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        let map_alias = vm
+            .find_type_info("std.hashmap.HashMap")
+            .unwrap()
+            .clone()
+            .into_type();
+        Type::app(map_alias, collect![K::make_type(vm), V::make_type(vm)])
+    }
+}
+
+impl<'vm, K, V, S> Pushable<'vm> for HashMap<K, V, S>
+where
+    K: Borrow<str> + VmType,
+    K::Type: Sized,
+    V: for<'vm2> Pushable<'vm2> + VmType,
+    V::Type: Sized,
+    S: 'static,
+{
+    fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+        to_gluon_map_with::<HashMap<String, V>, _, _>(
+            self,
+            context,
+            "std.hashmap.empty",
+            "std.hashmap.insert_string",
+        )
+    }
+}
+
+impl<'vm, 'value, K, V, S> Getable<'vm, 'value> for HashMap<K, V, S>
+where
+    K: Getable<'vm, 'value> + Eq + Hash,
+    V: Getable<'vm, 'value>,
+    S: BuildHasher + Default,
+{
+    impl_getable_simple!();
+
+    fn from_value(vm: &'vm Thread, value: Variants<'value>) -> Self {
+        let mut map = HashMap::default();
+        match value.as_ref() {
+            ValueRef::Data(data) => {
+                from_gluon_hashmap(&mut map, vm, data.get_variant(0).expect("root"))
+            }
+            _ => ice!("ValueRef is not a HashMap"),
+        }
+        map
+    }
+}
+
+fn from_gluon_hashmap<'vm2, 'value2, M, K2, V2>(
+    map: &mut M,
+    vm: &'vm2 Thread,
+    node: Variants<'value2>,
+) where
+    M: Extend<(K2, V2)>,
+    K2: Getable<'vm2, 'value2>,
+    V2: Getable<'vm2, 'value2>,
+{
+    match node.as_ref() {
+        // Leaf hash key value
+        ValueRef::Data(data) if data.tag() == 0 => {
+            let key = K2::from_value(vm, data.get_variant(1).expect("key"));
+            let value = V2::from_value(vm, data.get_variant(2).expect("value"));
+            map.extend(Some((key, value)));
+        }
+        // Collision hash (List { key, value })
+        ValueRef::Data(data) if data.tag() == 1 => {
+            let mut entries = data.get_variant(1).expect("entries");
+            while let ValueRef::Data(cons) = entries.as_ref() {
+                let entry = match cons.get_variant(0).expect("entry").as_ref() {
+                    ValueRef::Data(entry) => entry,
+                    _ => ice!("ValueRef is not a HashMap entry"),
+                };
+                let key = K2::from_value(vm, entry.get_variant(0).expect("key"));
+                let value = V2::from_value(vm, entry.get_variant(1).expect("value"));
+                map.extend(Some((key, value)));
+
+                entries = match cons.get_variant(1) {
+                    Some(tail) => tail,
+                    None => break,
+                };
+            }
+        }
+        // Branch bitmap children
+        ValueRef::Data(data) => match data.get_variant(1).expect("children").as_ref() {
+            ValueRef::Array(children) => {
+                for child in children.as_ref().iter() {
+                    from_gluon_hashmap(map, vm, child);
+                }
+            }
+            _ => ice!("ValueRef is not a HashMap branch"),
+        },
+        _ => ice!("ValueRef is not a HashMap node"),
+    }
+}
+
 impl<T: VmType> VmType for Option<T>
 where
     T::Type: Sized,
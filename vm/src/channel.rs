@@ -21,8 +21,7 @@ use crate::{
         primitive, Function, FunctionRef, Generic, Getable, OpaqueRef, OpaqueValue, OwnedFunction,
         Pushable, Pushed, RuntimeResult, Unrooted, VmType, WithVM, IO,
     },
-    gc::{self, CloneUnrooted, GcPtr, Trace},
-    stack::{ClosureState, ExternState, State},
+    gc::{CloneUnrooted, GcPtr, Trace},
     thread::{ActiveThread, ThreadInternal},
     types::VmInt,
     value::{Callable, Userdata, Value, ValueRepr},
@@ -151,6 +150,7 @@ fn send(sender: &Sender<A>, value: Generic<A>) -> Result<(), ()> {
         .thread
         .deep_clone_value(&sender.thread, value.get_value())
         .map_err(|_| ())?;
+    sender.thread.write_barrier(value.get_value());
     Ok(sender.send(value.get_value()))
 }
 
@@ -176,26 +176,18 @@ fn spawn<'vm>(
     spawn_(value).into()
 }
 fn spawn_<'vm>(value: WithVM<'vm, Function<&'vm Thread, fn(())>>) -> VmResult<RootedThread> {
-    let thread = value.vm.new_thread()?;
+    spawn_thread(value.vm, value.value.get_variant())
+}
+
+/// Creates a child thread of `vm` which calls the function `value` with `()` once it is resumed
+pub(crate) fn spawn_thread(vm: &Thread, value: Variants) -> VmResult<RootedThread> {
+    let thread = vm.new_thread()?;
     {
         let mut context = thread.current_context();
-        let value_variant = value.value.get_variant();
-        let callable = match value_variant.get_repr() {
-            ValueRepr::Closure(closure) => {
-                construct_gc!(State::Closure(@ construct_gc!(ClosureState {
-                    @ closure,
-                    instruction_index: 0,
-                })))
-            }
-            ValueRepr::Function(function) => {
-                construct_gc!(State::Extern(@ ExternState::new(function)))
-            }
-            _ => gc::Borrow::from_static(State::Unknown),
-        };
-        value_variant.clone().push(&mut context)?;
+        value.clone().push(&mut context)?;
         context.push(ValueRepr::Int(0));
-        context.context().stack.enter_scope(1, &*callable);
     }
+    thread.enter_call(1)?;
     Ok(thread)
 }
 
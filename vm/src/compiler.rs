@@ -452,6 +452,7 @@ pub struct Compiler<'a> {
     source: &'a ::codespan::FileMap,
     source_name: String,
     emit_debug_info: bool,
+    wrapping_int_arithmetic: bool,
     empty_symbol: Symbol,
 }
 
@@ -497,9 +498,15 @@ impl<'a> Compiler<'a> {
             source: source,
             source_name: source_name,
             emit_debug_info: emit_debug_info,
+            wrapping_int_arithmetic: false,
         }
     }
 
+    /// Sets whether `Int` arithmetic wraps around on overflow instead of returning an error
+    pub fn set_wrapping_int_arithmetic(&mut self, wrapping_int_arithmetic: bool) {
+        self.wrapping_int_arithmetic = wrapping_int_arithmetic;
+    }
+
     fn intern(&mut self, s: &str) -> Result<InternedStr> {
         self.vm.intern(s)
     }
@@ -983,6 +990,10 @@ impl<'a> Compiler<'a> {
             function.function.instructions[end - 2] = Jump(end as VmIndex);
         } else {
             let instr = match self.symbols.string(op) {
+                "#Int+" if self.wrapping_int_arithmetic => WrappingAddInt,
+                "#Int-" if self.wrapping_int_arithmetic => WrappingSubtractInt,
+                "#Int*" if self.wrapping_int_arithmetic => WrappingMultiplyInt,
+                "#Int/" if self.wrapping_int_arithmetic => WrappingDivideInt,
                 "#Int+" => AddInt,
                 "#Int-" => SubtractInt,
                 "#Int*" => MultiplyInt,
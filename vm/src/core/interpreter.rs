@@ -1386,11 +1386,19 @@ impl<'a, 'e> Compiler<'a, 'e> {
         let r = self.peek_reduced_expr(r);
         match (l.as_ref(), r.as_ref()) {
             (&Expr::Const(Literal::Int(l), ..), &Expr::Const(Literal::Int(r), ..)) => {
-                let f = binop!();
+                // Overflow and division by zero are left to the VM which wraps around or returns
+                // an error depending on how the expression is compiled
+                let f: fn(i64, i64) -> Option<i64> = match id.name.as_ref().chars().last() {
+                    Some('+') => i64::checked_add,
+                    Some('-') => i64::checked_sub,
+                    Some('*') => i64::checked_mul,
+                    Some('/') => i64::checked_div,
+                    _ => return None,
+                };
                 Some(
                     self.allocator
                         .arena
-                        .alloc(Expr::Const(Literal::Int(f(l, r)), expr.span())),
+                        .alloc(Expr::Const(Literal::Int(f(l, r)?), expr.span())),
                 )
             }
             (&Expr::Const(Literal::Float(l), ..), &Expr::Const(Literal::Float(r), ..)) => {
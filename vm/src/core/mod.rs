@@ -767,7 +767,18 @@ impl<'a, 'e> Translator<'a, 'e> {
             fn visit_expr(&mut self, expr: &'a Expr<'a>) -> Option<&'a Expr<'a>> {
                 match *expr {
                     Expr::Ident(ref id, span) => {
-                        return self.ident_replacments.get(&id.name).map(|new_name| {
+                        // Replacements may be chained if a replaced variable is itself replaced
+                        let mut replacement = self.ident_replacments.get(&id.name);
+                        while let Some(new_name) =
+                            replacement.and_then(|name| self.ident_replacments.get(name))
+                        {
+                            // `match x with | x -> ..` replaces `x` with itself
+                            if Some(new_name) == replacement {
+                                break;
+                            }
+                            replacement = Some(new_name);
+                        }
+                        return replacement.map(|new_name| {
                             &*self.allocator.arena.alloc(Expr::Ident(
                                 TypedIdent {
                                     name: new_name.clone(),
@@ -909,19 +920,11 @@ impl<'a, 'e> Translator<'a, 'e> {
                 }
             }
 
-            ast::Expr::IfElse(ref pred, ref if_true, ref if_false) => {
-                let alts = self.allocator.alternative_arena.alloc_fixed(iterator!(
-                    Alternative {
-                        pattern: Pattern::Constructor(self.bool_constructor(true), vec![]),
-                        expr: self.translate_alloc(if_true),
-                    },
-                    Alternative {
-                        pattern: Pattern::Constructor(self.bool_constructor(false), vec![]),
-                        expr: self.translate_alloc(if_false),
-                    },
-                ));
-                Expr::Match(self.translate_alloc(pred), alts)
-            }
+            ast::Expr::IfElse(ref pred, ref if_true, ref if_false) => self.if_else(
+                self.translate_alloc(pred),
+                self.translate_alloc(if_true),
+                self.translate_alloc(if_false),
+            ),
 
             ast::Expr::Infix {
                 ref lhs,
@@ -963,6 +966,7 @@ impl<'a, 'e> Translator<'a, 'e> {
                     .iter()
                     .map(|alt| Equation {
                         patterns: vec![&alt.pattern],
+                        guard: alt.guard.as_ref().map(|guard| self.translate_alloc(guard)),
                         result: self.translate_alloc(&alt.expr),
                     })
                     .collect();
@@ -1127,6 +1131,7 @@ impl<'a, 'e> Translator<'a, 'e> {
                             id_expr,
                             &[Equation {
                                 patterns: vec![&pat],
+                                guard: None,
                                 result: core_body,
                             }],
                         );
@@ -1236,6 +1241,7 @@ impl<'a, 'e> Translator<'a, 'e> {
                             bind_expr,
                             &[Equation {
                                 patterns: vec![&bind.name],
+                                guard: None,
                                 result: tail,
                             }],
                         );
@@ -1263,6 +1269,25 @@ impl<'a, 'e> Translator<'a, 'e> {
         }
     }
 
+    fn if_else(
+        &'a self,
+        pred: &'a Expr<'a>,
+        if_true: &'a Expr<'a>,
+        if_false: &'a Expr<'a>,
+    ) -> Expr<'a> {
+        let alts = self.allocator.alternative_arena.alloc_fixed(iterator!(
+            Alternative {
+                pattern: Pattern::Constructor(self.bool_constructor(true), vec![]),
+                expr: if_true,
+            },
+            Alternative {
+                pattern: Pattern::Constructor(self.bool_constructor(false), vec![]),
+                expr: if_false,
+            },
+        ));
+        Expr::Match(pred, alts)
+    }
+
     fn bool_constructor(&self, variant: bool) -> TypedIdent<Symbol> {
         let b = self.env.get_bool();
         match *b {
@@ -1372,6 +1397,17 @@ impl<'a, 'e> Translator<'a, 'e> {
         )
     }
 
+    fn unit_expr(&'a self, pos: BytePos) -> Expr<'a> {
+        Expr::Data(
+            TypedIdent {
+                name: self.dummy_record_symbol.name.clone(),
+                typ: Type::unit(),
+            },
+            self.allocator.arena.alloc_fixed(std::iter::empty()),
+            pos,
+        )
+    }
+
     fn error_expr(&'a self, msg: &str) -> Expr<'a> {
         let arena = &self.allocator.arena;
         let error = arena.alloc(Expr::Ident(
@@ -1427,17 +1463,19 @@ pub struct PatternTranslator<'a, 'e: 'a>(&'a Translator<'a, 'e>);
 #[derive(Clone, PartialEq, Debug)]
 struct Equation<'a, 'p> {
     patterns: Vec<&'p SpannedPattern<Symbol>>,
+    /// If the guard evaluates to `False` the equation is skipped and matching continues with the
+    /// next equation
+    guard: Option<&'a Expr<'a>>,
     result: &'a Expr<'a>,
 }
 
 impl<'a, 'p> fmt::Display for Equation<'a, 'p> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "[({:?},{})]",
-            self.patterns.iter().format(", "),
-            self.result
-        )
+        write!(f, "[({:?}", self.patterns.iter().format(", "))?;
+        if let Some(guard) = self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, ",{})]", self.result)
     }
 }
 
@@ -1503,52 +1541,41 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             // Gather the inner patterns so we can prepend them to equations
             let temp = first_iter()
                 .map(|pattern| match *unwrap_as(&pattern.value) {
-                    ast::Pattern::Record {
-                        ref typ,
-                        ref fields,
-                        ..
-                    } => {
-                        let mut record_type = None;
-                        // Core fields appear in the same order as the normal pattern so we can
-                        // get the types from it cheaply
+                    ast::Pattern::Record { ref fields, .. } => {
+                        // The core pattern contains the fields of every equation so each field
+                        // that this equation does not match on is matched by a wildcard
                         let core_fields = match &core_pattern {
                             Pattern::Record(core_fields) => core_fields,
                             _ => unreachable!(),
                         };
-                        fields
+                        core_fields
                             .iter()
-                            .zip(core_fields)
-                            .map(|(field, core_field)| {
-                                field.value.as_ref().map(Cow::Borrowed).unwrap_or_else(|| {
-                                    let typ = if field.name.value == core_field.0.name {
-                                        core_field.0.typ.clone()
-                                    } else {
-                                        // If the field has been renamed we need to go the slo path
-                                        // and do a lookup but this should be rare
-                                        if record_type.is_none() {
-                                            record_type = Some(remove_aliases_cow(
-                                                &self.0.env,
-                                                &mut NullInterner,
-                                                typ,
-                                            ));
-                                        }
-                                        record_type
-                                            .as_ref()
-                                            .unwrap()
-                                            .row_iter()
-                                            .find(|f| f.name.name_eq(&field.name.value))
-                                            .map(|f| f.typ.clone())
-                                            .unwrap_or_else(Type::hole)
-                                    };
-
-                                    Cow::Owned(spanned(
+                            .map(|core_field| {
+                                let field = fields
+                                    .iter()
+                                    .find(|field| field.name.value == core_field.0.name);
+                                match field {
+                                    Some(field) => {
+                                        field.value.as_ref().map(Cow::Borrowed).unwrap_or_else(
+                                            || {
+                                                Cow::Owned(spanned(
+                                                    Span::default(),
+                                                    ast::Pattern::Ident(TypedIdent {
+                                                        name: field.name.value.clone(),
+                                                        typ: core_field.0.typ.clone(),
+                                                    }),
+                                                ))
+                                            },
+                                        )
+                                    }
+                                    None => Cow::Owned(spanned(
                                         Span::default(),
                                         ast::Pattern::Ident(TypedIdent {
-                                            name: field.name.value.clone(),
-                                            typ,
+                                            name: Symbol::from("_"),
+                                            typ: core_field.0.typ.clone(),
                                         }),
-                                    ))
-                                })
+                                    )),
+                                }
                             })
                             .collect::<Vec<_>>()
                     }
@@ -1563,15 +1590,15 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             // (since those need to be solved first) and then the remaining_patterns
             let new_equations = equations
                 .iter()
-                .map(|equation| (&equation.patterns[1..], equation.result))
                 .zip(&temp)
-                .map(|((remaining_equations, result), first)| Equation {
+                .map(|(equation, first)| Equation {
                     patterns: first
                         .iter()
                         .map(|pattern| &**pattern)
-                        .chain(remaining_equations.iter().cloned())
+                        .chain(equation.patterns[1..].iter().cloned())
                         .collect(),
-                    result,
+                    guard: equation.guard,
+                    result: equation.result,
                 })
                 .collect::<Vec<_>>();
 
@@ -1619,6 +1646,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 | ast::Pattern::Record { .. }
                 | ast::Pattern::Ident(_)
                 | ast::Pattern::Literal(_)
+                | ast::Pattern::Or(_)
                 | ast::Pattern::Error => unreachable!(),
             }
         }
@@ -1655,6 +1683,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                                 .iter()
                                 .chain(equation.patterns.iter().cloned().skip(1))
                                 .collect(),
+                            guard: equation.guard,
                             result: equation.result,
                         }
                     })
@@ -1701,6 +1730,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 .iter()
                 .map(|equation| Equation {
                     patterns: equation.patterns[1..].to_owned(),
+                    guard: equation.guard,
                     result: equation.result,
                 })
                 .collect::<Vec<_>>(),
@@ -1751,6 +1781,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 | ast::Pattern::Tuple { .. }
                 | ast::Pattern::Record { .. }
                 | ast::Pattern::Ident(_)
+                | ast::Pattern::Or(_)
                 | ast::Pattern::Error => unreachable!(),
             }
         }
@@ -1765,6 +1796,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                     .iter()
                     .map(|equation| Equation {
                         patterns: equation.patterns.iter().cloned().skip(1).collect(),
+                        guard: equation.guard,
                         result: equation.result,
                     })
                     .collect::<Vec<_>>();
@@ -1816,6 +1848,13 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
         expr: &'a Expr<'a>,
         equations: &[Equation<'a, 'p>],
     ) -> Expr<'a> {
+        if equations
+            .iter()
+            .any(|equation| equation.patterns.iter().any(|p| contains_or_pattern(p)))
+        {
+            return self.translate_or_patterns(expr, equations);
+        }
+
         let arena = &self.0.allocator.arena;
         let default = arena.alloc(self.0.error_expr("Unmatched pattern"));
         match *expr {
@@ -1842,6 +1881,283 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
         }
     }
 
+    // Expands each equation which contains an or-pattern into one equation per alternative.
+    // Instead of duplicating the result (and guard) of the equation in every expanded equation,
+    // they are moved into closures which the expanded equations call with the variables that
+    // their patterns bound
+    //
+    // | A x | B x -> x
+    // // Becomes
+    // let or_pattern_result x = x
+    // match .. with
+    // | A x1 -> or_pattern_result x1
+    // | B x2 -> or_pattern_result x2
+    //
+    // An equation expands to one equation for every combination of alternatives so the number of
+    // equations grows exponentially with the number of or-patterns in it. The typechecker rejects
+    // patterns which expand to more than `gluon_check::typecheck::MAX_OR_PATTERN_EXPANSION`
+    // equations.
+    fn translate_or_patterns<'p>(
+        &mut self,
+        expr: &'a Expr<'a>,
+        equations: &[Equation<'a, 'p>],
+    ) -> Expr<'a> {
+        let translator = self.0;
+        let arena = &translator.allocator.arena;
+
+        let mut joins = Vec::new();
+        let mut expanded: Vec<(Vec<Cow<SpannedPattern<Symbol>>>, _, _)> = Vec::new();
+        for equation in equations {
+            if !equation.patterns.iter().any(|p| contains_or_pattern(p)) {
+                expanded.push((
+                    equation
+                        .patterns
+                        .iter()
+                        .map(|p| Cow::Borrowed(*p))
+                        .collect(),
+                    equation.guard,
+                    equation.result,
+                ));
+                continue;
+            }
+
+            let mut alternatives: Vec<_> = cartesian_product(
+                equation
+                    .patterns
+                    .iter()
+                    .map(|pattern| expand_or_pattern(pattern))
+                    .collect(),
+            )
+            .into_iter()
+            .map(|mut patterns| {
+                let mut bindings = Vec::new();
+                for pattern in &mut patterns {
+                    self.fresh_bindings(pattern, &mut bindings);
+                }
+                (patterns, bindings)
+            })
+            .collect();
+
+            let params: Vec<_> = alternatives[0].1.iter().map(|(id, _)| id.clone()).collect();
+            let pos = equation.result.span().start();
+            let result_join = match *equation.result {
+                // Constants do not refer to any variables so they can be shared directly
+                Expr::Const(..) => None,
+                _ => Some(self.or_pattern_join(
+                    "or_pattern_result",
+                    pos,
+                    &params,
+                    equation.result,
+                    &mut joins,
+                )),
+            };
+            let guard_join = equation.guard.map(|guard| {
+                self.or_pattern_join("or_pattern_guard", pos, &params, guard, &mut joins)
+            });
+
+            for (patterns, bindings) in alternatives.drain(..) {
+                let call = |join: &TypedIdent<Symbol>| -> &'a Expr<'a> {
+                    let args: Vec<_> = if params.is_empty() {
+                        vec![translator.unit_expr(pos)]
+                    } else {
+                        params
+                            .iter()
+                            .map(|param| {
+                                let (_, fresh) = bindings
+                                    .iter()
+                                    .find(|(id, _)| id.name == param.name)
+                                    .unwrap_or_else(|| {
+                                        ice!(
+                                            "Variable `{}` is not bound in every alternative",
+                                            param.name
+                                        )
+                                    });
+                                Expr::Ident(fresh.clone(), Span::new(pos, pos))
+                            })
+                            .collect()
+                    };
+                    arena.alloc(Expr::Call(
+                        arena.alloc(Expr::Ident(join.clone(), Span::new(pos, pos))),
+                        arena.alloc_fixed(args),
+                    ))
+                };
+                expanded.push((
+                    patterns.into_iter().map(Cow::Owned).collect(),
+                    guard_join.as_ref().map(&call),
+                    result_join.as_ref().map_or(equation.result, &call),
+                ));
+            }
+        }
+
+        let new_equations: Vec<_> = expanded
+            .iter()
+            .map(|(patterns, guard, result)| Equation {
+                patterns: patterns.iter().map(|pattern| &**pattern).collect(),
+                guard: *guard,
+                result,
+            })
+            .collect();
+        let expr = self.translate_top(expr, &new_equations);
+
+        joins
+            .into_iter()
+            .rev()
+            .fold(expr, |expr, join| self.bind_join(join, arena.alloc(expr)))
+    }
+
+    // Binds `join` in the innermost branch of `expr` which contains every call to it so that the
+    // closure is only allocated when a branch which may call it is taken
+    fn bind_join(&self, join: &'a LetBinding<'a>, expr: &'a Expr<'a>) -> Expr<'a> {
+        let allocator = &self.0.allocator;
+        let name = &join.name.name;
+        match *expr {
+            Expr::Let(bind, body) if !named_refers_to(&bind.expr, name) => {
+                Expr::Let(bind, allocator.arena.alloc(self.bind_join(join, body)))
+            }
+            Expr::Match(scrutinee, alts) if !refers_to(scrutinee, name) => {
+                let mut callers = (0..alts.len()).filter(|&i| refers_to(alts[i].expr, name));
+                match (callers.next(), callers.next()) {
+                    (Some(caller), None) => Expr::Match(
+                        scrutinee,
+                        allocator
+                            .alternative_arena
+                            .alloc_fixed(alts.iter().enumerate().map(|(i, alt)| Alternative {
+                                pattern: alt.pattern.clone(),
+                                expr: if i == caller {
+                                    allocator.arena.alloc(self.bind_join(join, alt.expr))
+                                } else {
+                                    alt.expr
+                                },
+                            })),
+                    ),
+                    _ => Expr::Let(join, expr),
+                }
+            }
+            _ => Expr::Let(join, expr),
+        }
+    }
+
+    // Creates a closure which takes `params` as arguments and returns `body`
+    fn or_pattern_join(
+        &self,
+        name: &str,
+        pos: BytePos,
+        params: &[TypedIdent<Symbol>],
+        body: &'a Expr<'a>,
+        joins: &mut Vec<&'a LetBinding<'a>>,
+    ) -> TypedIdent<Symbol> {
+        let args = if params.is_empty() {
+            vec![TypedIdent {
+                name: Symbol::from("_"),
+                typ: Type::unit(),
+            }]
+        } else {
+            params.to_owned()
+        };
+        let name = TypedIdent {
+            name: Symbol::from(name),
+            typ: Type::function(
+                args.iter().map(|arg| arg.typ.clone()),
+                body.env_type_of(&self.0.env),
+            ),
+        };
+        joins.push(self.0.allocator.let_binding_arena.alloc(LetBinding {
+            name: name.clone(),
+            expr: Named::Recursive(vec![Closure {
+                pos,
+                name: name.clone(),
+                args,
+                expr: body,
+            }]),
+            span_start: pos,
+        }));
+        name
+    }
+
+    // Renames every variable bound in `pattern` to a new symbol. Each renamed variable is added
+    // to `bindings` along with the variable it replaced
+    fn fresh_bindings(
+        &self,
+        pattern: &mut SpannedPattern<Symbol>,
+        bindings: &mut Vec<(TypedIdent<Symbol>, TypedIdent<Symbol>)>,
+    ) {
+        fn fresh(
+            bindings: &mut Vec<(TypedIdent<Symbol>, TypedIdent<Symbol>)>,
+            id: TypedIdent<Symbol>,
+        ) -> TypedIdent<Symbol> {
+            let new_id = TypedIdent {
+                name: Symbol::from(id.name.declared_name()),
+                typ: id.typ.clone(),
+            };
+            bindings.push((id, new_id.clone()));
+            new_id
+        }
+        match pattern.value {
+            ast::Pattern::Ident(ref id) if id.name.declared_name() == "_" => (),
+            ast::Pattern::Ident(ref mut id) => *id = fresh(bindings, id.clone()),
+            ast::Pattern::As(ref mut id, ref mut pat) => {
+                id.value = fresh(
+                    bindings,
+                    TypedIdent {
+                        name: id.value.clone(),
+                        typ: pat.env_type_of(&self.0.env),
+                    },
+                )
+                .name;
+                self.fresh_bindings(pat, bindings);
+            }
+            ast::Pattern::Record {
+                ref typ,
+                ref mut fields,
+                ref mut implicit_import,
+                ..
+            } => {
+                if let Some(ref mut implicit_import) = *implicit_import {
+                    implicit_import.value = fresh(
+                        bindings,
+                        TypedIdent {
+                            name: implicit_import.value.clone(),
+                            typ: typ.clone(),
+                        },
+                    )
+                    .name;
+                }
+                let typ = remove_aliases_cow(&self.0.env, &mut NullInterner, typ);
+                for field in fields {
+                    match field.value {
+                        Some(ref mut pat) => self.fresh_bindings(pat, bindings),
+                        // `{ x }` => `{ x = x1 }`
+                        None => {
+                            let field_type = typ
+                                .row_iter()
+                                .find(|f| f.name.name_eq(&field.name.value))
+                                .map(|f| f.typ.clone())
+                                .unwrap_or_else(Type::hole);
+                            let id = fresh(
+                                bindings,
+                                TypedIdent {
+                                    name: field.name.value.clone(),
+                                    typ: field_type,
+                                },
+                            );
+                            field.value = Some(spanned(field.name.span, ast::Pattern::Ident(id)));
+                        }
+                    }
+                }
+            }
+            ast::Pattern::Tuple {
+                elems: ref mut args,
+                ..
+            }
+            | ast::Pattern::Constructor(_, ref mut args) => {
+                for arg in args {
+                    self.fresh_bindings(arg, bindings);
+                }
+            }
+            ast::Pattern::Literal(_) | ast::Pattern::Or(_) | ast::Pattern::Error => (),
+        }
+    }
+
     fn translate<'p>(
         &mut self,
         default: &'a Expr<'a>,
@@ -1855,6 +2171,7 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                 ast::Pattern::Record { .. } | ast::Pattern::Tuple { .. } => CType::Record,
                 ast::Pattern::Constructor(_, _) => CType::Constructor,
                 ast::Pattern::Literal(_) => CType::Literal,
+                ast::Pattern::Or(_) => ice!("ICE: Or-pattern survived expansion"),
                 ast::Pattern::Error => ice!("ICE: Error pattern survived typechecking"),
             }
         }
@@ -1871,10 +2188,20 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
             .group_by(|equation| varcon(&equation.patterns.first().expect("Pattern").value));
 
         let expr = match variables.first() {
+            // Every pattern has matched so the first equation is selected unless its guard fails,
+            // in which case the next equation is tried
             None => equations
-                .first()
-                .map(|equation| equation.result)
-                .unwrap_or(default),
+                .iter()
+                .rev()
+                .fold(default, |next, equation| match equation.guard {
+                    Some(guard) => {
+                        self.0
+                            .allocator
+                            .arena
+                            .alloc(self.0.if_else(guard, equation.result, next))
+                    }
+                    None => equation.result,
+                }),
             Some(_) => {
                 fn bind_variables<'b>(
                     env: &dyn PrimitiveEnv<Type = ArcType>,
@@ -2006,12 +2333,16 @@ impl<'a, 'e> PatternTranslator<'a, 'e> {
                         }
                     }
                 }
-                ast::Pattern::As(..) => unreachable!(),
-                ast::Pattern::Ident(ref id) => {
-                    if core_pattern.is_none() {
-                        core_pattern = Some(Pattern::Ident(id.clone()));
+                ast::Pattern::As(..) | ast::Pattern::Or(_) => unreachable!(),
+                ast::Pattern::Ident(ref id) => match core_pattern {
+                    None => core_pattern = Some(Pattern::Ident(id.clone())),
+                    // A later identifier is only reached after the guard of an earlier equation
+                    // has failed so it must refer to the same variable
+                    Some(Pattern::Ident(ref earlier)) => {
+                        replacements.insert(id.name.clone(), earlier.name.clone());
                     }
-                }
+                    Some(_) => (),
+                },
                 ast::Pattern::Tuple { ref typ, ref elems } => {
                     let typ = remove_aliases_cow(&self.0.env, &mut NullInterner, typ);
 
@@ -2097,6 +2428,133 @@ fn get_ident(pattern: &ast::Pattern<Symbol>) -> Option<TypedIdent<Symbol>> {
     }
 }
 
+fn contains_or_pattern(pattern: &SpannedPattern<Symbol>) -> bool {
+    match pattern.value {
+        ast::Pattern::Or(_) => true,
+        ast::Pattern::As(_, ref pat) => contains_or_pattern(pat),
+        ast::Pattern::Record { ref fields, .. } => fields
+            .iter()
+            .any(|field| field.value.as_ref().is_some_and(contains_or_pattern)),
+        ast::Pattern::Tuple {
+            elems: ref args, ..
+        }
+        | ast::Pattern::Constructor(_, ref args) => args.iter().any(contains_or_pattern),
+        ast::Pattern::Ident(_) | ast::Pattern::Literal(_) | ast::Pattern::Error => false,
+    }
+}
+
+fn refers_to(expr: &Expr, name: &Symbol) -> bool {
+    match *expr {
+        Expr::Const(..) => false,
+        Expr::Ident(ref id, _) => id.name == *name,
+        Expr::Call(f, args) => refers_to(f, name) || args.iter().any(|arg| refers_to(arg, name)),
+        Expr::Data(_, args, _) => args.iter().any(|arg| refers_to(arg, name)),
+        Expr::Let(bind, body) => named_refers_to(&bind.expr, name) || refers_to(body, name),
+        Expr::Match(scrutinee, alts) => {
+            refers_to(scrutinee, name) || alts.iter().any(|alt| refers_to(alt.expr, name))
+        }
+        Expr::Cast(expr, _) => refers_to(expr, name),
+    }
+}
+
+fn named_refers_to(named: &Named, name: &Symbol) -> bool {
+    match *named {
+        Named::Recursive(ref closures) => {
+            closures.iter().any(|closure| refers_to(closure.expr, name))
+        }
+        Named::Expr(expr) => refers_to(expr, name),
+    }
+}
+
+// Returns a pattern for each way `pattern` can match, with every or-pattern replaced by one of
+// its alternatives
+fn expand_or_pattern(pattern: &SpannedPattern<Symbol>) -> Vec<SpannedPattern<Symbol>> {
+    let span = pattern.span;
+    match pattern.value {
+        ast::Pattern::Or(ref alternatives) => {
+            alternatives.iter().flat_map(expand_or_pattern).collect()
+        }
+        ast::Pattern::As(ref id, ref pat) => expand_or_pattern(pat)
+            .into_iter()
+            .map(|pat| spanned(span, ast::Pattern::As(id.clone(), Box::new(pat))))
+            .collect(),
+        ast::Pattern::Record {
+            ref typ,
+            ref types,
+            ref fields,
+            ref implicit_import,
+        } => cartesian_product(
+            fields
+                .iter()
+                .map(|field| match field.value {
+                    Some(ref pat) => expand_or_pattern(pat)
+                        .into_iter()
+                        .map(|pat| ast::PatternField {
+                            name: field.name.clone(),
+                            value: Some(pat),
+                        })
+                        .collect(),
+                    None => vec![field.clone()],
+                })
+                .collect(),
+        )
+        .into_iter()
+        .map(|fields| {
+            spanned(
+                span,
+                ast::Pattern::Record {
+                    typ: typ.clone(),
+                    types: types.clone(),
+                    fields,
+                    implicit_import: implicit_import.clone(),
+                },
+            )
+        })
+        .collect(),
+        ast::Pattern::Tuple { ref typ, ref elems } => {
+            cartesian_product(elems.iter().map(expand_or_pattern).collect())
+                .into_iter()
+                .map(|elems| {
+                    spanned(
+                        span,
+                        ast::Pattern::Tuple {
+                            typ: typ.clone(),
+                            elems,
+                        },
+                    )
+                })
+                .collect()
+        }
+        ast::Pattern::Constructor(ref id, ref args) => {
+            cartesian_product(args.iter().map(expand_or_pattern).collect())
+                .into_iter()
+                .map(|args| spanned(span, ast::Pattern::Constructor(id.clone(), args)))
+                .collect()
+        }
+        ast::Pattern::Ident(_) | ast::Pattern::Literal(_) | ast::Pattern::Error => {
+            vec![pattern.clone()]
+        }
+    }
+}
+
+// Returns every way to pick one element from each of `choices`
+fn cartesian_product<T: Clone>(choices: Vec<Vec<T>>) -> Vec<Vec<T>> {
+    choices
+        .into_iter()
+        .fold(vec![Vec::new()], |products, choice| {
+            products
+                .iter()
+                .flat_map(|product| {
+                    choice.iter().map(move |elem| {
+                        let mut product = product.clone();
+                        product.push(elem.clone());
+                        product
+                    })
+                })
+                .collect()
+        })
+}
+
 fn unwrap_as(pattern: &ast::Pattern<Symbol>) -> &ast::Pattern<Symbol> {
     match *pattern {
         ast::Pattern::As(_, ref pattern) => unwrap_as(&pattern.value),
@@ -2453,6 +2911,30 @@ pub mod tests {
         check_translation(expr_str, expected_str);
     }
 
+    #[test]
+    fn or_pattern_join_is_bound_in_the_branch_which_calls_it() {
+        let expr_str = r#"
+            match test with
+            | Ctor (A y | B y) -> y
+            | z -> 1
+        "#;
+
+        let expected_str = r#"
+            match test with
+            | Ctor p1 ->
+                rec let or_pattern_result y = y
+                in
+                match p1 with
+                | A y1 -> or_pattern_result y1
+                | B y2 -> or_pattern_result y2
+                | _ -> 1
+                end
+            | z -> 1
+            end
+        "#;
+        check_translation(expr_str, expected_str);
+    }
+
     #[test]
     fn translate_equality_match() {
         let expr_str = r#"
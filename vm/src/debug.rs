@@ -1,5 +1,6 @@
 use crate::{
-    api::{generic::A, Generic, OpaqueRef},
+    api::{generic::A, Generic, OpaqueRef, WithVM, IO},
+    gc,
     thread::Thread,
     value::ValueRepr,
     ExternModule, Result,
@@ -20,6 +21,58 @@ fn tag(a: OpaqueRef<A>) -> Option<String> {
     }
 }
 
+#[derive(Pushable, VmType)]
+#[gluon(gluon_vm)]
+struct GcStats {
+    collections: u64,
+    allocated_bytes: u64,
+    freed_bytes: u64,
+    live_bytes: usize,
+    last_pause: f64,
+    max_pause: f64,
+    total_pause: f64,
+    types: Vec<TypeStats>,
+}
+
+#[derive(Pushable, VmType)]
+#[gluon(gluon_vm)]
+struct TypeStats {
+    name: String,
+    allocations: u64,
+    allocated_bytes: u64,
+    live_objects: usize,
+    live_bytes: usize,
+}
+
+impl From<gc::GcStats> for GcStats {
+    fn from(stats: gc::GcStats) -> Self {
+        GcStats {
+            collections: stats.collections,
+            allocated_bytes: stats.allocated_bytes,
+            freed_bytes: stats.freed_bytes,
+            live_bytes: stats.live_bytes,
+            last_pause: stats.last_pause.as_secs_f64(),
+            max_pause: stats.max_pause.as_secs_f64(),
+            total_pause: stats.total_pause.as_secs_f64(),
+            types: stats
+                .types
+                .into_iter()
+                .map(|typ| TypeStats {
+                    name: typ.name,
+                    allocations: typ.allocations,
+                    allocated_bytes: typ.allocated_bytes,
+                    live_objects: typ.live_objects,
+                    live_bytes: typ.live_bytes,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn gc_stats(WithVM { vm, .. }: WithVM<()>) -> IO<GcStats> {
+    IO::Value(vm.gc_stats().into())
+}
+
 mod std {
     pub use crate::debug;
 }
@@ -30,7 +83,8 @@ pub fn load(vm: &Thread) -> Result<ExternModule> {
         record! {
             trace => primitive!(1, std::debug::trace),
             show => primitive!(1, std::debug::show),
-            tag => primitive!(1, std::debug::tag)
+            tag => primitive!(1, std::debug::tag),
+            gc_stats => primitive!(1, std::debug::gc_stats)
         },
     )
 }
@@ -0,0 +1,445 @@
+//! A step debugger built on top of the hooks of a `Thread`.
+//!
+//! A `Debugger` is attached to a thread with `Debugger::attach` after which it stops execution
+//! whenever a breakpoint is hit or a step finishes. What happens on a stop depends on whether a
+//! stop handler is installed:
+//!
+//! * With a handler (see `Debugger::set_stop_handler`) the handler is called with the stopped
+//!   thread and returns the `Step` that execution should resume with.
+//! * Without a handler the hook returns `Async::NotReady`, suspending the future that drives the
+//!   thread. The stop can then be inspected with `Debugger::stopped` and the thread's
+//!   `debug_info`, and execution resumes by calling `Debugger::resume` and polling the future
+//!   again. Since functions start executing before their future is returned the execution may
+//!   already be stopped before the future is polled the first time.
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use futures::Async;
+
+use crate::base::{
+    pos::Line,
+    resolve::remove_aliases_cow,
+    types::{arg_iter, remove_forall, ArcType, NullInterner, Type, TypeExt},
+};
+
+use crate::{
+    api::ValueRef,
+    thread::{DebugInfo, HookFlags, StackInfo, Thread, ThreadInternal},
+    value::ValuePrinter,
+    Result, Variants,
+};
+
+/// A location where execution should stop
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Breakpoint {
+    /// Stops when execution reaches `line` in the source named `source_name`
+    Line { source_name: String, line: Line },
+    /// Stops when a function named `name` is called
+    Function(String),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Line { source_name, line } => {
+                write!(f, "{}:{}", source_name, line.number())
+            }
+            Breakpoint::Function(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Parses `SOURCE:LINE` (where the line starts at 1) as a line breakpoint and anything else as a
+/// function breakpoint
+impl FromStr for Breakpoint {
+    type Err = String;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() || s.contains(char::is_whitespace) {
+            return Err(format!(
+                "Expected `SOURCE:LINE` or a function name, found `{}`",
+                s
+            ));
+        }
+        if let Some(i) = s.rfind(':') {
+            if let Ok(line) = s[i + 1..].parse::<u32>() {
+                if line == 0 || i == 0 {
+                    return Err(format!("Invalid line breakpoint `{}`", s));
+                }
+                return Ok(Breakpoint::Line {
+                    source_name: s[..i].to_string(),
+                    line: Line::from(line - 1),
+                });
+            }
+        }
+        Ok(Breakpoint::Function(s.to_string()))
+    }
+}
+
+/// How execution should continue after a stop
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Step {
+    /// Runs until the next breakpoint
+    #[default]
+    Continue,
+    /// Stops at the next line, entering called functions
+    In,
+    /// Stops at the next line of the current function, or of a caller if the function returns
+    Over,
+    /// Stops at the next line executed after the current function has returned
+    Out,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// Hit the breakpoint with the contained id
+    Breakpoint(usize),
+    /// Finished a step
+    Step,
+}
+
+/// Describes where and why execution was stopped
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stop {
+    pub reason: StopReason,
+    pub source_name: String,
+    pub line: Option<Line>,
+    pub function_name: Option<String>,
+    /// The number of frames on the stack when execution stopped
+    pub depth: usize,
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Stopped at {}", self.source_name)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line.number())?;
+        }
+        if let Some(ref function_name) = self.function_name {
+            write!(f, " in {}", function_name)?;
+        }
+        match self.reason {
+            StopReason::Breakpoint(id) => write!(f, " (breakpoint {})", id),
+            StopReason::Step => Ok(()),
+        }
+    }
+}
+
+/// A variable which is visible in a stack frame
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub typ: ArcType,
+    /// The value of the variable, formatted with `ValuePrinter`
+    pub value: String,
+}
+
+/// Called when execution stops, returning how the execution should resume
+pub type StopHandler = Box<dyn FnMut(&Debugger, &Thread, &DebugInfo, &Stop) -> Step + Send + Sync>;
+
+#[derive(Default)]
+struct State {
+    breakpoints: Vec<(usize, Breakpoint)>,
+    next_id: usize,
+    step: Step,
+    // The stack depth when `step` was requested
+    step_depth: usize,
+    stop: Option<Stop>,
+    // Set when the execution is suspended since resuming a function which were suspended before
+    // executing its first instruction runs the call hook a second time
+    suspended: Option<Stop>,
+}
+
+impl State {
+    fn resume(&mut self, step: Step) {
+        self.step_depth = self.stop.take().map_or(usize::MAX, |stop| stop.depth);
+        self.step = step;
+    }
+
+    fn check(&mut self, info: &DebugInfo) -> Option<Stop> {
+        let frame = info.stack_info(0)?;
+        let depth = info.stack_info_len();
+        let state = info.state();
+
+        let suspended = self.suspended.take();
+        let reason = if state.contains(HookFlags::CALL_FLAG) {
+            let function_name = frame.function_name()?;
+            if let Some(suspended) = suspended {
+                if suspended.depth == depth
+                    && suspended.line == frame.line()
+                    && suspended.function_name.as_ref().map(|s| &s[..]) == Some(function_name)
+                {
+                    return None;
+                }
+            }
+            self.breakpoints
+                .iter()
+                .find(|(_, breakpoint)| match breakpoint {
+                    Breakpoint::Function(name) => name == function_name,
+                    Breakpoint::Line { .. } => false,
+                })
+                .map(|&(id, _)| StopReason::Breakpoint(id))?
+        } else {
+            let line = frame.line()?;
+            let source_name = frame.source_name();
+            let breakpoint = self
+                .breakpoints
+                .iter()
+                .find(|(_, breakpoint)| match breakpoint {
+                    Breakpoint::Line {
+                        source_name: name,
+                        line: l,
+                    } => *l == line && name == source_name,
+                    Breakpoint::Function(_) => false,
+                });
+            match breakpoint {
+                Some(&(id, _)) => StopReason::Breakpoint(id),
+                None => {
+                    let finished = match self.step {
+                        Step::Continue => false,
+                        Step::In => true,
+                        Step::Over => depth <= self.step_depth,
+                        Step::Out => depth < self.step_depth,
+                    };
+                    if !finished {
+                        return None;
+                    }
+                    StopReason::Step
+                }
+            }
+        };
+
+        let stop = Stop {
+            reason,
+            source_name: frame.source_name().to_string(),
+            line: frame.line(),
+            function_name: frame.function_name().map(|s| s.to_string()),
+            depth,
+        };
+        self.step = Step::Continue;
+        self.stop = Some(stop.clone());
+        self.suspended = Some(stop.clone());
+        Some(stop)
+    }
+}
+
+/// A step debugger which can be attached to one or more threads. Cloning a `Debugger` returns a
+/// handle to the same breakpoints and stepping state.
+#[derive(Clone, Default)]
+pub struct Debugger {
+    state: Arc<Mutex<State>>,
+    handler: Arc<Mutex<Option<StopHandler>>>,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger::default()
+    }
+
+    /// Installs the hook of this debugger on `thread`, replacing any previous hook
+    pub fn attach(&self, thread: &Thread) {
+        let debugger = self.clone();
+        let mut context = thread.context();
+        context.set_hook(Some(Box::new(move |thread, info| {
+            debugger.on_event(thread, info)
+        })));
+        context.set_hook_mask(HookFlags::LINE_FLAG | HookFlags::CALL_FLAG);
+    }
+
+    /// Removes the hook of any debugger from `thread`
+    pub fn detach(thread: &Thread) {
+        let mut context = thread.context();
+        context.set_hook(None);
+        context.set_hook_mask(HookFlags::empty());
+    }
+
+    /// Sets the handler which is called whenever execution stops. If no handler is set, the
+    /// execution is suspended instead.
+    ///
+    /// The handler must not call `set_stop_handler` itself.
+    pub fn set_stop_handler(&self, handler: Option<StopHandler>) -> Option<StopHandler> {
+        std::mem::replace(&mut *self.handler.lock().unwrap(), handler)
+    }
+
+    /// Adds `breakpoint`, returning an id which can be used to remove it again
+    pub fn add_breakpoint(&self, breakpoint: Breakpoint) -> usize {
+        let mut state = self.state.lock().unwrap();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.breakpoints.push((id, breakpoint));
+        id
+    }
+
+    /// Removes the breakpoint with `id`, returning `false` if it did not exist
+    pub fn remove_breakpoint(&self, id: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let len = state.breakpoints.len();
+        state.breakpoints.retain(|&(i, _)| i != id);
+        len != state.breakpoints.len()
+    }
+
+    /// Returns all breakpoints together with their ids
+    pub fn breakpoints(&self) -> Vec<(usize, Breakpoint)> {
+        self.state.lock().unwrap().breakpoints.clone()
+    }
+
+    /// Returns `true` if the debugger has any breakpoints or a step in progress and therefore
+    /// needs to be attached to the threads that run
+    pub fn is_active(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        !state.breakpoints.is_empty() || state.step != Step::Continue
+    }
+
+    /// Returns where the execution is stopped, if it is stopped
+    pub fn stopped(&self) -> Option<Stop> {
+        self.state.lock().unwrap().stop.clone()
+    }
+
+    /// Sets how execution resumes the next time it is run. The depth of `Step::Over` and
+    /// `Step::Out` is relative to the current stop, if there is no stop they act as `Step::In`.
+    pub fn resume(&self, step: Step) {
+        self.state.lock().unwrap().resume(step)
+    }
+
+    fn on_event(&self, thread: &Thread, info: DebugInfo) -> Result<Async<()>> {
+        let stop = match self.state.lock().unwrap().check(&info) {
+            Some(stop) => stop,
+            None => return Ok(Async::Ready(())),
+        };
+        let step = match *self.handler.lock().unwrap() {
+            Some(ref mut handler) => handler(self, thread, &info, &stop),
+            None => return Ok(Async::NotReady),
+        };
+
+        let mut state = self.state.lock().unwrap();
+        // Execution continues directly so the call hook will not run a second time
+        state.suspended = None;
+        state.resume(step);
+        Ok(Async::Ready(()))
+    }
+}
+
+/// Formats `value` for display in a debugger
+pub fn format_value(thread: &Thread, typ: &ArcType, value: Variants) -> String {
+    let env = thread.get_env();
+    let debug_level = thread.global_env().get_debug_level();
+    match value.as_ref() {
+        // Avoid formatting the entire environment of modules (such as the implicit prelude)
+        ValueRef::Data(data) if data.len() > 50 => format!("<{} fields>", data.len()),
+        _ => ValuePrinter::new(&env, typ, value, &debug_level)
+            .width(80)
+            .max_level(3)
+            .to_string(),
+    }
+}
+
+/// Returns the values contained in `value` together with a name and type for each of them. These
+/// are the fields of a record, the arguments of a variant or the elements of an array.
+pub fn children<'a>(
+    thread: &Thread,
+    typ: &ArcType,
+    value: Variants<'a>,
+) -> Vec<(String, ArcType, Variants<'a>)> {
+    let env = thread.get_env();
+    let typ = remove_aliases_cow(&env, &mut NullInterner, remove_forall(typ));
+    match value.as_ref() {
+        ValueRef::Data(data) => match **typ {
+            Type::Record(ref row) => row
+                .row_iter()
+                .zip(data.iter())
+                .map(|(field, value)| {
+                    (
+                        field.name.declared_name().to_string(),
+                        field.typ.clone(),
+                        value,
+                    )
+                })
+                .collect(),
+            Type::Variant(ref row) => {
+                let args = row
+                    .row_iter()
+                    .nth(data.tag() as usize)
+                    .map(|field| arg_iter(&field.typ).cloned().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                data.iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let typ = args.get(i).cloned().unwrap_or_else(Type::hole);
+                        (i.to_string(), typ, value)
+                    })
+                    .collect()
+            }
+            // The type is not known (the value may be generic) but records still carry the
+            // names of their fields
+            _ if data.field_names().next().is_some() => {
+                let mut names = data
+                    .field_names()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+                    .into_iter()
+                    .filter_map(|name| {
+                        let value = data.lookup_field(thread, &name)?;
+                        Some((name, Type::hole(), value))
+                    })
+                    .collect()
+            }
+            _ => data
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (i.to_string(), Type::hole(), value))
+                .collect(),
+        },
+        ValueRef::Array(array) => {
+            let element_type = match **typ {
+                Type::App(_, ref args) if args.len() == 1 => args[0].clone(),
+                _ => Type::hole(),
+            };
+            array
+                .as_ref()
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (format!("[{}]", i), element_type.clone(), value))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Returns whether `children` would return any values for `value` without collecting them.
+pub fn has_children(value: Variants) -> bool {
+    match value.as_ref() {
+        ValueRef::Data(data) => data.len() != 0,
+        ValueRef::Array(array) => !array.is_empty(),
+        _ => false,
+    }
+}
+
+fn variable(thread: &Thread, name: &str, typ: &ArcType, value: Variants) -> Variable {
+    Variable {
+        name: name.to_string(),
+        typ: typ.clone(),
+        value: format_value(thread, typ, value),
+    }
+}
+
+/// Returns the local variables in scope at `frame`
+pub fn locals(thread: &Thread, frame: &StackInfo) -> Vec<Variable> {
+    frame
+        .local_values()
+        .map(|(local, value)| variable(thread, local.name.declared_name(), &local.typ, value))
+        .collect()
+}
+
+/// Returns the variables captured by the closure executing at `frame`
+pub fn upvars(thread: &Thread, frame: &StackInfo) -> Vec<Variable> {
+    frame
+        .upvar_values()
+        .map(|(upvar, value)| variable(thread, &upvar.name, &upvar.typ, value))
+        .collect()
+}
@@ -0,0 +1,93 @@
+use crate::base::{
+    ast::{AstType, Expr, ExprField, Pattern, TypeBinding, TypedIdent, ValueBinding},
+    pos,
+    symbol::{Symbol, Symbols},
+    types::{remove_forall, row_iter, Type},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate(
+    symbols: &mut Symbols,
+    bind: &TypeBinding<Symbol>,
+) -> Result<ValueBinding<Symbol>, Error> {
+    let span = bind.name.span;
+
+    let default_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Record(ref row) => {
+            let exprs = row_iter(row)
+                .map(|field| {
+                    if is_self_type(&bind.alias.value.name, &field.typ) {
+                        return Err(Error::message(format!(
+                            "Unable to derive Default for `{}` as the field `{}` contains the type itself",
+                            bind.alias.value.name.declared_name(),
+                            field.name.declared_name(),
+                        )));
+                    }
+                    Ok(ExprField {
+                        metadata: Default::default(),
+                        name: pos::spanned(span, field.name.clone()),
+                        value: Some(ident(span, symbols.simple_symbol("default"))),
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+            Expr::Record {
+                typ: Type::hole(),
+                types: Vec::new(),
+                exprs,
+                base: None,
+            }
+        }
+        Type::Variant(_) => {
+            return Err(Error::message(format!(
+                "Unable to derive Default for `{}` as it is not a record, there is no way to pick a default variant",
+                bind.alias.value.name.declared_name()
+            )));
+        }
+        _ => return Err(Error::message("Unable to derive Default for this type")),
+    };
+
+    let self_type: AstType<_> = Type::app(
+        Type::ident(bind.alias.value.name.clone()),
+        bind.alias
+            .value
+            .params()
+            .iter()
+            .cloned()
+            .map(Type::generic)
+            .collect(),
+    );
+
+    let export_expr = pos::spanned(
+        span,
+        Expr::Record {
+            typ: Type::hole(),
+            types: Vec::new(),
+            exprs: vec![ExprField {
+                metadata: Default::default(),
+                name: pos::spanned(span, symbols.simple_symbol("default")),
+                value: Some(pos::spanned(span, default_expr)),
+            }],
+            base: None,
+        },
+    );
+
+    let default_import = generate_import(span, symbols, &[], &["default"], "std.default");
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(symbols.simple_symbol(format!(
+                "default_{}",
+                bind.alias.value.name.declared_name()
+            )))),
+        ),
+        args: Vec::new(),
+        expr: pos::spanned(span, Expr::let_binding(default_import, export_expr)),
+        metadata: Default::default(),
+        typ: Some(binding_type(symbols, "Default", self_type, bind)),
+        resolved_type: Type::hole(),
+    })
+}
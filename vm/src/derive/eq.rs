@@ -1,7 +1,7 @@
 use crate::base::{
     ast::{
-        Alternative, Argument, AstType, Expr, ExprField, Pattern, TypeBinding, TypedIdent,
-        ValueBinding,
+        Alternative, Argument, AstType, Expr, ExprField, Pattern, SpannedExpr, TypeBinding,
+        TypedIdent, ValueBinding,
     },
     pos,
     symbol::{Symbol, Symbols},
@@ -18,6 +18,45 @@ pub fn generate(
 ) -> Result<ValueBinding<Symbol>, Error> {
     let span = bind.name.span;
 
+    let eq_record_expr = generate_eq_record(symbols, bind)?;
+
+    let self_type: AstType<_> = Type::app(
+        Type::ident(bind.alias.value.name.clone()),
+        bind.alias
+            .value
+            .params()
+            .iter()
+            .cloned()
+            .map(Type::generic)
+            .collect(),
+    );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(derived_eq_name(symbols, bind))),
+        ),
+        args: Vec::new(),
+        expr: eq_record_expr,
+        metadata: Default::default(),
+        typ: Some(binding_type(symbols, "Eq", self_type, bind)),
+        resolved_type: Type::hole(),
+    })
+}
+
+/// The name of the binding which `generate` creates for `bind`.
+pub(super) fn derived_eq_name(symbols: &mut Symbols, bind: &TypeBinding<Symbol>) -> Symbol {
+    symbols.simple_symbol(format!("eq_{}", bind.alias.value.name.declared_name()))
+}
+
+/// Generates an expression for the `Eq` record of `bind`, without the binding itself so that it
+/// can be reused by other derives which contain an `Eq` record (such as `Hash`).
+pub(super) fn generate_eq_record(
+    symbols: &mut Symbols,
+    bind: &TypeBinding<Symbol>,
+) -> Result<SpannedExpr<Symbol>, Error> {
+    let span = bind.name.span;
+
     let eq = TypedIdent::new(symbols.simple_symbol("eq"));
     let l = Symbol::from("l");
     let r = Symbol::from("r");
@@ -65,6 +104,7 @@ pub fn generate(
                     span,
                     Pattern::Ident(TypedIdent::new(symbols.simple_symbol("_"))),
                 ),
+                guard: None,
                 expr: ident(span, symbols.simple_symbol("False")),
             };
 
@@ -110,6 +150,7 @@ pub fn generate(
                                 ],
                             },
                         ),
+                        guard: None,
                         expr,
                     }
                 })
@@ -165,6 +206,7 @@ pub fn generate(
                             typ: Type::hole(),
                         },
                     ),
+                    guard: None,
                     expr,
                 }],
             )
@@ -213,18 +255,5 @@ pub fn generate(
         ),
     );
 
-    Ok(ValueBinding {
-        name: pos::spanned(
-            span,
-            Pattern::Ident(TypedIdent::new(symbols.simple_symbol(format!(
-                "eq_{}",
-                bind.alias.value.name.declared_name()
-            )))),
-        ),
-        args: Vec::new(),
-        expr: pos::spanned(span, eq_record_expr),
-        metadata: Default::default(),
-        typ: Some(binding_type(symbols, "Eq", self_type, bind)),
-        resolved_type: Type::hole(),
-    })
+    Ok(pos::spanned(span, eq_record_expr))
 }
@@ -0,0 +1,327 @@
+use crate::base::{
+    ast::{
+        Alternative, Argument, AstType, Expr, ExprField, Lambda, Pattern, SpannedExpr, TypeBinding,
+        TypedIdent, ValueBinding,
+    },
+    pos::{self, BytePos, Span},
+    symbol::{Symbol, Symbols},
+    types::{ctor_args, remove_forall, row_iter, walk_type, ArgType, Generic, Type},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate(
+    symbols: &mut Symbols,
+    bind: &TypeBinding<Symbol>,
+) -> Result<ValueBinding<Symbol>, Error> {
+    let span = bind.name.span;
+
+    let params = bind.alias.value.params();
+    let (param, rest_params) = params.split_last().ok_or_else(|| {
+        Error::message(format!(
+            "Unable to derive Functor for `{}` as it does not have any type parameters",
+            bind.alias.value.name.declared_name()
+        ))
+    })?;
+
+    let f = Symbol::from("f");
+    let x = Symbol::from("x");
+    let map_fn = TypedIdent::new(symbols.simple_symbol("map_"));
+
+    let mut mapper = Mapper {
+        symbols,
+        span,
+        self_name: &bind.alias.value.name,
+        param,
+        f: &f,
+        map_fn: &map_fn.name,
+    };
+
+    let map_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Variant(ref variants) => {
+            let alts = row_iter(variants)
+                .map(|variant| {
+                    let pattern_args: Vec<_> = ctor_args(&variant.typ)
+                        .enumerate()
+                        .map(|(i, typ)| (typ, TypedIdent::new(Symbol::from(format!("arg_{}", i)))))
+                        .collect();
+
+                    let args = pattern_args
+                        .iter()
+                        .map(|(typ, arg)| mapper.map_value(typ, ident(span, arg.name.clone())))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let expr = if args.is_empty() {
+                        ident(span, variant.name.clone())
+                    } else {
+                        apply(span, ident(span, variant.name.clone()), args)
+                    };
+
+                    Ok(Alternative {
+                        pattern: pos::spanned(
+                            span,
+                            Pattern::Constructor(
+                                TypedIdent::new(variant.name.clone()),
+                                pattern_args
+                                    .into_iter()
+                                    .map(|(_, arg)| pos::spanned(span, Pattern::Ident(arg)))
+                                    .collect(),
+                            ),
+                        ),
+                        guard: None,
+                        expr,
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+            Expr::Match(Box::new(ident(span, x.clone())), alts)
+        }
+        Type::Record(ref row) => {
+            let field_symbols: Vec<_> = row_iter(row)
+                .map(|field| TypedIdent::new(Symbol::from(field.name.declared_name())))
+                .collect();
+
+            let exprs = row_iter(row)
+                .zip(&field_symbols)
+                .map(|(field, symbol)| {
+                    Ok(ExprField {
+                        metadata: Default::default(),
+                        name: pos::spanned(span, field.name.clone()),
+                        value: Some(
+                            mapper.map_value(&field.typ, ident(span, symbol.name.clone()))?,
+                        ),
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+
+            Expr::Match(
+                Box::new(ident(span, x.clone())),
+                vec![Alternative {
+                    pattern: generate_record_pattern(span, row, field_symbols),
+                    guard: None,
+                    expr: pos::spanned(
+                        span,
+                        Expr::Record {
+                            typ: Type::hole(),
+                            types: Vec::new(),
+                            exprs,
+                            base: None,
+                        },
+                    ),
+                }],
+            )
+        }
+        _ => return Err(Error::message("Unable to derive Functor for this type")),
+    };
+
+    // `map_` needs a type annotation as the `Functor` instances used for the fields can't be
+    // resolved otherwise
+    let mapped_param = Generic {
+        id: symbols.simple_symbol(format!("{}_mapped", param.id.declared_name())),
+        kind: param.kind.clone(),
+    };
+    let self_type = |last: AstType<Symbol>| -> AstType<Symbol> {
+        Type::app(
+            Type::ident(bind.alias.value.name.clone()),
+            rest_params
+                .iter()
+                .cloned()
+                .map(Type::generic)
+                .chain(Some(last))
+                .collect(),
+        )
+    };
+
+    let map_binding = ValueBinding {
+        name: pos::spanned(span, Pattern::Ident(map_fn.clone())),
+        args: [f, x]
+            .iter()
+            .map(|arg| Argument::explicit(pos::spanned(span, TypedIdent::new(arg.clone()))))
+            .collect(),
+        expr: pos::spanned(span, map_expr),
+        metadata: Default::default(),
+        typ: Some(Type::forall(
+            vec![param.clone(), mapped_param.clone()],
+            Type::function(
+                vec![
+                    Type::function(
+                        vec![Type::generic(param.clone())],
+                        Type::generic(mapped_param.clone()),
+                    ),
+                    self_type(Type::generic(param.clone())),
+                ],
+                self_type(Type::generic(mapped_param)),
+            ),
+        )),
+        resolved_type: Type::hole(),
+    };
+
+    let export_expr = pos::spanned(
+        span,
+        Expr::Record {
+            typ: Type::hole(),
+            types: Vec::new(),
+            exprs: vec![ExprField {
+                metadata: Default::default(),
+                name: pos::spanned(span, symbols.simple_symbol("map")),
+                value: Some(ident(span, map_fn.name.clone())),
+            }],
+            base: None,
+        },
+    );
+
+    let functor_import = generate_import(span, symbols, &[], &["map"], "std.functor");
+
+    let functor_record_expr = pos::spanned(
+        span,
+        Expr::let_binding(
+            functor_import,
+            pos::spanned(span, Expr::rec_let_bindings(vec![map_binding], export_expr)),
+        ),
+    );
+
+    // `Functor` is implemented for the type applied to every parameter except the last
+    let functor_type: AstType<_> = Type::app(
+        Type::ident(symbols.simple_symbol("Functor")),
+        collect![Type::app(
+            Type::ident(bind.alias.value.name.clone()),
+            rest_params.iter().cloned().map(Type::generic).collect(),
+        )],
+    );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(symbols.simple_symbol(format!(
+                "functor_{}",
+                bind.alias.value.name.declared_name()
+            )))),
+        ),
+        args: Vec::new(),
+        expr: functor_record_expr,
+        metadata: Default::default(),
+        typ: Some(functor_type),
+        resolved_type: Type::hole(),
+    })
+}
+
+struct Mapper<'a> {
+    symbols: &'a mut Symbols,
+    span: Span<BytePos>,
+    self_name: &'a Symbol,
+    param: &'a Generic<Symbol>,
+    f: &'a Symbol,
+    map_fn: &'a Symbol,
+}
+
+impl Mapper<'_> {
+    fn contains_param(&self, typ: &AstType<Symbol>) -> bool {
+        let mut found = false;
+        walk_type(typ, |typ: &AstType<Symbol>| match **typ {
+            Type::Generic(ref gen) if gen.id.declared_name() == self.param.id.declared_name() => {
+                found = true
+            }
+            _ => (),
+        });
+        found
+    }
+
+    /// Maps `value` of type `typ`, leaving it as is if `typ` does not contain the mapped parameter
+    fn map_value(
+        &mut self,
+        typ: &AstType<Symbol>,
+        value: SpannedExpr<Symbol>,
+    ) -> Result<SpannedExpr<Symbol>, Error> {
+        Ok(match self.mapping_function(typ)? {
+            Some(function) => apply(self.span, function, vec![value]),
+            None => value,
+        })
+    }
+
+    /// Returns a function which maps a value of type `typ`, or `None` if `typ` does not contain the
+    /// mapped parameter.
+    fn mapping_function(
+        &mut self,
+        typ: &AstType<Symbol>,
+    ) -> Result<Option<SpannedExpr<Symbol>>, Error> {
+        let span = self.span;
+        if !self.contains_param(typ) {
+            return Ok(None);
+        }
+        Ok(Some(match **typ {
+            Type::Generic(_) => ident(span, self.f.clone()),
+
+            // `T a b` is mapped with the `Functor (T a)` instance if only `b` contains the parameter
+            Type::App(ref head, ref args)
+                if !args[..args.len() - 1]
+                    .iter()
+                    .any(|arg| self.contains_param(arg)) =>
+            {
+                let inner = self
+                    .mapping_function(args.last().unwrap())?
+                    .expect("Last argument contains the parameter");
+                let map_function = if is_self_type(self.self_name, head) {
+                    self.map_fn.clone()
+                } else {
+                    self.symbols.simple_symbol("map")
+                };
+                apply(span, ident(span, map_function), vec![paren(span, inner)])
+            }
+
+            // Functions are mapped by mapping their result
+            Type::Function(ArgType::Explicit, ref arg, ref ret) if !self.contains_param(arg) => {
+                let inner = self
+                    .mapping_function(ret)?
+                    .expect("Return type contains the parameter");
+                let g = TypedIdent::new(Symbol::from("g"));
+                let y = TypedIdent::new(Symbol::from("y"));
+                let body = apply(
+                    span,
+                    paren(span, inner),
+                    vec![paren(
+                        span,
+                        apply(
+                            span,
+                            ident(span, g.name.clone()),
+                            vec![ident(span, y.name.clone())],
+                        ),
+                    )],
+                );
+                pos::spanned(
+                    span,
+                    Expr::Lambda(Lambda {
+                        args: vec![
+                            Argument::explicit(pos::spanned(span, g)),
+                            Argument::explicit(pos::spanned(span, y)),
+                        ],
+                        body: Box::new(body),
+                        id: TypedIdent::new(self.symbols.simple_symbol("map_function")),
+                    }),
+                )
+            }
+
+            _ => {
+                return Err(Error::message(format!(
+                    "Unable to derive Functor for `{}` as `{}` is used in a position which can't be mapped over",
+                    self.self_name.declared_name(),
+                    self.param.id.declared_name(),
+                )));
+            }
+        }))
+    }
+}
+
+fn apply(
+    span: Span<BytePos>,
+    func: SpannedExpr<Symbol>,
+    args: Vec<SpannedExpr<Symbol>>,
+) -> SpannedExpr<Symbol> {
+    pos::spanned(
+        span,
+        Expr::App {
+            func: Box::new(func),
+            implicit_args: Vec::new(),
+            args,
+        },
+    )
+}
@@ -0,0 +1,195 @@
+use crate::base::{
+    ast::{
+        Alternative, Argument, AstType, Expr, ExprField, Literal, Pattern, TypeBinding, TypedIdent,
+        ValueBinding,
+    },
+    pos,
+    symbol::{Symbol, Symbols},
+    types::{ctor_args, remove_forall, row_iter, Type},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate(
+    symbols: &mut Symbols,
+    bind: &TypeBinding<Symbol>,
+    derives_eq: bool,
+) -> Result<ValueBinding<Symbol>, Error> {
+    let span = bind.name.span;
+
+    let x = Symbol::from("x");
+    let hash_fn = TypedIdent::new(symbols.simple_symbol("hash_"));
+
+    // Combines the hashes of each field into `seed`
+    let generate_combine =
+        |symbols: &mut Symbols, seed: i64, fields: &[(bool, TypedIdent<Symbol>)]| {
+            fields.iter().fold(
+                pos::spanned(span, Expr::Literal(Literal::Int(seed))),
+                |acc, &(self_type, ref field)| {
+                    let hash_function = if self_type {
+                        hash_fn.name.clone()
+                    } else {
+                        symbols.simple_symbol("hash")
+                    };
+                    app(
+                        span,
+                        symbols.simple_symbol("combine"),
+                        vec![
+                            paren(span, acc),
+                            paren(
+                                span,
+                                app(span, hash_function, vec![ident(span, field.name.clone())]),
+                            ),
+                        ],
+                    )
+                },
+            )
+        };
+
+    let hash_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Variant(ref variants) => {
+            let alts = row_iter(variants)
+                .enumerate()
+                .map(|(i, variant)| {
+                    let pattern_args: Vec<_> = ctor_args(&variant.typ)
+                        .enumerate()
+                        .map(|(j, field)| {
+                            (
+                                is_self_type(&bind.alias.value.name, field),
+                                TypedIdent::new(Symbol::from(format!("arg_{}", j))),
+                            )
+                        })
+                        .collect();
+
+                    // Use the index of the constructor as the seed so that constructors without
+                    // any arguments get distinct hashes
+                    let expr = generate_combine(symbols, i as i64, &pattern_args);
+
+                    Alternative {
+                        pattern: pos::spanned(
+                            span,
+                            Pattern::Constructor(
+                                TypedIdent::new(variant.name.clone()),
+                                pattern_args
+                                    .into_iter()
+                                    .map(|(_, arg)| pos::spanned(span, Pattern::Ident(arg)))
+                                    .collect(),
+                            ),
+                        ),
+                        guard: None,
+                        expr,
+                    }
+                })
+                .collect();
+            Expr::Match(Box::new(ident(span, x.clone())), alts)
+        }
+        Type::Record(ref row) => {
+            let field_symbols: Vec<_> = row_iter(row)
+                .map(|field| {
+                    (
+                        is_self_type(&bind.alias.value.name, &field.typ),
+                        TypedIdent::new(Symbol::from(field.name.declared_name())),
+                    )
+                })
+                .collect();
+
+            let expr = generate_combine(symbols, 0, &field_symbols);
+            Expr::Match(
+                Box::new(ident(span, x.clone())),
+                vec![Alternative {
+                    pattern: generate_record_pattern(
+                        span,
+                        row,
+                        field_symbols.into_iter().map(|(_, field)| field),
+                    ),
+                    guard: None,
+                    expr,
+                }],
+            )
+        }
+        _ => return Err(Error::message("Unable to derive Hash for this type")),
+    };
+
+    // Reuse the derived `Eq` instance if there is one, as two instances would be ambiguous
+    let eq_record_expr = if derives_eq {
+        ident(span, crate::derive::eq::derived_eq_name(symbols, bind))
+    } else {
+        crate::derive::eq::generate_eq_record(symbols, bind)?
+    };
+
+    let self_type: AstType<_> = Type::app(
+        Type::ident(bind.alias.value.name.clone()),
+        bind.alias
+            .value
+            .params()
+            .iter()
+            .cloned()
+            .map(Type::generic)
+            .collect(),
+    );
+
+    let hash_binding = ValueBinding {
+        name: pos::spanned(span, Pattern::Ident(hash_fn.clone())),
+        args: vec![Argument::explicit(pos::spanned(
+            span,
+            TypedIdent::new(x.clone()),
+        ))],
+        expr: pos::spanned(span, hash_expr),
+        metadata: Default::default(),
+        typ: Some(Type::function(vec![self_type.clone()], Type::int())),
+        resolved_type: Type::hole(),
+    };
+
+    let export_expr = pos::spanned(
+        span,
+        Expr::Record {
+            typ: Type::hole(),
+            types: Vec::new(),
+            exprs: vec![
+                ExprField {
+                    metadata: Default::default(),
+                    name: pos::spanned(span, symbols.simple_symbol("eq")),
+                    value: Some(eq_record_expr),
+                },
+                ExprField {
+                    metadata: Default::default(),
+                    name: pos::spanned(span, symbols.simple_symbol("hash")),
+                    value: Some(ident(span, hash_fn.name.clone())),
+                },
+            ],
+            base: None,
+        },
+    );
+
+    let types_import = generate_import(span, symbols, &["Bool"], &[], "std.types");
+    let cmp_import = generate_import(span, symbols, &[], &["=="], "std.cmp");
+    let hash_import = generate_import(span, symbols, &[], &["hash", "combine"], "std.hash");
+
+    let hash_record_expr = vec![types_import, cmp_import, hash_import]
+        .into_iter()
+        .rev()
+        .fold(
+            pos::spanned(
+                span,
+                Expr::rec_let_bindings(vec![hash_binding], export_expr),
+            ),
+            |expr, bind| pos::spanned(span, Expr::let_binding(bind, expr)),
+        );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(symbols.simple_symbol(format!(
+                "hash_{}",
+                bind.alias.value.name.declared_name()
+            )))),
+        ),
+        args: Vec::new(),
+        expr: hash_record_expr,
+        metadata: Default::default(),
+        typ: Some(binding_type(symbols, "Hash", self_type, bind)),
+        resolved_type: Type::hole(),
+    })
+}
@@ -9,8 +9,12 @@ use crate::base::types::{row_iter, Type};
 
 use crate::macros::Error;
 
+mod default;
 mod deserialize;
 mod eq;
+mod functor;
+mod hash;
+mod ord;
 mod serialize;
 mod show;
 
@@ -20,24 +24,30 @@ pub fn generate(
     bind: &TypeBinding<Symbol>,
 ) -> Result<Vec<ValueBinding<Symbol>>, Error> {
     match derive.arguments {
-        Some(ref args) => args
-            .split(',')
-            .map(|s| s.trim())
-            .map(|arg| {
-                Ok(match arg {
-                    "Eq" => eq::generate(symbols, bind),
-                    "Show" => show::generate(symbols, bind),
-                    "Deserialize" => deserialize::generate(symbols, bind),
-                    "Serialize" => serialize::generate(symbols, bind),
-                    _ => {
-                        return Err(Error::message(format!(
-                            "`{}` is not a type that can be derived",
-                            arg
-                        )));
-                    }
+        Some(ref args) => {
+            let derives_eq = args.split(',').any(|s| s.trim() == "Eq");
+            args.split(',')
+                .map(|s| s.trim())
+                .map(|arg| {
+                    Ok(match arg {
+                        "Eq" => eq::generate(symbols, bind),
+                        "Ord" => ord::generate(symbols, bind, derives_eq),
+                        "Hash" => hash::generate(symbols, bind, derives_eq),
+                        "Show" => show::generate(symbols, bind),
+                        "Functor" => functor::generate(symbols, bind),
+                        "Default" => default::generate(symbols, bind),
+                        "Deserialize" => deserialize::generate(symbols, bind),
+                        "Serialize" => serialize::generate(symbols, bind),
+                        _ => {
+                            return Err(Error::message(format!(
+                                "`{}` is not a type that can be derived",
+                                arg
+                            )));
+                        }
+                    })
                 })
-            })
-            .collect::<Result<_, _>>()?,
+                .collect::<Result<_, _>>()?
+        }
         _ => Err(Error::message("Invalid `derive` attribute")),
     }
 }
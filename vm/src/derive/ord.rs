@@ -0,0 +1,344 @@
+use crate::base::{
+    ast::{
+        Alternative, Argument, AstType, Expr, ExprField, Lambda, Pattern, SpannedPattern,
+        TypeBinding, TypedIdent, ValueBinding,
+    },
+    pos::{self, BytePos, Span},
+    symbol::{Symbol, Symbols},
+    types::{ctor_args, remove_forall, row_iter, Type},
+};
+
+use crate::macros::Error;
+
+use crate::derive::*;
+
+pub fn generate(
+    symbols: &mut Symbols,
+    bind: &TypeBinding<Symbol>,
+    derives_eq: bool,
+) -> Result<ValueBinding<Symbol>, Error> {
+    let span = bind.name.span;
+
+    let compare_fn = TypedIdent::new(symbols.simple_symbol("compare_"));
+    let l = Symbol::from("l");
+    let r = Symbol::from("r");
+
+    let matcher = || {
+        Box::new(pos::spanned(
+            span,
+            Expr::Tuple {
+                typ: Type::hole(),
+                elems: vec![ident(span, l.clone()), ident(span, r.clone())],
+            },
+        ))
+    };
+
+    // Compares each pair of fields in order, stopping at the first pair which is not equal
+    let generate_compare_chain =
+        |symbols: &mut Symbols, fields: &[(bool, TypedIdent<Symbol>, TypedIdent<Symbol>)]| {
+            fields
+                .iter()
+                .rev()
+                .fold(None, |acc, &(self_type, ref l, ref r)| {
+                    let compare_function = if self_type {
+                        compare_fn.name.clone()
+                    } else {
+                        symbols.simple_symbol("compare")
+                    };
+                    let compare = app(
+                        span,
+                        compare_function,
+                        vec![ident(span, l.name.clone()), ident(span, r.name.clone())],
+                    );
+                    Some(match acc {
+                        Some(rest) => {
+                            let ordering = TypedIdent::new(Symbol::from("ordering"));
+                            pos::spanned(
+                                span,
+                                Expr::Match(
+                                    Box::new(compare),
+                                    vec![
+                                        Alternative {
+                                            pattern: pos::spanned(
+                                                span,
+                                                Pattern::Constructor(
+                                                    TypedIdent::new(symbols.simple_symbol("EQ")),
+                                                    Vec::new(),
+                                                ),
+                                            ),
+                                            guard: None,
+                                            expr: rest,
+                                        },
+                                        Alternative {
+                                            pattern: pos::spanned(
+                                                span,
+                                                Pattern::Ident(ordering.clone()),
+                                            ),
+                                            guard: None,
+                                            expr: ident(span, ordering.name),
+                                        },
+                                    ],
+                                ),
+                            )
+                        }
+                        None => compare,
+                    })
+                })
+                .unwrap_or_else(|| ident(span, symbols.simple_symbol("EQ")))
+        };
+
+    let comparison_expr = match **remove_forall(bind.alias.value.unresolved_type()) {
+        Type::Variant(ref variants) => {
+            let variants: Vec<_> = row_iter(variants).collect();
+            let mut alts = Vec::new();
+            for (i, variant) in variants.iter().enumerate() {
+                let fields: Vec<_> = ctor_args(&variant.typ)
+                    .map(|field| {
+                        (
+                            is_self_type(&bind.alias.value.name, field),
+                            TypedIdent::new(Symbol::from("arg_l")),
+                            TypedIdent::new(Symbol::from("arg_r")),
+                        )
+                    })
+                    .collect();
+
+                let ctor_pattern = |pattern_args: Vec<TypedIdent<Symbol>>| {
+                    pos::spanned(
+                        span,
+                        Pattern::Constructor(
+                            TypedIdent::new(variant.name.clone()),
+                            pattern_args
+                                .into_iter()
+                                .map(|arg| pos::spanned(span, Pattern::Ident(arg)))
+                                .collect(),
+                        ),
+                    )
+                };
+                let wildcard_ctor_pattern = |symbols: &mut Symbols| {
+                    ctor_pattern(
+                        fields
+                            .iter()
+                            .map(|_| TypedIdent::new(symbols.simple_symbol("_")))
+                            .collect(),
+                    )
+                };
+
+                alts.push(Alternative {
+                    pattern: tuple_pattern(
+                        span,
+                        ctor_pattern(fields.iter().map(|t| t.1.clone()).collect()),
+                        ctor_pattern(fields.iter().map(|t| t.2.clone()).collect()),
+                    ),
+                    guard: None,
+                    expr: generate_compare_chain(symbols, &fields),
+                });
+
+                // Constructors which are declared first are less than the constructors after them
+                if i + 1 != variants.len() {
+                    alts.push(Alternative {
+                        pattern: tuple_pattern(
+                            span,
+                            wildcard_ctor_pattern(symbols),
+                            wildcard_pattern(span, symbols),
+                        ),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("LT")),
+                    });
+                    alts.push(Alternative {
+                        pattern: tuple_pattern(
+                            span,
+                            wildcard_pattern(span, symbols),
+                            wildcard_ctor_pattern(symbols),
+                        ),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("GT")),
+                    });
+                }
+            }
+            Expr::Match(matcher(), alts)
+        }
+        Type::Record(ref row) => {
+            let fields: Vec<_> = row_iter(row)
+                .map(|field| {
+                    (
+                        is_self_type(&bind.alias.value.name, &field.typ),
+                        TypedIdent::new(Symbol::from(format!("{}_l", field.name.declared_name()))),
+                        TypedIdent::new(Symbol::from(format!("{}_r", field.name.declared_name()))),
+                    )
+                })
+                .collect();
+
+            let expr = generate_compare_chain(symbols, &fields);
+            Expr::Match(
+                matcher(),
+                vec![Alternative {
+                    pattern: tuple_pattern(
+                        span,
+                        generate_record_pattern(span, row, fields.iter().map(|t| t.1.clone())),
+                        generate_record_pattern(span, row, fields.iter().map(|t| t.2.clone())),
+                    ),
+                    guard: None,
+                    expr,
+                }],
+            )
+        }
+        _ => return Err(Error::message("Unable to derive Ord for this type")),
+    };
+
+    let self_type: AstType<_> = Type::app(
+        Type::ident(bind.alias.value.name.clone()),
+        bind.alias
+            .value
+            .params()
+            .iter()
+            .cloned()
+            .map(Type::generic)
+            .collect(),
+    );
+
+    let compare_binding = ValueBinding {
+        name: pos::spanned(span, Pattern::Ident(compare_fn.clone())),
+        args: [l.clone(), r.clone()]
+            .iter()
+            .map(|arg| Argument::explicit(pos::spanned(span, TypedIdent::new(arg.clone()))))
+            .collect(),
+        expr: pos::spanned(span, comparison_expr),
+        metadata: Default::default(),
+        typ: Some(Type::function(
+            vec![self_type.clone(), self_type.clone()],
+            Type::ident(symbols.simple_symbol("Ordering")),
+        )),
+        resolved_type: Type::hole(),
+    };
+
+    // Reuse the derived `Eq` instance if there is one, as two instances would be ambiguous.
+    // Otherwise two values are equal if they compare as `EQ`
+    let eq_expr = if derives_eq {
+        ident(span, crate::derive::eq::derived_eq_name(symbols, bind))
+    } else {
+        let eq_l = TypedIdent::new(Symbol::from("l"));
+        let eq_r = TypedIdent::new(Symbol::from("r"));
+        let body = pos::spanned(
+            span,
+            Expr::Match(
+                Box::new(app(
+                    span,
+                    compare_fn.name.clone(),
+                    vec![
+                        ident(span, eq_l.name.clone()),
+                        ident(span, eq_r.name.clone()),
+                    ],
+                )),
+                vec![
+                    Alternative {
+                        pattern: pos::spanned(
+                            span,
+                            Pattern::Constructor(
+                                TypedIdent::new(symbols.simple_symbol("EQ")),
+                                Vec::new(),
+                            ),
+                        ),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("True")),
+                    },
+                    Alternative {
+                        pattern: wildcard_pattern(span, symbols),
+                        guard: None,
+                        expr: ident(span, symbols.simple_symbol("False")),
+                    },
+                ],
+            ),
+        );
+        pos::spanned(
+            span,
+            Expr::Record {
+                typ: Type::hole(),
+                types: Vec::new(),
+                exprs: vec![ExprField {
+                    metadata: Default::default(),
+                    name: pos::spanned(span, symbols.simple_symbol("==")),
+                    value: Some(pos::spanned(
+                        span,
+                        Expr::Lambda(Lambda {
+                            args: vec![
+                                Argument::explicit(pos::spanned(span, eq_l)),
+                                Argument::explicit(pos::spanned(span, eq_r)),
+                            ],
+                            body: Box::new(body),
+                            id: TypedIdent::new(symbols.simple_symbol("eq")),
+                        }),
+                    )),
+                }],
+                base: None,
+            },
+        )
+    };
+
+    let export_expr = pos::spanned(
+        span,
+        Expr::Record {
+            typ: Type::hole(),
+            types: Vec::new(),
+            exprs: vec![
+                ExprField {
+                    metadata: Default::default(),
+                    name: pos::spanned(span, symbols.simple_symbol("eq")),
+                    value: Some(eq_expr),
+                },
+                ExprField {
+                    metadata: Default::default(),
+                    name: pos::spanned(span, symbols.simple_symbol("compare")),
+                    value: Some(ident(span, compare_fn.name.clone())),
+                },
+            ],
+            base: None,
+        },
+    );
+
+    let types_import = generate_import(span, symbols, &["Bool", "Ordering"], &[], "std.types");
+    let cmp_import = generate_import(span, symbols, &[], &["compare"], "std.cmp");
+
+    let ord_record_expr = vec![types_import, cmp_import].into_iter().rev().fold(
+        pos::spanned(
+            span,
+            Expr::rec_let_bindings(vec![compare_binding], export_expr),
+        ),
+        |expr, bind| pos::spanned(span, Expr::let_binding(bind, expr)),
+    );
+
+    Ok(ValueBinding {
+        name: pos::spanned(
+            span,
+            Pattern::Ident(TypedIdent::new(symbols.simple_symbol(format!(
+                "ord_{}",
+                bind.alias.value.name.declared_name()
+            )))),
+        ),
+        args: Vec::new(),
+        expr: ord_record_expr,
+        metadata: Default::default(),
+        typ: Some(binding_type(symbols, "Ord", self_type, bind)),
+        resolved_type: Type::hole(),
+    })
+}
+
+fn tuple_pattern(
+    span: Span<BytePos>,
+    l: SpannedPattern<Symbol>,
+    r: SpannedPattern<Symbol>,
+) -> SpannedPattern<Symbol> {
+    pos::spanned(
+        span,
+        Pattern::Tuple {
+            typ: Type::hole(),
+            elems: vec![l, r],
+        },
+    )
+}
+
+fn wildcard_pattern(span: Span<BytePos>, symbols: &mut Symbols) -> SpannedPattern<Symbol> {
+    pos::spanned(
+        span,
+        Pattern::Ident(TypedIdent::new(symbols.simple_symbol("_"))),
+    )
+}
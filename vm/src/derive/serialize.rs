@@ -85,6 +85,7 @@ pub fn generate(
                     Box::new(ident(span, x.clone())),
                     vec![Alternative {
                         pattern: generate_record_pattern(span, row, field_symbols),
+                        guard: None,
                         expr,
                     }],
                 ),
@@ -141,6 +142,7 @@ pub fn generate(
                     };
                     Ok(Alternative {
                         pattern: ctor_pattern(pattern_args),
+                        guard: None,
                         expr,
                     })
                 })
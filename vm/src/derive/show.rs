@@ -82,6 +82,7 @@ pub fn generate(
                     };
                     Alternative {
                         pattern: ctor_pattern(pattern_args.into_iter().map(|t| t.1).collect()),
+                        guard: None,
                         expr,
                     }
                 })
@@ -141,6 +142,7 @@ pub fn generate(
                 Box::new(ident(span, x.clone())),
                 vec![Alternative {
                     pattern: generate_record_pattern(span, row, field_symbols),
+                    guard: None,
                     expr,
                 }],
             )
@@ -12,13 +12,18 @@ use std::{
     rc::Rc,
     result::Result as StdResult,
     sync::{self, Arc},
+    time::{Duration, Instant},
 };
 
 use crate::{
-    base::fnv::FnvMap, forget_lifetime, interner::InternedStr, types::VmIndex, Error, Result,
+    base::fnv::FnvMap, forget_lifetime, interner::InternedStr, types::VmIndex, value::Userdata,
+    Error, Result,
 };
 
 pub mod mutex;
+pub mod snapshot;
+
+use self::snapshot::{HeapSnapshot, SnapshotBuilder};
 
 #[doc(hidden)]
 #[macro_export]
@@ -205,6 +210,127 @@ impl Generation {
     }
 }
 
+/// Statistics about the allocations and collections done by a `Gc`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GcStats {
+    /// How many collections have been run
+    pub collections: u64,
+    /// How many bytes have been allocated in total, including memory which has since been freed
+    pub allocated_bytes: u64,
+    /// How many bytes have been freed
+    pub freed_bytes: u64,
+    /// How many bytes are currently allocated
+    pub live_bytes: usize,
+    /// The duration of the latest collection, or of the latest step of an incremental collection
+    pub last_pause: Duration,
+    /// The duration of the longest collection or incremental step
+    pub max_pause: Duration,
+    /// The total time spent collecting
+    pub total_pause: Duration,
+    /// The most values visited (marked, found to already be marked or swept) by a single
+    /// collection or step of an incremental collection, including the values of child threads.
+    /// Unlike the pause durations this does not depend on the speed of the machine.
+    pub max_pause_work: usize,
+    /// Allocation statistics for each type of value, sorted by the number of live bytes
+    pub types: Vec<TypeStats>,
+}
+
+/// Allocation statistics for one type of value allocated by a `Gc`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeStats {
+    /// The name of the type. Records are named by their fields, variants by their tag and any other
+    /// value by its Rust type
+    pub name: String,
+    /// How many values of this type have been allocated
+    pub allocations: u64,
+    /// How many bytes have been allocated for values of this type
+    pub allocated_bytes: u64,
+    /// How many values of this type are currently allocated
+    pub live_objects: usize,
+    /// How many bytes are currently allocated for values of this type
+    pub live_bytes: usize,
+}
+
+#[derive(Debug, Default)]
+struct CollectionStats {
+    collections: u64,
+    freed_memory: u64,
+    last_pause: Duration,
+    max_pause: Duration,
+    total_pause: Duration,
+    /// The values visited since the current pause started
+    pause_work: usize,
+    max_pause_work: usize,
+}
+
+/// How a `Gc` collects its values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GcMode {
+    /// Marks and sweeps every value in one pause once the collection limit is reached
+    #[default]
+    StopTheWorld,
+    /// Spreads each collection over many allocations. Every allocation marks or sweeps at most
+    /// `step_size` values (at least 2), which bounds the pauses at the cost of some throughput and
+    /// of values surviving until the collection after the one they became unreachable in.
+    ///
+    /// The last step of marking is not bounded by `step_size`. It traces the roots again, since
+    /// stacks are not protected by the write barrier, and it marks and sweeps the heaps of child
+    /// threads without interruption, so its length depends on the size of the stacks and of the
+    /// child heaps.
+    Incremental { step_size: usize },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Phase {
+    #[default]
+    Idle,
+    Marking,
+    Sweeping,
+}
+
+/// The state of an incremental collection.
+///
+/// Values are marked with the usual tri-color abstraction. White values are unmarked, gray values
+/// are marked but have not been traced yet (they are kept in `gray`) and black values are marked
+/// and traced. Values allocated while marking are gray. The write barrier (`Gc::write_barrier`)
+/// makes sure that a black value never references a white value so that every value which is
+/// reachable when marking finishes gets marked.
+#[derive(Debug, Default)]
+struct Incremental {
+    phase: Phase,
+    gray: Vec<*const GcHeader>,
+    /// The values which have not been swept yet. Survivors and values allocated while sweeping
+    /// are kept in `Gc::values`
+    unswept: Option<AllocPtr>,
+}
+
+// The gray pointers are only accessed by the `Gc` which owns this state
+unsafe impl Send for Incremental {}
+
+/// `Userdata` values which have opted into finalization (see `Userdata::has_finalizer`).
+///
+/// Once a collection finds a registered value to be unreachable it is moved to `pending` instead
+/// of being freed. Pending and running values are treated as roots so they, and every value they
+/// reference, stay alive until the finalizer has run. The value is then freed by the first
+/// collection which finds it unreachable again.
+#[derive(Debug, Default)]
+struct Finalizers {
+    registered: Vec<GcPtr<Box<dyn Userdata>>>,
+    pending: Vec<GcPtr<Box<dyn Userdata>>>,
+    running: Vec<GcPtr<Box<dyn Userdata>>>,
+}
+
+/// A reference to a garbage collected value which does not keep the value alive.
+///
+/// The `Trace` implementation of a type holding a weak reference must pass the reference to
+/// `Gc::trace_weak` instead of tracing the value it refers to. Once marking is done the collector
+/// calls `clear_collected` on every weak reference it found.
+pub trait WeakRef: fmt::Debug + Send + Sync {
+    /// Clears the reference if the value it refers to is about to be freed, as reported by
+    /// `Gc::is_collected`.
+    fn clear_collected(&self, gc: &Gc);
+}
+
 /// A mark and sweep garbage collector.
 #[derive(Debug)]
 #[cfg_attr(feature = "serde_derive", derive(DeserializeState, SerializeState))]
@@ -225,11 +351,29 @@ pub struct Gc {
     values: Option<AllocPtr>,
     /// How many bytes which is currently allocated
     allocated_memory: usize,
+    /// How many bytes which have been allocated in total, including memory which has since been
+    /// freed
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    total_allocated_memory: u64,
     /// How many bytes this garbage collector can allocate before a collection is run
     collect_limit: usize,
     /// The maximum number of bytes this garbage collector may contain
     memory_limit: usize,
     #[cfg_attr(feature = "serde_derive", serde(skip))]
+    stats: CollectionStats,
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    mode: GcMode,
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    incremental: Incremental,
+    /// Weak references found while marking
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    weak_refs: Vec<Arc<dyn WeakRef>>,
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    finalizers: Finalizers,
+    /// Set while a heap snapshot is taken
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    snapshot: Option<SnapshotBuilder>,
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
     type_infos: FnvMap<TypeId, Box<TypeInfo>>,
     #[cfg_attr(feature = "serde_derive", serde(skip))]
     record_infos: FnvMap<Arc<[InternedStr]>, Box<TypeInfo>>,
@@ -259,7 +403,8 @@ pub struct Gc {
 
 impl Drop for Gc {
     fn drop(&mut self) {
-        if let Some(values) = self.values.take() {
+        let values = (self.values.take(), self.incremental.unswept.take());
+        if values.0.is_some() || values.1.is_some() {
             mem::forget(values);
             if std::thread::panicking() {
                 eprintln!("Gc values were not dropped explicitly. Leaking the allocatons!");
@@ -330,10 +475,52 @@ where
 #[derive(Debug)]
 struct TypeInfo {
     drop: unsafe fn(*mut ()),
+    trace: unsafe fn(*const (), &mut Gc),
     generation: Generation,
     tag: Option<InternedStr>,
     fields: FnvMap<InternedStr, VmIndex>,
     fields_key: Arc<[InternedStr]>,
+    name: String,
+    allocations: Cell<u64>,
+    allocated_bytes: Cell<u64>,
+    live_objects: Cell<usize>,
+    live_bytes: Cell<usize>,
+}
+
+impl TypeInfo {
+    fn new(
+        drop: unsafe fn(*mut ()),
+        trace: unsafe fn(*const (), &mut Gc),
+        generation: Generation,
+        tag: Option<InternedStr>,
+        fields: FnvMap<InternedStr, VmIndex>,
+        fields_key: Arc<[InternedStr]>,
+        name: String,
+    ) -> TypeInfo {
+        TypeInfo {
+            drop,
+            trace,
+            generation,
+            tag,
+            fields,
+            fields_key,
+            name,
+            allocations: Cell::new(0),
+            allocated_bytes: Cell::new(0),
+            live_objects: Cell::new(0),
+            live_bytes: Cell::new(0),
+        }
+    }
+
+    fn stats(&self) -> TypeStats {
+        TypeStats {
+            name: self.name.clone(),
+            allocations: self.allocations.get(),
+            allocated_bytes: self.allocated_bytes.get(),
+            live_objects: self.live_objects.get(),
+            live_bytes: self.live_bytes.get(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -421,6 +608,10 @@ impl GcHeader {
         }
     }
 
+    fn value_ptr(&self) -> *const () {
+        unsafe { (self as *const GcHeader as *const u8).add(GcHeader::value_offset()) as *const () }
+    }
+
     fn value_offset() -> usize {
         let hs = mem::size_of::<GcHeader>();
         let max_align = mem::align_of::<f64>();
@@ -506,7 +697,6 @@ where
     }
 }
 
-
 deref_trace! { ['a, T: Trace] Borrow<'a, T> }
 
 unsafe impl<'a, T> DataDef for Borrow<'a, T>
@@ -756,8 +946,8 @@ pub trait CollectScope {
 /// A type unsafe implementing Trace must call trace on each of its fields
 /// which in turn contains `GcPtr`
 pub unsafe trait Trace {
-    unsafe fn root(&mut self) { }
-    unsafe fn unroot(&mut self) { }
+    unsafe fn root(&mut self) {}
+    unsafe fn unroot(&mut self) {}
 
     fn trace(&self, gc: &mut Gc) {
         let _ = gc;
@@ -857,7 +1047,6 @@ macro_rules! construct_gc {
     };
 }
 
-
 deref_trace! { ['a, T: ?Sized + Trace] &'a T }
 deref_trace_mut! { ['a, T: ?Sized + Trace] &'a mut T }
 deref_trace_mut! { ['a, T: ?Sized + Trace] Box<T> }
@@ -941,7 +1130,7 @@ where
     }
 }
 
-    // Don't root/unroot the contents as an unrooted value could be moved out of the Mutex
+// Don't root/unroot the contents as an unrooted value could be moved out of the Mutex
 unsafe impl<T> Trace for sync::Mutex<T>
 where
     T: Trace,
@@ -951,7 +1140,7 @@ where
     }
 }
 
-    // Don't root/unroot the contents as an unrooted value could be moved out of the RwLock
+// Don't root/unroot the contents as an unrooted value could be moved out of the RwLock
 unsafe impl<T> Trace for sync::RwLock<T>
 where
     T: Trace,
@@ -1037,8 +1226,15 @@ impl Gc {
         Gc {
             values: None,
             allocated_memory: 0,
+            total_allocated_memory: 0,
             collect_limit: 100,
             memory_limit: memory_limit,
+            stats: CollectionStats::default(),
+            mode: GcMode::default(),
+            incremental: Incremental::default(),
+            weak_refs: Vec::new(),
+            finalizers: Finalizers::default(),
+            snapshot: None,
             type_infos: FnvMap::default(),
             record_infos: FnvMap::default(),
             tag_infos: FnvMap::default(),
@@ -1050,16 +1246,70 @@ impl Gc {
         self.allocated_memory
     }
 
+    /// Returns the number of bytes allocated by this garbage collector since it was created
+    pub fn total_allocated_memory(&self) -> u64 {
+        self.total_allocated_memory
+    }
+
     pub fn set_memory_limit(&mut self, memory_limit: usize) {
         self.memory_limit = memory_limit;
     }
 
+    /// Returns statistics about the allocations and collections done by this garbage collector
+    pub fn stats(&self) -> GcStats {
+        let mut types: Vec<_> = self
+            .type_infos
+            .values()
+            .chain(self.record_infos.values())
+            .chain(self.tag_infos.values())
+            .filter(|info| info.allocations.get() != 0)
+            .map(|info| info.stats())
+            .collect();
+        types.sort_by(|l, r| {
+            r.live_bytes
+                .cmp(&l.live_bytes)
+                .then_with(|| r.allocated_bytes.cmp(&l.allocated_bytes))
+                .then_with(|| l.name.cmp(&r.name))
+        });
+
+        GcStats {
+            collections: self.stats.collections,
+            allocated_bytes: self.total_allocated_memory,
+            freed_bytes: self.stats.freed_memory,
+            live_bytes: self.allocated_memory,
+            last_pause: self.stats.last_pause,
+            max_pause: self.stats.max_pause,
+            total_pause: self.stats.total_pause,
+            max_pause_work: self.stats.max_pause_work,
+            types,
+        }
+    }
+
+    pub fn mode(&self) -> GcMode {
+        self.mode
+    }
+
+    /// Sets how values are collected. Garbage collectors created with `new_child_gc` inherit the
+    /// mode. An incremental collection which is in progress is abandoned.
+    pub fn set_mode(&mut self, mode: GcMode) {
+        self.abort_incremental();
+        self.mode = mode;
+    }
+
+    /// Returns true while an incremental collection is marking values, unless a heap snapshot is
+    /// being taken
+    pub fn is_marking(&self) -> bool {
+        self.incremental.phase == Phase::Marking && self.snapshot.is_none()
+    }
+
     pub fn generation(&self) -> Generation {
         self.generation
     }
 
     pub fn new_child_gc(&self) -> Gc {
-        Gc::new(self.generation.next(), self.memory_limit)
+        let mut gc = Gc::new(self.generation.next(), self.memory_limit);
+        gc.mode = self.mode;
+        gc
     }
 
     /// Allocates a new object. If the garbage collector has hit the collection limit a collection
@@ -1134,7 +1384,9 @@ impl Gc {
         tag: Option<&InternedStr>,
         fields: Option<&[InternedStr]>,
         type_id: TypeId,
+        type_name: &'static str,
         drop: unsafe fn(*mut ()),
+        trace: unsafe fn(*const (), &mut Gc),
     ) -> *const TypeInfo {
         match fields {
             Some(fields) => match self
@@ -1150,44 +1402,62 @@ impl Gc {
                             .map(|v| unsafe { v.clone_unrooted() })
                             .collect::<Vec<_>>(),
                     );
+                    let name = if fields.is_empty() {
+                        "{}".to_string()
+                    } else {
+                        format!(
+                            "{{ {} }}",
+                            fields
+                                .iter()
+                                .map(|field| field.as_ref())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    };
                     &**self
                         .record_infos
                         .entry(owned_fields.clone())
-                        .or_insert(Box::new(TypeInfo {
+                        .or_insert(Box::new(TypeInfo::new(
                             drop,
-                            generation: self.generation,
-                            tag: unsafe { tag.map(|tag| tag.clone_unrooted()) },
-                            fields: unsafe {
+                            trace,
+                            self.generation,
+                            unsafe { tag.map(|tag| tag.clone_unrooted()) },
+                            unsafe {
                                 fields
                                     .iter()
                                     .enumerate()
                                     .map(|(i, ref s)| (s.clone_unrooted(), i as VmIndex))
                                     .collect()
                             },
-                            fields_key: owned_fields,
-                        }))
+                            owned_fields,
+                            name,
+                        )))
                 }
             },
             None => match tag {
                 Some(tag) => match self.tag_infos.entry(unsafe { tag.clone_unrooted() }) {
                     Entry::Occupied(entry) => &**entry.get(),
-                    Entry::Vacant(entry) => &**entry.insert(Box::new(TypeInfo {
+                    Entry::Vacant(entry) => &**entry.insert(Box::new(TypeInfo::new(
                         drop,
-                        generation: self.generation,
-                        tag: Some(unsafe { tag.clone_unrooted() }),
-                        fields: FnvMap::default(),
-                        fields_key: Arc::from(Vec::new()),
-                    })),
+                        trace,
+                        self.generation,
+                        Some(unsafe { tag.clone_unrooted() }),
+                        FnvMap::default(),
+                        Arc::from(Vec::new()),
+                        tag.to_string(),
+                    ))),
                 },
                 None => match self.type_infos.entry(type_id) {
                     Entry::Occupied(entry) => &**entry.get(),
-                    Entry::Vacant(entry) => &**entry.insert(Box::new(TypeInfo {
+                    Entry::Vacant(entry) => &**entry.insert(Box::new(TypeInfo::new(
                         drop,
-                        generation: self.generation,
-                        tag: None,
-                        fields: FnvMap::default(),
-                        fields_key: Arc::from(Vec::new()),
-                    })),
+                        trace,
+                        self.generation,
+                        None,
+                        FnvMap::default(),
+                        Arc::from(Vec::new()),
+                        type_name.to_string(),
+                    ))),
                 },
             },
         }
@@ -1202,22 +1472,47 @@ impl Gc {
             ptr::drop_in_place(t as *mut T);
         }
 
+        unsafe fn trace<T: Trace>(t: *const (), gc: &mut Gc) {
+            (*(t as *const T)).trace(gc);
+        }
+
         let type_info = self.get_type_info(
             def.tag(),
             def.fields(),
             TypeId::of::<D::Value>(),
+            std::any::type_name::<D::Value>(),
             drop::<D::Value>,
+            trace::<D::Value>,
         );
 
         let mut ptr = AllocPtr::new::<D::Value>(type_info, size);
         ptr.next = self.values.take();
         self.allocated_memory += ptr.size();
+        self.total_allocated_memory += ptr.size() as u64;
+        {
+            let type_info = unsafe { &*type_info };
+            type_info.allocations.set(type_info.allocations.get() + 1);
+            type_info
+                .allocated_bytes
+                .set(type_info.allocated_bytes.get() + ptr.size() as u64);
+            type_info.live_objects.set(type_info.live_objects.get() + 1);
+            type_info
+                .live_bytes
+                .set(type_info.live_bytes.get() + ptr.size());
+        }
         unsafe {
             let p: *mut D::Value = D::Value::make_ptr(&def, ptr.value());
             let ret: *const D::Value = &*def.initialize(WriteOnly::new(p));
             // Check that the returned pointer is the same as the one we sent as an extra precaution
             // that the pointer was initialized
             assert!(ret == p);
+            if self.is_marking() {
+                // Allocate gray as the values it references may not be reachable from anywhere
+                // else. Tracing it is deferred to the next step since the value may still be
+                // initialized further, such as when deep cloning.
+                ptr.marked.set(true);
+                self.incremental.gray.push(&*ptr);
+            }
             self.values = Some(ptr);
             let mut ptr = OwnedPtr(NonNull::new_unchecked(p));
             D::Value::unroot(&mut ptr);
@@ -1229,11 +1524,19 @@ impl Gc {
     where
         R: Trace + CollectScope,
     {
-        if self.allocated_memory >= self.collect_limit {
-            self.collect(roots);
-            true
-        } else {
-            false
+        match self.mode {
+            GcMode::Incremental { step_size }
+                if self.incremental.phase != Phase::Idle
+                    || self.allocated_memory >= self.collect_limit =>
+            {
+                self.collect_step(roots, step_size);
+                true
+            }
+            GcMode::StopTheWorld if self.allocated_memory >= self.collect_limit => {
+                self.collect(roots);
+                true
+            }
+            _ => false,
         }
     }
 
@@ -1244,30 +1547,317 @@ impl Gc {
         R: Trace + CollectScope,
     {
         info!("Start collect {:?}", self.generation);
+        let start = Instant::now();
+        self.abort_incremental();
+        self.stats.pause_work = 0;
         roots.scope(self, |self_| {
             roots.trace(self_);
+            self_.finish_tracing();
             self_.sweep();
             self_.collect_limit = 2 * self_.allocated_memory;
-        })
+        });
+
+        self.stats.collections += 1;
+        self.record_pause(start);
+    }
+
+    /// Does a bounded amount of work on an incremental collection, starting a new collection if
+    /// none is in progress. This function is unsafe since roots need to cover all reachable
+    /// object.
+    pub unsafe fn collect_step<R>(&mut self, roots: R, step_size: usize)
+    where
+        R: Trace + CollectScope,
+    {
+        let start = Instant::now();
+        self.stats.pause_work = 0;
+        // Every allocation adds a gray value while marking so at least two values must be traced
+        // for marking to finish
+        let step_size = step_size.max(2);
+        match self.incremental.phase {
+            Phase::Idle => {
+                info!("Start incremental collect {:?}", self.generation);
+                self.incremental.phase = Phase::Marking;
+                roots.trace(self);
+            }
+            Phase::Marking => {
+                for _ in 0..step_size {
+                    match self.incremental.gray.pop() {
+                        Some(header) => self.trace_header(header),
+                        None => break,
+                    }
+                }
+                if self.incremental.gray.is_empty() {
+                    self.finish_marking(roots);
+                }
+            }
+            Phase::Sweeping => {
+                for _ in 0..step_size {
+                    match self.incremental.unswept.take() {
+                        Some(mut header) => {
+                            self.stats.pause_work += 1;
+                            self.incremental.unswept = header.next.take();
+                            if header.marked.get() {
+                                header.marked.set(false);
+                                header.next = self.values.take();
+                                self.values = Some(header);
+                            } else {
+                                self.free(Some(header));
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                if self.incremental.unswept.is_none() {
+                    info!("Finished incremental collect {:?}", self.generation);
+                    self.incremental.phase = Phase::Idle;
+                    self.collect_limit = 2 * self.allocated_memory;
+                    self.stats.collections += 1;
+                }
+            }
+        }
+        self.record_pause(start);
+    }
+
+    /// Finishes marking in a single pause. The roots are traced again since the stacks are not
+    /// protected by the write barrier, as are the roots of every child thread since they may hold
+    /// values from this `Gc` as well. Child heaps are not marked incrementally, their values are
+    /// marked and swept stop-the-world in this pause.
+    unsafe fn finish_marking<R>(&mut self, roots: R)
+    where
+        R: Trace + CollectScope,
+    {
+        // Values are traced recursively from here on as no allocations or writes can happen until
+        // marking is done
+        self.incremental.phase = Phase::Idle;
+        roots.scope(self, |self_| {
+            roots.trace(self_);
+            while let Some(header) = self_.incremental.gray.pop() {
+                self_.trace_header(header);
+            }
+            self_.finish_tracing();
+            self_.incremental.unswept = self_.values.take();
+            self_.incremental.phase = Phase::Sweeping;
+        });
+    }
+
+    /// Called once every reachable value has been marked. Keeps the registered values which were
+    /// found unreachable alive until their finalizers have run and clears the weak references to
+    /// values which are about to be freed. Weak references are cleared last so that they are kept
+    /// for the values which are reachable from a value waiting on its finalizer.
+    fn finish_tracing(&mut self) {
+        let mut finalizers = mem::take(&mut self.finalizers);
+        let (unreachable, reachable) = finalizers
+            .registered
+            .drain(..)
+            .partition::<Vec<_>, _>(|value| self.is_collected(value));
+        finalizers.registered = reachable;
+        finalizers.pending.extend(unreachable);
+        finalizers.pending.trace(self);
+        finalizers.running.trace(self);
+        self.finalizers = finalizers;
+
+        for weak in mem::take(&mut self.weak_refs) {
+            weak.clear_collected(self);
+        }
+    }
+
+    unsafe fn trace_header(&mut self, header: *const GcHeader) {
+        let header = &*header;
+        ((*header.type_info).trace)(header.value_ptr(), self);
+    }
+
+    /// Abandons an incremental collection which is in progress, leaving every value unmarked.
+    /// Must be called before a parent `Gc` marks the values of this `Gc` as it would otherwise
+    /// stop tracing at values which are marked but whose references have not been marked.
+    pub fn abort_incremental(&mut self) {
+        match self.incremental.phase {
+            Phase::Idle => return,
+            Phase::Marking => {
+                self.incremental.gray.clear();
+                self.weak_refs.clear();
+                let mut current = self.values.as_ref();
+                while let Some(header) = current {
+                    header.marked.set(false);
+                    current = header.next.as_ref();
+                }
+            }
+            Phase::Sweeping => {
+                while let Some(mut header) = self.incremental.unswept.take() {
+                    self.incremental.unswept = header.next.take();
+                    header.marked.set(false);
+                    header.next = self.values.take();
+                    self.values = Some(header);
+                }
+            }
+        }
+        info!("Aborted incremental collect {:?}", self.generation);
+        self.incremental.phase = Phase::Idle;
+    }
+
+    /// Adds `work` values, visited on behalf of this `Gc`, to the work of the current pause
+    pub(crate) fn add_pause_work(&mut self, work: usize) {
+        self.stats.pause_work += work;
+    }
+
+    fn record_pause(&mut self, start: Instant) {
+        let pause = start.elapsed();
+        self.stats.last_pause = pause;
+        self.stats.max_pause = self.stats.max_pause.max(pause);
+        self.stats.total_pause += pause;
+        self.stats.max_pause_work = self.stats.max_pause_work.max(self.stats.pause_work);
+    }
+
+    /// Must be called when `value` is written into a value allocated by this `Gc`. Shades the
+    /// values `value` references if an incremental collection is marking so that they are not
+    /// freed while only being reachable from an already traced value.
+    pub fn write_barrier<T>(&mut self, value: &T)
+    where
+        T: ?Sized + Trace,
+    {
+        if self.is_marking() {
+            value.trace(self);
+        }
+    }
+
+    /// Reports a weak reference found while tracing. See `WeakRef`.
+    pub fn trace_weak(&mut self, weak: Arc<dyn WeakRef>) {
+        // Weak references are not part of snapshots
+        if self.snapshot.is_none() {
+            self.weak_refs.push(weak);
+        }
+    }
+
+    /// Returns true if `value` is about to be freed by the collection in progress, that is, if it
+    /// belongs to this `Gc` or to a child of it and was not marked. Only meaningful from
+    /// `WeakRef::clear_collected`.
+    pub fn is_collected<T: ?Sized>(&self, value: &GcPtr<T>) -> bool {
+        let header = value.header();
+        !header.generation().is_parent_of(self.generation) && !header.marked.get()
+    }
+
+    /// Registers `value`, which must be allocated by this `Gc`, to have its finalizer run once it
+    /// has become unreachable. See `Userdata::finalize`.
+    pub fn register_finalizer(&mut self, value: GcPtr<Box<dyn Userdata>>) {
+        debug_assert!(!value.generation().is_parent_of(self.generation));
+        self.finalizers.registered.push(value);
+    }
+
+    /// Returns true if there are unreachable values whose finalizers have not started running
+    pub fn has_pending_finalizers(&self) -> bool {
+        !self.finalizers.pending.is_empty()
+    }
+
+    /// Takes a value whose finalizer should run. The value is kept alive until
+    /// `finish_finalizer` is called with it.
+    pub(crate) fn start_finalizer(&mut self) -> Option<GcPtr<Box<dyn Userdata>>> {
+        let value = self.finalizers.pending.pop()?;
+        // SAFETY The pointer is kept alive by `running`
+        self.finalizers.running.push(unsafe { value.clone() });
+        Some(value)
+    }
+
+    pub(crate) fn finish_finalizer(&mut self, value: &GcPtr<Box<dyn Userdata>>) {
+        if let Some(index) = self
+            .finalizers
+            .running
+            .iter()
+            .position(|running| running.ptr_eq(value))
+        {
+            self.finalizers.running.swap_remove(index);
+        }
+    }
+
+    /// Traces the values which wait on, or may later need, their finalizer so that a parent `Gc`
+    /// does not free them when it collects.
+    pub(crate) fn trace_finalizers(&self, gc: &mut Gc) {
+        self.finalizers.registered.trace(gc);
+        self.finalizers.pending.trace(gc);
+        self.finalizers.running.trace(gc);
+    }
+
+    /// Returns a snapshot of all values reachable from `roots`. No values are freed and the marks
+    /// used by `collect` are left untouched.
+    pub fn snapshot<R>(&mut self, roots: R) -> HeapSnapshot
+    where
+        R: Trace,
+    {
+        info!("Start heap snapshot {:?}", self.generation);
+        self.snapshot = Some(SnapshotBuilder::new());
+        roots.trace(self);
+
+        // Values are traced from this loop instead of recursively so that the builder knows which
+        // value references each found value
+        while let Some(header) = self.snapshot.as_mut().and_then(|snapshot| snapshot.next()) {
+            unsafe {
+                let header = &*header;
+                ((*header.type_info).trace)(header.value_ptr(), self);
+            }
+        }
+
+        self.snapshot.take().unwrap().finish()
+    }
+
+    /// Marks `value` and calls `trace` to trace what it references instead of using its `Trace`
+    /// implementation. Used for values which can't be traced normally, such as a thread whose stack
+    /// is already borrowed.
+    pub fn mark_with<T, F>(&mut self, value: &GcPtr<T>, trace: F)
+    where
+        T: ?Sized,
+        F: FnOnce(&mut Gc),
+    {
+        let generation = self.generation;
+        match self.snapshot {
+            Some(ref mut snapshot) => {
+                let id = snapshot.visit(value.header(), generation, false);
+                let parent = snapshot.set_current(id);
+                trace(self);
+                if let Some(ref mut snapshot) = self.snapshot {
+                    snapshot.set_current(parent);
+                }
+            }
+            None => {
+                self.mark(value);
+                trace(self);
+            }
+        }
     }
 
     /// Marks the GcPtr
     /// Returns true if the pointer was already marked
     pub fn mark<T: ?Sized>(&mut self, value: &GcPtr<T>) -> bool {
         let header = value.header();
+        if let Some(ref mut snapshot) = self.snapshot {
+            // Found values are traced by `snapshot` so report that it was already marked
+            snapshot.visit(header, self.generation, true);
+            return true;
+        }
+        // Values which are already marked are counted as well since finishing an incremental
+        // collection visits every root again
+        self.stats.pause_work += 1;
         // We only need to mark and trace values from this garbage collectors generation
         if header.generation().is_parent_of(self.generation()) || header.marked.get() {
             true
+        } else if self.is_marking() {
+            // Values are traced later by `collect_step`. Values from child generations are left to
+            // `finish_marking` as the child threads may still be modifying them
+            if !self.generation.is_parent_of(header.generation()) {
+                header.marked.set(true);
+                self.incremental.gray.push(header);
+            }
+            true
         } else {
             header.marked.set(true);
             false
         }
     }
 
-    /// Clears out any unmarked pointers and resets marked pointers.
+    /// Clears out any unmarked pointers and resets marked pointers. Returns the number of values
+    /// which were visited.
     ///
-    /// Unsafe as it is up to the caller to make sure that all reachable pointers have been marked
-    pub unsafe fn sweep(&mut self) {
+    /// # Safety
+    ///
+    /// It is up to the caller to make sure that all reachable pointers have been marked
+    pub unsafe fn sweep(&mut self) -> usize {
         fn moving<T>(t: T) -> T {
             t
         }
@@ -1309,7 +1899,9 @@ impl Gc {
             }
         }
         info!("GC: Freed {} / Traversed {}", free_count, count);
+        self.stats.pause_work += count;
         self.values = first;
+        count
     }
 
     // Drop all values.
@@ -1317,11 +1909,21 @@ impl Gc {
     // SAFETY: No `GcPtr` allocated from this Gc must be reachable after calling this
     pub unsafe fn clear(&mut self) {
         self.values = None;
+        self.incremental = Incremental::default();
+        self.weak_refs.clear();
+        self.finalizers = Finalizers::default();
     }
 
     fn free(&mut self, header: Option<AllocPtr>) {
         if let Some(ref ptr) = header {
             self.allocated_memory -= ptr.size();
+            self.stats.freed_memory += ptr.size() as u64;
+            // Only values allocated by this `Gc` are freed so the `TypeInfo` is still alive
+            let type_info = unsafe { &*ptr.type_info };
+            type_info.live_objects.set(type_info.live_objects.get() - 1);
+            type_info
+                .live_bytes
+                .set(type_info.live_bytes.get() - ptr.size());
         }
         debug!("FREE: {:?}", header);
         drop(header);
@@ -1477,6 +2079,138 @@ mod tests {
         unsafe { gc.clear() }
     }
 
+    #[test]
+    fn stats() {
+        let mut gc: Gc = Gc::new(Generation::default(), usize::MAX);
+        let mut stack: Vec<Value> = Vec::new();
+        stack.push(new_data(gc.alloc(Def { elems: &[Int(1)] }).unwrap()));
+        stack.push(new_data(gc.alloc(Def { elems: &[Int(2)] }).unwrap()));
+        let allocated = gc.allocated_memory();
+
+        stack.pop();
+        unsafe {
+            gc.collect(&mut *stack);
+        }
+
+        let stats = gc.stats();
+        assert_eq!(stats.collections, 1);
+        assert_eq!(stats.allocated_bytes, allocated as u64);
+        assert_eq!(stats.freed_bytes, allocated as u64 / 2);
+        assert_eq!(stats.live_bytes, allocated / 2);
+        assert_eq!(stats.max_pause, stats.last_pause);
+        assert_eq!(stats.total_pause, stats.last_pause);
+
+        assert_eq!(stats.types.len(), 1);
+        let typ = &stats.types[0];
+        assert_eq!(typ.name, std::any::type_name::<Vec<Value>>());
+        assert_eq!(typ.allocations, 2);
+        assert_eq!(typ.allocated_bytes, allocated as u64);
+        assert_eq!(typ.live_objects, 1);
+        assert_eq!(typ.live_bytes, allocated / 2);
+
+        unsafe { gc.clear() }
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut gc: Gc = Gc::new(Generation::default(), usize::MAX);
+        let mut stack: Vec<Value> = Vec::new();
+        let leaf = new_data(gc.alloc(Def { elems: &[Int(1)] }).unwrap());
+        stack.push(new_data(
+            gc.alloc(Def {
+                elems: &[unsafe { leaf.clone_unrooted() }, leaf],
+            })
+            .unwrap(),
+        ));
+        // Unreachable
+        gc.alloc(Def { elems: &[] }).unwrap();
+
+        let snapshot = gc.snapshot(&mut *stack);
+        let size = GcHeader::value_offset() + mem::size_of::<Vec<Value>>();
+        let name = std::any::type_name::<Vec<Value>>();
+        assert_eq!(
+            &snapshot.nodes[1..],
+            &[
+                snapshot::Node {
+                    id: 1,
+                    name: name.to_string(),
+                    size,
+                    ..snapshot::Node::default()
+                },
+                snapshot::Node {
+                    id: 2,
+                    name: name.to_string(),
+                    size,
+                    ..snapshot::Node::default()
+                },
+            ][..]
+        );
+        assert_eq!(
+            snapshot.edges,
+            [
+                snapshot::Edge { from: 0, to: 1 },
+                snapshot::Edge { from: 1, to: 2 },
+                snapshot::Edge { from: 1, to: 2 },
+            ]
+        );
+
+        // Taking a snapshot does not leave any values marked
+        unsafe {
+            gc.collect(&mut *stack);
+        }
+        assert_eq!(object_count(&gc), 2);
+
+        unsafe { gc.clear() }
+    }
+
+    #[test]
+    fn incremental() {
+        let mut gc: Gc = Gc::new(Generation::default(), usize::MAX);
+        gc.set_mode(GcMode::Incremental { step_size: 2 });
+        let mut stack: Vec<Value> = Vec::new();
+        for i in 0..3 {
+            stack.push(new_data(gc.alloc(Def { elems: &[Int(i)] }).unwrap()));
+        }
+        let holder = new_data(gc.alloc(Def { elems: &[Int(3)] }).unwrap());
+        stack.push(unsafe { holder.clone_unrooted() });
+        let leaf = new_data(gc.alloc(Def { elems: &[Int(4)] }).unwrap());
+        assert_eq!(object_count(&gc), 5);
+
+        unsafe {
+            // Shades the values on the stack
+            gc.collect_step(&mut *stack, 2);
+            assert!(gc.is_marking());
+            // Traces `holder` and one other value
+            gc.collect_step(&mut *stack, 2);
+            assert!(gc.is_marking());
+
+            // `leaf` is only reachable through `holder` which has already been traced
+            gc.write_barrier(&leaf);
+            match holder {
+                Data(ref data) => data.fields.clone().as_mut()[0] = leaf,
+                _ => ice!(),
+            }
+
+            while gc.incremental.phase != Phase::Idle {
+                gc.collect_step(&mut *stack, 2);
+            }
+        }
+        assert_eq!(object_count(&gc), 5);
+        assert_eq!(gc.stats().collections, 1);
+
+        stack.clear();
+        unsafe {
+            gc.collect_step(&mut *stack, 2);
+            while gc.incremental.phase != Phase::Idle {
+                gc.collect_step(&mut *stack, 2);
+            }
+        }
+        assert_eq!(object_count(&gc), 0);
+        assert_eq!(gc.stats().collections, 2);
+
+        unsafe { gc.clear() }
+    }
+
     #[derive(Trace)]
     #[gluon(gluon_vm)]
     pub struct Dropable {
@@ -0,0 +1,239 @@
+//! Heap snapshots, describing which values are allocated by a `Gc` and how they reference each
+//! other.
+//!
+//! A snapshot is taken with `Thread::heap_snapshot` (or `Gc::snapshot`) and can be written as JSON
+//! with `HeapSnapshot::write_json`. The JSON has the following format.
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "nodes": [
+//!         { "id": 0, "name": "(roots)", "size": 0, "tag": null, "fields": [], "external": false },
+//!         { "id": 1, "name": "{ x, y }", "size": 48, "tag": null, "fields": ["x", "y"], "external": false },
+//!         { "id": 2, "name": "Some", "size": 40, "tag": "Some", "fields": [], "external": false }
+//!     ],
+//!     "edges": [
+//!         { "from": 0, "to": 1 },
+//!         { "from": 1, "to": 2 }
+//!     ]
+//! }
+//! ```
+//!
+//! Each node is a value allocated by the garbage collector.
+//!
+//! * `id` is the index of the node in `nodes`. Node `0` does not correspond to a value. It
+//!   represents the roots and references every value which is reachable directly from the roots.
+//! * `name` is the name of the value's type. Records are named by their fields, variants by
+//!   their tag and any other value by its Rust type.
+//! * `size` is the number of bytes allocated for the value, including the header used by the
+//!   garbage collector.
+//! * `tag` is the tag of the variant the value was constructed with, if any.
+//! * `fields` are the names of the fields of a record.
+//! * `external` is `true` if the value is allocated by a parent garbage collector, such as a global
+//!   value referenced from a child thread. The values referenced by an external value are not
+//!   part of the snapshot.
+//!
+//! Each edge is a reference from the value `from` to the value `to`. A value which refers to the
+//! same value several times has an edge for each reference.
+
+use std::{collections::hash_map::Entry, io};
+
+use crate::{
+    base::fnv::FnvMap,
+    gc::{GcHeader, Generation},
+};
+
+/// The version of the JSON format written by `HeapSnapshot::write_json`
+pub const VERSION: u32 = 1;
+
+/// A graph of the values reachable from the roots of a `Gc`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HeapSnapshot {
+    /// The values in the snapshot, indexed by their `id`
+    pub nodes: Vec<Node>,
+    /// The references between the values
+    pub edges: Vec<Edge>,
+}
+
+/// A value in a `HeapSnapshot`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Node {
+    pub id: usize,
+    pub name: String,
+    pub size: usize,
+    pub tag: Option<String>,
+    pub fields: Vec<String>,
+    pub external: bool,
+}
+
+/// A reference from the value `from` to the value `to`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl HeapSnapshot {
+    /// The id of the node which represents the roots
+    pub const ROOT: usize = 0;
+
+    /// Writes the snapshot as JSON in the format described in the module documentation
+    pub fn write_json<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        writeln!(writer, "{{")?;
+        writeln!(writer, "    \"version\": {},", VERSION)?;
+
+        writeln!(writer, "    \"nodes\": [")?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            write!(writer, "        {{ \"id\": {}, \"name\": ", node.id)?;
+            write_string(&mut writer, &node.name)?;
+            write!(writer, ", \"size\": {}, \"tag\": ", node.size)?;
+            match node.tag {
+                Some(ref tag) => write_string(&mut writer, tag)?,
+                None => write!(writer, "null")?,
+            }
+            write!(writer, ", \"fields\": [")?;
+            for (j, field) in node.fields.iter().enumerate() {
+                if j != 0 {
+                    write!(writer, ", ")?;
+                }
+                write_string(&mut writer, field)?;
+            }
+            write!(writer, "], \"external\": {} }}", node.external)?;
+            writeln!(writer, "{}", separator(i, self.nodes.len()))?;
+        }
+        writeln!(writer, "    ],")?;
+
+        writeln!(writer, "    \"edges\": [")?;
+        for (i, edge) in self.edges.iter().enumerate() {
+            writeln!(
+                writer,
+                "        {{ \"from\": {}, \"to\": {} }}{}",
+                edge.from,
+                edge.to,
+                separator(i, self.edges.len())
+            )?;
+        }
+        writeln!(writer, "    ]")?;
+        writeln!(writer, "}}")
+    }
+}
+
+fn separator(i: usize, len: usize) -> &'static str {
+    if i + 1 == len {
+        ""
+    } else {
+        ","
+    }
+}
+
+fn write_string<W>(writer: &mut W, s: &str) -> io::Result<()>
+where
+    W: io::Write,
+{
+    write!(writer, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(writer, "\"")
+}
+
+/// Records the nodes and edges found while a `Gc` traces its roots in snapshot mode.
+#[derive(Debug)]
+pub(super) struct SnapshotBuilder {
+    snapshot: HeapSnapshot,
+    ids: FnvMap<*const GcHeader, usize>,
+    /// Values which have been found but not yet traced
+    queue: Vec<(usize, *const GcHeader)>,
+    /// The node of the value currently being traced
+    current: usize,
+}
+
+// The pointers are only used to identify values and to trace them from the `Gc` which owns the
+// builder
+unsafe impl Send for SnapshotBuilder {}
+
+impl SnapshotBuilder {
+    pub(super) fn new() -> Self {
+        SnapshotBuilder {
+            snapshot: HeapSnapshot {
+                nodes: vec![Node {
+                    id: HeapSnapshot::ROOT,
+                    name: "(roots)".to_string(),
+                    ..Node::default()
+                }],
+                edges: Vec::new(),
+            },
+            ids: FnvMap::default(),
+            queue: Vec::new(),
+            current: HeapSnapshot::ROOT,
+        }
+    }
+
+    /// Adds an edge from the current node to `header`, creating a node for `header` if it has not
+    /// been visited before. New values are queued to be traced if `trace` is true.
+    pub(super) fn visit(
+        &mut self,
+        header: &GcHeader,
+        generation: Generation,
+        trace: bool,
+    ) -> usize {
+        let id = match self.ids.entry(header) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                let id = self.snapshot.nodes.len();
+                let external = header.generation().is_parent_of(generation);
+                // SAFETY `header` is a live allocation so its `TypeInfo` is alive as well
+                let type_info = unsafe { &*header.type_info };
+                self.snapshot.nodes.push(Node {
+                    id,
+                    name: type_info.name.clone(),
+                    size: GcHeader::value_offset() + header.value_size,
+                    tag: type_info.tag.as_ref().map(|tag| tag.to_string()),
+                    fields: type_info
+                        .fields_key
+                        .iter()
+                        .map(|field| field.to_string())
+                        .collect(),
+                    external,
+                });
+                entry.insert(id);
+                if trace && !external {
+                    self.queue.push((id, header));
+                }
+                id
+            }
+        };
+        self.snapshot.edges.push(Edge {
+            from: self.current,
+            to: id,
+        });
+        id
+    }
+
+    /// Sets the node which references found values are added to, returning the previous node
+    pub(super) fn set_current(&mut self, id: usize) -> usize {
+        std::mem::replace(&mut self.current, id)
+    }
+
+    /// Returns the next value to trace, making it the current node
+    pub(super) fn next(&mut self) -> Option<*const GcHeader> {
+        let (id, header) = self.queue.pop()?;
+        self.current = id;
+        Some(header)
+    }
+
+    pub(super) fn finish(self) -> HeapSnapshot {
+        self.snapshot
+    }
+}
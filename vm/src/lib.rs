@@ -65,15 +65,19 @@ pub mod channel;
 pub mod compiler;
 pub mod core;
 pub mod debug;
+pub mod debugger;
 pub mod dynamic;
 pub mod lazy;
 pub mod macros;
 pub mod primitives;
+pub mod profiler;
 pub mod reference;
+pub mod scheduler;
 pub mod stack;
 pub mod thread;
 pub mod types;
 pub mod vm;
+pub mod weak;
 
 mod array;
 mod derive;
@@ -91,7 +95,9 @@ use crate::{
     types::{VmIndex, VmInt},
     value::{Value, ValueRepr},
 };
-use crate::{base::metadata::Metadata, base::symbol::Symbol, base::types::ArcType};
+use crate::{
+    base::metadata::Metadata, base::pos::Line, base::symbol::Symbol, base::types::ArcType,
+};
 
 unsafe fn forget_lifetime<'a, 'b, T: ?Sized>(x: &'a T) -> &'b T {
     ::std::mem::transmute(x)
@@ -197,6 +203,19 @@ quick_error! {
         Interrupted {
             display("Thread was interrupted")
         }
+        OutOfFuel {
+            display("Thread ran out of fuel")
+        }
+        DeadlineExceeded {
+            display("Thread exceeded its deadline")
+        }
+        ArithmeticOverflow(source_name: String, line: Option<Line>) {
+            display(
+                "Arithmetic overflow in `{}`{}",
+                source_name,
+                line.map(|line| format!(" at line {}", line.number())).unwrap_or_default()
+            )
+        }
         Panic(err: String, stacktrace: Option<Stacktrace>) {
             display("{}", Panic { err, stacktrace })
         }
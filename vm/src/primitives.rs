@@ -282,6 +282,48 @@ mod string {
     }
 }
 
+#[doc(hidden)]
+pub mod hash {
+    use crate::real_std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use crate::types::VmInt;
+
+    /// Scrambles the bits of `i` so that similar integers get dissimilar hashes (the finalizer of
+    /// splitmix64).
+    pub fn hash_int(i: VmInt) -> VmInt {
+        let mut x = i as u64;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        (x ^ (x >> 31)) as VmInt
+    }
+
+    pub fn hash_float(f: f64) -> VmInt {
+        // `0.0 == -0.0` so both must hash the same
+        let f = if f == 0.0 { 0.0 } else { f };
+        hash_int(f.to_bits() as VmInt)
+    }
+
+    pub fn hash_string(s: &str) -> VmInt {
+        // `DefaultHasher::new` uses fixed keys so hashes are the same in every run
+        let mut hasher = DefaultHasher::new();
+        s.hash(&mut hasher);
+        hasher.finish() as VmInt
+    }
+
+    pub fn combine(seed: VmInt, hash: VmInt) -> VmInt {
+        let seed = seed as u64;
+        let hash = hash as u64;
+        (seed
+            ^ hash
+                .wrapping_add(0x9e37_79b9_7f4a_7c15)
+                .wrapping_add(seed << 6)
+                .wrapping_add(seed >> 2)) as VmInt
+    }
+}
+
 fn parse<T>(s: &str) -> StdResult<T, ()>
 where
     T: FromStr,
@@ -338,6 +380,9 @@ mod std {
     pub mod array {
         pub use crate::primitives::array as prim;
     }
+    pub mod hash {
+        pub use crate::primitives::hash as prim;
+    }
     pub mod byte {
         pub type prim = u8;
     }
@@ -542,6 +587,18 @@ pub fn load_array(vm: &Thread) -> Result<ExternModule> {
     )
 }
 
+pub fn load_hash(vm: &Thread) -> Result<ExternModule> {
+    ExternModule::new(
+        vm,
+        record! {
+            hash_int => primitive!(1, std::hash::prim::hash_int),
+            hash_float => primitive!(1, std::hash::prim::hash_float),
+            hash_string => primitive!(1, std::hash::prim::hash_string),
+            combine => primitive!(2, std::hash::prim::combine)
+        },
+    )
+}
+
 pub fn load_string(vm: &Thread) -> Result<ExternModule> {
     ExternModule::new(
         vm,
@@ -0,0 +1,430 @@
+//! An instrumenting and sampling profiler built on top of the hooks of a `Thread`.
+//!
+//! A `Profiler` is attached to a thread with `Profiler::attach` after which every function call
+//! and return is recorded. For each function it counts how often it was called, how many
+//! instructions it executed and how many bytes it allocated, both including (inclusive) and
+//! excluding (exclusive) the functions it called.
+//!
+//! Recording every call slows down call heavy programs considerably so a profiler can instead be
+//! attached with `Profiler::attach_sampling` which only records the stack of the thread each time
+//! it has executed a fixed number of instructions. Each function then counts the samples where
+//! it was on the stack (inclusive) or executing (exclusive).
+//!
+//! The results are retrieved with `Profiler::profile` which can be written as a textual report or
+//! as folded stacks which can be rendered by flamegraph tools (such as `inferno` or
+//! `flamegraph.pl`).
+
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+
+use futures::Async;
+
+use crate::base::{fnv::FnvMap, pos::Line};
+
+use crate::{
+    thread::{DebugInfo, HookFlags, StackInfo, Thread, ThreadInternal},
+    Result,
+};
+
+/// The statistics collected for a single function
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    /// The source which defined the function. Extern functions do not have a source.
+    pub source_name: Option<String>,
+    /// The line of the first instruction of the function
+    pub line: Option<Line>,
+    pub calls: u64,
+    /// Instructions executed by the function and the functions it called. Recursive calls are
+    /// only counted once.
+    pub inclusive_instructions: u64,
+    /// Instructions executed by the function itself
+    pub exclusive_instructions: u64,
+    /// Bytes allocated by the function and the functions it called. Recursive calls are only
+    /// counted once.
+    pub inclusive_allocated: u64,
+    /// Bytes allocated by the function itself
+    pub exclusive_allocated: u64,
+    /// Samples where the function was on the stack. Recursive calls are only counted once.
+    pub inclusive_samples: u64,
+    /// Samples where the function was executing
+    pub exclusive_samples: u64,
+}
+
+impl FunctionProfile {
+    /// Returns a name which identifies the function, including where it was defined
+    pub fn label(&self) -> String {
+        let mut label = self.name.replace(';', ":");
+        if let Some(ref source_name) = self.source_name {
+            label.push_str(&format!(" ({}", source_name));
+            if let Some(line) = self.line {
+                label.push_str(&format!(":{}", line.number()));
+            }
+            label.push(')');
+        }
+        label
+    }
+}
+
+/// What the stacks written by `Profile::write_folded` are weighted by
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Metric {
+    Instructions,
+    AllocatedBytes,
+    /// Only recorded by profilers attached with `Profiler::attach_sampling`
+    Samples,
+}
+
+/// A snapshot of the statistics collected by a `Profiler`
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    functions: Vec<FunctionProfile>,
+    // Indexes into `functions` from the outermost to the innermost call mapped to the exclusive
+    // counts of the innermost call
+    stacks: FnvMap<Vec<usize>, Counts>,
+    sample_interval: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Counts {
+    instructions: u64,
+    allocated: u64,
+    samples: u64,
+}
+
+impl Profile {
+    /// Returns the number of instructions between each sample if the profile was collected by
+    /// sampling
+    pub fn sample_interval(&self) -> Option<u64> {
+        self.sample_interval
+    }
+
+    /// Returns the profiled functions, sorted with the function that executed the most
+    /// instructions (or was sampled the most) itself first
+    pub fn functions(&self) -> Vec<&FunctionProfile> {
+        let mut functions = self.functions.iter().collect::<Vec<_>>();
+        functions.sort_by(|l, r| {
+            r.exclusive_samples
+                .cmp(&l.exclusive_samples)
+                .then_with(|| r.exclusive_instructions.cmp(&l.exclusive_instructions))
+                .then_with(|| r.exclusive_allocated.cmp(&l.exclusive_allocated))
+                .then_with(|| l.label().cmp(&r.label()))
+        });
+        functions
+    }
+
+    /// Returns the function labeled `label` (see `FunctionProfile::label`) or named `label`
+    pub fn function(&self, label: &str) -> Option<&FunctionProfile> {
+        self.functions
+            .iter()
+            .find(|function| function.label() == label)
+            .or_else(|| {
+                self.functions
+                    .iter()
+                    .find(|function| function.name == label)
+            })
+    }
+
+    /// Writes the stacks of the profile in the folded format used by flamegraph tools. Each line
+    /// contains the functions of a stack, separated by `;`, followed by a space and the amount of
+    /// `metric` spent in the innermost function. The lines are sorted and stacks where no
+    /// `metric` were spent are left out.
+    pub fn write_folded<W>(&self, mut writer: W, metric: Metric) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        let mut lines = self
+            .stacks
+            .iter()
+            .filter_map(|(stack, counts)| {
+                let count = match metric {
+                    Metric::Instructions => counts.instructions,
+                    Metric::AllocatedBytes => counts.allocated,
+                    Metric::Samples => counts.samples,
+                };
+                if count == 0 {
+                    return None;
+                }
+                let labels = stack
+                    .iter()
+                    .map(|&i| self.functions[i].label())
+                    .collect::<Vec<_>>();
+                Some((labels.join(";"), count))
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        for (stack, count) in lines {
+            writeln!(writer, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays the profile as a table, see `Profile::functions` for the order of the functions
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(interval) = self.sample_interval {
+            writeln!(f, "Sampled every {} instructions", interval)?;
+            writeln!(f, "{:>10} {:>14}  function", "samples", "self samples")?;
+            for function in self.functions() {
+                writeln!(
+                    f,
+                    "{:>10} {:>14}  {}",
+                    function.inclusive_samples,
+                    function.exclusive_samples,
+                    function.label()
+                )?;
+            }
+            return Ok(());
+        }
+        writeln!(
+            f,
+            "{:>10} {:>14} {:>14} {:>14} {:>14}  function",
+            "calls", "instructions", "self", "bytes", "self bytes"
+        )?;
+        for function in self.functions() {
+            writeln!(
+                f,
+                "{:>10} {:>14} {:>14} {:>14} {:>14}  {}",
+                function.calls,
+                function.inclusive_instructions,
+                function.exclusive_instructions,
+                function.inclusive_allocated,
+                function.exclusive_allocated,
+                function.label()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// A function call which has not returned yet
+#[derive(Debug)]
+struct Frame {
+    function: usize,
+    // The number of frames on the stack while the function executes
+    depth: usize,
+    // The counters of the thread when the function were called
+    instructions: u64,
+    allocated: u64,
+    // The inclusive counts of the functions called by this function
+    child_instructions: u64,
+    child_allocated: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Counters {
+    instructions: u64,
+    allocated: u64,
+}
+
+#[derive(Default)]
+struct State {
+    profile: Profile,
+    // Maps the name, source name and line of a function to its index in `profile.functions`
+    indexes: FnvMap<(String, Option<String>, Option<Line>), usize>,
+    // The calls which are in progress in each thread
+    frames: FnvMap<usize, Vec<Frame>>,
+}
+
+impl State {
+    fn function_index(&mut self, name: &str, frame: &StackInfo) -> usize {
+        let name = name.to_string();
+        let line = frame.function_line();
+        let source_name = if line.is_some() {
+            Some(frame.source_name().to_string())
+        } else {
+            None
+        };
+        let functions = &mut self.profile.functions;
+        *self
+            .indexes
+            .entry((name.clone(), source_name.clone(), line))
+            .or_insert_with(|| {
+                functions.push(FunctionProfile {
+                    name,
+                    source_name,
+                    line,
+                    calls: 0,
+                    inclusive_instructions: 0,
+                    exclusive_instructions: 0,
+                    inclusive_allocated: 0,
+                    exclusive_allocated: 0,
+                    inclusive_samples: 0,
+                    exclusive_samples: 0,
+                });
+                functions.len() - 1
+            })
+    }
+
+    fn on_sample(&mut self, info: &DebugInfo) {
+        let mut stack = Vec::with_capacity(info.stack_info_len());
+        for level in (0..info.stack_info_len()).rev() {
+            let frame = match info.stack_info(level) {
+                Some(frame) => frame,
+                None => continue,
+            };
+            if let Some(name) = frame.function_name() {
+                stack.push(self.function_index(name, &frame));
+            }
+        }
+        let innermost = match stack.last() {
+            Some(&innermost) => innermost,
+            None => return,
+        };
+
+        self.profile.functions[innermost].exclusive_samples += 1;
+        for (i, &function) in stack.iter().enumerate() {
+            // Recursive calls are only counted once
+            if !stack[..i].contains(&function) {
+                self.profile.functions[function].inclusive_samples += 1;
+            }
+        }
+        self.profile.stacks.entry(stack).or_default().samples += 1;
+    }
+
+    fn on_event(&mut self, thread: &Thread, info: &DebugInfo) {
+        if info.state().contains(HookFlags::SAMPLE_FLAG) {
+            self.on_sample(info);
+            return;
+        }
+
+        let frame = match info.stack_info(0) {
+            Some(frame) => frame,
+            None => return,
+        };
+        let name = match frame.function_name() {
+            Some(name) => name,
+            None => return,
+        };
+        let depth = info.stack_info_len();
+        let counters = Counters {
+            instructions: info.executed_instructions(),
+            allocated: info.allocated_memory(),
+        };
+        let thread_id = thread as *const Thread as usize;
+        let mut frames = self.frames.remove(&thread_id).unwrap_or_default();
+
+        if info.state().contains(HookFlags::CALL_FLAG) {
+            // Any frames at the same depth or deeper have been exited without the return hook
+            // running (due to an error)
+            self.exit_frames(&mut frames, depth, counters);
+
+            let function = self.function_index(name, &frame);
+            self.profile.functions[function].calls += 1;
+            frames.push(Frame {
+                function,
+                depth,
+                instructions: counters.instructions,
+                allocated: counters.allocated,
+                child_instructions: 0,
+                child_allocated: 0,
+            });
+        } else if info.state().contains(HookFlags::RETURN_FLAG) {
+            self.exit_frames(&mut frames, depth, counters);
+        }
+
+        if !frames.is_empty() {
+            self.frames.insert(thread_id, frames);
+        }
+    }
+
+    /// Records the frames at `depth` or deeper as exited
+    fn exit_frames(&mut self, frames: &mut Vec<Frame>, depth: usize, counters: Counters) {
+        while frames.last().is_some_and(|frame| frame.depth >= depth) {
+            let frame = frames.pop().unwrap();
+            let instructions = counters.instructions - frame.instructions;
+            let allocated = counters.allocated - frame.allocated;
+            let exclusive_instructions = instructions - frame.child_instructions;
+            let exclusive_allocated = allocated - frame.child_allocated;
+
+            let function = &mut self.profile.functions[frame.function];
+            function.exclusive_instructions += exclusive_instructions;
+            function.exclusive_allocated += exclusive_allocated;
+            // Recursive calls are already included in the outermost call
+            if frames.iter().all(|f| f.function != frame.function) {
+                function.inclusive_instructions += instructions;
+                function.inclusive_allocated += allocated;
+            }
+
+            let mut stack = frames.iter().map(|f| f.function).collect::<Vec<_>>();
+            stack.push(frame.function);
+            let counts = self.profile.stacks.entry(stack).or_default();
+            counts.instructions += exclusive_instructions;
+            counts.allocated += exclusive_allocated;
+
+            if let Some(parent) = frames.last_mut() {
+                parent.child_instructions += instructions;
+                parent.child_allocated += allocated;
+            }
+        }
+    }
+}
+
+/// A profiler which can be attached to one or more threads. Cloning a `Profiler` returns a handle
+/// to the same statistics.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    state: Arc<Mutex<State>>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    /// Installs the hook of this profiler on `thread`, replacing any previous hook
+    pub fn attach(&self, thread: &Thread) {
+        self.attach_with(thread, HookFlags::CALL_FLAG | HookFlags::RETURN_FLAG);
+    }
+
+    /// Installs the hook of this profiler on `thread`, replacing any previous hook, and records
+    /// the stack of `thread` after every `interval` instructions instead of every call.
+    ///
+    /// A profiler should not be attached both with `attach` and `attach_sampling`.
+    pub fn attach_sampling(&self, thread: &Thread, interval: u64) {
+        let interval = interval.max(1);
+        self.state.lock().unwrap().profile.sample_interval = Some(interval);
+        self.attach_with(thread, HookFlags::SAMPLE_FLAG);
+        thread.context().set_sample_interval(interval);
+    }
+
+    fn attach_with(&self, thread: &Thread, flags: HookFlags) {
+        let profiler = self.clone();
+        let mut context = thread.context();
+        context.set_hook(Some(Box::new(move |thread, info| {
+            profiler.on_event(thread, info)
+        })));
+        context.set_hook_mask(flags);
+    }
+
+    /// Removes the hook of any profiler from `thread`
+    pub fn detach(thread: &Thread) {
+        let mut context = thread.context();
+        context.set_hook(None);
+        context.set_hook_mask(HookFlags::empty());
+    }
+
+    /// Returns the statistics of all calls that have returned
+    pub fn profile(&self) -> Profile {
+        self.state.lock().unwrap().profile.clone()
+    }
+
+    /// Removes all collected statistics
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.profile = Profile {
+            sample_interval: state.profile.sample_interval,
+            ..Profile::default()
+        };
+        state.indexes.clear();
+        state.frames.clear();
+    }
+
+    fn on_event(&self, thread: &Thread, info: DebugInfo) -> Result<Async<()>> {
+        self.state.lock().unwrap().on_event(thread, &info);
+        Ok(Async::Ready(()))
+    }
+}
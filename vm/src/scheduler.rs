@@ -0,0 +1,627 @@
+//! A work-stealing scheduler which runs gluon threads on a fixed pool of OS threads.
+//!
+//! Each gluon thread spawned on the scheduler becomes a task. A task runs until it finishes,
+//! waits on a future returned from an extern function (it is then blocked until the future wakes
+//! it up), yields (`std.thread.yield`) or has executed `quantum` instructions. Yielding and
+//! preempted tasks are put back at the end of their queue so a thread which never yields can not
+//! starve the others.
+//!
+//! Tasks with a higher `Priority` are always run before tasks with a lower priority. Runnable tasks
+//! are put in a global queue for their priority from which the workers take batches of tasks into
+//! their own queues. A worker whose queues are empty steals tasks from the other workers.
+//!
+//! A deterministic scheduler has no workers. Its tasks are instead run, one at a time and in the
+//! order they became runnable, on the thread calling `run_until_idle` or `join`, which makes the
+//! interleaving of the tasks reproducible.
+use crate::real_std::{
+    cell::RefCell,
+    iter,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self as os_thread, JoinHandle},
+};
+
+use crossbeam_deque::{Injector, Steal, Stealer, Worker};
+use futures::{
+    executor::{self, Notify, NotifyHandle, Spawn},
+    Async,
+};
+use slab::Slab;
+
+use crate::{
+    api::{Function, OpaqueValue, WithVM, IO},
+    channel::spawn_thread,
+    thread::{Execute, RootedValue, VmRootInternal},
+    vm::{RootedThread, Thread},
+    Error, ExternModule, Result,
+};
+
+/// How many instructions a thread executes before it is preempted, unless it is changed with
+/// `SchedulerBuilder::quantum`
+pub const DEFAULT_QUANTUM: u64 = 10_000;
+
+const PRIORITIES: usize = 3;
+
+/// The priority of a task. Runnable tasks of a higher priority are always run before tasks of a
+/// lower priority. Mirrors `std.scheduler.Priority`.
+#[derive(Getable, VmType, Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[gluon(gluon_vm)]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl Priority {
+    // The index of the queues for this priority, the queues are searched in index order
+    fn index(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// Identifies a task. The slot of a task is reused once the task has been joined or detached so
+/// the id also records which generation of the slot it refers to, making ids of removed tasks
+/// refer to no task instead of to whichever task was spawned in the slot afterwards.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct TaskId {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TaskState {
+    /// Waiting in a queue to be run
+    Runnable,
+    /// Currently executed by a worker
+    Running,
+    /// Waiting on a future returned from an extern function
+    Blocked,
+    /// Finished, the value can be retrieved with `Scheduler::join`
+    Finished,
+    /// Stopped with an error which is returned from `Scheduler::join`
+    Failed,
+}
+
+/// Information about a task, returned from `Scheduler::tasks`
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub priority: Priority,
+    pub state: TaskState,
+    /// How many times the task has been run, including when it was resumed after being
+    /// preempted, yielding or being woken up
+    pub polls: u64,
+}
+
+struct Task {
+    generation: u64,
+    thread: RootedThread,
+    // `None` while the task runs or after it has finished
+    execute: Option<Spawn<Execute<RootedThread>>>,
+    priority: Priority,
+    state: TaskState,
+    // Set if the task is woken up while it is running
+    notified: bool,
+    // Detached tasks are removed as soon as they finish as nothing will join them
+    detached: bool,
+    // Wakes up this task. Each task has its own handle so that a future which outlives the task
+    // can not wake up a task which was spawned in its slot later
+    notify: NotifyHandle,
+    polls: u64,
+    result: Option<Result<RootedValue<RootedThread>>>,
+}
+
+impl Task {
+    fn info(&self, index: usize) -> TaskInfo {
+        TaskInfo {
+            id: TaskId {
+                index,
+                generation: self.generation,
+            },
+            priority: self.priority,
+            state: self.state,
+            polls: self.polls,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    tasks: Slab<Task>,
+    next_generation: u64,
+}
+
+impl State {
+    fn task(&self, id: TaskId) -> Option<&Task> {
+        self.tasks
+            .get(id.index)
+            .filter(|task| task.generation == id.generation)
+    }
+
+    fn task_mut(&mut self, id: TaskId) -> Option<&mut Task> {
+        self.tasks
+            .get_mut(id.index)
+            .filter(|task| task.generation == id.generation)
+    }
+
+    fn remove(&mut self, id: TaskId) -> Option<Task> {
+        self.task(id)?;
+        Some(self.tasks.remove(id.index))
+    }
+}
+
+struct Queues([Worker<TaskId>; PRIORITIES]);
+
+impl Queues {
+    fn new() -> Queues {
+        Queues([Worker::new_fifo(), Worker::new_fifo(), Worker::new_fifo()])
+    }
+
+    fn stealers(&self) -> [Stealer<TaskId>; PRIORITIES] {
+        [
+            self.0[0].stealer(),
+            self.0[1].stealer(),
+            self.0[2].stealer(),
+        ]
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Signaled when a task is queued or the scheduler shuts down
+    work_available: Condvar,
+    // Signaled when a task stops running or is removed
+    task_changed: Condvar,
+    injectors: [Injector<TaskId>; PRIORITIES],
+    // The stealers of each worker, indexed by the index of the worker
+    stealers: Vec<[Stealer<TaskId>; PRIORITIES]>,
+    quantum: Option<u64>,
+    // Checked before each task is run as the queues may never be empty
+    shutdown: AtomicBool,
+}
+
+thread_local! {
+    // The scheduler and task which is running on this OS thread
+    static CURRENT: RefCell<Option<(Arc<Shared>, TaskId)>> = const { RefCell::new(None) };
+}
+
+struct TaskNotify {
+    shared: Arc<Shared>,
+    id: TaskId,
+}
+
+impl Notify for TaskNotify {
+    fn notify(&self, _: usize) {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(task) = state.task_mut(self.id) {
+            match task.state {
+                TaskState::Blocked => {
+                    task.state = TaskState::Runnable;
+                    self.shared.push(self.id, task.priority);
+                }
+                TaskState::Running => task.notified = true,
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn spawn(self: &Arc<Self>, thread: RootedThread, priority: Priority, detached: bool) -> TaskId {
+        thread.set_quantum(self.quantum);
+        let execute = executor::spawn(Execute::new(thread.clone()));
+
+        let mut state = self.state.lock().unwrap();
+        let generation = state.next_generation;
+        state.next_generation += 1;
+        let entry = state.tasks.vacant_entry();
+        let id = TaskId {
+            index: entry.key(),
+            generation,
+        };
+        entry.insert(Task {
+            generation,
+            thread,
+            execute: Some(execute),
+            priority,
+            state: TaskState::Runnable,
+            notified: false,
+            detached,
+            notify: NotifyHandle::from(Arc::new(TaskNotify {
+                shared: self.clone(),
+                id,
+            })),
+            polls: 0,
+            result: None,
+        });
+        self.push(id, priority);
+        id
+    }
+
+    // Must be called while `state` is locked so that sleeping workers can not miss the task.
+    // Tasks are always queued in the global queue so that a preempted task is run after every
+    // task which was already queued, and not just after the tasks queued on its worker.
+    fn push(&self, id: TaskId, priority: Priority) {
+        self.injectors[priority.index()].push(id);
+        self.work_available.notify_one();
+    }
+
+    fn find_task(&self, local: Option<&Queues>) -> Option<TaskId> {
+        (0..PRIORITIES).find_map(|priority| {
+            if let Some(id) = local.and_then(|local| local.0[priority].pop()) {
+                return Some(id);
+            }
+            iter::repeat_with(|| {
+                let steal = match local {
+                    Some(local) => self.injectors[priority].steal_batch_and_pop(&local.0[priority]),
+                    None => self.injectors[priority].steal(),
+                };
+                steal.or_else(|| {
+                    self.stealers
+                        .iter()
+                        .map(|stealers| stealers[priority].steal())
+                        .collect()
+                })
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(Steal::success)
+        })
+    }
+
+    fn has_queued_tasks(&self) -> bool {
+        self.injectors.iter().any(|injector| !injector.is_empty())
+            || self
+                .stealers
+                .iter()
+                .any(|stealers| stealers.iter().any(|stealer| !stealer.is_empty()))
+    }
+
+    fn run_task(self: &Arc<Self>, id: TaskId) {
+        let (mut execute, thread, notify) = {
+            let mut state = self.state.lock().unwrap();
+            let task = match state.task_mut(id) {
+                Some(task) if task.state == TaskState::Runnable => task,
+                _ => return,
+            };
+            task.state = TaskState::Running;
+            task.notified = false;
+            task.polls += 1;
+            (
+                task.execute.take().expect("Runnable task"),
+                task.thread.clone(),
+                task.notify.clone(),
+            )
+        };
+
+        let previous = CURRENT.with(|current| current.replace(Some((self.clone(), id))));
+        let result = execute.poll_future_notify(&notify, 0);
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+
+        // A thread which yielded or was preempted can continue immediately while a thread which
+        // waits on a future is queued again once the future wakes it up
+        let runnable = match result {
+            Ok(Async::NotReady) => !thread.is_waiting_on_future(),
+            _ => false,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let task = state.task_mut(id).expect("Running task");
+        match result {
+            Ok(Async::NotReady) => {
+                task.execute = Some(execute);
+                if runnable || task.notified {
+                    task.state = TaskState::Runnable;
+                    self.push(id, task.priority);
+                } else {
+                    task.state = TaskState::Blocked;
+                }
+            }
+            Ok(Async::Ready(value)) => {
+                task.state = TaskState::Finished;
+                task.result = Some(Ok(value));
+            }
+            Err(err) => {
+                task.state = TaskState::Failed;
+                task.result = Some(Err(err));
+            }
+        }
+        if task.detached && task.result.is_some() {
+            if let Some(Some(Err(err))) = state.remove(id).map(|task| task.result) {
+                warn!("Detached task {:?} failed: {}", id, err);
+            }
+        }
+        self.task_changed.notify_all();
+    }
+
+    fn run_worker(self: Arc<Self>, local: Queues) {
+        while !self.shutdown.load(Ordering::SeqCst) {
+            match self.find_task(Some(&local)) {
+                Some(id) => self.run_task(id),
+                None => {
+                    let state = self.state.lock().unwrap();
+                    if self.shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if !self.has_queued_tasks() {
+                        drop(self.work_available.wait(state).unwrap());
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct SchedulerBuilder {
+    worker_threads: Option<usize>,
+    quantum: Option<u64>,
+    deterministic: bool,
+}
+
+impl Default for SchedulerBuilder {
+    fn default() -> Self {
+        SchedulerBuilder {
+            worker_threads: None,
+            quantum: Some(DEFAULT_QUANTUM),
+            deterministic: false,
+        }
+    }
+}
+
+impl SchedulerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many OS threads run the tasks (default: the number of CPUs)
+    pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = Some(worker_threads);
+        self
+    }
+
+    /// Sets how many instructions a thread may execute before it is preempted. `None` disables
+    /// preemption (default: `Some(DEFAULT_QUANTUM)`)
+    pub fn quantum(mut self, quantum: Option<u64>) -> Self {
+        self.quantum = quantum;
+        self
+    }
+
+    /// Runs all tasks on the thread which calls `Scheduler::run_until_idle` or `Scheduler::join`
+    /// instead of on worker threads, making the order the tasks are run in reproducible
+    /// (default: false)
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    pub fn build(self) -> Scheduler {
+        let worker_threads = if self.deterministic {
+            0
+        } else {
+            self.worker_threads.unwrap_or_else(num_cpus::get).max(1)
+        };
+        let queues: Vec<_> = (0..worker_threads).map(|_| Queues::new()).collect();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State::default()),
+            work_available: Condvar::new(),
+            task_changed: Condvar::new(),
+            injectors: [Injector::new(), Injector::new(), Injector::new()],
+            stealers: queues.iter().map(Queues::stealers).collect(),
+            quantum: self.quantum,
+            shutdown: AtomicBool::new(false),
+        });
+        let workers = queues
+            .into_iter()
+            .enumerate()
+            .map(|(i, local)| {
+                let shared = shared.clone();
+                os_thread::Builder::new()
+                    .name(format!("gluon-worker-{}", i))
+                    .spawn(move || shared.run_worker(local))
+                    .expect("Unable to spawn worker thread")
+            })
+            .collect();
+        Scheduler { shared, workers }
+    }
+}
+
+/// Runs gluon threads on a pool of worker threads. See the module documentation.
+pub struct Scheduler {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        {
+            let _state = self.shared.state.lock().unwrap();
+            self.shared.work_available.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        // The tasks keep the scheduler alive through their `NotifyHandle`s
+        self.shared.state.lock().unwrap().tasks.clear();
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        SchedulerBuilder::new().build()
+    }
+
+    /// Spawns a task which calls `function` on a new child thread of the thread of `function`
+    pub fn spawn<T, R>(
+        &self,
+        function: &Function<T, fn(()) -> R>,
+        priority: Priority,
+    ) -> Result<TaskId>
+    where
+        T: VmRootInternal,
+    {
+        let thread = spawn_thread(function.vm(), function.get_variant())?;
+        Ok(self.shared.spawn(thread, priority, false))
+    }
+
+    /// Spawns a task which runs the IO `action` on a new child thread of the thread of `action`
+    pub fn spawn_io<T, R>(
+        &self,
+        action: &OpaqueValue<T, IO<R>>,
+        priority: Priority,
+    ) -> Result<TaskId>
+    where
+        T: VmRootInternal,
+    {
+        let thread = spawn_thread(action.vm(), action.get_variant())?;
+        Ok(self.shared.spawn(thread, priority, false))
+    }
+
+    /// Changes the priority of a task. Takes effect the next time the task is queued.
+    pub fn set_priority(&self, id: TaskId, priority: Priority) {
+        if let Some(task) = self.shared.state.lock().unwrap().task_mut(id) {
+            task.priority = priority;
+        }
+    }
+
+    /// Returns information about the task, or `None` if it has been joined or detached and
+    /// finished
+    pub fn task(&self, id: TaskId) -> Option<TaskInfo> {
+        let state = self.shared.state.lock().unwrap();
+        state.task(id).map(|task| task.info(id.index))
+    }
+
+    /// Returns information about all tasks which have not been joined
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        let state = self.shared.state.lock().unwrap();
+        state.tasks.iter().map(|(id, task)| task.info(id)).collect()
+    }
+
+    /// Removes the task once it finishes instead of keeping its result for `join`
+    pub fn detach(&self, id: TaskId) {
+        let mut state = self.shared.state.lock().unwrap();
+        let finished = match state.task_mut(id) {
+            Some(task) => {
+                task.detached = true;
+                task.result.is_some()
+            }
+            None => false,
+        };
+        if finished {
+            state.remove(id);
+        }
+    }
+
+    /// Waits until no task is runnable or running. Tasks which are blocked on a future may still
+    /// exist.
+    pub fn run_until_idle(&self) {
+        if self.workers.is_empty() {
+            while let Some(id) = self.shared.find_task(None) {
+                self.shared.run_task(id);
+            }
+        } else {
+            let mut state = self.shared.state.lock().unwrap();
+            while state
+                .tasks
+                .iter()
+                .any(|(_, task)| matches!(task.state, TaskState::Runnable | TaskState::Running))
+            {
+                state = self.shared.task_changed.wait(state).unwrap();
+            }
+        }
+    }
+
+    /// Waits for the task to finish and returns its value
+    pub fn join(&self, id: TaskId) -> Result<RootedValue<RootedThread>> {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            match state.task(id) {
+                Some(task) if !task.detached => {
+                    if task.result.is_some() {
+                        return state.remove(id).and_then(|task| task.result).unwrap();
+                    }
+                }
+                _ => return Err(Error::Message(format!("Task {:?} can not be joined", id))),
+            }
+            state = self.wait(state);
+        }
+    }
+
+    // Runs a task if the scheduler is deterministic, otherwise waits for a task to change state
+    fn wait<'a>(&'a self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        if !self.workers.is_empty() {
+            return self.shared.task_changed.wait(state).unwrap();
+        }
+        if self.shared.has_queued_tasks() {
+            drop(state);
+            if let Some(id) = self.shared.find_task(None) {
+                self.shared.run_task(id);
+            }
+            self.shared.state.lock().unwrap()
+        } else {
+            // Every task is blocked so wait for a future to wake one of them
+            self.shared.work_available.wait(state).unwrap()
+        }
+    }
+}
+
+fn with_current<T>(f: impl FnOnce(&Arc<Shared>, TaskId) -> T) -> IO<T> {
+    CURRENT.with(|current| match *current.borrow() {
+        Some((ref shared, id)) => IO::Value(f(shared, id)),
+        None => IO::Exception("The thread is not run by a scheduler".into()),
+    })
+}
+
+fn spawn(priority: Priority, action: WithVM<OpaqueValue<&Thread, IO<()>>>) -> IO<()> {
+    // The thread is only created once it is known that a scheduler will run it
+    let spawned = with_current(|shared, _| {
+        spawn_thread(action.vm, action.value.get_variant()).map(|thread| {
+            shared.spawn(thread, priority, true);
+        })
+    });
+    match spawned {
+        IO::Value(result) => result.into(),
+        IO::Exception(err) => IO::Exception(err),
+    }
+}
+
+fn set_priority(priority: Priority) -> IO<()> {
+    with_current(|shared, id| {
+        if let Some(task) = shared.state.lock().unwrap().task_mut(id) {
+            task.priority = priority;
+        }
+    })
+}
+
+mod std {
+    pub mod scheduler {
+        pub use crate::scheduler as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> Result<ExternModule> {
+    ExternModule::new(
+        vm,
+        record! {
+            type Priority => Priority,
+            spawn => primitive!(2, "std.scheduler.prim.spawn", std::scheduler::prim::spawn),
+            set_priority => primitive!(
+                1,
+                "std.scheduler.prim.set_priority",
+                std::scheduler::prim::set_priority
+            ),
+        },
+    )
+}
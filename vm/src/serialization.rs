@@ -9,9 +9,10 @@ use crate::serde::{
 };
 
 use crate::base::{
+    fnv::FnvSet,
     serialization::{NodeMap, NodeToId, SharedSeed},
     symbol::{Symbol, Symbols},
-    types::ArcType,
+    types::{walk_type, AliasData, ArcType, Type},
 };
 
 use crate::{
@@ -46,6 +47,47 @@ impl<'de, 'gc> DeSeed<'gc> {
         }
     }
 
+    /// Makes deserialized types refer to `symbol` instead of a new symbol with the same name
+    pub fn add_symbol(&mut self, symbol: Symbol) {
+        self.base_seed.add_symbol(symbol);
+    }
+
+    /// Makes deserialized types refer to the aliases in `group` instead of new aliases with the
+    /// same names
+    pub fn add_alias_group(&mut self, group: &Arc<[AliasData<Symbol, ArcType>]>) {
+        self.base_seed.add_alias_group(group);
+    }
+
+    /// Makes deserialized types refer to every alias (and type name) that is reachable from
+    /// `types`. Aliases are compared by identity so values which are deserialized separately from
+    /// the modules they refer to must be deserialized with the types of those modules added.
+    pub fn add_aliases(&mut self, types: &[ArcType]) {
+        let mut visited = FnvSet::default();
+        let mut stack = types.to_vec();
+        while let Some(typ) = stack.pop() {
+            walk_type(&typ, |typ: &ArcType| {
+                let groups = match **typ {
+                    Type::Alias(ref alias) => vec![alias.group.clone()],
+                    // `walk_type` only visits the aliased types of the type fields
+                    Type::ExtendTypeRow { ref types, .. } => {
+                        types.iter().map(|field| field.typ.group.clone()).collect()
+                    }
+                    Type::Ident(ref id) => {
+                        self.add_symbol(id.clone());
+                        Vec::new()
+                    }
+                    _ => Vec::new(),
+                };
+                for group in groups {
+                    if visited.insert(&*group as *const [_] as *const ()) {
+                        self.add_alias_group(&group);
+                        stack.extend(group.iter().map(|alias| alias.unresolved_type().clone()));
+                    }
+                }
+            });
+        }
+    }
+
     pub fn deserialize<D, T>(mut self, deserializer: D) -> Result<T, D::Error>
     where
         D: Deserializer<'de>,
@@ -10,9 +10,10 @@ use std::{
     string::String as StdString,
     sync::{
         self,
-        atomic::{self, AtomicBool},
+        atomic::{self, AtomicBool, AtomicU64},
         Arc, Mutex, MutexGuard, RwLock,
     },
+    time::Instant,
     usize,
 };
 
@@ -31,10 +32,13 @@ use crate::base::{
 use crate::{
     api::{Getable, Pushable, ValueRef, VmType},
     compiler::UpvarInfo,
-    gc::{self, CloneUnrooted, DataDef, Gc, GcPtr, GcRef, Generation, Move},
+    gc::{
+        self, snapshot::HeapSnapshot, CloneUnrooted, DataDef, Gc, GcPtr, GcRef, GcStats,
+        Generation, Move,
+    },
     interner::InternedStr,
     macros::MacroEnv,
-    source_map::LocalIter,
+    source_map::{Local, LocalIter},
     stack::{
         ClosureState, ExternCallState, ExternState, Frame, Stack, StackFrame, StackState, State,
     },
@@ -342,11 +346,12 @@ unsafe impl<'b> Trace for Roots<'b> {
     fn trace(&self, gc: &mut Gc) {
         // Since this vm's stack is already borrowed in self we need to manually mark it to prevent
         // it from being traced normally
-        gc.mark(&self.vm);
-        self.stack.trace(gc);
+        gc.mark_with(self.vm, |gc| {
+            self.stack.trace(gc);
 
-        // Traverse the vm's fields, avoiding the stack which is traced above
-        self.vm.trace_fields_except_stack(gc);
+            // Traverse the vm's fields, avoiding the stack which is traced above
+            self.vm.trace_fields_except_stack(gc);
+        });
     }
 }
 
@@ -365,9 +370,10 @@ impl<'b> crate::gc::CollectScope for Roots<'b> {
 
             sweep(gc);
 
-            // `sweep` all child gcs
+            // `sweep` all child gcs, as part of the pause of this gc
             for (_, mut context, _) in locks {
-                context.gc.sweep();
+                let swept = context.gc.sweep();
+                gc.add_pause_work(swept);
             }
         }
     }
@@ -397,7 +403,12 @@ impl<'b> Roots<'b> {
 
             let thread = &*(&*thread_ptr as *const Thread);
 
-            let context = thread.context.lock().unwrap();
+            let mut context = thread.context.lock().unwrap();
+            // The marks of an incremental collection in the child would stop us from tracing
+            // through its values
+            context.gc.abort_incremental();
+            // Finalizers are only run by the thread which owns the values
+            context.gc.trace_finalizers(gc);
 
             let child_threads = thread.child_threads.read().unwrap();
             stack.extend(child_threads.iter().map(|(_, t)| t.clone()));
@@ -484,7 +495,11 @@ unsafe impl Trace for Thread {
     }
     fn trace(&self, gc: &mut Gc) {
         self.trace_fields_except_stack(gc);
-        self.context.lock().unwrap().stack.trace(gc);
+        // An incremental collection traces the stacks of child threads once it finishes marking
+        // (see `Roots::mark_child_roots`) as they may change until then
+        if !gc.is_marking() {
+            self.context.lock().unwrap().stack.trace(gc);
+        }
     }
 }
 
@@ -733,10 +748,20 @@ impl Thread {
     /// Spawns a new gluon thread with its own stack and heap but while still sharing the same
     /// global environment
     pub fn new_thread(&self) -> Result<RootedThread> {
+        // Child threads share the fuel and deadline of their parent so they can not be used to
+        // escape them
+        let context = {
+            let parent = self.owned_context();
+            let mut context = Context::new(parent.gc.new_child_gc());
+            context.limits = parent.limits.clone();
+            // Only threads which are run by a `Scheduler` may be preempted
+            context.limits.quantum = None;
+            context
+        };
         let vm = Thread {
             global_state: self.global_state.clone(),
             parent: Some(unsafe { GcPtr::from_raw(self) }),
-            context: Mutex::new(Context::new(self.owned_context().gc.new_child_gc())),
+            context: Mutex::new(context),
             rooted_values: RwLock::new(Vec::new()),
             child_threads: Default::default(),
             interrupt: AtomicBool::new(false),
@@ -906,10 +931,28 @@ impl Thread {
         self.global_env().get_macros()
     }
 
-    /// Runs a garbage collection.
+    /// Runs a garbage collection, followed by the finalizers of the values it found to be
+    /// unreachable.
     pub fn collect(&self) {
-        let mut context = self.owned_context();
-        self.collect_with_context(&mut context);
+        {
+            let mut context = self.owned_context();
+            self.collect_with_context(&mut context);
+        }
+        self.run_finalizers();
+    }
+
+    /// Runs the finalizers (`Userdata::finalize`) of the values which the garbage collector of this
+    /// thread has found to be unreachable. This is done automatically whenever the thread calls a
+    /// Rust function and after `collect`.
+    pub fn run_finalizers(&self) {
+        loop {
+            let value = match self.owned_context().gc.start_finalizer() {
+                Some(value) => value,
+                None => break,
+            };
+            value.finalize(self);
+            self.owned_context().gc.finish_finalizer(&value);
+        }
     }
 
     fn collect_with_context(&self, context: &mut OwnedContext) {
@@ -941,6 +984,93 @@ impl Thread {
         self.owned_context().gc.set_memory_limit(memory_limit)
     }
 
+    /// Returns a snapshot of the values reachable from this thread. See `gc::snapshot` for a
+    /// description of the snapshot and its JSON format.
+    pub fn heap_snapshot(&self) -> HeapSnapshot {
+        let mut context = self.owned_context();
+        self.with_roots(&mut context, |gc, roots| gc.snapshot(roots))
+    }
+
+    /// Returns statistics about the allocations and collections done by the garbage collector of
+    /// this thread
+    pub fn gc_stats(&self) -> GcStats {
+        self.owned_context().gc.stats()
+    }
+
+    /// Must be called before `value` is stored into a value allocated by this thread, such as a
+    /// reference, outside of the interpreter. See `Gc::write_barrier`.
+    pub(crate) fn write_barrier(&self, value: &Value) {
+        self.owned_context().gc.write_barrier(value)
+    }
+
+    /// Sets how many instructions the thread may execute before it stops with
+    /// `Error::OutOfFuel`. `None` removes the limit. Threads created afterwards with `new_thread`
+    /// draw from the same fuel as this thread (and start with the same deadline) so a script can
+    /// not get more fuel by spawning threads.
+    ///
+    /// The thread stops before executing the instruction which it has no fuel for so if
+    /// `Error::OutOfFuel` is returned by a future (such as `Execute`) which drives the thread, the
+    /// future can be polled again after adding more fuel to resume the execution. This is not
+    /// possible if fuel ran out while a function is called from Rust code (such as the function
+    /// passed to `std.array.map`) since that call fails with the error.
+    pub fn set_fuel(&self, fuel: Option<u64>) {
+        self.owned_context().limits.fuel = fuel.map(|fuel| Arc::new(AtomicU64::new(fuel)));
+    }
+
+    /// Adds `fuel` to the remaining fuel of the thread (and every thread sharing its fuel). Does
+    /// nothing if the fuel is unlimited.
+    pub fn add_fuel(&self, fuel: u64) {
+        if let Some(ref remaining) = self.owned_context().limits.fuel {
+            let _ = remaining.fetch_update(
+                atomic::Ordering::Relaxed,
+                atomic::Ordering::Relaxed,
+                |remaining| Some(remaining.saturating_add(fuel)),
+            );
+        }
+    }
+
+    /// Returns how many instructions the thread may still execute, if it is limited
+    pub fn fuel(&self) -> Option<u64> {
+        self.owned_context()
+            .limits
+            .fuel
+            .as_ref()
+            .map(|fuel| fuel.load(atomic::Ordering::Relaxed))
+    }
+
+    /// Sets a point in time after which the thread stops with `Error::DeadlineExceeded`. `None`
+    /// removes the deadline.
+    ///
+    /// The deadline is only checked between instructions and not while an extern function runs.
+    /// Like `set_fuel` the execution can be resumed after the deadline is moved.
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        self.owned_context().limits.deadline = deadline;
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.owned_context().limits.deadline
+    }
+
+    /// Makes the thread yield after executing `quantum` instructions. Only used for threads run
+    /// by a `Scheduler` as nothing else would wake the thread up again.
+    pub(crate) fn set_quantum(&self, quantum: Option<u64>) {
+        let mut context = self.owned_context();
+        context.limits.quantum = quantum;
+        context.limits.slice = quantum.unwrap_or(0);
+    }
+
+    /// Calls the function at `stack.len() - args - 1` once the thread is resumed
+    pub(crate) fn enter_call(&self, args: VmIndex) -> Result<()> {
+        let mut context = self.owned_context();
+        context.borrow_mut().do_call(args)?;
+        Ok(())
+    }
+
+    /// Returns `true` if the thread is suspended on a future returned from an extern function
+    pub(crate) fn is_waiting_on_future(&self) -> bool {
+        !self.context().poll_fns.is_empty()
+    }
+
     pub fn interrupt(&self) {
         self.interrupt.store(true, atomic::Ordering::Relaxed)
     }
@@ -1290,6 +1420,8 @@ pub type HookFn = Box<dyn FnMut(&Thread, DebugInfo) -> Result<Async<()>> + Send
 pub struct DebugInfo<'a> {
     stack: &'a Stack,
     state: HookFlags,
+    executed_instructions: u64,
+    allocated_memory: u64,
 }
 
 pub struct StackInfo<'a> {
@@ -1320,6 +1452,18 @@ impl<'a> DebugInfo<'a> {
     pub fn stack_info_len(&self) -> usize {
         self.stack.get_frames().len()
     }
+
+    /// Returns the number of instructions the thread has executed. Instructions are only counted
+    /// while a hook or a limit (such as `Thread::set_fuel`) is set.
+    pub fn executed_instructions(&self) -> u64 {
+        self.executed_instructions
+    }
+
+    /// Returns the number of bytes the thread has allocated, including memory which has since been
+    /// freed
+    pub fn allocated_memory(&self) -> u64 {
+        self.allocated_memory
+    }
 }
 
 impl<'a> StackInfo<'a> {
@@ -1352,6 +1496,16 @@ impl<'a> StackInfo<'a> {
         }
     }
 
+    /// Returns the line of the first instruction of the function executing at this frame
+    pub fn function_line(&self) -> Option<Line> {
+        match self.frame().state {
+            State::Closure(ClosureState { ref closure, .. }) => {
+                closure.function.debug_info.source_map.line(0)
+            }
+            _ => None,
+        }
+    }
+
     /// Returns the name of the source which defined the funtion executing at this frame
     pub fn source_name(&self) -> &str {
         match self.frame().state {
@@ -1396,6 +1550,27 @@ impl<'a> StackInfo<'a> {
             _ => ice!("Attempted to access upvar in non closure function"),
         }
     }
+
+    /// Returns an iterator over all locals available at the current executing instruction
+    /// together with their values
+    pub fn local_values(&self) -> impl Iterator<Item = (&Local, Variants<'_>)> {
+        let stack = self.info.stack;
+        let offset = self.frame().offset;
+        self.locals()
+            .filter_map(move |local| Some((local, stack.get_variant(offset + local.index)?)))
+    }
+
+    /// Returns an iterator over the values bound to this closure together with information about
+    /// them. Frames which are not executing a closure do not have any upvars.
+    pub fn upvar_values(&self) -> impl Iterator<Item = (&UpvarInfo, Variants<'_>)> {
+        let (infos, values): (&[UpvarInfo], &[Value]) = match self.frame().state {
+            State::Closure(ClosureState { ref closure, .. }) => {
+                (&closure.function.debug_info.upvars, &closure.upvars)
+            }
+            _ => (&[], &[]),
+        };
+        infos.iter().zip(values.iter().map(Variants::new))
+    }
 }
 
 bitflags::bitflags! {
@@ -1405,6 +1580,13 @@ bitflags::bitflags! {
         const LINE_FLAG = 0b01;
         /// Call the hook when a function is called
         const CALL_FLAG = 0b10;
+        /// Call the hook when a function returns, before its frame is removed. Execution can not
+        /// be suspended from this hook so returning `Async::NotReady` is the same as returning
+        /// `Async::Ready`
+        const RETURN_FLAG = 0b100;
+        /// Call the hook each time the number of instructions set with
+        /// `Context::set_sample_interval` have been executed
+        const SAMPLE_FLAG = 0b1000;
     }
 }
 
@@ -1414,6 +1596,78 @@ struct Hook {
     flags: HookFlags,
     // The index of the last executed instruction
     previous_instruction_index: usize,
+    executed_instructions: u64,
+    sample_interval: u64,
+}
+
+impl Hook {
+    // Instructions are only counted while a hook is active
+    fn is_active(&self) -> bool {
+        !self.flags.is_empty()
+    }
+}
+
+// How many instructions are executed between each check of the deadline (a power of two)
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+const DEFAULT_SAMPLE_INTERVAL: u64 = 1000;
+
+#[derive(Clone, Default)]
+struct Limits {
+    // The number of instructions that may be executed, shared with the threads spawned from this
+    // thread
+    fuel: Option<Arc<AtomicU64>>,
+    deadline: Option<Instant>,
+    // How many instructions the thread executes before yielding to the `Scheduler` running it
+    quantum: Option<u64>,
+    // The number of instructions left of the current quantum
+    slice: u64,
+}
+
+impl Limits {
+    fn is_active(&self) -> bool {
+        self.fuel.is_some() || self.deadline.is_some() || self.quantum.is_some()
+    }
+
+    fn check_deadline(&self) -> Result<()> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(Error::DeadlineExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    // Called before each instruction is executed
+    #[inline]
+    fn consume(&mut self, executed_instructions: u64) -> Result<()> {
+        if let Some(ref fuel) = self.fuel {
+            fuel.fetch_update(
+                atomic::Ordering::Relaxed,
+                atomic::Ordering::Relaxed,
+                |fuel| fuel.checked_sub(1),
+            )
+            .map_err(|_| Error::OutOfFuel)?;
+        }
+        if self.deadline.is_some() && executed_instructions & (DEADLINE_CHECK_INTERVAL - 1) == 0 {
+            self.check_deadline()?;
+        }
+        Ok(())
+    }
+
+    // Called before each instruction is executed, returns `true` once the quantum is used up
+    #[inline]
+    fn preempt(&mut self) -> bool {
+        match self.quantum {
+            Some(quantum) if self.slice == 0 => {
+                self.slice = quantum;
+                true
+            }
+            Some(_) => {
+                self.slice -= 1;
+                false
+            }
+            None => false,
+        }
+    }
 }
 
 struct PollFn {
@@ -1440,6 +1694,8 @@ pub struct Context {
     pub(crate) gc: Gc,
     #[cfg_attr(feature = "serde_derive", serde(skip))]
     hook: Hook,
+    #[cfg_attr(feature = "serde_derive", serde(skip))]
+    limits: Limits,
     max_stack_size: VmIndex,
 
     /// Stack of polling functions used for extern functions returning futures
@@ -1456,7 +1712,10 @@ impl Context {
                 function: None,
                 flags: HookFlags::empty(),
                 previous_instruction_index: usize::max_value(),
+                executed_instructions: 0,
+                sample_interval: DEFAULT_SAMPLE_INTERVAL,
             },
+            limits: Limits::default(),
             max_stack_size: VmIndex::max_value(),
             poll_fns: Vec::new(),
         }
@@ -1532,6 +1791,12 @@ impl Context {
         self.hook.flags = flags;
     }
 
+    /// Sets how many instructions are executed between each call of the hook when
+    /// `HookFlags::SAMPLE_FLAG` is set (at least 1)
+    pub fn set_sample_interval(&mut self, interval: u64) {
+        self.hook.sample_interval = interval.max(1);
+    }
+
     pub fn set_max_stack_size(&mut self, limit: VmIndex) {
         self.max_stack_size = limit;
     }
@@ -1596,6 +1861,8 @@ impl<'b> OwnedContext<'b> {
         DebugInfo {
             stack: &self.stack,
             state: HookFlags::empty(),
+            executed_instructions: self.hook.executed_instructions,
+            allocated_memory: self.gc.total_allocated_memory(),
         }
     }
 
@@ -1681,6 +1948,7 @@ impl<'b> OwnedContext<'b> {
             if context.thread.interrupted() {
                 return Err(Error::Interrupted);
             }
+            context.limits.check_deadline()?;
             trace!("STACK\n{:?}", context.stack.stack().get_frames());
             let state = &context.stack.frame().state;
 
@@ -1699,6 +1967,8 @@ impl<'b> OwnedContext<'b> {
                             let info = DebugInfo {
                                 stack: &context.stack.stack(),
                                 state: HookFlags::CALL_FLAG,
+                                executed_instructions: context.hook.executed_instructions,
+                                allocated_memory: context.gc.total_allocated_memory(),
                             };
                             try_ready!(hook(thread, info))
                         }
@@ -1797,7 +2067,11 @@ impl<'b> OwnedContext<'b> {
                 // Make sure that the stack is not borrowed during the external function call
                 // Necessary since we do not know what will happen during the function call
                 let thread = self.thread;
+                let run_finalizers = self.gc.has_pending_finalizers();
                 drop(self);
+                if run_finalizers {
+                    thread.run_finalizers();
+                }
                 status = (function.function)(thread);
 
                 if status == Status::Yield {
@@ -1880,6 +2154,7 @@ impl<'b> OwnedContext<'b> {
                 function.id
             )
         }
+        self.borrow_mut().run_return_hook()?;
         self = self.exit_scope().map_err(|_| {
             Error::Message(StdString::from("Poped the last frame in execute_function"))
         })?;
@@ -1913,6 +2188,7 @@ impl<'b> OwnedContext<'b> {
             gc: &mut context.gc,
             stack: StackFrame::current(&mut context.stack),
             hook: &mut context.hook,
+            limits: &mut context.limits,
             max_stack_size: context.max_stack_size,
             poll_fns: &context.poll_fns,
         }
@@ -1948,6 +2224,7 @@ pub struct ExecuteContext<'b, 'gc, S: StackState = ClosureState> {
     pub stack: StackFrame<'b, S>,
     pub gc: &'gc mut Gc,
     hook: &'b mut Hook,
+    limits: &'b mut Limits,
     max_stack_size: VmIndex,
     poll_fns: &'b [PollFn],
 }
@@ -2029,6 +2306,10 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
             );
         }
 
+        // Hooks and limits can only be changed while the thread is not running so if neither is
+        // active the checks for them can be skipped for every instruction of this frame
+        let instrumented = self.hook.is_active() || self.limits.is_active();
+
         let instructions = &function.instructions[..];
         let mut program_counter = ProgramCounter::new(state.instruction_index, instructions);
         loop {
@@ -2040,8 +2321,31 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
 
             debug_instruction(&self.stack, instruction_index, instr);
 
-            if self.hook.flags.contains(HookFlags::LINE_FLAG) {
-                try_ready!(self.run_hook(&function, instruction_index));
+            if instrumented {
+                if self.limits.preempt() && self.can_suspend() {
+                    // Resume at the same instruction once the scheduler runs the thread again
+                    self.stack.frame_mut().state.instruction_index = instruction_index;
+                    return Ok(Async::NotReady);
+                }
+
+                if let Err(err) = self.limits.consume(self.hook.executed_instructions) {
+                    // Store the instruction so that execution can be resumed if the limit is
+                    // raised
+                    self.stack.frame_mut().state.instruction_index = instruction_index;
+                    return Err(err);
+                }
+                self.hook.executed_instructions += 1;
+                if self.hook.flags.contains(HookFlags::LINE_FLAG) {
+                    try_ready!(self.run_hook(&function, instruction_index));
+                }
+                if self.hook.flags.contains(HookFlags::SAMPLE_FLAG)
+                    && self
+                        .hook
+                        .executed_instructions
+                        .is_multiple_of(self.hook.sample_interval)
+                {
+                    try_ready!(self.run_sample_hook(instruction_index));
+                }
             }
 
             match instr {
@@ -2094,6 +2398,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                         self.stack.frame().state,
                         function.name
                     );
+                    self.run_return_hook()?;
                     let mut context = self.exit_scope().unwrap_or_else(|x| x);
                     debug!(
                         "Clearing {} {} {:?}",
@@ -2211,6 +2516,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                             unsafe {
                                 let mut data = data.unrooted();
                                 let start = self.stack.len() - data.fields.len() as VmIndex;
+                                self.gc.write_barrier(&self.stack[start..]);
                                 for (var, value) in
                                     data.as_mut().fields.iter_mut().zip(&self.stack[start..])
                                 {
@@ -2366,6 +2672,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                             unsafe {
                                 let mut closure = closure.clone_unrooted();
                                 let start = self.stack.len() - closure.upvars.len() as VmIndex;
+                                self.gc.write_barrier(&self.stack[start..]);
                                 for (var, value) in
                                     closure.as_mut().upvars.iter_mut().zip(&self.stack[start..])
                                 {
@@ -2382,10 +2689,38 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                     let v = transfer!(self, self.stack.get_upvar(i).get_value());
                     self.stack.push(v);
                 }
-                AddInt => binop_int(self.thread, &mut self.stack, VmInt::checked_add)?,
-                SubtractInt => binop_int(self.thread, &mut self.stack, VmInt::checked_sub)?,
-                MultiplyInt => binop_int(self.thread, &mut self.stack, VmInt::checked_mul)?,
-                DivideInt => binop_int(self.thread, &mut self.stack, VmInt::checked_div)?,
+                AddInt | SubtractInt | MultiplyInt | DivideInt => {
+                    let f = match instr {
+                        AddInt => VmInt::checked_add,
+                        SubtractInt => VmInt::checked_sub,
+                        MultiplyInt => VmInt::checked_mul,
+                        _ => VmInt::checked_div,
+                    };
+                    binop(self.thread, &mut self.stack, |l, r| {
+                        f(l, r).map(ValueRepr::Int).ok_or_else(|| {
+                            Error::ArithmeticOverflow(
+                                function.debug_info.source_name.clone(),
+                                function.debug_info.source_map.line(instruction_index),
+                            )
+                        })
+                    })?
+                }
+                WrappingAddInt => binop_int(self.thread, &mut self.stack, |l: VmInt, r| {
+                    Some(l.wrapping_add(r))
+                })?,
+                WrappingSubtractInt => binop_int(self.thread, &mut self.stack, |l: VmInt, r| {
+                    Some(l.wrapping_sub(r))
+                })?,
+                WrappingMultiplyInt => binop_int(self.thread, &mut self.stack, |l: VmInt, r| {
+                    Some(l.wrapping_mul(r))
+                })?,
+                WrappingDivideInt => binop_int(self.thread, &mut self.stack, |l: VmInt, r| {
+                    if r == 0 {
+                        None
+                    } else {
+                        Some(l.wrapping_div(r))
+                    }
+                })?,
                 IntLT => binop_bool(self.thread, &mut self.stack, |l: VmInt, r| l < r)?,
                 IntEQ => binop_bool(self.thread, &mut self.stack, |l: VmInt, r| l == r)?,
 
@@ -2419,6 +2754,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
             self.stack.frame().state,
             function.name
         );
+        self.run_return_hook()?;
         let (stack_exists, mut context) = {
             let r = self.exit_scope();
             (
@@ -2456,6 +2792,16 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
         }
     }
 
+    // Functions called from an extern function must run to completion so the thread can only
+    // be suspended if no extern function is on the stack
+    fn can_suspend(&self) -> bool {
+        self.stack
+            .stack()
+            .get_frames()
+            .iter()
+            .all(|frame| !matches!(frame.state, State::Extern(_)))
+    }
+
     fn run_hook(&mut self, function: &BytecodeFunction, index: usize) -> Result<Async<()>> {
         if let Some(ref mut hook) = self.hook.function {
             let current_line = function.debug_info.source_map.line(index);
@@ -2469,12 +2815,28 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc> {
                 let info = DebugInfo {
                     stack: &self.stack.stack(),
                     state: HookFlags::LINE_FLAG,
+                    executed_instructions: self.hook.executed_instructions,
+                    allocated_memory: self.gc.total_allocated_memory(),
                 };
                 try_ready!(hook(self.thread, info))
             }
         }
         Ok(().into())
     }
+
+    fn run_sample_hook(&mut self, index: usize) -> Result<Async<()>> {
+        if let Some(ref mut hook) = self.hook.function {
+            self.stack.frame_mut().state.instruction_index = index;
+            let info = DebugInfo {
+                stack: self.stack.stack(),
+                state: HookFlags::SAMPLE_FLAG,
+                executed_instructions: self.hook.executed_instructions,
+                allocated_memory: self.gc.total_allocated_memory(),
+            };
+            try_ready!(hook(self.thread, info))
+        }
+        Ok(().into())
+    }
 }
 
 impl<'b, 'gc> ExecuteContext<'b, 'gc, State> {
@@ -2487,6 +2849,7 @@ impl<'b, 'gc> ExecuteContext<'b, 'gc, State> {
             stack: self.stack.from_state(),
             gc: self.gc,
             hook: self.hook,
+            limits: self.limits,
             max_stack_size: self.max_stack_size,
             poll_fns: self.poll_fns,
         }
@@ -2503,11 +2866,27 @@ where
             stack: self.stack.to_state(),
             gc: self.gc,
             hook: self.hook,
+            limits: self.limits,
             max_stack_size: self.max_stack_size,
             poll_fns: self.poll_fns,
         }
     }
 
+    fn run_return_hook(&mut self) -> Result<()> {
+        if self.hook.flags.contains(HookFlags::RETURN_FLAG) {
+            if let Some(ref mut hook) = self.hook.function {
+                let info = DebugInfo {
+                    stack: self.stack.stack(),
+                    state: HookFlags::RETURN_FLAG,
+                    executed_instructions: self.hook.executed_instructions,
+                    allocated_memory: self.gc.total_allocated_memory(),
+                };
+                hook(self.thread, info)?;
+            }
+        }
+        Ok(())
+    }
+
     fn enter_scope<T>(self, args: VmIndex, state: &T, excess: bool) -> ExecuteContext<'b, 'gc, T>
     where
         T: StackState,
@@ -2519,6 +2898,7 @@ where
             stack,
             gc: self.gc,
             hook: self.hook,
+            limits: self.limits,
             max_stack_size: self.max_stack_size,
             poll_fns: self.poll_fns,
         }
@@ -2542,6 +2922,7 @@ where
                     stack,
                     gc: self.gc,
                     hook: self.hook,
+                    limits: self.limits,
                     max_stack_size: self.max_stack_size,
                     poll_fns: self.poll_fns,
                 })
@@ -2551,6 +2932,7 @@ where
                 stack: StackFrame::current(stack),
                 gc: self.gc,
                 hook: self.hook,
+                limits: self.limits,
                 max_stack_size: self.max_stack_size,
                 poll_fns: self.poll_fns,
             }),
@@ -2835,6 +3217,7 @@ impl<'vm> ActiveThread<'vm> {
             gc: &mut context.gc,
             stack: StackFrame::current(&mut context.stack),
             hook: &mut context.hook,
+            limits: &mut context.limits,
             max_stack_size: context.max_stack_size,
             poll_fns: &context.poll_fns,
         }
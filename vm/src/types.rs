@@ -163,6 +163,11 @@ pub enum Instruction {
     SubtractInt,
     MultiplyInt,
     DivideInt,
+    /// Like `AddInt` but wraps around on overflow instead of returning an error
+    WrappingAddInt,
+    WrappingSubtractInt,
+    WrappingMultiplyInt,
+    WrappingDivideInt,
     IntLT,
     IntEQ,
 
@@ -210,9 +215,10 @@ impl Instruction {
             NewClosure { .. } => 1,
             CloseClosure(_) => -1,
             PushUpVar(_) => 1,
-            AddInt | SubtractInt | MultiplyInt | DivideInt | IntLT | IntEQ | AddFloat | AddByte
-            | SubtractByte | MultiplyByte | DivideByte | ByteLT | ByteEQ | SubtractFloat
-            | MultiplyFloat | DivideFloat | FloatLT | FloatEQ => -1,
+            AddInt | SubtractInt | MultiplyInt | DivideInt | WrappingAddInt
+            | WrappingSubtractInt | WrappingMultiplyInt | WrappingDivideInt | IntLT | IntEQ
+            | AddFloat | AddByte | SubtractByte | MultiplyByte | DivideByte | ByteLT | ByteEQ
+            | SubtractFloat | MultiplyFloat | DivideFloat | FloatLT | FloatEQ => -1,
             Return => 0,
         }
     }
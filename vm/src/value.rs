@@ -43,6 +43,21 @@ pub trait Userdata: Downcast + Trace + fmt::Debug + Send + Sync {
         let _ = deep_cloner;
         Err(Error::Message("Userdata cannot be cloned".into()))
     }
+
+    /// Returns true if `finalize` should be called once the value has become unreachable. Checked
+    /// when the value is pushed to a thread, copies made by `deep_clone` are not finalized.
+    fn has_finalizer(&self) -> bool {
+        false
+    }
+
+    /// Called on the thread which owns the value after a garbage collection found it to be
+    /// unreachable. It runs once, the next time the thread calls a Rust function or
+    /// `Thread::collect` returns. The value and everything it references are kept alive until the
+    /// finalizer has returned and the value is dropped by the first collection which finds it to
+    /// be unreachable after that.
+    fn finalize(&self, thread: &Thread) {
+        let _ = thread;
+    }
 }
 
 impl PartialEq for dyn Userdata {
@@ -520,6 +535,21 @@ impl Value {
         }
     }
 
+    /// Returns true if `gc` is about to free this value. See `Gc::is_collected`.
+    pub(crate) fn is_collected(&self, gc: &Gc) -> bool {
+        match self.get_repr() {
+            String(p) => gc.is_collected(p),
+            ValueRepr::Data(p) => gc.is_collected(p),
+            Function(p) => gc.is_collected(p),
+            Closure(p) => gc.is_collected(p),
+            ValueRepr::Array(p) => gc.is_collected(p),
+            PartialApplication(p) => gc.is_collected(p),
+            ValueRepr::Userdata(p) => gc.is_collected(p),
+            ValueRepr::Thread(p) => gc.is_collected(p),
+            ValueRepr::Tag(_) | ValueRepr::Byte(_) | Int(_) | Float(_) => false,
+        }
+    }
+
     pub fn obj_eq(&self, other: &Self) -> bool {
         let (self_, other) = (self.get_repr(), other.get_repr());
         if mem::discriminant(self_) != mem::discriminant(other) {
@@ -734,7 +764,7 @@ impl<'a, 't> InternalPrinter<'a, 't> {
                 arena.concat(array.iter().map(|field| {
                     match **self.typ {
                         Type::App(_, ref args) => self.p(&args[0], Top).pretty(field),
-                        _ => arena.text(format!("{:?}", field)),
+                        _ => self.p(&Type::hole(), Top).pretty(field),
                     }
                 }).intersperse(arena.text(",").append(arena.space())))
                     .nest(INDENT),
@@ -1522,6 +1552,12 @@ impl<'t> Cloner<'t> {
         self
     }
 
+    /// Returns true if `value` can be used as is by the receiving `Gc`, without being cloned
+    pub fn can_share(&self, value: &Value) -> bool {
+        self.receiver_generation
+            .can_contain_values_from(value.generation())
+    }
+
     pub fn deep_clone<'gc>(&'gc mut self, value: &Value) -> Result<Variants<'gc>> {
         unsafe {
             self.deep_clone_inner(value)
@@ -1532,10 +1568,7 @@ impl<'t> Cloner<'t> {
     unsafe fn deep_clone_inner(&mut self, value: &Value) -> Result<Value> {
         // Only need to clone values which belong to a younger generation than the gc that the new
         // value will live in
-        if self
-            .receiver_generation
-            .can_contain_values_from(value.generation())
-        {
+        if self.can_share(value) {
             return Ok(value.clone_unrooted());
         }
 
@@ -26,7 +26,7 @@ use crate::{
     api::{OpaqueValue, ValueRef, IO},
     compiler::{CompiledFunction, CompiledModule, CompilerEnv, Variable},
     core::{interpreter, optimize::OptimizeEnv, CoreExpr},
-    gc::{CloneUnrooted, Gc, GcPtr, GcRef, Generation, Move, Trace},
+    gc::{CloneUnrooted, Gc, GcMode, GcPtr, GcRef, Generation, Move, Trace},
     interner::{InternedStr, Interner},
     lazy::Lazy,
     macros::MacroEnv,
@@ -534,20 +534,31 @@ impl<'a> VmEnvInstance<'a> {
 }
 
 #[derive(Default)]
-pub struct GlobalVmStateBuilder {}
+pub struct GlobalVmStateBuilder {
+    gc_mode: GcMode,
+}
 
 impl GlobalVmStateBuilder {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Sets how the garbage collectors of every thread collect their values (default:
+    /// `GcMode::StopTheWorld`)
+    pub fn gc_mode(mut self, gc_mode: GcMode) -> Self {
+        self.gc_mode = gc_mode;
+        self
+    }
+
     pub fn build(self) -> GlobalVmState {
+        let mut gc = Gc::new(Generation::default(), usize::MAX);
+        gc.set_mode(self.gc_mode);
         let mut vm = GlobalVmState {
             env: Default::default(),
             generics: RwLock::new(FnvMap::default()),
             typeids: RwLock::new(FnvMap::default()),
             interner: RwLock::new(Interner::new()),
-            gc: Mutex::new(Gc::new(Generation::default(), usize::MAX)),
+            gc: Mutex::new(gc),
             macros: MacroEnv::new(),
             type_cache: TypeCache::default(),
             generation_0_threads: Default::default(),
@@ -0,0 +1,183 @@
+//! Weak references to gluon values (`std.weak`).
+use crate::real_std::{
+    any::Any,
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    api::{generic::A, ActiveThread, Generic, Pushable, Unrooted, Userdata, VmType},
+    base::types::ArcType,
+    gc::{CloneUnrooted, Gc, GcRef, Move, Trace, WeakRef},
+    thread::{RootedValue, ThreadInternal},
+    value::{Cloner, Value},
+    vm::Thread,
+    ExternModule, Result, Variants,
+};
+
+/// A reference to a gluon value which does not keep the value alive.
+///
+/// Values which contain a `WeakValue` report it to the garbage collector when they are traced,
+/// which the `Trace` implementations of `Vec`, `Mutex`, etc. and derived implementations do
+/// automatically. Values which are not garbage collected, such as integers, are never cleared.
+///
+/// A `WeakValue` which is only held by host code (in a local, a global, or taken out of a traced
+/// value) is never reported and therefore never cleared, which is why creating and upgrading one
+/// is `unsafe`.
+#[derive(Clone, Debug)]
+pub struct WeakValue(Arc<WeakSlot>);
+
+#[derive(Debug)]
+struct WeakSlot(Mutex<Option<Value>>);
+
+impl WeakRef for WeakSlot {
+    fn clear_collected(&self, gc: &Gc) {
+        let mut value = self.0.lock().unwrap();
+        if value.as_ref().is_some_and(|value| value.is_collected(gc)) {
+            *value = None;
+        }
+    }
+}
+
+unsafe impl Trace for WeakValue {
+    fn trace(&self, gc: &mut Gc) {
+        gc.trace_weak(self.0.clone());
+    }
+}
+
+impl WeakValue {
+    /// Creates a weak reference to `value`
+    ///
+    /// # Safety
+    ///
+    /// The returned `WeakValue` (and every clone of it) must stay reachable from a value which is
+    /// traced by the heap that `value` belongs to, for as long as it is used. Only then is it
+    /// cleared before `value` is freed.
+    pub unsafe fn new(value: Variants) -> WeakValue {
+        // The caller guarantees that the value is cleared before it is freed
+        WeakValue::from_value(Some(value.get_value().clone_unrooted()))
+    }
+
+    fn from_value(value: Option<Value>) -> WeakValue {
+        WeakValue(Arc::new(WeakSlot(Mutex::new(value))))
+    }
+
+    /// Returns the value without rooting it. It must be rooted before anything is allocated.
+    unsafe fn get_unrooted(&self) -> Option<Value> {
+        let value = self.0 .0.lock().unwrap();
+        value.as_ref().map(|value| value.clone_unrooted())
+    }
+
+    /// Returns the value if it has not been freed yet
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for `WeakValue::new` apply and `thread` must share the heap of the
+    /// value this refers to.
+    pub unsafe fn upgrade<'vm>(&self, thread: &'vm Thread) -> Option<RootedValue<&'vm Thread>> {
+        // Locking the thread prevents a collection from freeing the value between reading and
+        // rooting it
+        let _context = thread.context();
+        // The value is rooted before the thread is unlocked
+        self.get_unrooted()
+            .map(|value| thread.root_value(Variants::new(&value)))
+    }
+}
+
+#[derive(VmType)]
+#[gluon(gluon_vm)]
+#[gluon(vm_type = "std.weak.Weak")]
+pub struct Weak<T> {
+    value: WeakValue,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Userdata for Weak<T>
+where
+    T: Any + Send + Sync,
+{
+    fn deep_clone<'gc>(
+        &self,
+        deep_cloner: &'gc mut Cloner,
+    ) -> Result<GcRef<'gc, Box<dyn Userdata>>> {
+        // A copy of the value would not be kept alive by anything so the reference is only kept
+        // if the value can be shared with the receiver
+        // SAFETY Nothing is allocated before the value is stored in the new reference
+        let value =
+            unsafe { self.value.get_unrooted() }.filter(|value| deep_cloner.can_share(value));
+        let data: Box<dyn Userdata> = Box::new(Weak {
+            value: WeakValue::from_value(value),
+            _marker: PhantomData::<A>,
+        });
+        deep_cloner.gc().alloc(Move(data))
+    }
+}
+
+impl<T> fmt::Debug for Weak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Weak({:?})", *self.value.0 .0.lock().unwrap())
+    }
+}
+
+unsafe impl<T> Trace for Weak<T> {
+    impl_trace_fields! { self, gc; value }
+}
+
+/// Pushes the value of a weak reference as an `Option`. The value is read while pushing since the
+/// thread is locked at that point.
+struct Upgrade<T>(WeakValue, PhantomData<T>);
+
+impl<T> VmType for Upgrade<T>
+where
+    T: VmType,
+    T::Type: Sized,
+{
+    type Type = Option<T::Type>;
+
+    fn make_type(vm: &Thread) -> ArcType {
+        Option::<T>::make_type(vm)
+    }
+}
+
+impl<'vm, T> Pushable<'vm> for Upgrade<T>
+where
+    T: VmType,
+{
+    fn push(self, context: &mut ActiveThread<'vm>) -> Result<()> {
+        // SAFETY The value is pushed to the stack before anything is allocated
+        unsafe { self.0.get_unrooted() }
+            .map(Unrooted::<T>::from)
+            .push(context)
+    }
+}
+
+fn downgrade(value: Generic<A>) -> Weak<A> {
+    Weak {
+        // SAFETY The reference is only stored in the returned `Weak` which traces it
+        value: unsafe { WeakValue::new(Variants::new(value.get_value())) },
+        _marker: PhantomData,
+    }
+}
+
+fn upgrade(weak: &Weak<A>) -> Upgrade<A> {
+    Upgrade(weak.value.clone(), PhantomData)
+}
+
+mod std {
+    pub mod weak {
+        pub use crate::weak as prim;
+    }
+}
+
+pub fn load(vm: &Thread) -> Result<ExternModule> {
+    let _ = vm.register_type::<Weak<A>>("std.weak.Weak", &["a"]);
+    ExternModule::new(
+        vm,
+        record! {
+            type Weak a => Weak<A>,
+            downgrade => primitive!(1, "std.weak.prim.downgrade", std::weak::prim::downgrade),
+            upgrade => primitive!(1, "std.weak.prim.upgrade", std::weak::prim::upgrade),
+        },
+    )
+}